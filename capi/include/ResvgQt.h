@@ -125,11 +125,34 @@ static QString errorToString(const int err)
             return QLatin1Literal("Failed to parse an SVG data."); break;
         case RESVG_ERROR_NO_CANVAS :
             return QLatin1Literal("Failed to allocate the canvas."); break;
+        case RESVG_ERROR_UNSUPPORTED :
+            return QLatin1Literal("SVG has unsupported features."); break;
+        case RESVG_ERROR_LIMIT_EXCEEDED :
+            return QLatin1Literal("One of the resource limits was exceeded."); break;
     }
 
     Q_UNREACHABLE();
 }
 
+static QString diagnosticsToString(const resvg_diagnostics *diagnostics)
+{
+    QString text;
+    if (!diagnostics) {
+        return text;
+    }
+
+    // In the strict mode, diagnostics explain why parsing has failed.
+    resvg_diagnostic diagnostic;
+    const auto count = resvg_get_diagnostics_count(diagnostics);
+    for (size_t i = 0; i < count; ++i) {
+        if (resvg_get_diagnostic(diagnostics, i, &diagnostic)) {
+            text += QLatin1Char('\n') + QString::fromUtf8(diagnostic.message);
+        }
+    }
+
+    return text;
+}
+
 } //ResvgPrivate
 
 /**
@@ -294,9 +317,18 @@ inline bool ResvgRenderer::load(const QString &filePath)
 
     d->opt.path = ResvgPrivate::toCStr(filePath);
 
-    const auto err = resvg_parse_tree_from_file(d->opt.path, &d->opt, &d->tree);
+    resvg_diagnostics *diagnostics = nullptr;
+    const auto err = resvg_parse_tree_from_file(d->opt.path, &d->opt, &d->tree, &diagnostics);
+    if (err != RESVG_OK) {
+        d->errMsg = ResvgPrivate::errorToString(err)
+                    + ResvgPrivate::diagnosticsToString(diagnostics);
+    }
+
+    if (diagnostics) {
+        resvg_diagnostics_destroy(diagnostics);
+    }
+
     if (err != RESVG_OK) {
-        d->errMsg = ResvgPrivate::errorToString(err);
         return false;
     }
 
@@ -310,9 +342,19 @@ inline bool ResvgRenderer::load(const QByteArray &data)
 {
     d->reset();
 
-    const auto err = resvg_parse_tree_from_data(data.constData(), data.size(), &d->opt, &d->tree);
+    resvg_diagnostics *diagnostics = nullptr;
+    const auto err = resvg_parse_tree_from_data(data.constData(), data.size(), &d->opt, &d->tree,
+                                                &diagnostics);
+    if (err != RESVG_OK) {
+        d->errMsg = ResvgPrivate::errorToString(err)
+                    + ResvgPrivate::diagnosticsToString(diagnostics);
+    }
+
+    if (diagnostics) {
+        resvg_diagnostics_destroy(diagnostics);
+    }
+
     if (err != RESVG_OK) {
-        d->errMsg = ResvgPrivate::errorToString(err);
         return false;
     }
 
@@ -33,6 +33,11 @@
  */
 typedef struct resvg_render_tree resvg_render_tree;
 
+/**
+ * @brief An opaque pointer to the list of conversion diagnostics.
+ */
+typedef struct resvg_diagnostics resvg_diagnostics;
+
 /**
  * @brief List of possible errors.
  */
@@ -54,14 +59,19 @@ typedef enum resvg_error {
      *
      * Occurs when width and/or height are <= 0.
      *
-     * Also occurs if width, height and viewBox are not set.
-     * This is against the SVG spec, but an automatic size detection is not supported yet.
+     * Also occurs if width, height and viewBox are not set
+     * and the document doesn't have any renderable elements,
+     * so the size cannot be detected from the content bounding box.
      */
     RESVG_ERROR_INVALID_SIZE,
     /** Failed to parse an SVG data. */
     RESVG_ERROR_PARSING_FAILED,
     /** Failed to allocate an image. */
     RESVG_ERROR_NO_CANVAS,
+    /** SVG cannot be converted without losses. Occurs only in the strict mode. */
+    RESVG_ERROR_UNSUPPORTED,
+    /** One of the resource limits was exceeded. */
+    RESVG_ERROR_LIMIT_EXCEEDED,
 } resvg_error;
 
 /**
@@ -119,6 +129,27 @@ typedef enum resvg_image_rendering {
     RESVG_IMAGE_RENDERING_OPTIMIZE_SPEED,
 } resvg_image_rendering;
 
+/**
+ * @brief Resource limits.
+ *
+ * Should be used to process untrusted SVG files.
+ * Zero means no limit.
+ */
+typedef struct resvg_limits {
+    /** Maximum number of elements in the document. */
+    uint64_t max_elements;
+    /** Maximum elements nesting depth. */
+    uint64_t max_depth;
+    /** Maximum number of nodes that can be created by \b use elements resolving. */
+    uint64_t max_use_expansion;
+    /** Maximum size of an image data in bytes. */
+    uint64_t max_image_size;
+    /** Maximum number of pixels in the output image and in decoded raster images. */
+    uint64_t max_pixels;
+    /** Maximum number of pixels in a filter region. */
+    uint64_t max_filter_region;
+} resvg_limits;
+
 /**
  * @brief Rendering options.
  */
@@ -210,6 +241,22 @@ typedef struct resvg_options {
      * Default: false
      */
     bool keep_named_groups;
+
+    /**
+     * Fail on any unsupported or lossy SVG feature
+     * with #RESVG_ERROR_UNSUPPORTED.
+     *
+     * Default: false
+     */
+    bool strict;
+
+    /**
+     * Resource limits. Parsing or rendering will fail
+     * with #RESVG_ERROR_LIMIT_EXCEEDED when any of them was exceeded.
+     *
+     * Default: all limits are disabled.
+     */
+    resvg_limits limits;
 } resvg_options;
 
 /**
@@ -242,6 +289,37 @@ typedef struct resvg_transform {
     double f; /**< \b f value */
 } resvg_transform;
 
+/**
+ * @brief A diagnostic severity.
+ */
+typedef enum resvg_diagnostic_severity {
+    /** The problem was recovered from, but some data may be lost. */
+    RESVG_DIAGNOSTIC_WARNING,
+    /** The document cannot be converted properly. An empty tree will be produced. */
+    RESVG_DIAGNOSTIC_ERROR,
+} resvg_diagnostic_severity;
+
+/**
+ * @brief A conversion diagnostic.
+ *
+ * All strings are owned by #resvg_diagnostics
+ * and valid until the list is destroyed.
+ */
+typedef struct resvg_diagnostic {
+    /** A diagnostic code. Like \b invalid-shape or \b font-not-found. */
+    const char *code;
+    /** A diagnostic severity. */
+    resvg_diagnostic_severity severity;
+    /** An ID of the element that caused the diagnostic. Can be NULL. */
+    const char *element_id;
+    /** A row in the source data. 0 when unknown. */
+    uint32_t row;
+    /** A column in the source data. 0 when unknown. */
+    uint32_t col;
+    /** A human-readable message. */
+    const char *message;
+} resvg_diagnostic;
+
 /**
  * @brief Initializes the library log.
  *
@@ -268,11 +346,14 @@ void resvg_init_options(resvg_options *opt);
  * @param file_path UTF-8 file path.
  * @param opt Rendering options.
  * @param tree Parsed render tree. Should be destroyed via #resvg_tree_destroy.
+ * @param diagnostics Conversion diagnostics. Set even when parsing has failed.
+ *                    Can be NULL. Should be destroyed via #resvg_diagnostics_destroy.
  * @return #resvg_error
  */
 int resvg_parse_tree_from_file(const char *file_path,
                                const resvg_options *opt,
-                               resvg_render_tree **tree);
+                               resvg_render_tree **tree,
+                               resvg_diagnostics **diagnostics);
 
 /**
  * @brief Creates #resvg_render_tree from data.
@@ -283,12 +364,15 @@ int resvg_parse_tree_from_file(const char *file_path,
  * @param len Data length.
  * @param opt Rendering options.
  * @param tree Parsed render tree. Should be destroyed via #resvg_tree_destroy.
+ * @param diagnostics Conversion diagnostics. Set even when parsing has failed.
+ *                    Can be NULL. Should be destroyed via #resvg_diagnostics_destroy.
  * @return #resvg_error
  */
 int resvg_parse_tree_from_data(const char *data,
                                const size_t len,
                                const resvg_options *opt,
-                               resvg_render_tree **tree);
+                               resvg_render_tree **tree,
+                               resvg_diagnostics **diagnostics);
 
 /**
  * @brief Checks that tree has any nodes.
@@ -340,6 +424,36 @@ bool resvg_get_node_transform(const resvg_render_tree *tree,
                               const char *id,
                               resvg_transform *ts);
 
+/**
+ * @brief Returns the number of diagnostics produced during the tree parsing.
+ *
+ * Only conversion diagnostics are collected. Problems reported during rendering,
+ * like \b unsupported-blend-mode in the Qt backend, are not available via the C-API.
+ *
+ * @param diagnostics Diagnostics list.
+ * @return Diagnostics count.
+ */
+size_t resvg_get_diagnostics_count(const resvg_diagnostics *diagnostics);
+
+/**
+ * @brief Returns a diagnostic by index.
+ *
+ * @param diagnostics Diagnostics list.
+ * @param index Diagnostic index.
+ * @param diagnostic Diagnostic.
+ * @return \b false if index is out of bounds.
+ */
+bool resvg_get_diagnostic(const resvg_diagnostics *diagnostics,
+                          size_t index,
+                          resvg_diagnostic *diagnostic);
+
+/**
+ * @brief Destroys the #resvg_diagnostics.
+ *
+ * @param diagnostics Diagnostics list.
+ */
+void resvg_diagnostics_destroy(resvg_diagnostics *diagnostics);
+
 /**
  * @brief Destroys the #resvg_render_tree.
  *
@@ -467,4 +581,33 @@ void resvg_qt_render_to_canvas_by_id(const resvg_render_tree *tree,
                                      void *painter);
 #endif /* RESVG_QT_BACKEND */
 
+#ifdef RESVG_RASTER_BACKEND
+/**
+ * @brief Returns node's bounding box by ID.
+ *
+ * @param tree Render tree.
+ * @param opt Rendering options.
+ * @param id Node's ID.
+ * @param bbox Node's bounding box.
+ * @return \b false if a node with such an ID does not exist,
+ *         ID is an empty string or ID isn't a UTF-8 string.
+ */
+bool resvg_raster_get_node_bbox(const resvg_render_tree *tree,
+                                const resvg_options *opt,
+                                const char *id,
+                                resvg_rect *bbox);
+
+/**
+ * @brief Renders the #resvg_render_tree to file.
+ *
+ * @param tree Render tree.
+ * @param opt Rendering options.
+ * @param file_path File path.
+ * @return #resvg_error
+ */
+int resvg_raster_render_to_image(const resvg_render_tree *tree,
+                                 const resvg_options *opt,
+                                 const char *file_path);
+#endif /* RESVG_RASTER_BACKEND */
+
 #endif /* RESVG_H */
@@ -4,7 +4,7 @@
 
 #![allow(non_camel_case_types)]
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::fmt;
 use std::os::raw::c_char;
 use std::path;
@@ -37,6 +37,18 @@ pub struct resvg_options {
     pub draw_background: bool,
     pub background: resvg_color,
     pub keep_named_groups: bool,
+    pub strict: bool,
+    pub limits: resvg_limits,
+}
+
+#[repr(C)]
+pub struct resvg_limits {
+    pub max_elements: u64,
+    pub max_depth: u64,
+    pub max_use_expansion: u64,
+    pub max_image_size: u64,
+    pub max_pixels: u64,
+    pub max_filter_region: u64,
 }
 
 enum ErrorId {
@@ -49,6 +61,8 @@ enum ErrorId {
     InvalidSize,
     ParsingFailed,
     NoCanvas,
+    Unsupported,
+    LimitExceeded,
 }
 
 #[repr(C)]
@@ -116,9 +130,48 @@ pub struct resvg_transform {
     pub f: f64,
 }
 
+#[repr(C)]
+pub enum resvg_diagnostic_severity {
+    RESVG_DIAGNOSTIC_WARNING,
+    RESVG_DIAGNOSTIC_ERROR,
+}
+
+#[repr(C)]
+pub struct resvg_diagnostic {
+    pub code: *const c_char,
+    pub severity: resvg_diagnostic_severity,
+    pub element_id: *const c_char,
+    pub row: u32,
+    pub col: u32,
+    pub message: *const c_char,
+}
+
+struct Diagnostic {
+    code: CString,
+    severity: usvg::Severity,
+    element_id: Option<CString>,
+    position: Option<usvg::SourcePos>,
+    message: CString,
+}
+
+impl From<usvg::Diagnostic> for Diagnostic {
+    fn from(d: usvg::Diagnostic) -> Self {
+        Diagnostic {
+            code: CString::new(d.code.as_str()).unwrap_or_default(),
+            severity: d.severity,
+            element_id: d.element_id.map(|id| CString::new(id).unwrap_or_default()),
+            position: d.position,
+            message: CString::new(d.message).unwrap_or_default(),
+        }
+    }
+}
+
 #[repr(C)]
 pub struct resvg_render_tree(usvg::Tree);
 
+#[repr(C)]
+pub struct resvg_diagnostics(Vec<Diagnostic>);
+
 #[no_mangle]
 pub extern "C" fn resvg_init_log() {
     fern::Dispatch::new()
@@ -173,6 +226,15 @@ pub extern "C" fn resvg_init_options(
         (*opt).background.g = 0;
         (*opt).background.b = 0;
         (*opt).keep_named_groups = false;
+        (*opt).strict = false;
+        (*opt).limits = resvg_limits {
+            max_elements: 0,
+            max_depth: 0,
+            max_use_expansion: 0,
+            max_image_size: 0,
+            max_pixels: 0,
+            max_filter_region: 0,
+        };
     }
 }
 
@@ -181,6 +243,7 @@ pub extern "C" fn resvg_parse_tree_from_file(
     file_path: *const c_char,
     opt: *const resvg_options,
     raw_tree: *mut *mut resvg_render_tree,
+    raw_diagnostics: *mut *mut resvg_diagnostics,
 ) -> i32 {
     let file_path = match cstr_to_str(file_path) {
         Some(v) => v,
@@ -192,7 +255,10 @@ pub extern "C" fn resvg_parse_tree_from_file(
         &*opt
     });
 
-    let tree = match usvg::Tree::from_file(file_path, &opt.usvg) {
+    let (tree, diagnostics) = usvg::collect_diagnostics(|| usvg::Tree::from_file(file_path, &opt.usvg));
+    set_diagnostics(diagnostics, raw_diagnostics);
+
+    let tree = match tree {
         Ok(tree) => tree,
         Err(e) => return convert_error(e) as i32,
     };
@@ -209,6 +275,7 @@ pub extern "C" fn resvg_parse_tree_from_data(
     len: usize,
     opt: *const resvg_options,
     raw_tree: *mut *mut resvg_render_tree,
+    raw_diagnostics: *mut *mut resvg_diagnostics,
 ) -> i32 {
     let data = unsafe { slice::from_raw_parts(data as *const u8, len) };
 
@@ -217,7 +284,10 @@ pub extern "C" fn resvg_parse_tree_from_data(
         &*opt
     });
 
-    let tree = match usvg::Tree::from_data(data, &opt.usvg) {
+    let (tree, diagnostics) = usvg::collect_diagnostics(|| usvg::Tree::from_data(data, &opt.usvg));
+    set_diagnostics(diagnostics, raw_diagnostics);
+
+    let tree = match tree {
         Ok(tree) => tree,
         Err(e) => return convert_error(e) as i32,
     };
@@ -228,6 +298,23 @@ pub extern "C" fn resvg_parse_tree_from_data(
     ErrorId::Ok as i32
 }
 
+/// Passes diagnostics to the caller, if requested.
+///
+/// Diagnostics are set even when parsing has failed,
+/// since in the strict mode they explain the failure.
+fn set_diagnostics(
+    diagnostics: Vec<usvg::Diagnostic>,
+    raw_diagnostics: *mut *mut resvg_diagnostics,
+) {
+    if raw_diagnostics.is_null() {
+        return;
+    }
+
+    let list = diagnostics.into_iter().map(Diagnostic::from).collect();
+    let diagnostics_box = Box::new(resvg_diagnostics(list));
+    unsafe { *raw_diagnostics = Box::into_raw(diagnostics_box); }
+}
+
 #[no_mangle]
 pub extern "C" fn resvg_tree_destroy(
     tree: *mut resvg_render_tree,
@@ -260,6 +347,17 @@ pub extern "C" fn resvg_cairo_render_to_image(
     render_to_image(tree, opt, file_path, backend)
 }
 
+#[cfg(feature = "raster-backend")]
+#[no_mangle]
+pub extern "C" fn resvg_raster_render_to_image(
+    tree: *const resvg_render_tree,
+    opt: *const resvg_options,
+    file_path: *const c_char,
+) -> i32 {
+    let backend = Box::new(resvg::backend_raster::Backend);
+    render_to_image(tree, opt, file_path, backend)
+}
+
 fn render_to_image(
     tree: *const resvg_render_tree,
     opt: *const resvg_options,
@@ -283,9 +381,9 @@ fn render_to_image(
 
     let img = backend.render_to_image(&tree.0, &opt);
     let img = match img {
-        Some(img) => img,
-        None => {
-            return ErrorId::NoCanvas as i32;
+        Ok(img) => img,
+        Err(e) => {
+            return convert_render_error(e) as i32;
         }
     };
 
@@ -509,6 +607,18 @@ pub extern "C" fn resvg_cairo_get_node_bbox(
     get_node_bbox(tree, opt, id, bbox, backend)
 }
 
+#[cfg(feature = "raster-backend")]
+#[no_mangle]
+pub extern "C" fn resvg_raster_get_node_bbox(
+    tree: *const resvg_render_tree,
+    opt: *const resvg_options,
+    id: *const c_char,
+    bbox: *mut resvg_rect,
+) -> bool {
+    let backend = Box::new(resvg::backend_raster::Backend);
+    get_node_bbox(tree, opt, id, bbox, backend)
+}
+
 fn get_node_bbox(
     tree: *const resvg_render_tree,
     opt: *const resvg_options,
@@ -621,6 +731,66 @@ pub extern "C" fn resvg_get_node_transform(
     false
 }
 
+#[no_mangle]
+pub extern "C" fn resvg_get_diagnostics_count(
+    diagnostics: *const resvg_diagnostics,
+) -> usize {
+    let diagnostics = unsafe {
+        assert!(!diagnostics.is_null());
+        &*diagnostics
+    };
+
+    diagnostics.0.len()
+}
+
+#[no_mangle]
+pub extern "C" fn resvg_get_diagnostic(
+    diagnostics: *const resvg_diagnostics,
+    index: usize,
+    diagnostic: *mut resvg_diagnostic,
+) -> bool {
+    let diagnostics = unsafe {
+        assert!(!diagnostics.is_null());
+        &*diagnostics
+    };
+
+    let d = match diagnostics.0.get(index) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    let severity = match d.severity {
+        usvg::Severity::Warning => resvg_diagnostic_severity::RESVG_DIAGNOSTIC_WARNING,
+        usvg::Severity::Error => resvg_diagnostic_severity::RESVG_DIAGNOSTIC_ERROR,
+    };
+
+    let (row, col) = match d.position {
+        Some(pos) => (pos.row, pos.col),
+        None => (0, 0),
+    };
+
+    unsafe {
+        (*diagnostic).code = d.code.as_ptr();
+        (*diagnostic).severity = severity;
+        (*diagnostic).element_id = d.element_id.as_ref().map_or(ptr::null(), |id| id.as_ptr());
+        (*diagnostic).row = row;
+        (*diagnostic).col = col;
+        (*diagnostic).message = d.message.as_ptr();
+    }
+
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn resvg_diagnostics_destroy(
+    diagnostics: *mut resvg_diagnostics,
+) {
+    unsafe {
+        assert!(!diagnostics.is_null());
+        Box::from_raw(diagnostics)
+    };
+}
+
 fn cstr_to_str(
     text: *const c_char,
 ) -> Option<&'static str> {
@@ -742,6 +912,17 @@ fn to_native_opt(
     }
 
 
+    // Zero means no limit.
+    let limit = |v: u64| if v == 0 { None } else { Some(v) };
+    let limits = usvg::Limits {
+        max_elements: limit(opt.limits.max_elements).map(|v| v as usize),
+        max_depth: limit(opt.limits.max_depth).map(|v| v as usize),
+        max_use_expansion: limit(opt.limits.max_use_expansion).map(|v| v as usize),
+        max_image_size: limit(opt.limits.max_image_size),
+        max_pixels: limit(opt.limits.max_pixels),
+        max_filter_region: limit(opt.limits.max_filter_region),
+    };
+
     resvg::Options {
         usvg: usvg::Options {
             path,
@@ -753,6 +934,11 @@ fn to_native_opt(
             text_rendering,
             image_rendering,
             keep_named_groups: opt.keep_named_groups,
+            strict: opt.strict,
+            resource_loader: None,
+            limits,
+            style_sheet: None,
+            css_variables: Vec::new(),
         },
         fit_to,
         background,
@@ -769,5 +955,17 @@ fn convert_error(
         usvg::Error::MalformedGZip => ErrorId::MalformedGZip,
         usvg::Error::InvalidSize => ErrorId::InvalidSize,
         usvg::Error::ParsingFailed(_) => ErrorId::ParsingFailed,
+        usvg::Error::Unsupported(_) => ErrorId::Unsupported,
+        usvg::Error::LimitExceeded(_) => ErrorId::LimitExceeded,
+    }
+}
+
+fn convert_render_error(
+    e: resvg::RenderError,
+) -> ErrorId {
+    match e {
+        resvg::RenderError::InvalidSize => ErrorId::InvalidSize,
+        resvg::RenderError::LimitExceeded(_) => ErrorId::LimitExceeded,
+        resvg::RenderError::ImageAllocationFailed => ErrorId::NoCanvas,
     }
 }
@@ -37,7 +37,7 @@ parse_doc(const char *path)
     opt.font_family = "Times New Roman";
     opt.languages = "en";
 
-    int err = resvg_parse_tree_from_file(path, &opt, &tree);
+    int err = resvg_parse_tree_from_file(path, &opt, &tree, NULL);
     if (err != RESVG_OK) {
         printf("Error id: %i\n", err);
         abort();
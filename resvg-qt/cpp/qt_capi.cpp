@@ -242,11 +242,45 @@ void qtc_qpainter_reset_clip_path(qtc_qpainter *c_p)
     PAINTER_CAST->setClipPath(QPainterPath(), Qt::NoClip);
 }
 
+void qtc_qpainter_clip_image(qtc_qpainter *c_p, qtc_qimage *c_img)
+{
+    const QPainter *p = PAINTER_CAST;
+    if (!p->hasClipping()) {
+        return;
+    }
+
+    QImage *img = IMAGE_CAST;
+
+    // The clip path is in logical coordinates.
+    const QPainterPath clip = p->transform().map(p->clipPath());
+
+    QPainterPath outside;
+    outside.addRect(img->rect());
+    outside = outside.subtracted(clip);
+
+    QPainter img_p(img);
+    img_p.setRenderHint(QPainter::Antialiasing);
+    img_p.setCompositionMode(QPainter::CompositionMode_Clear);
+    img_p.fillPath(outside, Qt::black);
+}
+
 void qtc_qpainter_set_composition_mode(qtc_qpainter *c_p, CompositionMode mode)
 {
     PAINTER_CAST->setCompositionMode(QPainter::CompositionMode(mode));
 }
 
+qtc_qimage* qtc_qpainter_get_image(qtc_qpainter *c_p)
+{
+    QImage *img = dynamic_cast<QImage*>(PAINTER_CAST->device());
+
+    // Only premultiplied images can be accessed directly.
+    if (!img || img->format() != QImage::Format_ARGB32_Premultiplied) {
+        return 0;
+    }
+
+    return reinterpret_cast<qtc_qimage*>(img);
+}
+
 void qtc_qpainter_end(qtc_qpainter *c_p)
 {
     PAINTER_CAST->end();
@@ -363,6 +397,11 @@ void qtc_qpen_set_width(qtc_qpen *c_pen, double width)
     PEN_CAST->setWidthF(width);
 }
 
+void qtc_qpen_set_cosmetic(qtc_qpen *c_pen, bool flag)
+{
+    PEN_CAST->setCosmetic(flag);
+}
+
 void qtc_qpen_set_miter_limit(qtc_qpen *c_pen, double limit)
 {
     PEN_CAST->setMiterLimit(limit);
@@ -133,7 +133,9 @@ void qtc_qpainter_set_transform(qtc_qpainter *c_p, qtc_qtransform *q_ts, bool co
 void qtc_qpainter_set_clip_rect(qtc_qpainter *c_p, double x, double y, double w, double h);
 void qtc_qpainter_set_clip_path(qtc_qpainter *c_p, qtc_qpainterpath *c_pp);
 void qtc_qpainter_reset_clip_path(qtc_qpainter *c_p);
+void qtc_qpainter_clip_image(qtc_qpainter *c_p, qtc_qimage *c_img);
 void qtc_qpainter_set_composition_mode(qtc_qpainter *c_p, CompositionMode mode);
+qtc_qimage* qtc_qpainter_get_image(qtc_qpainter *c_p);
 void qtc_qpainter_end(qtc_qpainter *c_p);
 void qtc_qpainter_destroy(qtc_qpainter *c_p);
 
@@ -164,6 +166,7 @@ void qtc_qpen_set_brush(qtc_qpen *c_pen, qtc_qbrush *c_brush);
 void qtc_qpen_set_line_cap(qtc_qpen *c_pen, PenCapStyle s);
 void qtc_qpen_set_line_join(qtc_qpen *c_pen, PenJoinStyle s);
 void qtc_qpen_set_width(qtc_qpen *c_pen, double width);
+void qtc_qpen_set_cosmetic(qtc_qpen *c_pen, bool flag);
 void qtc_qpen_set_miter_limit(qtc_qpen *c_pen, double limit);
 void qtc_qpen_set_dash_offset(qtc_qpen *c_pen, double offset);
 void qtc_qpen_set_dash_array(qtc_qpen *c_pen, const double *array, int len);
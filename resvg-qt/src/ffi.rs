@@ -541,9 +541,15 @@ extern "C" {
 extern "C" {
     pub fn qtc_qpainter_reset_clip_path(c_p: *mut qtc_qpainter);
 }
+extern "C" {
+    pub fn qtc_qpainter_clip_image(c_p: *mut qtc_qpainter, c_img: *mut qtc_qimage);
+}
 extern "C" {
     pub fn qtc_qpainter_set_composition_mode(c_p: *mut qtc_qpainter, mode: CompositionMode);
 }
+extern "C" {
+    pub fn qtc_qpainter_get_image(c_p: *mut qtc_qpainter) -> *mut qtc_qimage;
+}
 extern "C" {
     pub fn qtc_qpainter_end(c_p: *mut qtc_qpainter);
 }
@@ -616,6 +622,9 @@ extern "C" {
 extern "C" {
     pub fn qtc_qpen_set_width(c_pen: *mut qtc_qpen, width: f64);
 }
+extern "C" {
+    pub fn qtc_qpen_set_cosmetic(c_pen: *mut qtc_qpen, flag: bool);
+}
 extern "C" {
     pub fn qtc_qpen_set_miter_limit(c_pen: *mut qtc_qpen, limit: f64);
 }
@@ -287,10 +287,30 @@ impl Painter {
         unsafe { ffi::qtc_qpainter_reset_clip_path(self.0) }
     }
 
+    pub fn clip_image(&self, img: &mut Image) {
+        unsafe { ffi::qtc_qpainter_clip_image(self.0, img.0) }
+    }
+
     pub fn set_composition_mode(&mut self, mode: CompositionMode) {
         unsafe { ffi::qtc_qpainter_set_composition_mode(self.0, mode as ffi::CompositionMode) }
     }
 
+    pub fn device_data(&mut self) -> Option<ImageData> {
+        unsafe {
+            let img = ffi::qtc_qpainter_get_image(self.0);
+            if img.is_null() {
+                return None;
+            }
+
+            let ptr = ffi::qtc_qimage_get_data(img);
+            let len = ffi::qtc_qimage_get_byte_count(img) as usize;
+
+            Some(ImageData {
+                slice: slice::from_raw_parts_mut(ptr, len),
+            })
+        }
+    }
+
     pub fn end(&mut self) {
         unsafe { ffi::qtc_qpainter_end(self.0) }
     }
@@ -393,6 +413,10 @@ impl Pen {
         unsafe { ffi::qtc_qpen_set_width(self.0, width) }
     }
 
+    pub fn set_cosmetic(&mut self, flag: bool) {
+        unsafe { ffi::qtc_qpen_set_cosmetic(self.0, flag) }
+    }
+
     pub fn set_miter_limit(&mut self, limit: f64) {
         unsafe { ffi::qtc_qpen_set_miter_limit(self.0, limit) }
     }
@@ -164,7 +164,7 @@ pub fn mask(
             mask_cr.transform(usvg::Transform::from_bbox(bbox).to_native());
         }
 
-        super::render_group(node, opt, layers, &mask_cr);
+        super::render_group(node, opt, &mut RenderState::Ok, layers, &mask_cr);
     }
 
     {
@@ -32,6 +32,7 @@ use super::{
 
 type Image = filter::Image<cairo::ImageSurface>;
 type FilterResult = filter::FilterResult<cairo::ImageSurface>;
+pub type FilterInputs<'a> = filter::FilterInputs<'a, cairo::ImageSurface>;
 
 
 pub fn apply(
@@ -39,9 +40,10 @@ pub fn apply(
     bbox: Rect,
     ts: &usvg::Transform,
     opt: &Options,
-    canvas: &mut cairo::ImageSurface,
+    tree: &usvg::Tree,
+    inputs: &mut FilterInputs,
 ) {
-    CairoFilter::apply(filter, bbox, ts, opt, canvas);
+    CairoFilter::apply(filter, bbox, ts, opt, tree, inputs);
 }
 
 
@@ -166,38 +168,63 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
     fn get_input(
         input: &usvg::FilterInput,
         region: ScreenRect,
+        inputs: &FilterInputs,
         results: &[FilterResult],
-        canvas: &cairo::ImageSurface,
     ) -> Result<Image, Error> {
+        let convert = |in_image: Option<&cairo::ImageSurface>| -> Result<Image, Error> {
+            let image = if let Some(image) = in_image {
+                copy_image(image, region)?
+            } else {
+                // An unavailable input is a transparent black image.
+                create_image(region.width(), region.height())?
+            };
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
+        let convert_alpha = |in_image: Option<&cairo::ImageSurface>| -> Result<Image, Error> {
+            let mut image = convert(in_image)?.take()?;
+
+            // Set RGB to black. Keep alpha as is.
+            if let Ok(ref mut data) = image.get_data() {
+                for p in data.chunks_mut(4) {
+                    p[0] = 0;
+                    p[1] = 0;
+                    p[2] = 0;
+                }
+            } else {
+                warn!("Cairo surface is already borrowed.");
+            }
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
         match input {
             usvg::FilterInput::SourceGraphic => {
-                let image = copy_image(canvas, region)?;
-
-                Ok(Image {
-                    image: Rc::new(image),
-                    region: region.translate_to(0, 0),
-                    color_space: ColorSpace::SRGB,
-                })
+                convert(Some(&*inputs.source))
             }
             usvg::FilterInput::SourceAlpha => {
-                let mut image = copy_image(canvas, region)?;
-
-                // Set RGB to black. Keep alpha as is.
-                if let Ok(ref mut data) = image.get_data() {
-                    for p in data.chunks_mut(4) {
-                        p[0] = 0;
-                        p[1] = 0;
-                        p[2] = 0;
-                    }
-                } else {
-                    warn!("Cairo surface is already borrowed.");
-                }
-
-                Ok(Image {
-                    image: Rc::new(image),
-                    region: region.translate_to(0, 0),
-                    color_space: ColorSpace::SRGB,
-                })
+                convert_alpha(Some(&*inputs.source))
+            }
+            usvg::FilterInput::BackgroundImage => {
+                convert(inputs.background)
+            }
+            usvg::FilterInput::BackgroundAlpha => {
+                convert_alpha(inputs.background)
+            }
+            usvg::FilterInput::FillPaint => {
+                convert(inputs.fill_paint)
+            }
+            usvg::FilterInput::StrokePaint => {
+                convert(inputs.stroke_paint)
             }
             usvg::FilterInput::Reference(ref name) => {
                 if let Some(ref v) = results.iter().rev().find(|v| v.name == *name) {
@@ -205,13 +232,9 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
                 } else {
                     // Technically unreachable.
                     warn!("Unknown filter primitive reference '{}'.", name);
-                    Self::get_input(&usvg::FilterInput::SourceGraphic, region, results, canvas)
+                    Self::get_input(&usvg::FilterInput::SourceGraphic, region, inputs, results)
                 }
             }
-            _ => {
-                warn!("Filter input '{}' is not supported.", input.to_string());
-                Self::get_input(&usvg::FilterInput::SourceGraphic, region, results, canvas)
-            }
         }
     }
 
@@ -360,18 +383,145 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
         Ok(Image::from_image(buffer, cs))
     }
 
+    fn apply_color_matrix(
+        fe: &usvg::FeColorMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            from_premultiplied(data);
+            filter::color_matrix::apply(&fe.kind, data.as_bgra_mut());
+            into_premultiplied(data);
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_component_transfer(
+        fe: &usvg::FeComponentTransfer,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            from_premultiplied(data);
+            filter::component_transfer::apply(fe, data.as_bgra_mut());
+            into_premultiplied(data);
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_convolve_matrix(
+        fe: &usvg::FeConvolveMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            filter::convolve_matrix::apply(fe, w, h, data.as_bgra_mut());
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_diffuse_lighting(
+        fe: &usvg::FeDiffuseLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            filter::lighting::diffuse_lighting(fe, light_source, kernel_unit_length, cs, w, h, data.as_bgra_mut());
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_specular_lighting(
+        fe: &usvg::FeSpecularLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            filter::lighting::specular_lighting(fe, light_source, kernel_unit_length, cs, w, h, data.as_bgra_mut());
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_displacement_map(
+        fe: &usvg::FeDisplacementMap,
+        region: ScreenRect,
+        cs: ColorSpace,
+        sx: f64,
+        sy: f64,
+        input1: Image,
+        input2: Image,
+    ) -> Result<Image, Error> {
+        // 'The color-interpolation-filters property only applies to the `in2` source image.'
+        let cs1 = input1.color_space;
+        let mut pixmap1 = input1.take()?;
+        let mut pixmap2 = input2.into_color_space(cs)?.take()?;
+
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        {
+            let data1 = pixmap1.get_data().unwrap();
+            let mut data2 = pixmap2.get_data().unwrap();
+            from_premultiplied(&mut data2);
+
+            let mut data3 = buffer.get_data().unwrap();
+            filter::displacement_map::apply(
+                fe, sx, sy, data1.as_bgra(), data2.as_bgra(), data3.as_bgra_mut(), region.width(),
+            );
+        }
+
+        Ok(Image::from_image(buffer, cs1))
+    }
+
     fn apply_merge(
         fe: &usvg::FeMerge,
         cs: ColorSpace,
         region: ScreenRect,
+        inputs: &FilterInputs,
         results: &[FilterResult],
-        canvas: &cairo::ImageSurface,
     ) -> Result<Image, Error> {
         let buffer = create_image(region.width(), region.height())?;
         let cr = cairo::Context::new(&buffer);
 
         for input in &fe.inputs {
-            let input = Self::get_input(input, region, &results, canvas)?;
+            let input = Self::get_input(input, region, inputs, results)?;
             let input = input.into_color_space(cs)?;
 
             cr.set_source_surface(input.as_ref(), 0.0, 0.0);
@@ -381,6 +531,30 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
         Ok(Image::from_image(buffer, cs))
     }
 
+    fn apply_morphology(
+        fe: &usvg::FeMorphology,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let (rx, ry) = try_opt_or!(
+            Self::resolve_morphology_radius(fe, units, bbox, ts), Ok(input)
+        );
+
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            filter::morphology::apply(fe.operator, rx, ry, data, w, h);
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
     fn apply_flood(
         fe: &usvg::FeFlood,
         region: ScreenRect,
@@ -425,7 +599,9 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
         fe: &usvg::FeImage,
         region: ScreenRect,
         subregion: ScreenRect,
+        ts: &usvg::Transform,
         opt: &Options,
+        tree: &usvg::Tree,
     ) -> Result<Image, Error> {
         let buffer = create_image(region.width(), region.height())?;
 
@@ -449,12 +625,41 @@ impl Filter<cairo::ImageSurface> for CairoFilter {
                     super::image::draw_raster(data, view_box, fe.rendering_mode, opt, &cr);
                 }
             }
-            usvg::FeImageKind::Use(..) => {}
+            usvg::FeImageKind::Use(ref id) => {
+                if let Some(ref node) = tree.defs_by_id(id) {
+                    let mut layers = super::create_layers(region.size(), opt);
+
+                    let cr = cairo::Context::new(&buffer);
+
+                    let mut ts2 = usvg::Transform::new_translate(-region.x() as f64, -region.y() as f64);
+                    ts2.append(ts);
+                    cr.set_matrix(ts2.to_native());
+
+                    super::render_group(node, opt, &mut RenderState::Ok, &mut layers, &cr);
+                }
+            }
         }
 
         Ok(Image::from_image(buffer, ColorSpace::SRGB))
     }
 
+    fn apply_turbulence(
+        fe: &usvg::FeTurbulence,
+        cs: ColorSpace,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        if let Ok(ref mut data) = buffer.get_data() {
+            filter::turbulence::apply(fe, region, subregion, ts, data.as_bgra_mut());
+            into_premultiplied(data);
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
     fn apply_to_canvas(
         input: Image,
         region: ScreenRect,
@@ -14,9 +14,13 @@ use log::warn;
 // self
 use crate::prelude::*;
 use crate::layers;
+use crate::RenderError;
 use crate::backend_utils::{
+    self,
     ConvTransform,
+    RenderState,
 };
+use crate::backend_utils::filter::ImageExt;
 
 
 macro_rules! try_create_surface {
@@ -85,18 +89,18 @@ impl Render for Backend {
         &self,
         tree: &usvg::Tree,
         opt: &Options,
-    ) -> Option<Box<OutputImage>> {
+    ) -> Result<Box<OutputImage>, RenderError> {
         let img = render_to_image(tree, opt)?;
-        Some(Box::new(img))
+        Ok(Box::new(img))
     }
 
     fn render_node_to_image(
         &self,
         node: &usvg::Node,
         opt: &Options,
-    ) -> Option<Box<OutputImage>> {
+    ) -> Result<Box<OutputImage>, RenderError> {
         let img = render_node_to_image(node, opt)?;
-        Some(Box::new(img))
+        Ok(Box::new(img))
     }
 
     fn calc_node_bbox(
@@ -130,7 +134,7 @@ impl OutputImage for cairo::ImageSurface {
 pub fn render_to_image(
     tree: &usvg::Tree,
     opt: &Options,
-) -> Option<cairo::ImageSurface> {
+) -> Result<cairo::ImageSurface, RenderError> {
     let (surface, img_view) = create_surface(
         tree.svg_node().size.to_screen_size(),
         opt,
@@ -144,21 +148,21 @@ pub fn render_to_image(
         cr.paint();
     }
 
-    render_to_canvas(tree, opt, img_view, &cr);
+    backend_utils::check_render_limits(|| render_to_canvas(tree, opt, img_view, &cr))?;
 
-    Some(surface)
+    Ok(surface)
 }
 
 /// Renders SVG to image.
 pub fn render_node_to_image(
     node: &usvg::Node,
     opt: &Options,
-) -> Option<cairo::ImageSurface> {
+) -> Result<cairo::ImageSurface, RenderError> {
     let node_bbox = if let Some(bbox) = calc_node_bbox(node, opt) {
         bbox
     } else {
         warn!("Node '{}' has a zero size.", node.id());
-        return None;
+        return Err(RenderError::InvalidSize);
     };
 
     let (surface, img_size) = create_surface(node_bbox.to_screen_size(), opt)?;
@@ -176,9 +180,9 @@ pub fn render_node_to_image(
         cr.paint();
     }
 
-    render_node_to_canvas(node, opt, vbox, img_size, &cr);
+    backend_utils::check_render_limits(|| render_node_to_canvas(node, opt, vbox, img_size, &cr))?;
 
-    Some(surface)
+    Ok(surface)
 }
 
 /// Renders SVG to canvas.
@@ -208,19 +212,18 @@ pub fn render_node_to_canvas(
     ts.append(&node.transform());
 
     cr.transform(ts.to_native());
-    render_node(node, opt, &mut layers, cr);
+    render_node(node, opt, &mut RenderState::Ok, &mut layers, cr);
     cr.set_matrix(curr_ts);
 }
 
 fn create_surface(
     size: ScreenSize,
     opt: &Options,
-) -> Option<(cairo::ImageSurface, ScreenSize)> {
-    let img_size = utils::fit_to(size, opt.fit_to)?;
+) -> Result<(cairo::ImageSurface, ScreenSize), RenderError> {
+    let img_size = backend_utils::prepare_image_size(size, opt)?;
+    let surface = try_create_surface!(img_size, Err(RenderError::ImageAllocationFailed));
 
-    let surface = try_create_surface!(img_size, None);
-
-    Some((surface, img_size))
+    Ok((surface, img_size))
 }
 
 /// Applies viewbox transformation to the painter.
@@ -236,12 +239,13 @@ fn apply_viewbox_transform(
 fn render_node(
     node: &usvg::Node,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut CairoLayers,
     cr: &cairo::Context,
 ) -> Option<Rect> {
     match *node.borrow() {
         usvg::NodeKind::Svg(_) => {
-            Some(render_group(node, opt, layers, cr))
+            Some(render_group(node, opt, state, layers, cr))
         }
         usvg::NodeKind::Path(ref path) => {
             path::draw(&node.tree(), path, opt, cr)
@@ -250,7 +254,7 @@ fn render_node(
             Some(image::draw(img, opt, cr))
         }
         usvg::NodeKind::Group(ref g) => {
-            render_group_impl(node, g, opt, layers, cr)
+            render_group_impl(node, g, opt, state, layers, cr)
         }
         _ => None,
     }
@@ -259,6 +263,7 @@ fn render_node(
 fn render_group(
     parent: &usvg::Node,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut CairoLayers,
     cr: &cairo::Context,
 ) -> Rect {
@@ -266,9 +271,21 @@ fn render_group(
     let mut g_bbox = Rect::new_bbox();
 
     for node in parent.children() {
+        match *state {
+            RenderState::Ok => {}
+            RenderState::RenderUntil(ref last) => {
+                if node == *last {
+                    // Stop rendering.
+                    *state = RenderState::BackgroundFinished;
+                    break;
+                }
+            }
+            RenderState::BackgroundFinished => break,
+        }
+
         cr.transform(node.transform().to_native());
 
-        let bbox = render_node(&node, opt, layers, cr);
+        let bbox = render_node(&node, opt, state, layers, cr);
 
         if let Some(bbox) = bbox {
             let bbox = bbox.transform(&node.transform()).unwrap();
@@ -286,6 +303,7 @@ fn render_group_impl(
     node: &usvg::Node,
     g: &usvg::Group,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut CairoLayers,
     cr: &cairo::Context,
 ) -> Option<Rect> {
@@ -298,14 +316,31 @@ fn render_group_impl(
         let sub_cr = cairo::Context::new(&*sub_surface);
         sub_cr.set_matrix(curr_ts);
 
-        render_group(node, opt, layers, &sub_cr)
+        render_group(node, opt, state, layers, &sub_cr)
     };
 
-    if let Some(ref id) = g.filter {
+    for id in &g.filter {
         if let Some(filter_node) = node.tree().defs_by_id(id) {
             if let usvg::NodeKind::Filter(ref filter) = *filter_node.borrow() {
                 let ts = usvg::Transform::from_native(&curr_ts);
-                filter::apply(filter, bbox, &ts, opt, &mut *sub_surface);
+                let img_size = layers.image_size();
+
+                let background = prepare_filter_background(node, filter, &ts, opt, img_size);
+                let fill_paint = prepare_filter_paint(
+                    node, filter, bbox, &ts, opt, &g.filter_fill, img_size,
+                );
+                let stroke_paint = prepare_filter_paint(
+                    node, filter, bbox, &ts, opt, &g.filter_stroke, img_size,
+                );
+
+                let mut inputs = filter::FilterInputs {
+                    source: &mut *sub_surface,
+                    background: background.as_ref(),
+                    fill_paint: fill_paint.as_ref(),
+                    stroke_paint: stroke_paint.as_ref(),
+                };
+
+                filter::apply(filter, bbox, &ts, opt, &node.tree(), &mut inputs);
             }
         }
     }
@@ -335,12 +370,14 @@ fn render_group_impl(
     let curr_matrix = cr.get_matrix();
     cr.set_matrix(cairo::Matrix::identity());
     cr.set_source_surface(&*sub_surface, 0.0, 0.0);
+    cr.set_operator(convert_blend_mode(g.blend_mode));
     if !g.opacity.is_default() {
         cr.paint_with_alpha(g.opacity.value());
     } else {
         cr.paint();
     }
 
+    cr.set_operator(cairo::Operator::Over);
     cr.set_matrix(curr_matrix);
 
     // All layers must be unlinked from the main context/cr after used.
@@ -350,6 +387,76 @@ fn render_group_impl(
     Some(bbox)
 }
 
+fn convert_blend_mode(
+    mode: usvg::BlendMode,
+) -> cairo::Operator {
+    match mode {
+        usvg::BlendMode::Normal => cairo::Operator::Over,
+        usvg::BlendMode::Multiply => cairo::Operator::Multiply,
+        usvg::BlendMode::Screen => cairo::Operator::Screen,
+        usvg::BlendMode::Overlay => cairo::Operator::Overlay,
+        usvg::BlendMode::Darken => cairo::Operator::Darken,
+        usvg::BlendMode::Lighten => cairo::Operator::Lighten,
+        usvg::BlendMode::ColorDodge => cairo::Operator::ColorDodge,
+        usvg::BlendMode::ColorBurn => cairo::Operator::ColorBurn,
+        usvg::BlendMode::HardLight => cairo::Operator::HardLight,
+        usvg::BlendMode::SoftLight => cairo::Operator::SoftLight,
+        usvg::BlendMode::Difference => cairo::Operator::Difference,
+        usvg::BlendMode::Exclusion => cairo::Operator::Exclusion,
+        usvg::BlendMode::Hue => cairo::Operator::HslHue,
+        usvg::BlendMode::Saturation => cairo::Operator::HslSaturation,
+        usvg::BlendMode::Color => cairo::Operator::HslColor,
+        usvg::BlendMode::Luminosity => cairo::Operator::HslLuminosity,
+    }
+}
+
+/// Renders an image used by the `BackgroundImage` and `BackgroundAlpha` filter inputs.
+fn prepare_filter_background(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    ts: &usvg::Transform,
+    opt: &Options,
+    img_size: ScreenSize,
+) -> Option<cairo::ImageSurface> {
+    let task = backend_utils::filter::prepare_background(node, filter, ts)?;
+
+    let mut surface = try_create_surface!(img_size, None);
+
+    {
+        let cr = cairo::Context::new(&surface);
+        cr.set_matrix(task.ts.to_native());
+
+        let mut layers = create_layers(img_size, opt);
+        let mut state = RenderState::RenderUntil(node.clone());
+        render_group(&task.start_node, opt, &mut state, &mut layers, &cr);
+    }
+
+    if let Some(rect) = task.clip {
+        surface.clip(rect);
+    }
+
+    Some(surface)
+}
+
+/// Renders an image used by the `FillPaint` and `StrokePaint` filter inputs.
+fn prepare_filter_paint(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    opt: &Options,
+    paint: &Option<usvg::Paint>,
+    img_size: ScreenSize,
+) -> Option<cairo::ImageSurface> {
+    let path = backend_utils::filter::prepare_paint_path(filter, bbox, ts, paint, img_size)?;
+
+    let surface = try_create_surface!(img_size, None);
+    let cr = cairo::Context::new(&surface);
+    path::draw(&node.tree(), &path, opt, &cr);
+
+    Some(surface)
+}
+
 /// Calculates node's absolute bounding box.
 ///
 /// Note: this method can be pretty expensive.
@@ -386,7 +493,7 @@ fn _calc_node_bbox(
 
     match *node.borrow() {
         usvg::NodeKind::Path(ref path) => {
-            utils::path_bbox(&path.segments, path.stroke.as_ref(), Some(ts2))
+            backend_utils::calc_path_bbox(path, ts2, &node.tree(), opt)
         }
         usvg::NodeKind::Image(ref img) => {
             let segments = utils::rect_to_path(img.view_box.rect);
@@ -39,14 +39,28 @@ pub fn draw(
         cr.set_antialias(cairo::Antialias::None);
     }
 
-    style::fill(tree, &path.fill, opt, style_bbox, cr);
-    if path.stroke.is_some() {
-        cr.fill_preserve();
+    match path.paint_order {
+        usvg::PaintOrder::FillAndStroke => {
+            style::fill(tree, &path.fill, opt, style_bbox, cr);
+            if let Some(ref stroke) = path.stroke {
+                cr.fill_preserve();
+
+                style::stroke(tree, &path.stroke, opt, style_bbox, cr);
+                draw_stroke(stroke, cr);
+                cr.new_path();
+            } else {
+                cr.fill();
+            }
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            if let Some(ref stroke) = path.stroke {
+                style::stroke(tree, &path.stroke, opt, style_bbox, cr);
+                draw_stroke(stroke, cr);
+            }
 
-        style::stroke(tree, &path.stroke, opt, style_bbox, cr);
-        cr.stroke();
-    } else {
-        cr.fill();
+            style::fill(tree, &path.fill, opt, style_bbox, cr);
+            cr.fill();
+        }
     }
 
     // Revert anti-aliasing.
@@ -55,6 +69,24 @@ pub fn draw(
     bbox
 }
 
+/// Strokes the current path, preserving it.
+fn draw_stroke(
+    stroke: &usvg::Stroke,
+    cr: &cairo::Context,
+) {
+    if stroke.non_scaling {
+        // The path is already in the device space, so we have to reset
+        // the transform to make the stroke width and dashes independent of it.
+        // The paint is locked to the user space when it was set.
+        cr.save();
+        cr.identity_matrix();
+        cr.stroke_preserve();
+        cr.restore();
+    } else {
+        cr.stroke_preserve();
+    }
+}
+
 fn draw_path(
     segments: &[usvg::PathSegment],
     is_square_cap: bool,
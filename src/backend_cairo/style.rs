@@ -219,7 +219,7 @@ fn prepare_pattern(
     }
 
     let mut layers = super::create_layers(img_size, opt);
-    super::render_group(node, opt, &mut layers, &sub_cr);
+    super::render_group(node, opt, &mut RenderState::Ok, &mut layers, &sub_cr);
 
     let mut ts = usvg::Transform::default();
     ts.append(&pattern.transform);
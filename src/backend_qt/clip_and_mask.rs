@@ -146,7 +146,7 @@ pub fn mask(
             mask_p.apply_transform(&usvg::Transform::from_bbox(bbox).to_native());
         }
 
-        super::render_group(node, opt, layers, &mut mask_p);
+        super::render_group(node, opt, &mut RenderState::Ok, layers, &mut mask_p);
     }
 
     image_to_mask(&mut mask_img.data_mut(), layers.image_size());
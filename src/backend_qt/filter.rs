@@ -25,6 +25,7 @@ use crate::backend_utils::filter::{
 
 type Image = filter::Image<qt::Image>;
 type FilterResult = filter::FilterResult<qt::Image>;
+pub type FilterInputs<'a> = filter::FilterInputs<'a, qt::Image>;
 
 
 pub fn apply(
@@ -32,9 +33,10 @@ pub fn apply(
     bbox: Rect,
     ts: &usvg::Transform,
     opt: &Options,
-    canvas: &mut qt::Image,
+    tree: &usvg::Tree,
+    inputs: &mut FilterInputs,
 ) {
-    QtFilter::apply(filter, bbox, ts, opt, canvas);
+    QtFilter::apply(filter, bbox, ts, opt, tree, inputs);
 }
 
 
@@ -99,42 +101,88 @@ fn copy_image(image: &qt::Image, region: ScreenRect) -> Result<qt::Image, Error>
     image.copy(x, y, region.width(), region.height()).ok_or(Error::AllocFailed)
 }
 
+fn into_premultiplied(data: &mut [u8]) {
+    for p in data.as_rgba_mut() {
+        let a = p.a as f64 / 255.0;
+        p.b = (p.b as f64 * a + 0.5) as u8;
+        p.g = (p.g as f64 * a + 0.5) as u8;
+        p.r = (p.r as f64 * a + 0.5) as u8;
+    }
+}
+
+fn from_premultiplied(data: &mut [u8]) {
+    for p in data.as_rgba_mut() {
+        if p.a == 0 {
+            continue;
+        }
+
+        let a = p.a as f64 / 255.0;
+        p.b = (p.b as f64 / a + 0.5) as u8;
+        p.g = (p.g as f64 / a + 0.5) as u8;
+        p.r = (p.r as f64 / a + 0.5) as u8;
+    }
+}
+
 struct QtFilter;
 
 impl Filter<qt::Image> for QtFilter {
     fn get_input(
         input: &usvg::FilterInput,
         region: ScreenRect,
+        inputs: &FilterInputs,
         results: &[FilterResult],
-        canvas: &qt::Image,
     ) -> Result<Image, Error> {
+        let convert = |in_image: Option<&qt::Image>| -> Result<Image, Error> {
+            let image = if let Some(image) = in_image {
+                let image = copy_image(image, region)?;
+                image.to_rgba().ok_or(Error::AllocFailed)? // TODO: optional
+            } else {
+                // An unavailable input is a transparent black image.
+                create_image(region.width(), region.height())?
+            };
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
+        let convert_alpha = |in_image: Option<&qt::Image>| -> Result<Image, Error> {
+            let mut image = convert(in_image)?.take()?;
+
+            // Set RGB to black. Keep alpha as is.
+            for p in image.data_mut().chunks_mut(4) {
+                p[0] = 0;
+                p[1] = 0;
+                p[2] = 0;
+            }
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
         match input {
             usvg::FilterInput::SourceGraphic => {
-                let image = copy_image(canvas, region)?;
-                let image = image.to_rgba().ok_or(Error::AllocFailed)?; // TODO: optional
-
-                Ok(Image {
-                    image: Rc::new(image),
-                    region: region.translate_to(0, 0),
-                    color_space: ColorSpace::SRGB,
-                })
+                convert(Some(&*inputs.source))
             }
             usvg::FilterInput::SourceAlpha => {
-                let image = copy_image(canvas, region)?;
-                let mut image = image.to_rgba().ok_or(Error::AllocFailed)?;
-
-                // Set RGB to black. Keep alpha as is.
-                for p in image.data_mut().chunks_mut(4) {
-                    p[0] = 0;
-                    p[1] = 0;
-                    p[2] = 0;
-                }
-
-                Ok(Image {
-                    image: Rc::new(image),
-                    region: region.translate_to(0, 0),
-                    color_space: ColorSpace::SRGB,
-                })
+                convert_alpha(Some(&*inputs.source))
+            }
+            usvg::FilterInput::BackgroundImage => {
+                convert(inputs.background)
+            }
+            usvg::FilterInput::BackgroundAlpha => {
+                convert_alpha(inputs.background)
+            }
+            usvg::FilterInput::FillPaint => {
+                convert(inputs.fill_paint)
+            }
+            usvg::FilterInput::StrokePaint => {
+                convert(inputs.stroke_paint)
             }
             usvg::FilterInput::Reference(ref name) => {
                 if let Some(ref v) = results.iter().rev().find(|v| v.name == *name) {
@@ -142,13 +190,9 @@ impl Filter<qt::Image> for QtFilter {
                 } else {
                     // Technically unreachable.
                     warn!("Unknown filter primitive reference '{}'.", name);
-                    Self::get_input(&usvg::FilterInput::SourceGraphic, region, results, canvas)
+                    Self::get_input(&usvg::FilterInput::SourceGraphic, region, inputs, results)
                 }
             }
-            _ => {
-                warn!("Filter input '{}' is not supported.", input.to_string());
-                Self::get_input(&usvg::FilterInput::SourceGraphic, region, results, canvas)
-            }
         }
     }
 
@@ -305,18 +349,134 @@ impl Filter<qt::Image> for QtFilter {
         Ok(Image::from_image(buffer, cs))
     }
 
+    fn apply_color_matrix(
+        fe: &usvg::FeColorMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        filter::color_matrix::apply(&fe.kind, buffer.data_mut().as_rgba_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_component_transfer(
+        fe: &usvg::FeComponentTransfer,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        filter::component_transfer::apply(fe, buffer.data_mut().as_rgba_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_convolve_matrix(
+        fe: &usvg::FeConvolveMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+
+        let mut data = buffer.data_mut();
+        into_premultiplied(&mut data);
+        filter::convolve_matrix::apply(fe, w, h, data.as_rgba_mut());
+        from_premultiplied(&mut data);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_diffuse_lighting(
+        fe: &usvg::FeDiffuseLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        let mut data = buffer.data_mut();
+        filter::lighting::diffuse_lighting(fe, light_source, kernel_unit_length, cs, w, h, data.as_rgba_mut());
+        from_premultiplied(&mut data);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_specular_lighting(
+        fe: &usvg::FeSpecularLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        let mut data = buffer.data_mut();
+        filter::lighting::specular_lighting(fe, light_source, kernel_unit_length, cs, w, h, data.as_rgba_mut());
+        from_premultiplied(&mut data);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_displacement_map(
+        fe: &usvg::FeDisplacementMap,
+        region: ScreenRect,
+        cs: ColorSpace,
+        sx: f64,
+        sy: f64,
+        input1: Image,
+        input2: Image,
+    ) -> Result<Image, Error> {
+        // 'The color-interpolation-filters property only applies to the `in2` source image.'
+        let cs1 = input1.color_space;
+        let input2 = input2.into_color_space(cs)?;
+
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        filter::displacement_map::apply(
+            fe, sx, sy,
+            input1.as_ref().data().as_rgba(),
+            input2.as_ref().data().as_rgba(),
+            buffer.data_mut().as_rgba_mut(),
+            region.width(),
+        );
+
+        Ok(Image::from_image(buffer, cs1))
+    }
+
     fn apply_merge(
         fe: &usvg::FeMerge,
         cs: ColorSpace,
         region: ScreenRect,
+        inputs: &FilterInputs,
         results: &[FilterResult],
-        canvas: &qt::Image,
     ) -> Result<Image, Error> {
         let mut buffer = create_image(region.width(), region.height())?;
         let mut p = qt::Painter::new(&mut buffer);
 
         for input in &fe.inputs {
-            let input = Self::get_input(input, region, &results, canvas)?;
+            let input = Self::get_input(input, region, inputs, results)?;
             let input = input.into_color_space(cs)?;
 
             p.draw_image(0.0, 0.0, input.as_ref());
@@ -325,6 +485,27 @@ impl Filter<qt::Image> for QtFilter {
         Ok(Image::from_image(buffer, cs))
     }
 
+    fn apply_morphology(
+        fe: &usvg::FeMorphology,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let (rx, ry) = try_opt_or!(
+            Self::resolve_morphology_radius(fe, units, bbox, ts), Ok(input)
+        );
+
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+        filter::morphology::apply(fe.operator, rx, ry, &mut buffer.data_mut(), w, h);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
     fn apply_flood(
         fe: &usvg::FeFlood,
         region: ScreenRect,
@@ -363,7 +544,9 @@ impl Filter<qt::Image> for QtFilter {
         fe: &usvg::FeImage,
         region: ScreenRect,
         subregion: ScreenRect,
+        ts: &usvg::Transform,
         opt: &Options,
+        tree: &usvg::Tree,
     ) -> Result<Image, Error> {
         let mut buffer = create_image(region.width(), region.height())?;
 
@@ -387,12 +570,36 @@ impl Filter<qt::Image> for QtFilter {
                     super::image::draw_raster(data, view_box, fe.rendering_mode, opt, &mut p);
                 }
             }
-            usvg::FeImageKind::Use(..) => {}
+            usvg::FeImageKind::Use(ref id) => {
+                if let Some(ref node) = tree.defs_by_id(id) {
+                    let mut layers = super::create_layers(region.size(), opt);
+
+                    let mut p = qt::Painter::new(&mut buffer);
+
+                    let mut ts2 = usvg::Transform::new_translate(-region.x() as f64, -region.y() as f64);
+                    ts2.append(ts);
+                    p.set_transform(&ts2.to_native());
+
+                    super::render_group(node, opt, &mut RenderState::Ok, &mut layers, &mut p);
+                }
+            }
         }
 
         Ok(Image::from_image(buffer, ColorSpace::SRGB))
     }
 
+    fn apply_turbulence(
+        fe: &usvg::FeTurbulence,
+        cs: ColorSpace,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+        filter::turbulence::apply(fe, region, subregion, ts, buffer.data_mut().as_rgba_mut());
+        Ok(Image::from_image(buffer, cs))
+    }
+
     fn apply_to_canvas(
         input: Image,
         region: ScreenRect,
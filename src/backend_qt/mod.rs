@@ -7,13 +7,18 @@
 // external
 use crate::qt;
 use log::warn;
+use rgb::FromSlice;
 
 // self
 use crate::prelude::*;
 use crate::layers;
+use crate::RenderError;
 use crate::backend_utils::{
+    self,
     ConvTransform,
+    RenderState,
 };
+use crate::backend_utils::filter::ImageExt;
 
 
 macro_rules! try_create_image {
@@ -58,18 +63,18 @@ impl Render for Backend {
         &self,
         tree: &usvg::Tree,
         opt: &Options,
-    ) -> Option<Box<OutputImage>> {
+    ) -> Result<Box<OutputImage>, RenderError> {
         let img = render_to_image(tree, opt)?;
-        Some(Box::new(img))
+        Ok(Box::new(img))
     }
 
     fn render_node_to_image(
         &self,
         node: &usvg::Node,
         opt: &Options,
-    ) -> Option<Box<OutputImage>> {
+    ) -> Result<Box<OutputImage>, RenderError> {
         let img = render_node_to_image(node, opt)?;
-        Some(Box::new(img))
+        Ok(Box::new(img))
     }
 
     fn calc_node_bbox(
@@ -94,26 +99,29 @@ impl OutputImage for qt::Image {
 pub fn render_to_image(
     tree: &usvg::Tree,
     opt: &Options,
-) -> Option<qt::Image> {
+) -> Result<qt::Image, RenderError> {
     let (mut img, img_size) = create_root_image(tree.svg_node().size.to_screen_size(), opt)?;
 
     let mut painter = qt::Painter::new(&mut img);
-    render_to_canvas(tree, opt, img_size, &mut painter);
+    let res = backend_utils::check_render_limits(|| {
+        render_to_canvas(tree, opt, img_size, &mut painter);
+    });
     painter.end();
+    res?;
 
-    Some(img)
+    Ok(img)
 }
 
 /// Renders SVG node to image.
 pub fn render_node_to_image(
     node: &usvg::Node,
     opt: &Options,
-) -> Option<qt::Image> {
+) -> Result<qt::Image, RenderError> {
     let node_bbox = if let Some(bbox) = calc_node_bbox(node, opt) {
         bbox
     } else {
         warn!("Node '{}' has zero size.", node.id());
-        return None;
+        return Err(RenderError::InvalidSize);
     };
 
     let vbox = usvg::ViewBox {
@@ -124,10 +132,13 @@ pub fn render_node_to_image(
     let (mut img, img_size) = create_root_image(node_bbox.size().to_screen_size(), opt)?;
 
     let mut painter = qt::Painter::new(&mut img);
-    render_node_to_canvas(node, opt, vbox, img_size, &mut painter);
+    let res = backend_utils::check_render_limits(|| {
+        render_node_to_canvas(node, opt, vbox, img_size, &mut painter);
+    });
     painter.end();
+    res?;
 
-    Some(img)
+    Ok(img)
 }
 
 /// Renders SVG to canvas.
@@ -158,17 +169,16 @@ pub fn render_node_to_canvas(
     ts.append(&node.transform());
 
     painter.apply_transform(&ts.to_native());
-    render_node(node, opt, &mut layers, painter);
+    render_node(node, opt, &mut RenderState::Ok, &mut layers, painter);
     painter.set_transform(&curr_ts);
 }
 
 fn create_root_image(
     size: ScreenSize,
     opt: &Options,
-) -> Option<(qt::Image, ScreenSize)> {
-    let img_size = utils::fit_to(size, opt.fit_to)?;
-
-    let mut img = try_create_image!(img_size, None);
+) -> Result<(qt::Image, ScreenSize), RenderError> {
+    let img_size = backend_utils::prepare_image_size(size, opt)?;
+    let mut img = try_create_image!(img_size, Err(RenderError::ImageAllocationFailed));
 
     // Fill background.
     if let Some(c) = opt.background {
@@ -178,7 +188,7 @@ fn create_root_image(
     }
     img.set_dpi(opt.usvg.dpi);
 
-    Some((img, img_size))
+    Ok((img, img_size))
 }
 
 /// Applies viewbox transformation to the painter.
@@ -194,12 +204,13 @@ fn apply_viewbox_transform(
 fn render_node(
     node: &usvg::Node,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut QtLayers,
     p: &mut qt::Painter,
 ) -> Option<Rect> {
     match *node.borrow() {
         usvg::NodeKind::Svg(_) => {
-            Some(render_group(node, opt, layers, p))
+            Some(render_group(node, opt, state, layers, p))
         }
         usvg::NodeKind::Path(ref path) => {
             path::draw(&node.tree(), path, opt, p)
@@ -208,7 +219,7 @@ fn render_node(
             Some(image::draw(img, opt, p))
         }
         usvg::NodeKind::Group(ref g) => {
-            render_group_impl(node, g, opt, layers, p)
+            render_group_impl(node, g, opt, state, layers, p)
         }
         _ => None,
     }
@@ -217,6 +228,7 @@ fn render_node(
 fn render_group(
     parent: &usvg::Node,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut QtLayers,
     p: &mut qt::Painter,
 ) -> Rect {
@@ -224,9 +236,21 @@ fn render_group(
     let mut g_bbox = Rect::new_bbox();
 
     for node in parent.children() {
+        match *state {
+            RenderState::Ok => {}
+            RenderState::RenderUntil(ref last) => {
+                if node == *last {
+                    // Stop rendering.
+                    *state = RenderState::BackgroundFinished;
+                    break;
+                }
+            }
+            RenderState::BackgroundFinished => break,
+        }
+
         p.apply_transform(&node.transform().to_native());
 
-        let bbox = render_node(&node, opt, layers, p);
+        let bbox = render_node(&node, opt, state, layers, p);
         if let Some(bbox) = bbox {
             if let Some(bbox) = bbox.transform(&node.transform()) {
                 g_bbox = g_bbox.expand(bbox);
@@ -244,6 +268,7 @@ fn render_group_impl(
     node: &usvg::Node,
     g: &usvg::Group,
     opt: &Options,
+    state: &mut RenderState,
     layers: &mut QtLayers,
     p: &mut qt::Painter,
 ) -> Option<Rect> {
@@ -256,14 +281,31 @@ fn render_group_impl(
         let mut sub_p = qt::Painter::new(&mut sub_img);
         sub_p.set_transform(&curr_ts);
 
-        render_group(node, opt, layers, &mut sub_p)
+        render_group(node, opt, state, layers, &mut sub_p)
     };
 
-    if let Some(ref id) = g.filter {
+    for id in &g.filter {
         if let Some(filter_node) = node.tree().defs_by_id(id) {
             if let usvg::NodeKind::Filter(ref filter) = *filter_node.borrow() {
                 let ts = usvg::Transform::from_native(&curr_ts);
-                filter::apply(filter, bbox, &ts, opt, &mut sub_img);
+                let img_size = layers.image_size();
+
+                let background = prepare_filter_background(node, filter, &ts, opt, img_size);
+                let fill_paint = prepare_filter_paint(
+                    node, filter, bbox, &ts, opt, &g.filter_fill, img_size,
+                );
+                let stroke_paint = prepare_filter_paint(
+                    node, filter, bbox, &ts, opt, &g.filter_stroke, img_size,
+                );
+
+                let mut inputs = filter::FilterInputs {
+                    source: &mut *sub_img,
+                    background: background.as_ref(),
+                    fill_paint: fill_paint.as_ref(),
+                    stroke_paint: stroke_paint.as_ref(),
+                };
+
+                filter::apply(filter, bbox, &ts, opt, &node.tree(), &mut inputs);
             }
         }
     }
@@ -290,21 +332,124 @@ fn render_group_impl(
         }
     }
 
+    // Qt doesn't support non-separable blending modes,
+    // so we have to blend them manually.
+    if !g.blend_mode.is_separable() {
+        // Manual blending bypasses the painter, so the clip should be applied beforehand.
+        p.clip_image(&mut *sub_img);
+
+        if let Some(mut data) = p.device_data() {
+            if data.len() == sub_img.data().len() {
+                backend_utils::blend::blend_image(
+                    g.blend_mode,
+                    sub_img.data().as_bgra(),
+                    data.as_bgra_mut(),
+                    None,
+                    g.opacity.value(),
+                );
+
+                return Some(bbox);
+            }
+        }
+
+        let message = format!("'{}' blending mode is not supported on this device. \
+                               Fallback to 'normal'.", g.blend_mode.to_string());
+        warn!("{}", message);
+        usvg::report_diagnostic(
+            usvg::DiagnosticCode::UnsupportedBlendMode,
+            usvg::Severity::Warning,
+            &g.id,
+            message,
+        );
+    }
+
     if !g.opacity.is_default() {
         p.set_opacity(g.opacity.value());
     }
 
     let curr_ts = p.get_transform();
     p.set_transform(&qt::Transform::default());
+    p.set_composition_mode(convert_blend_mode(g.blend_mode));
 
     p.draw_image(0.0, 0.0, &sub_img);
 
+    p.set_composition_mode(qt::CompositionMode::SourceOver);
     p.set_opacity(1.0);
     p.set_transform(&curr_ts);
 
     Some(bbox)
 }
 
+fn convert_blend_mode(
+    mode: usvg::BlendMode,
+) -> qt::CompositionMode {
+    match mode {
+        usvg::BlendMode::Multiply => qt::CompositionMode::Multiply,
+        usvg::BlendMode::Screen => qt::CompositionMode::Screen,
+        usvg::BlendMode::Overlay => qt::CompositionMode::Overlay,
+        usvg::BlendMode::Darken => qt::CompositionMode::Darken,
+        usvg::BlendMode::Lighten => qt::CompositionMode::Lighten,
+        usvg::BlendMode::ColorDodge => qt::CompositionMode::ColorDodge,
+        usvg::BlendMode::ColorBurn => qt::CompositionMode::ColorBurn,
+        usvg::BlendMode::HardLight => qt::CompositionMode::HardLight,
+        usvg::BlendMode::SoftLight => qt::CompositionMode::SoftLight,
+        usvg::BlendMode::Difference => qt::CompositionMode::Difference,
+        usvg::BlendMode::Exclusion => qt::CompositionMode::Exclusion,
+        // Non-separable modes are handled separately.
+        _ => qt::CompositionMode::SourceOver,
+    }
+}
+
+/// Renders an image used by the `BackgroundImage` and `BackgroundAlpha` filter inputs.
+fn prepare_filter_background(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    ts: &usvg::Transform,
+    opt: &Options,
+    img_size: ScreenSize,
+) -> Option<qt::Image> {
+    let task = backend_utils::filter::prepare_background(node, filter, ts)?;
+
+    let mut img = create_subimage(img_size, opt.usvg.dpi)?;
+
+    {
+        let mut p = qt::Painter::new(&mut img);
+        p.set_transform(&task.ts.to_native());
+
+        let mut layers = create_layers(img_size, opt);
+        let mut state = RenderState::RenderUntil(node.clone());
+        render_group(&task.start_node, opt, &mut state, &mut layers, &mut p);
+    }
+
+    if let Some(rect) = task.clip {
+        img.clip(rect);
+    }
+
+    Some(img)
+}
+
+/// Renders an image used by the `FillPaint` and `StrokePaint` filter inputs.
+fn prepare_filter_paint(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    opt: &Options,
+    paint: &Option<usvg::Paint>,
+    img_size: ScreenSize,
+) -> Option<qt::Image> {
+    let path = backend_utils::filter::prepare_paint_path(filter, bbox, ts, paint, img_size)?;
+
+    let mut img = create_subimage(img_size, opt.usvg.dpi)?;
+
+    {
+        let mut p = qt::Painter::new(&mut img);
+        path::draw(&node.tree(), &path, opt, &mut p);
+    }
+
+    Some(img)
+}
+
 /// Calculates node's absolute bounding box.
 ///
 /// Note: this method can be pretty expensive.
@@ -333,7 +478,7 @@ fn _calc_node_bbox(
 
     match *node.borrow() {
         usvg::NodeKind::Path(ref path) => {
-            utils::path_bbox(&path.segments, path.stroke.as_ref(), Some(ts2))
+            backend_utils::calc_path_bbox(path, ts2, &node.tree(), opt)
         }
         usvg::NodeKind::Image(ref img) => {
             let segments = utils::rect_to_path(img.view_box.rect);
@@ -376,3 +521,38 @@ fn create_subimage(
 fn clear_image(img: &mut qt::Image) {
     img.fill(0, 0, 0, 0);
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipped_non_separable_blend() {
+        // The mask region is applied as a painter clip,
+        // so the luminosity blending should not affect the right half.
+        let text = "\
+<svg xmlns='http://www.w3.org/2000/svg' width='20' height='20'>
+    <mask id='mask1' maskUnits='userSpaceOnUse' x='0' y='0' width='10' height='20'>
+        <g mix-blend-mode='luminosity'>
+            <rect width='20' height='20' fill='white'/>
+        </g>
+    </mask>
+    <rect width='20' height='20' fill='#00ff00' mask='url(#mask1)'/>
+</svg>";
+
+        let opt = Options::default();
+        let tree = usvg::Tree::from_str(text, &opt.usvg).unwrap();
+        let img = render_to_image(&tree, &opt).unwrap();
+
+        let data = img.data();
+        let pixels = data.as_bgra();
+        let pixel = |x: usize, y: usize| {
+            let p = pixels[y * img.width() as usize + x];
+            (p.r, p.g, p.b, p.a)
+        };
+
+        assert_eq!(pixel(5, 10), (0, 255, 0, 255));
+        assert_eq!(pixel(15, 10), (0, 0, 0, 0));
+    }
+}
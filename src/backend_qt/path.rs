@@ -38,11 +38,29 @@ pub fn draw(
         return bbox;
     }
 
-    style::fill(tree, &path.fill, opt, style_bbox, p);
-    style::stroke(tree, &path.stroke, opt, style_bbox, p);
     p.set_antialiasing(use_shape_antialiasing(path.rendering_mode));
 
-    p.draw_path(&p_path);
+    match path.paint_order {
+        usvg::PaintOrder::FillAndStroke => {
+            style::fill(tree, &path.fill, opt, style_bbox, p);
+            style::stroke(tree, &path.stroke, opt, style_bbox, p);
+            p.draw_path(&p_path);
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            // Qt always draws a fill first, so we have to draw a path twice.
+            if path.stroke.is_some() {
+                style::fill(tree, &None, opt, style_bbox, p);
+                style::stroke(tree, &path.stroke, opt, style_bbox, p);
+                p.draw_path(&p_path);
+            }
+
+            if path.fill.is_some() {
+                style::fill(tree, &path.fill, opt, style_bbox, p);
+                style::stroke(tree, &None, opt, style_bbox, p);
+                p.draw_path(&p_path);
+            }
+        }
+    }
 
     // Revert anti-aliasing.
     p.set_antialiasing(true);
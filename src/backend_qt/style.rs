@@ -112,6 +112,9 @@ pub fn stroke(
             pen.set_miter_limit(stroke.miterlimit.value());
             pen.set_width(stroke.width.value());
 
+            // A cosmetic pen width is set in the device space.
+            pen.set_cosmetic(stroke.non_scaling);
+
             if let Some(ref list) = stroke.dasharray {
                 pen.set_dash_offset(stroke.dashoffset as f64);
                 pen.set_dash_array(list);
@@ -231,7 +234,7 @@ fn prepare_pattern(
     }
 
     let mut layers = super::create_layers(img_size, opt);
-    super::render_group(pattern_node, opt, &mut layers, &mut p);
+    super::render_group(pattern_node, opt, &mut RenderState::Ok, &mut layers, &mut p);
     p.end();
 
     let img = if !opacity.is_default() {
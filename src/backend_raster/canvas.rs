@@ -0,0 +1,283 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// self
+use crate::ScreenSize;
+use crate::backend_utils::blend;
+use super::pixmap::{
+    self,
+    BlendMode,
+    Pixmap,
+};
+use super::rasterizer::{
+    self,
+    Edge,
+    FillRule,
+};
+use super::shader::Shader;
+use super::stroker::{
+    self,
+    Point,
+    StrokeProps,
+};
+
+
+/// Maximum distance in pixels between a curve and its flattened version.
+const TOLERANCE: f64 = 0.1;
+
+
+/// A drawing context.
+///
+/// Like cairo's context, it holds a current transform, a clip
+/// and a compositing operator, which are applied to all drawing operations.
+pub struct Canvas<'a> {
+    pixmap: &'a mut Pixmap,
+    ts: usvg::Transform,
+    clip: Option<Vec<u8>>,
+    blend_mode: BlendMode,
+    anti_alias: bool,
+    stack: Vec<(usvg::Transform, Option<Vec<u8>>)>,
+}
+
+impl<'a> Canvas<'a> {
+    /// Creates a new canvas for the pixmap.
+    pub fn new(pixmap: &'a mut Pixmap) -> Self {
+        Canvas {
+            pixmap,
+            ts: usvg::Transform::default(),
+            clip: None,
+            blend_mode: BlendMode::SourceOver,
+            anti_alias: true,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying pixmap.
+    pub fn pixmap(&mut self) -> &mut Pixmap {
+        self.pixmap
+    }
+
+    pub(crate) fn pixmap_size(&self) -> ScreenSize {
+        // Unwrap is safe, because `Pixmap` cannot have a zero size.
+        ScreenSize::new(self.pixmap.width(), self.pixmap.height()).unwrap()
+    }
+
+    /// Saves the current transform and clip.
+    pub fn save(&mut self) {
+        self.stack.push((self.ts, self.clip.clone()));
+    }
+
+    /// Restores the previously saved transform and clip.
+    pub fn restore(&mut self) {
+        if let Some((ts, clip)) = self.stack.pop() {
+            self.ts = ts;
+            self.clip = clip;
+        }
+    }
+
+    /// Returns the current transform.
+    pub fn get_transform(&self) -> usvg::Transform {
+        self.ts
+    }
+
+    /// Sets the current transform.
+    pub fn set_transform(&mut self, ts: usvg::Transform) {
+        self.ts = ts;
+    }
+
+    /// Appends a transform to the current one.
+    pub fn transform(&mut self, ts: usvg::Transform) {
+        self.ts.append(&ts);
+    }
+
+    /// Resets the current transform.
+    pub fn reset_transform(&mut self) {
+        self.ts = usvg::Transform::default();
+    }
+
+    /// Sets a compositing operator.
+    pub(crate) fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    /// Enables or disables shapes anti-aliasing.
+    pub(crate) fn set_anti_alias(&mut self, flag: bool) {
+        self.anti_alias = flag;
+    }
+
+    /// Intersects the current clip with a rectangle in the current coordinates.
+    pub(crate) fn set_clip_rect(&mut self, r: usvg::Rect) {
+        let segments = usvg::utils::rect_to_path(r);
+        let polylines = stroker::flatten(&segments, &self.ts, TOLERANCE);
+        let edges = polylines_to_edges(&polylines, None);
+
+        let (w, h) = (self.pixmap.width(), self.pixmap.height());
+        let mut mask = vec![0u8; (w * h) as usize];
+        let aa = self.anti_alias;
+        if let Some(coverage) = rasterizer::rasterize(&edges, FillRule::NonZero, aa, w, h) {
+            for y in 0..coverage.height {
+                for x in 0..coverage.width {
+                    let idx = ((coverage.y + y) * w + coverage.x + x) as usize;
+                    mask[idx] = coverage.data[(y * coverage.width + x) as usize];
+                }
+            }
+        }
+
+        if let Some(ref prev) = self.clip {
+            for (m, p) in mask.iter_mut().zip(prev) {
+                *m = pixmap::div255(*m as u32 * *p as u32) as u8;
+            }
+        }
+
+        self.clip = Some(mask);
+    }
+
+    /// Fills the path using the current transform.
+    pub(crate) fn fill_path(
+        &mut self,
+        segments: &[usvg::PathSegment],
+        shader: &Shader,
+        rule: FillRule,
+    ) {
+        let polylines = stroker::flatten(segments, &self.ts, TOLERANCE);
+        let edges = polylines_to_edges(&polylines, None);
+        self.fill_edges(&edges, shader, rule);
+    }
+
+    /// Strokes the path using the current transform.
+    pub(crate) fn stroke_path(
+        &mut self,
+        segments: &[usvg::PathSegment],
+        shader: &Shader,
+        props: &StrokeProps,
+    ) {
+        // Stroking is done in user space, because a non-uniform
+        // transform affects the stroke outline.
+        let (sx, sy) = self.ts.get_scale();
+        let scale = sx.max(sy);
+        if !(scale > 0.0) || !scale.is_finite() {
+            return;
+        }
+
+        let tolerance = TOLERANCE / scale;
+        let polylines = stroker::flatten(segments, &usvg::Transform::default(), tolerance);
+        let polygons = stroker::stroke(&polylines, props, tolerance);
+
+        let mut edges = Vec::new();
+        for polygon in &polygons {
+            add_polygon_edges(polygon, Some(&self.ts), &mut edges);
+        }
+
+        self.fill_edges(&edges, shader, FillRule::NonZero);
+    }
+
+    /// Fills the whole canvas, respecting the clip.
+    pub(crate) fn paint(&mut self, shader: &Shader) {
+        let (w, h) = (self.pixmap.width(), self.pixmap.height());
+        let coverage = rasterizer::Coverage {
+            x: 0,
+            y: 0,
+            width: w,
+            height: h,
+            data: vec![255; (w * h) as usize],
+        };
+
+        self.blend_coverage(&coverage, shader);
+    }
+
+    /// Draws a pixmap at the specified position.
+    ///
+    /// Ignores the current transform.
+    pub(crate) fn draw_pixmap(&mut self, x: i32, y: i32, src: &Pixmap, opacity: f64) {
+        let mode = self.blend_mode;
+        self.pixmap.draw_pixmap(x, y, src, opacity, mode, self.clip.as_ref().map(|v| v.as_slice()));
+    }
+
+    /// Blends a pixmap of the same size using a `mix-blend-mode`.
+    ///
+    /// Ignores the current transform.
+    pub(crate) fn blend_pixmap(&mut self, src: &Pixmap, mode: usvg::BlendMode, opacity: f64) {
+        use rgb::FromSlice;
+
+        let clip = self.clip.as_ref().map(|v| v.as_slice());
+        blend::blend_image(mode, src.data().as_bgra(), self.pixmap.data_mut().as_bgra_mut(),
+                           clip, opacity);
+    }
+
+    fn fill_edges(&mut self, edges: &[Edge], shader: &Shader, rule: FillRule) {
+        if self.blend_mode == BlendMode::SourceOver && shader.is_transparent() {
+            return;
+        }
+
+        let (w, h) = (self.pixmap.width(), self.pixmap.height());
+        if let Some(coverage) = rasterizer::rasterize(edges, rule, self.anti_alias, w, h) {
+            self.blend_coverage(&coverage, shader);
+        }
+    }
+
+    fn blend_coverage(&mut self, coverage: &rasterizer::Coverage, shader: &Shader) {
+        use rgb::FromSlice;
+
+        let mode = self.blend_mode;
+        let w = self.pixmap.width();
+        let clip = self.clip.as_ref();
+        let data = self.pixmap.data_mut().as_bgra_mut();
+        for y in 0..coverage.height {
+            let py = coverage.y + y;
+            for x in 0..coverage.width {
+                let px = coverage.x + x;
+                let idx = (py * w + px) as usize;
+
+                let mut c = coverage.data[(y * coverage.width + x) as usize] as u32;
+                if let Some(clip) = clip {
+                    c = pixmap::div255(c * clip[idx] as u32);
+                }
+
+                if c == 0 {
+                    continue;
+                }
+
+                data[idx] = pixmap::blend_pixel(mode, shader.shade(px, py), data[idx], c);
+            }
+        }
+    }
+}
+
+
+fn polylines_to_edges(
+    polylines: &[stroker::Polyline],
+    ts: Option<&usvg::Transform>,
+) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for polyline in polylines {
+        // Fill closes all subpaths implicitly.
+        add_polygon_edges(&polyline.points, ts, &mut edges);
+    }
+
+    edges
+}
+
+fn add_polygon_edges(
+    points: &[Point],
+    ts: Option<&usvg::Transform>,
+    edges: &mut Vec<Edge>,
+) {
+    if points.len() < 2 {
+        return;
+    }
+
+    let apply = |p: Point| {
+        match ts {
+            Some(ts) => ts.apply(p.x, p.y),
+            None => (p.x, p.y),
+        }
+    };
+
+    let mut prev = apply(points[points.len() - 1]);
+    for p in points {
+        let p = apply(*p);
+        edges.push(Edge { x0: prev.0, y0: prev.1, x1: p.0, y1: p.1 });
+        prev = p;
+    }
+}
@@ -0,0 +1,176 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// external
+use usvg::{
+    try_opt,
+};
+
+// self
+use crate::prelude::*;
+use crate::backend_utils::*;
+use super::pixmap::BlendMode;
+use super::{
+    path,
+    Canvas,
+    RasterLayers,
+};
+
+
+pub fn clip(
+    node: &usvg::Node,
+    cp: &usvg::ClipPath,
+    opt: &Options,
+    bbox: Rect,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) {
+    let clip_pixmap = try_opt!(layers.get());
+    let mut clip_pixmap = clip_pixmap.borrow_mut();
+    clip_pixmap.fill(0, 0, 0, 255);
+
+    {
+        let mut clip_canvas = Canvas::new(&mut *clip_pixmap);
+        clip_canvas.set_transform(canvas.get_transform());
+        clip_canvas.transform(cp.transform);
+
+        if cp.units == usvg::Units::ObjectBoundingBox {
+            clip_canvas.transform(usvg::Transform::from_bbox(bbox));
+        }
+
+        clip_canvas.set_blend_mode(BlendMode::Clear);
+
+        let ts = clip_canvas.get_transform();
+        for node in node.children() {
+            clip_canvas.transform(node.transform());
+
+            match *node.borrow() {
+                usvg::NodeKind::Path(ref p) => {
+                    path::draw(&node.tree(), p, opt, &mut clip_canvas);
+                }
+                usvg::NodeKind::Group(ref g) => {
+                    clip_group(&node, g, opt, bbox, layers, &mut clip_canvas);
+                }
+                _ => {}
+            }
+
+            clip_canvas.set_transform(ts);
+        }
+    }
+
+    if let Some(ref id) = cp.clip_path {
+        if let Some(ref clip_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::ClipPath(ref cp) = *clip_node.borrow() {
+                clip(clip_node, cp, opt, bbox, layers, canvas);
+            }
+        }
+    }
+
+    canvas.set_blend_mode(BlendMode::DestinationOut);
+    canvas.draw_pixmap(0, 0, &clip_pixmap, 1.0);
+
+    // Reset blend mode.
+    canvas.set_blend_mode(BlendMode::SourceOver);
+}
+
+fn clip_group(
+    node: &usvg::Node,
+    g: &usvg::Group,
+    opt: &Options,
+    bbox: Rect,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) {
+    if let Some(ref id) = g.clip_path {
+        if let Some(ref clip_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::ClipPath(ref cp) = *clip_node.borrow() {
+                // If a `clipPath` child also has a `clip-path`
+                // then we should render this child on a new canvas,
+                // clip it, and only then draw it to the `clipPath`.
+
+                let clip_pixmap = try_opt!(layers.get());
+                let mut clip_pixmap = clip_pixmap.borrow_mut();
+
+                {
+                    let mut clip_canvas = Canvas::new(&mut *clip_pixmap);
+                    clip_canvas.set_transform(canvas.get_transform());
+
+                    draw_group_child(node, opt, &mut clip_canvas);
+
+                    clip(clip_node, cp, opt, bbox, layers, &mut clip_canvas);
+                }
+
+                canvas.set_blend_mode(BlendMode::DestinationOut);
+                canvas.draw_pixmap(0, 0, &clip_pixmap, 1.0);
+
+                // Restore the `clipPath` blend mode.
+                canvas.set_blend_mode(BlendMode::Clear);
+            }
+        }
+    }
+}
+
+fn draw_group_child(
+    node: &usvg::Node,
+    opt: &Options,
+    canvas: &mut Canvas,
+) {
+    if let Some(child) = node.first_child() {
+        canvas.transform(child.transform());
+
+        match *child.borrow() {
+            usvg::NodeKind::Path(ref path_node) => {
+                path::draw(&child.tree(), path_node, opt, canvas);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn mask(
+    node: &usvg::Node,
+    mask: &usvg::Mask,
+    opt: &Options,
+    bbox: Rect,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) {
+    let mask_pixmap = try_opt!(layers.get());
+    let mut mask_pixmap = mask_pixmap.borrow_mut();
+
+    {
+        let mut mask_canvas = Canvas::new(&mut *mask_pixmap);
+        mask_canvas.set_transform(canvas.get_transform());
+
+        let r = if mask.units == usvg::Units::ObjectBoundingBox {
+            mask.rect.bbox_transform(bbox)
+        } else {
+            mask.rect
+        };
+
+        mask_canvas.set_clip_rect(r);
+
+        if mask.content_units == usvg::Units::ObjectBoundingBox {
+            mask_canvas.transform(usvg::Transform::from_bbox(bbox));
+        }
+
+        super::render_group(node, opt, &mut RenderState::Ok, layers, &mut mask_canvas);
+    }
+
+    image_to_mask(mask_pixmap.data_mut(), layers.image_size());
+
+    if let Some(ref id) = mask.mask {
+        if let Some(ref mask_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::Mask(ref mask) = *mask_node.borrow() {
+                self::mask(mask_node, mask, opt, bbox, layers, canvas);
+            }
+        }
+    }
+
+    canvas.set_blend_mode(BlendMode::DestinationIn);
+    canvas.draw_pixmap(0, 0, &mask_pixmap, 1.0);
+
+    // Reset blend mode.
+    canvas.set_blend_mode(BlendMode::SourceOver);
+}
@@ -0,0 +1,685 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cmp;
+use std::rc::Rc;
+
+// external
+use rgb::FromSlice;
+use rgb::alt::BGRA8;
+use log::warn;
+use usvg::{
+    try_opt_or,
+    ColorInterpolation as ColorSpace,
+};
+
+// self
+use crate::prelude::*;
+use crate::backend_utils::*;
+use crate::backend_utils::filter::{
+    Error,
+    Filter,
+    ImageExt,
+};
+use super::pixmap::{
+    self,
+    BlendMode,
+};
+use super::shader::{
+    Extend,
+    FilterQuality,
+    Shader,
+};
+use super::{
+    Canvas,
+    Pixmap,
+};
+
+type Image = filter::Image<Pixmap>;
+type FilterResult = filter::FilterResult<Pixmap>;
+pub type FilterInputs<'a> = filter::FilterInputs<'a, Pixmap>;
+
+
+pub fn apply(
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    opt: &Options,
+    tree: &usvg::Tree,
+    inputs: &mut FilterInputs,
+) {
+    RasterFilter::apply(filter, bbox, ts, opt, tree, inputs);
+}
+
+
+impl ImageExt for Pixmap {
+    fn width(&self) -> u32 {
+        self.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.height()
+    }
+
+    fn try_clone(&self) -> Result<Self, Error> {
+        Ok(self.clone())
+    }
+
+    fn clip(&mut self, region: ScreenRect) {
+        let width = self.width() as i32;
+        for (i, p) in self.data_mut().as_bgra_mut().iter_mut().enumerate() {
+            let x = i as i32 % width;
+            let y = i as i32 / width;
+            if x < region.x() || x >= region.right() || y < region.y() || y >= region.bottom() {
+                *p = BGRA8 { b: 0, g: 0, r: 0, a: 0 };
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        Pixmap::clear(self);
+    }
+
+    fn into_srgb(&mut self) {
+        from_premultiplied(self.data_mut());
+
+        for p in self.data_mut().as_bgra_mut() {
+            p.r = filter::LINEAR_RGB_TO_SRGB_TABLE[p.r as usize];
+            p.g = filter::LINEAR_RGB_TO_SRGB_TABLE[p.g as usize];
+            p.b = filter::LINEAR_RGB_TO_SRGB_TABLE[p.b as usize];
+        }
+
+        into_premultiplied(self.data_mut());
+    }
+
+    fn into_linear_rgb(&mut self) {
+        from_premultiplied(self.data_mut());
+
+        for p in self.data_mut().as_bgra_mut() {
+            p.r = filter::SRGB_TO_LINEAR_RGB_TABLE[p.r as usize];
+            p.g = filter::SRGB_TO_LINEAR_RGB_TABLE[p.g as usize];
+            p.b = filter::SRGB_TO_LINEAR_RGB_TABLE[p.b as usize];
+        }
+
+        into_premultiplied(self.data_mut());
+    }
+}
+
+fn create_image(width: u32, height: u32) -> Result<Pixmap, Error> {
+    Pixmap::new(width, height).ok_or(Error::AllocFailed)
+}
+
+fn copy_image(
+    image: &Pixmap,
+    region: ScreenRect,
+) -> Result<Pixmap, Error> {
+    let x = cmp::max(0, region.x());
+    let y = cmp::max(0, region.y());
+
+    image.copy_region(x, y, region.width(), region.height()).ok_or(Error::AllocFailed)
+}
+
+fn from_premultiplied(data: &mut [u8]) {
+    for p in data.as_bgra_mut() {
+        let (r, g, b, a) = pixmap::demultiply(*p);
+        *p = BGRA8 { b, g, r, a };
+    }
+}
+
+fn into_premultiplied(data: &mut [u8]) {
+    for p in data.as_bgra_mut() {
+        *p = pixmap::premultiply(p.r, p.g, p.b, p.a);
+    }
+}
+
+/// Draws `src` onto `dst` using the selected blend mode.
+fn draw_image(dst: &mut Pixmap, src: &Pixmap, x: i32, y: i32, mode: BlendMode) {
+    dst.draw_pixmap(x, y, src, 1.0, mode, None);
+}
+
+struct RasterFilter;
+
+impl Filter<Pixmap> for RasterFilter {
+    fn get_input(
+        input: &usvg::FilterInput,
+        region: ScreenRect,
+        inputs: &FilterInputs,
+        results: &[FilterResult],
+    ) -> Result<Image, Error> {
+        let convert = |in_image: Option<&Pixmap>| -> Result<Image, Error> {
+            let image = if let Some(image) = in_image {
+                copy_image(image, region)?
+            } else {
+                // An unavailable input is a transparent black image.
+                create_image(region.width(), region.height())?
+            };
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
+        let convert_alpha = |in_image: Option<&Pixmap>| -> Result<Image, Error> {
+            let mut image = convert(in_image)?.take()?;
+
+            // Set RGB to black. Keep alpha as is.
+            for p in image.data_mut().chunks_mut(4) {
+                p[0] = 0;
+                p[1] = 0;
+                p[2] = 0;
+            }
+
+            Ok(Image {
+                image: Rc::new(image),
+                region: region.translate_to(0, 0),
+                color_space: ColorSpace::SRGB,
+            })
+        };
+
+        match input {
+            usvg::FilterInput::SourceGraphic => {
+                convert(Some(&*inputs.source))
+            }
+            usvg::FilterInput::SourceAlpha => {
+                convert_alpha(Some(&*inputs.source))
+            }
+            usvg::FilterInput::BackgroundImage => {
+                convert(inputs.background)
+            }
+            usvg::FilterInput::BackgroundAlpha => {
+                convert_alpha(inputs.background)
+            }
+            usvg::FilterInput::FillPaint => {
+                convert(inputs.fill_paint)
+            }
+            usvg::FilterInput::StrokePaint => {
+                convert(inputs.stroke_paint)
+            }
+            usvg::FilterInput::Reference(ref name) => {
+                if let Some(ref v) = results.iter().rev().find(|v| v.name == *name) {
+                    Ok(v.image.clone())
+                } else {
+                    // Technically unreachable.
+                    warn!("Unknown filter primitive reference '{}'.", name);
+                    Self::get_input(&usvg::FilterInput::SourceGraphic, region, inputs, results)
+                }
+            }
+        }
+    }
+
+    fn apply_blur(
+        fe: &usvg::FeGaussianBlur,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let (std_dx, std_dy) = try_opt_or!(Self::resolve_std_dev(fe, units, bbox, ts), Ok(input));
+
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+
+        from_premultiplied(buffer.data_mut());
+        filter::blur::apply(buffer.data_mut(), w, h, std_dx, std_dy, 4);
+        into_premultiplied(buffer.data_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_offset(
+        fe: &usvg::FeOffset,
+        units: usvg::Units,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let (dx, dy) = try_opt_or!(Self::resolve_offset(fe, units, bbox, ts), Ok(input));
+
+        // Subpixel offset is not supported.
+        let dx = dx.round() as i32;
+        let dy = dy.round() as i32;
+
+        let mut buffer = create_image(input.width(), input.height())?;
+        draw_image(&mut buffer, input.as_ref(), dx, dy, BlendMode::SourceOver);
+
+        Ok(Image::from_image(buffer, input.color_space))
+    }
+
+    fn apply_blend(
+        fe: &usvg::FeBlend,
+        cs: ColorSpace,
+        region: ScreenRect,
+        input1: Image,
+        input2: Image,
+    ) -> Result<Image, Error> {
+        let input1 = input1.into_color_space(cs)?;
+        let input2 = input2.into_color_space(cs)?;
+
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        draw_image(&mut buffer, input2.as_ref(), 0, 0, BlendMode::SourceOver);
+
+        let mode = match fe.mode {
+            usvg::FeBlendMode::Normal => BlendMode::SourceOver,
+            usvg::FeBlendMode::Multiply => BlendMode::Multiply,
+            usvg::FeBlendMode::Screen => BlendMode::Screen,
+            usvg::FeBlendMode::Darken => BlendMode::Darken,
+            usvg::FeBlendMode::Lighten => BlendMode::Lighten,
+        };
+
+        draw_image(&mut buffer, input1.as_ref(), 0, 0, mode);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_composite(
+        fe: &usvg::FeComposite,
+        cs: ColorSpace,
+        region: ScreenRect,
+        input1: Image,
+        input2: Image,
+    ) -> Result<Image, Error> {
+        use usvg::FeCompositeOperator as Operator;
+
+        let input1 = input1.into_color_space(cs)?;
+        let input2 = input2.into_color_space(cs)?;
+
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        if let Operator::Arithmetic { k1, k2, k3, k4 } = fe.operator {
+            let calc = |i1, i2, max| {
+                let i1 = i1 as f64 / 255.0;
+                let i2 = i2 as f64 / 255.0;
+                let result = k1.value() * i1 * i2 + k2.value() * i1 + k3.value() * i2 + k4.value();
+                f64_bound(0.0, result, max)
+            };
+
+            {
+                let data1 = input1.as_ref().data().as_bgra();
+                let data2 = input2.as_ref().data().as_bgra();
+                let data3 = buffer.data_mut().as_bgra_mut();
+                for ((c1, c2), c3) in data1.iter().zip(data2).zip(data3) {
+                    let a = calc(c1.a, c2.a, 1.0);
+                    if a.is_fuzzy_zero() {
+                        continue;
+                    }
+
+                    let r = (calc(c1.r, c2.r, a) * 255.0) as u8;
+                    let g = (calc(c1.g, c2.g, a) * 255.0) as u8;
+                    let b = (calc(c1.b, c2.b, a) * 255.0) as u8;
+                    let a = (a * 255.0) as u8;
+
+                    *c3 = BGRA8 { r, g, b, a };
+                }
+            }
+
+            return Ok(Image::from_image(buffer, cs));
+        }
+
+        draw_image(&mut buffer, input2.as_ref(), 0, 0, BlendMode::SourceOver);
+
+        let mode = match fe.operator {
+            Operator::Over => BlendMode::SourceOver,
+            Operator::In => BlendMode::SourceIn,
+            Operator::Out => BlendMode::SourceOut,
+            Operator::Atop => BlendMode::SourceAtop,
+            Operator::Xor => BlendMode::Xor,
+            Operator::Arithmetic { .. } => BlendMode::SourceOver,
+        };
+
+        draw_image(&mut buffer, input1.as_ref(), 0, 0, mode);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_color_matrix(
+        fe: &usvg::FeColorMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        from_premultiplied(buffer.data_mut());
+        filter::color_matrix::apply(&fe.kind, buffer.data_mut().as_bgra_mut());
+        into_premultiplied(buffer.data_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_component_transfer(
+        fe: &usvg::FeComponentTransfer,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        from_premultiplied(buffer.data_mut());
+        filter::component_transfer::apply(fe, buffer.data_mut().as_bgra_mut());
+        into_premultiplied(buffer.data_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_convolve_matrix(
+        fe: &usvg::FeConvolveMatrix,
+        cs: ColorSpace,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+        filter::convolve_matrix::apply(fe, w, h, buffer.data_mut().as_bgra_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_diffuse_lighting(
+        fe: &usvg::FeDiffuseLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        filter::lighting::diffuse_lighting(fe, light_source, kernel_unit_length, cs, w, h, buffer.data_mut().as_bgra_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_specular_lighting(
+        fe: &usvg::FeSpecularLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let light_source = Self::transform_light_source(fe.light_source, units, bbox, region, ts);
+        let kernel_unit_length = Self::resolve_kernel_unit_length(fe.kernel_unit_length, units, bbox, ts);
+
+        // Only the alpha channel is used, so the input color space doesn't matter.
+        let mut buffer = input.take()?;
+        let (w, h) = (buffer.width(), buffer.height());
+
+        filter::lighting::specular_lighting(fe, light_source, kernel_unit_length, cs, w, h, buffer.data_mut().as_bgra_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_displacement_map(
+        fe: &usvg::FeDisplacementMap,
+        region: ScreenRect,
+        cs: ColorSpace,
+        sx: f64,
+        sy: f64,
+        input1: Image,
+        input2: Image,
+    ) -> Result<Image, Error> {
+        // 'The color-interpolation-filters property only applies to the `in2` source image.'
+        let cs1 = input1.color_space;
+        let mut pixmap2 = input2.into_color_space(cs)?.take()?;
+        from_premultiplied(pixmap2.data_mut());
+
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        filter::displacement_map::apply(
+            fe, sx, sy,
+            input1.as_ref().data().as_bgra(),
+            pixmap2.data().as_bgra(),
+            buffer.data_mut().as_bgra_mut(),
+            region.width(),
+        );
+
+        Ok(Image::from_image(buffer, cs1))
+    }
+
+    fn apply_merge(
+        fe: &usvg::FeMerge,
+        cs: ColorSpace,
+        region: ScreenRect,
+        inputs: &FilterInputs,
+        results: &[FilterResult],
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        for input in &fe.inputs {
+            let input = Self::get_input(input, region, inputs, results)?;
+            let input = input.into_color_space(cs)?;
+
+            draw_image(&mut buffer, input.as_ref(), 0, 0, BlendMode::SourceOver);
+        }
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_morphology(
+        fe: &usvg::FeMorphology,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image,
+    ) -> Result<Image, Error> {
+        let (rx, ry) = try_opt_or!(
+            Self::resolve_morphology_radius(fe, units, bbox, ts), Ok(input)
+        );
+
+        let input = input.into_color_space(cs)?;
+        let mut buffer = input.take()?;
+
+        let (w, h) = (buffer.width(), buffer.height());
+        filter::morphology::apply(fe.operator, rx, ry, buffer.data_mut(), w, h);
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_flood(
+        fe: &usvg::FeFlood,
+        region: ScreenRect,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        let c = fe.color;
+        let a = (fe.opacity.value() * 255.0 + 0.5) as u8;
+        buffer.fill(c.red, c.green, c.blue, a);
+
+        Ok(Image::from_image(buffer, ColorSpace::SRGB))
+    }
+
+    fn apply_tile(
+        input: Image,
+        region: ScreenRect,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        let subregion = input.region.translate(-region.x(), -region.y());
+
+        let tile = copy_image(&input.image, subregion)?;
+        let brush_ts = usvg::Transform::new_translate(subregion.x() as f64, subregion.y() as f64);
+
+        {
+            let shader = Shader::new_pattern(&tile, &brush_ts, Extend::Repeat, FilterQuality::Nearest);
+            if let Some(shader) = shader {
+                let mut canvas = Canvas::new(&mut buffer);
+                canvas.paint(&shader);
+            }
+        }
+
+        Ok(Image::from_image(buffer, ColorSpace::SRGB))
+    }
+
+    fn apply_image(
+        fe: &usvg::FeImage,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
+        opt: &Options,
+        tree: &usvg::Tree,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        match fe.data {
+            usvg::FeImageKind::None => {}
+            usvg::FeImageKind::Image(ref data, format) => {
+                let mut canvas = Canvas::new(&mut buffer);
+
+                let dx = (subregion.x() - region.x()) as f64;
+                let dy = (subregion.y() - region.y()) as f64;
+                canvas.transform(usvg::Transform::new_translate(dx, dy));
+
+                let view_box = usvg::ViewBox {
+                    rect: subregion.translate_to(0, 0).to_rect(),
+                    aspect: fe.aspect,
+                };
+
+                if format == usvg::ImageFormat::SVG {
+                    super::image::draw_svg(data, view_box, opt, &mut canvas);
+                } else {
+                    super::image::draw_raster(data, view_box, fe.rendering_mode, opt, &mut canvas);
+                }
+            }
+            usvg::FeImageKind::Use(ref id) => {
+                if let Some(ref node) = tree.defs_by_id(id) {
+                    let mut layers = super::create_layers(region.size(), opt);
+
+                    let mut canvas = Canvas::new(&mut buffer);
+
+                    let mut ts2 = usvg::Transform::new_translate(-region.x() as f64, -region.y() as f64);
+                    ts2.append(ts);
+                    canvas.set_transform(ts2);
+
+                    super::render_group(node, opt, &mut RenderState::Ok, &mut layers, &mut canvas);
+                }
+            }
+        }
+
+        Ok(Image::from_image(buffer, ColorSpace::SRGB))
+    }
+
+    fn apply_turbulence(
+        fe: &usvg::FeTurbulence,
+        cs: ColorSpace,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
+    ) -> Result<Image, Error> {
+        let mut buffer = create_image(region.width(), region.height())?;
+
+        filter::turbulence::apply(fe, region, subregion, ts, buffer.data_mut().as_bgra_mut());
+        into_premultiplied(buffer.data_mut());
+
+        Ok(Image::from_image(buffer, cs))
+    }
+
+    fn apply_to_canvas(
+        input: Image,
+        region: ScreenRect,
+        canvas: &mut Pixmap,
+    ) -> Result<(), Error> {
+        let input = input.into_color_space(ColorSpace::SRGB)?;
+
+        canvas.clear();
+        draw_image(canvas, input.as_ref(), region.x(), region.y(), BlendMode::SourceOver);
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(text: &str) -> Pixmap {
+        let opt = Options::default();
+        let tree = usvg::Tree::from_str(text, &opt.usvg).unwrap();
+        crate::backend_raster::render_to_image(&tree, &opt).unwrap()
+    }
+
+    fn pixel(r: u8, g: u8, b: u8, a: u8) -> BGRA8 {
+        BGRA8 { b, g, r, a }
+    }
+
+    #[test]
+    fn fe_image_element() {
+        let pixmap = render("\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'
+     width='40' height='40' viewBox='0 0 20 20'>
+    <defs>
+        <rect id='rect1' x='10' width='10' height='10' fill='#00ff00'/>
+    </defs>
+    <filter id='filter1' filterUnits='userSpaceOnUse' x='0' y='0' width='20' height='20'>
+        <feImage xlink:href='#rect1'/>
+    </filter>
+    <rect width='20' height='20' fill='#ff0000' filter='url(#filter1)'/>
+</svg>");
+
+        // The referenced element is rendered in the user space.
+        assert_eq!(pixmap.pixel(30, 10), pixel(0, 255, 0, 255));
+        assert_eq!(pixmap.pixel(10, 10), pixel(0, 0, 0, 0));
+        assert_eq!(pixmap.pixel(30, 30), pixel(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn paint_inputs() {
+        let pixmap = render("\
+<svg xmlns='http://www.w3.org/2000/svg' width='20' height='20'>
+    <filter id='filter1' filterUnits='userSpaceOnUse' x='0' y='0' width='20' height='10'>
+        <feOffset in='FillPaint'/>
+    </filter>
+    <filter id='filter2' filterUnits='userSpaceOnUse' x='0' y='10' width='20' height='10'>
+        <feOffset in='StrokePaint'/>
+    </filter>
+    <rect width='5' height='5' fill='#0000ff' filter='url(#filter1)'/>
+    <rect y='10' width='5' height='5' fill='none' stroke='#00ff00' filter='url(#filter2)'/>
+</svg>");
+
+        // The whole filter region is filled with the paint.
+        assert_eq!(pixmap.pixel(15, 5), pixel(0, 0, 255, 255));
+        assert_eq!(pixmap.pixel(15, 15), pixel(0, 255, 0, 255));
+    }
+
+    #[test]
+    fn background_inputs() {
+        let pixmap = render("\
+<svg xmlns='http://www.w3.org/2000/svg' width='20' height='20'>
+    <filter id='filter1' filterUnits='userSpaceOnUse' x='0' y='0' width='10' height='20'>
+        <feOffset in='BackgroundImage' dy='10'/>
+    </filter>
+    <filter id='filter2' filterUnits='userSpaceOnUse' x='10' y='0' width='10' height='20'>
+        <feOffset in='BackgroundAlpha'/>
+    </filter>
+    <g enable-background='new'>
+        <rect width='20' height='10' fill='#00ff00'/>
+        <rect width='5' height='5' fill='#ff0000' filter='url(#filter1)'/>
+        <rect x='10' width='5' height='5' fill='#ff0000' filter='url(#filter2)'/>
+    </g>
+</svg>");
+
+        // The background is shifted down and the source graphic is ignored.
+        assert_eq!(pixmap.pixel(2, 2), pixel(0, 255, 0, 255));
+        assert_eq!(pixmap.pixel(2, 15), pixel(0, 255, 0, 255));
+
+        // Only the alpha of the background is used.
+        assert_eq!(pixmap.pixel(12, 2), pixel(0, 0, 0, 255));
+        assert_eq!(pixmap.pixel(12, 15), pixel(0, 0, 0, 0));
+    }
+}
@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fs;
+
+// external
+use png::HasParameters;
+use usvg::{
+    try_opt,
+    try_opt_warn,
+};
+
+// self
+use crate::prelude::*;
+use crate::backend_utils::*;
+use super::shader::{
+    Extend,
+    FilterQuality,
+    Shader,
+};
+use super::{
+    Canvas,
+    Pixmap,
+};
+
+
+pub fn draw(
+    image: &usvg::Image,
+    opt: &Options,
+    canvas: &mut Canvas,
+) -> Rect {
+    if image.visibility != usvg::Visibility::Visible {
+        return image.view_box.rect;
+    }
+
+    if image.format == usvg::ImageFormat::SVG {
+        draw_svg(&image.data, image.view_box, opt, canvas);
+    } else {
+        draw_raster(&image.data, image.view_box, image.rendering_mode, opt, canvas);
+    }
+
+    image.view_box.rect
+}
+
+pub fn draw_raster(
+    data: &usvg::ImageData,
+    view_box: usvg::ViewBox,
+    rendering_mode: usvg::ImageRendering,
+    opt: &Options,
+    canvas: &mut Canvas,
+) {
+    let img = match data {
+        usvg::ImageData::Path(ref path) => {
+            let path = image::get_abs_path(path, opt);
+            try_opt_warn!(
+                fs::read(&path).ok().and_then(|data| load_raster_data(&data)),
+                "Failed to load an external image: {:?}.", path
+            )
+        }
+        usvg::ImageData::Raw(ref data) => {
+            try_opt_warn!(
+                load_raster_data(data),
+                "Failed to load an embedded image."
+            )
+        }
+    };
+
+    let img_size = try_opt!(ScreenSize::new(img.width(), img.height()));
+
+    let (ts, clip) = image::prepare_sub_svg_geom(view_box, img_size);
+
+    canvas.save();
+
+    if let Some(clip) = clip {
+        canvas.set_clip_rect(clip);
+    } else {
+        // We have to clip the image before rendering because we use `Extend::Pad`.
+        let r = image::image_rect(&view_box, img_size);
+        canvas.set_clip_rect(r);
+    }
+
+    canvas.transform(ts);
+
+    let quality = match rendering_mode {
+        usvg::ImageRendering::OptimizeQuality => FilterQuality::Bilinear,
+        usvg::ImageRendering::OptimizeSpeed   => FilterQuality::Nearest,
+    };
+
+    // Do not use a transparent extend, because it will introduce a "transparent border".
+    if let Some(shader) = Shader::new_pattern(&img, &canvas.get_transform(), Extend::Pad, quality) {
+        canvas.paint(&shader);
+    }
+
+    canvas.restore();
+}
+
+fn load_raster_data(data: &[u8]) -> Option<Pixmap> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        load_png(data)
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        load_jpeg(data)
+    } else {
+        None
+    }
+}
+
+fn load_png(data: &[u8]) -> Option<Pixmap> {
+    let mut decoder = png::Decoder::new(data);
+    // Expand palette and low bit depth images to 8 bit RGB(A)/Gray(A).
+    decoder.set(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let (info, mut reader) = decoder.read_info().ok()?;
+    let mut img_data = vec![0; info.buffer_size()];
+    reader.next_frame(&mut img_data).ok()?;
+
+    let rgba = match info.color_type {
+        png::ColorType::RGBA => img_data,
+        png::ColorType::RGB => {
+            let mut rgba = Vec::with_capacity(img_data.len() / 3 * 4);
+            for c in img_data.chunks(3) {
+                rgba.extend_from_slice(&[c[0], c[1], c[2], 255]);
+            }
+
+            rgba
+        }
+        png::ColorType::Grayscale => {
+            let mut rgba = Vec::with_capacity(img_data.len() * 4);
+            for &c in &img_data {
+                rgba.extend_from_slice(&[c, c, c, 255]);
+            }
+
+            rgba
+        }
+        png::ColorType::GrayscaleAlpha => {
+            let mut rgba = Vec::with_capacity(img_data.len() * 2);
+            for c in img_data.chunks(2) {
+                rgba.extend_from_slice(&[c[0], c[0], c[0], c[1]]);
+            }
+
+            rgba
+        }
+        png::ColorType::Indexed => {
+            // Palette images are always expanded.
+            return None;
+        }
+    };
+
+    Pixmap::from_rgba(info.width, info.height, &rgba)
+}
+
+fn load_jpeg(data: &[u8]) -> Option<Pixmap> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    let img_data = decoder.decode().ok()?;
+    let info = decoder.info()?;
+
+    let rgba = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => {
+            let mut rgba = Vec::with_capacity(img_data.len() / 3 * 4);
+            for c in img_data.chunks(3) {
+                rgba.extend_from_slice(&[c[0], c[1], c[2], 255]);
+            }
+
+            rgba
+        }
+        jpeg_decoder::PixelFormat::L8 => {
+            let mut rgba = Vec::with_capacity(img_data.len() * 4);
+            for &c in &img_data {
+                rgba.extend_from_slice(&[c, c, c, 255]);
+            }
+
+            rgba
+        }
+        jpeg_decoder::PixelFormat::CMYK32 => {
+            let mut rgba = Vec::with_capacity(img_data.len());
+            for c in img_data.chunks(4) {
+                let k = c[3] as u32;
+                let conv = |n: u8| ((255 - n as u32) * (255 - k) / 255) as u8;
+                rgba.extend_from_slice(&[conv(c[0]), conv(c[1]), conv(c[2]), 255]);
+            }
+
+            rgba
+        }
+    };
+
+    Pixmap::from_rgba(info.width as u32, info.height as u32, &rgba)
+}
+
+pub fn draw_svg(
+    data: &usvg::ImageData,
+    view_box: usvg::ViewBox,
+    opt: &Options,
+    canvas: &mut Canvas,
+) {
+    let (tree, sub_opt) = try_opt!(image::load_sub_svg(data, opt));
+
+    let img_size = tree.svg_node().size.to_screen_size();
+    let (ts, clip) = image::prepare_sub_svg_geom(view_box, img_size);
+
+    canvas.save();
+
+    if let Some(clip) = clip {
+        canvas.set_clip_rect(clip);
+    }
+
+    canvas.transform(ts);
+    super::render_to_canvas(&tree, &sub_opt, img_size, canvas);
+    canvas.restore();
+}
@@ -0,0 +1,480 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Pure Rust backend implementation.
+//!
+//! Unlike other backends, doesn't depend on any system libraries.
+
+// external
+use log::warn;
+
+// self
+use crate::prelude::*;
+use crate::layers;
+use crate::RenderError;
+use crate::backend_utils::{
+    self,
+    RenderState,
+};
+use crate::backend_utils::filter::ImageExt;
+
+
+macro_rules! try_create_pixmap {
+    ($size:expr, $ret:expr) => {
+        usvg::try_opt_warn_or!(
+            $crate::backend_raster::Pixmap::new($size.width(), $size.height()),
+            $ret,
+            "Failed to create a {}x{} pixmap.", $size.width(), $size.height()
+        );
+    };
+}
+
+
+mod canvas;
+mod clip_and_mask;
+mod filter;
+mod image;
+mod path;
+mod pixmap;
+mod rasterizer;
+mod shader;
+mod stroker;
+mod style;
+
+pub use self::canvas::Canvas;
+pub use self::pixmap::Pixmap;
+
+
+type RasterLayers = layers::Layers<Pixmap>;
+
+
+/// Raster backend handle.
+#[derive(Clone, Copy)]
+pub struct Backend;
+
+impl Render for Backend {
+    fn render_to_image(
+        &self,
+        tree: &usvg::Tree,
+        opt: &Options,
+    ) -> Result<Box<OutputImage>, RenderError> {
+        let img = render_to_image(tree, opt)?;
+        Ok(Box::new(img))
+    }
+
+    fn render_node_to_image(
+        &self,
+        node: &usvg::Node,
+        opt: &Options,
+    ) -> Result<Box<OutputImage>, RenderError> {
+        let img = render_node_to_image(node, opt)?;
+        Ok(Box::new(img))
+    }
+
+    fn calc_node_bbox(
+        &self,
+        node: &usvg::Node,
+        opt: &Options,
+    ) -> Option<Rect> {
+        calc_node_bbox(node, opt)
+    }
+}
+
+impl OutputImage for Pixmap {
+    fn save(
+        &self,
+        path: &std::path::Path,
+    ) -> bool {
+        self.save_png(path)
+    }
+}
+
+
+/// Renders SVG to image.
+pub fn render_to_image(
+    tree: &usvg::Tree,
+    opt: &Options,
+) -> Result<Pixmap, RenderError> {
+    let (mut pixmap, img_view) = create_pixmap(
+        tree.svg_node().size.to_screen_size(),
+        opt,
+    )?;
+
+    // Fill background.
+    if let Some(color) = opt.background {
+        pixmap.fill(color.red, color.green, color.blue, 255);
+    }
+
+    backend_utils::check_render_limits(|| {
+        render_to_canvas(tree, opt, img_view, &mut Canvas::new(&mut pixmap));
+    })?;
+
+    Ok(pixmap)
+}
+
+/// Renders SVG node to image.
+pub fn render_node_to_image(
+    node: &usvg::Node,
+    opt: &Options,
+) -> Result<Pixmap, RenderError> {
+    let node_bbox = if let Some(bbox) = calc_node_bbox(node, opt) {
+        bbox
+    } else {
+        warn!("Node '{}' has a zero size.", node.id());
+        return Err(RenderError::InvalidSize);
+    };
+
+    let (mut pixmap, img_size) = create_pixmap(node_bbox.to_screen_size(), opt)?;
+
+    let vbox = usvg::ViewBox {
+        rect: node_bbox,
+        aspect: usvg::AspectRatio::default(),
+    };
+
+    // Fill background.
+    if let Some(color) = opt.background {
+        pixmap.fill(color.red, color.green, color.blue, 255);
+    }
+
+    backend_utils::check_render_limits(|| {
+        render_node_to_canvas(node, opt, vbox, img_size, &mut Canvas::new(&mut pixmap));
+    })?;
+
+    Ok(pixmap)
+}
+
+/// Renders SVG to canvas.
+pub fn render_to_canvas(
+    tree: &usvg::Tree,
+    opt: &Options,
+    img_size: ScreenSize,
+    canvas: &mut Canvas,
+) {
+    render_node_to_canvas(&tree.root(), opt, tree.svg_node().view_box, img_size, canvas);
+}
+
+/// Renders SVG node to canvas.
+pub fn render_node_to_canvas(
+    node: &usvg::Node,
+    opt: &Options,
+    view_box: usvg::ViewBox,
+    img_size: ScreenSize,
+    canvas: &mut Canvas,
+) {
+    // Layers must have the same size as the canvas and not as the image,
+    // because they are composited onto the canvas as is.
+    let mut layers = create_layers(canvas.pixmap_size(), opt);
+
+    let curr_ts = canvas.get_transform();
+
+    apply_viewbox_transform(view_box, img_size, canvas);
+
+    let mut ts = utils::abs_transform(node);
+    ts.append(&node.transform());
+
+    canvas.transform(ts);
+    render_node(node, opt, &mut RenderState::Ok, &mut layers, canvas);
+    canvas.set_transform(curr_ts);
+}
+
+fn create_pixmap(
+    size: ScreenSize,
+    opt: &Options,
+) -> Result<(Pixmap, ScreenSize), RenderError> {
+    let img_size = backend_utils::prepare_image_size(size, opt)?;
+    let pixmap = try_create_pixmap!(img_size, Err(RenderError::ImageAllocationFailed));
+
+    Ok((pixmap, img_size))
+}
+
+/// Applies viewbox transformation to the canvas.
+fn apply_viewbox_transform(
+    view_box: usvg::ViewBox,
+    img_size: ScreenSize,
+    canvas: &mut Canvas,
+) {
+    let ts = utils::view_box_to_transform(view_box.rect, view_box.aspect, img_size.to_size());
+    canvas.transform(ts);
+}
+
+fn render_node(
+    node: &usvg::Node,
+    opt: &Options,
+    state: &mut RenderState,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) -> Option<Rect> {
+    match *node.borrow() {
+        usvg::NodeKind::Svg(_) => {
+            Some(render_group(node, opt, state, layers, canvas))
+        }
+        usvg::NodeKind::Path(ref path) => {
+            path::draw(&node.tree(), path, opt, canvas)
+        }
+        usvg::NodeKind::Image(ref img) => {
+            Some(image::draw(img, opt, canvas))
+        }
+        usvg::NodeKind::Group(ref g) => {
+            render_group_impl(node, g, opt, state, layers, canvas)
+        }
+        _ => None,
+    }
+}
+
+fn render_group(
+    parent: &usvg::Node,
+    opt: &Options,
+    state: &mut RenderState,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) -> Rect {
+    let curr_ts = canvas.get_transform();
+    let mut g_bbox = Rect::new_bbox();
+
+    for node in parent.children() {
+        match *state {
+            RenderState::Ok => {}
+            RenderState::RenderUntil(ref last) => {
+                if node == *last {
+                    // Stop rendering.
+                    *state = RenderState::BackgroundFinished;
+                    break;
+                }
+            }
+            RenderState::BackgroundFinished => break,
+        }
+
+        canvas.transform(node.transform());
+
+        let bbox = render_node(&node, opt, state, layers, canvas);
+
+        if let Some(bbox) = bbox {
+            let bbox = bbox.transform(&node.transform()).unwrap();
+            g_bbox = g_bbox.expand(bbox);
+        }
+
+        // Revert transform.
+        canvas.set_transform(curr_ts);
+    }
+
+    g_bbox
+}
+
+fn render_group_impl(
+    node: &usvg::Node,
+    g: &usvg::Group,
+    opt: &Options,
+    state: &mut RenderState,
+    layers: &mut RasterLayers,
+    canvas: &mut Canvas,
+) -> Option<Rect> {
+    let sub_pixmap = layers.get()?;
+    let mut sub_pixmap = sub_pixmap.borrow_mut();
+
+    let curr_ts = canvas.get_transform();
+
+    let bbox = {
+        let mut sub_canvas = Canvas::new(&mut *sub_pixmap);
+        sub_canvas.set_transform(curr_ts);
+
+        render_group(node, opt, state, layers, &mut sub_canvas)
+    };
+
+    for id in &g.filter {
+        if let Some(filter_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::Filter(ref filter) = *filter_node.borrow() {
+                let img_size = layers.image_size();
+
+                let background = prepare_filter_background(node, filter, &curr_ts, opt, img_size);
+                let fill_paint = prepare_filter_paint(
+                    node, filter, bbox, &curr_ts, opt, &g.filter_fill, img_size,
+                );
+                let stroke_paint = prepare_filter_paint(
+                    node, filter, bbox, &curr_ts, opt, &g.filter_stroke, img_size,
+                );
+
+                let mut inputs = filter::FilterInputs {
+                    source: &mut *sub_pixmap,
+                    background: background.as_ref(),
+                    fill_paint: fill_paint.as_ref(),
+                    stroke_paint: stroke_paint.as_ref(),
+                };
+
+                filter::apply(filter, bbox, &curr_ts, opt, &node.tree(), &mut inputs);
+            }
+        }
+    }
+
+    if let Some(ref id) = g.clip_path {
+        if let Some(clip_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::ClipPath(ref cp) = *clip_node.borrow() {
+                let mut sub_canvas = Canvas::new(&mut *sub_pixmap);
+                sub_canvas.set_transform(curr_ts);
+
+                clip_and_mask::clip(&clip_node, cp, opt, bbox, layers, &mut sub_canvas);
+            }
+        }
+    }
+
+    if let Some(ref id) = g.mask {
+        if let Some(mask_node) = node.tree().defs_by_id(id) {
+            if let usvg::NodeKind::Mask(ref mask) = *mask_node.borrow() {
+                let mut sub_canvas = Canvas::new(&mut *sub_pixmap);
+                sub_canvas.set_transform(curr_ts);
+
+                clip_and_mask::mask(&mask_node, mask, opt, bbox, layers, &mut sub_canvas);
+            }
+        }
+    }
+
+    if g.blend_mode == usvg::BlendMode::Normal {
+        canvas.draw_pixmap(0, 0, &sub_pixmap, g.opacity.value());
+    } else {
+        canvas.blend_pixmap(&sub_pixmap, g.blend_mode, g.opacity.value());
+    }
+
+    Some(bbox)
+}
+
+/// Renders an image used by the `BackgroundImage` and `BackgroundAlpha` filter inputs.
+fn prepare_filter_background(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    ts: &usvg::Transform,
+    opt: &Options,
+    img_size: ScreenSize,
+) -> Option<Pixmap> {
+    let task = backend_utils::filter::prepare_background(node, filter, ts)?;
+
+    let mut pixmap = try_create_pixmap!(img_size, None);
+
+    {
+        let mut canvas = Canvas::new(&mut pixmap);
+        canvas.set_transform(task.ts);
+
+        let mut layers = create_layers(img_size, opt);
+        let mut state = RenderState::RenderUntil(node.clone());
+        render_group(&task.start_node, opt, &mut state, &mut layers, &mut canvas);
+    }
+
+    if let Some(rect) = task.clip {
+        pixmap.clip(rect);
+    }
+
+    Some(pixmap)
+}
+
+/// Renders an image used by the `FillPaint` and `StrokePaint` filter inputs.
+fn prepare_filter_paint(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    opt: &Options,
+    paint: &Option<usvg::Paint>,
+    img_size: ScreenSize,
+) -> Option<Pixmap> {
+    let path = backend_utils::filter::prepare_paint_path(filter, bbox, ts, paint, img_size)?;
+
+    let mut pixmap = try_create_pixmap!(img_size, None);
+
+    {
+        let mut canvas = Canvas::new(&mut pixmap);
+        path::draw(&node.tree(), &path, opt, &mut canvas);
+    }
+
+    Some(pixmap)
+}
+
+/// Calculates node's absolute bounding box.
+///
+/// Note: this method can be pretty expensive.
+pub fn calc_node_bbox(
+    node: &usvg::Node,
+    opt: &Options,
+) -> Option<Rect> {
+    let abs_ts = utils::abs_transform(node);
+    _calc_node_bbox(node, opt, abs_ts)
+}
+
+fn _calc_node_bbox(
+    node: &usvg::Node,
+    opt: &Options,
+    ts: usvg::Transform,
+) -> Option<Rect> {
+    let mut ts2 = ts;
+    ts2.append(&node.transform());
+
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            backend_utils::calc_path_bbox(path, ts2, &node.tree(), opt)
+        }
+        usvg::NodeKind::Image(ref img) => {
+            let segments = utils::rect_to_path(img.view_box.rect);
+            utils::path_bbox(&segments, None, Some(ts2))
+        }
+        usvg::NodeKind::Group(_) => {
+            let mut bbox = Rect::new_bbox();
+
+            for child in node.children() {
+                if let Some(c_bbox) = _calc_node_bbox(&child, opt, ts2) {
+                    bbox = bbox.expand(c_bbox);
+                }
+            }
+
+            Some(bbox)
+        }
+        _ => None
+    }
+}
+
+fn create_layers(
+    img_size: ScreenSize,
+    opt: &Options,
+) -> RasterLayers {
+    layers::Layers::new(img_size, opt.usvg.dpi, create_subpixmap, clear_subpixmap)
+}
+
+fn create_subpixmap(
+    size: ScreenSize,
+    _: f64,
+) -> Option<Pixmap> {
+    Some(try_create_pixmap!(size, None))
+}
+
+fn clear_subpixmap(
+    pixmap: &mut Pixmap,
+) {
+    pixmap.clear();
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipped_non_separable_blend() {
+        // The mask region is applied as a canvas clip,
+        // so the luminosity blending should not affect the right half.
+        let text = "\
+<svg xmlns='http://www.w3.org/2000/svg' width='20' height='20'>
+    <mask id='mask1' maskUnits='userSpaceOnUse' x='0' y='0' width='10' height='20'>
+        <g mix-blend-mode='luminosity'>
+            <rect width='20' height='20' fill='white'/>
+        </g>
+    </mask>
+    <rect width='20' height='20' fill='#00ff00' mask='url(#mask1)'/>
+</svg>";
+
+        let opt = Options::default();
+        let tree = usvg::Tree::from_str(text, &opt.usvg).unwrap();
+        let pixmap = render_to_image(&tree, &opt).unwrap();
+
+        let p = pixmap.pixel(5, 10);
+        assert_eq!((p.r, p.g, p.b, p.a), (0, 255, 0, 255));
+        let p = pixmap.pixel(15, 10);
+        assert_eq!((p.r, p.g, p.b, p.a), (0, 0, 0, 0));
+    }
+}
@@ -0,0 +1,52 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// self
+use crate::prelude::*;
+use crate::backend_utils::*;
+use super::style;
+use super::Canvas;
+
+
+pub fn draw(
+    tree: &usvg::Tree,
+    path: &usvg::Path,
+    opt: &Options,
+    canvas: &mut Canvas,
+) -> Option<Rect> {
+    let bbox = utils::path_bbox(&path.segments, None, None);
+
+    // `usvg` guaranties that path without a bbox will not use
+    // a paint server with ObjectBoundingBox,
+    // so we can pass whatever rect we want, because it will not be used anyway.
+    let style_bbox = bbox.unwrap_or_else(|| Rect::new(0.0, 0.0, 1.0, 1.0).unwrap());
+
+    if path.visibility != usvg::Visibility::Visible {
+        return bbox;
+    }
+
+    if !use_shape_antialiasing(path.rendering_mode) {
+        canvas.set_anti_alias(false);
+    }
+
+    match path.paint_order {
+        usvg::PaintOrder::FillAndStroke => {
+            style::fill(tree, &path.segments, &path.fill, opt, style_bbox, canvas);
+            if let Some(ref stroke) = path.stroke {
+                style::stroke(tree, &path.segments, stroke, opt, style_bbox, canvas);
+            }
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            if let Some(ref stroke) = path.stroke {
+                style::stroke(tree, &path.segments, stroke, opt, style_bbox, canvas);
+            }
+            style::fill(tree, &path.segments, &path.fill, opt, style_bbox, canvas);
+        }
+    }
+
+    // Revert anti-aliasing.
+    canvas.set_anti_alias(true);
+
+    bbox
+}
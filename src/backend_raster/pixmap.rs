@@ -0,0 +1,347 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cmp;
+use std::fs;
+use std::io;
+use std::path;
+
+// external
+use rgb::FromSlice;
+use rgb::alt::BGRA8;
+
+
+/// A raster image.
+///
+/// Pixels are stored as premultiplied BGRA, which is the same memory layout
+/// as cairo's `ARgb32` on little-endian machines.
+/// This allows us to share pixel processing code between backends.
+#[derive(Clone)]
+pub struct Pixmap {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Pixmap {
+    /// Creates a new, transparent pixmap.
+    ///
+    /// Returns `None` when size is zero or too big.
+    pub fn new(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let len = (width as usize).checked_mul(height as usize)?.checked_mul(4)?;
+        if len > isize::max_value() as usize {
+            return None;
+        }
+
+        Some(Pixmap {
+            width,
+            height,
+            data: vec![0; len],
+        })
+    }
+
+    /// Creates a new pixmap from non-premultiplied RGBA data.
+    pub(crate) fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Option<Self> {
+        let mut pixmap = Self::new(width, height)?;
+        if rgba.len() != pixmap.data.len() {
+            return None;
+        }
+
+        for (p, c) in pixmap.data.as_bgra_mut().iter_mut().zip(rgba.as_rgba()) {
+            *p = premultiply(c.r, c.g, c.b, c.a);
+        }
+
+        Some(pixmap)
+    }
+
+    /// Returns pixmap's width.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns pixmap's height.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns raw pixels data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns mutable raw pixels data.
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    pub(crate) fn pixel(&self, x: u32, y: u32) -> BGRA8 {
+        self.data.as_bgra()[(y * self.width + x) as usize]
+    }
+
+    /// Fills the whole pixmap with a non-premultiplied color.
+    pub fn fill(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        let c = premultiply(r, g, b, a);
+        for p in self.data.as_bgra_mut() {
+            *p = c;
+        }
+    }
+
+    /// Makes the whole pixmap transparent.
+    pub fn clear(&mut self) {
+        for p in self.data.iter_mut() {
+            *p = 0;
+        }
+    }
+
+    /// Returns a copy of the selected region.
+    ///
+    /// Parts of the region that are outside the pixmap will be transparent.
+    pub(crate) fn copy_region(&self, x: i32, y: i32, width: u32, height: u32) -> Option<Self> {
+        let mut pixmap = Pixmap::new(width, height)?;
+        pixmap.draw_pixmap(-x, -y, self, 1.0, BlendMode::Source, None);
+        Some(pixmap)
+    }
+
+    /// Composites `src` onto the current pixmap at the specified position.
+    ///
+    /// `mask` is an optional coverage mask with the same size as the current pixmap.
+    pub(crate) fn draw_pixmap(
+        &mut self,
+        x: i32,
+        y: i32,
+        src: &Pixmap,
+        opacity: f64,
+        mode: BlendMode,
+        mask: Option<&[u8]>,
+    ) {
+        let opacity = (opacity.max(0.0).min(1.0) * 255.0 + 0.5) as u32;
+
+        // Unbounded operators affect pixels outside the source too.
+        let (x1, y1, x2, y2) = if mode.is_unbounded() {
+            (0, 0, self.width as i32, self.height as i32)
+        } else {
+            (
+                cmp::max(x, 0),
+                cmp::max(y, 0),
+                cmp::min(x + src.width as i32, self.width as i32),
+                cmp::min(y + src.height as i32, self.height as i32),
+            )
+        };
+
+        let transparent = BGRA8 { b: 0, g: 0, r: 0, a: 0 };
+        let src_data = src.data.as_bgra();
+        let width = self.width as i32;
+        let dst_data = self.data.as_bgra_mut();
+        for dy in y1..y2 {
+            let sy = dy - y;
+            for dx in x1..x2 {
+                let sx = dx - x;
+                let s = if sx >= 0 && sy >= 0 && sx < src.width as i32 && sy < src.height as i32 {
+                    src_data[(sy * src.width as i32 + sx) as usize]
+                } else {
+                    transparent
+                };
+
+                let idx = (dy * width + dx) as usize;
+                let mut coverage = opacity;
+                if let Some(mask) = mask {
+                    coverage = div255(coverage * mask[idx] as u32);
+                }
+
+                dst_data[idx] = blend_pixel(mode, s, dst_data[idx], coverage);
+            }
+        }
+    }
+
+    /// Saves the pixmap as a PNG image.
+    pub fn save_png(&self, path: &path::Path) -> bool {
+        match self.write_png(path) {
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    }
+
+    fn write_png(&self, path: &path::Path) -> Result<(), io::Error> {
+        use png::HasParameters;
+
+        let mut rgba = Vec::with_capacity(self.data.len());
+        for p in self.data.as_bgra() {
+            let (r, g, b, a) = demultiply(*p);
+            rgba.extend_from_slice(&[r, g, b, a]);
+        }
+
+        let file = fs::File::create(path)?;
+        let ref mut w = io::BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&rgba)?;
+
+        Ok(())
+    }
+}
+
+
+/// A compositing operator.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    Clear,
+    Source,
+    SourceOver,
+    SourceIn,
+    SourceOut,
+    SourceAtop,
+    DestinationIn,
+    DestinationOut,
+    Xor,
+    Multiply,
+    Screen,
+    Darken,
+    Lighten,
+}
+
+impl BlendMode {
+    fn is_unbounded(&self) -> bool {
+        match *self {
+              BlendMode::Clear
+            | BlendMode::Source
+            | BlendMode::SourceIn
+            | BlendMode::SourceOut
+            | BlendMode::DestinationIn => true,
+            _ => false,
+        }
+    }
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::SourceOver
+    }
+}
+
+
+#[inline]
+pub(crate) fn div255(v: u32) -> u32 {
+    let v = v + 128;
+    (v + (v >> 8)) >> 8
+}
+
+#[inline]
+pub(crate) fn premultiply(r: u8, g: u8, b: u8, a: u8) -> BGRA8 {
+    let a32 = a as u32;
+    BGRA8 {
+        b: div255(b as u32 * a32) as u8,
+        g: div255(g as u32 * a32) as u8,
+        r: div255(r as u32 * a32) as u8,
+        a,
+    }
+}
+
+#[inline]
+pub(crate) fn demultiply(p: BGRA8) -> (u8, u8, u8, u8) {
+    if p.a == 0 {
+        return (0, 0, 0, 0);
+    }
+
+    let a = p.a as u32;
+    let f = |c: u8| cmp::min(255, (c as u32 * 255 + a / 2) / a) as u8;
+    (f(p.r), f(p.g), f(p.b), p.a)
+}
+
+/// Blends a premultiplied source pixel with a destination one.
+///
+/// `coverage` is in a 0..255 range.
+#[inline]
+pub(crate) fn blend_pixel(mode: BlendMode, s: BGRA8, d: BGRA8, coverage: u32) -> BGRA8 {
+    if coverage == 0 {
+        return d;
+    }
+
+    if mode == BlendMode::SourceOver {
+        // Fast path.
+        let s = if coverage == 255 {
+            s
+        } else {
+            BGRA8 {
+                b: div255(s.b as u32 * coverage) as u8,
+                g: div255(s.g as u32 * coverage) as u8,
+                r: div255(s.r as u32 * coverage) as u8,
+                a: div255(s.a as u32 * coverage) as u8,
+            }
+        };
+
+        if s.a == 255 {
+            return s;
+        }
+
+        let inv_sa = 255 - s.a as u32;
+        return BGRA8 {
+            b: (s.b as u32 + div255(d.b as u32 * inv_sa)) as u8,
+            g: (s.g as u32 + div255(d.g as u32 * inv_sa)) as u8,
+            r: (s.r as u32 + div255(d.r as u32 * inv_sa)) as u8,
+            a: (s.a as u32 + div255(d.a as u32 * inv_sa)) as u8,
+        };
+    }
+
+    let sa = s.a as f32 / 255.0;
+    let da = d.a as f32 / 255.0;
+
+    let color = |sc: u8, dc: u8| -> f32 {
+        let sc = sc as f32 / 255.0;
+        let dc = dc as f32 / 255.0;
+        match mode {
+            BlendMode::Clear => 0.0,
+            BlendMode::Source => sc,
+            BlendMode::SourceOver => sc + dc * (1.0 - sa),
+            BlendMode::SourceIn => sc * da,
+            BlendMode::SourceOut => sc * (1.0 - da),
+            BlendMode::SourceAtop => sc * da + dc * (1.0 - sa),
+            BlendMode::DestinationIn => dc * sa,
+            BlendMode::DestinationOut => dc * (1.0 - sa),
+            BlendMode::Xor => sc * (1.0 - da) + dc * (1.0 - sa),
+            BlendMode::Multiply => sc * dc + sc * (1.0 - da) + dc * (1.0 - sa),
+            BlendMode::Screen => sc + dc - sc * dc,
+            BlendMode::Darken => (sc * da).min(dc * sa) + sc * (1.0 - da) + dc * (1.0 - sa),
+            BlendMode::Lighten => (sc * da).max(dc * sa) + sc * (1.0 - da) + dc * (1.0 - sa),
+        }
+    };
+
+    let alpha = match mode {
+        BlendMode::Clear => 0.0,
+        BlendMode::Source => sa,
+        BlendMode::SourceIn => sa * da,
+        BlendMode::SourceOut => sa * (1.0 - da),
+        BlendMode::SourceAtop => da,
+        BlendMode::DestinationIn => da * sa,
+        BlendMode::DestinationOut => da * (1.0 - sa),
+        BlendMode::Xor => sa * (1.0 - da) + da * (1.0 - sa),
+          BlendMode::SourceOver
+        | BlendMode::Multiply
+        | BlendMode::Screen
+        | BlendMode::Darken
+        | BlendMode::Lighten => sa + da - sa * da,
+    };
+
+    let c = coverage as f32 / 255.0;
+    let mix = |v: f32, dc: u8| -> u8 {
+        let dc = dc as f32 / 255.0;
+        let v = v.max(0.0).min(1.0) * c + dc * (1.0 - c);
+        (v * 255.0 + 0.5) as u8
+    };
+
+    let a = mix(alpha, d.a);
+    let clamp = |v: u8| cmp::min(v, a);
+    BGRA8 {
+        b: clamp(mix(color(s.b, d.b), d.b)),
+        g: clamp(mix(color(s.g, d.g), d.g)),
+        r: clamp(mix(color(s.r, d.r), d.r)),
+        a,
+    }
+}
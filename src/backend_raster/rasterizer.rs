@@ -0,0 +1,350 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An anti-aliased scanline rasterizer.
+//!
+//! Uses the signed area accumulation approach (like font-rs),
+//! which gives an exact coverage for a single edge per pixel
+//! and a good approximation otherwise.
+
+use std::cmp;
+
+
+/// A fill rule.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+
+/// A line in device coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Edge {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+
+/// A rasterized coverage mask.
+///
+/// Covers only the `x`, `y`, `width`, `height` region of the canvas.
+pub struct Coverage {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+
+/// Rasterizes edges into a coverage mask.
+///
+/// All edges must form closed contours.
+/// Returns `None` when the shape is outside the canvas or empty.
+pub fn rasterize(
+    edges: &[Edge],
+    rule: FillRule,
+    anti_alias: bool,
+    canvas_width: u32,
+    canvas_height: u32,
+) -> Option<Coverage> {
+    if edges.is_empty() {
+        return None;
+    }
+
+    let mut min_x = std::f64::MAX;
+    let mut min_y = std::f64::MAX;
+    let mut max_x = std::f64::MIN;
+    let mut max_y = std::f64::MIN;
+    for e in edges {
+        if !(e.x0.is_finite() && e.y0.is_finite() && e.x1.is_finite() && e.y1.is_finite()) {
+            return None;
+        }
+
+        min_x = min_x.min(e.x0).min(e.x1);
+        min_y = min_y.min(e.y0).min(e.y1);
+        max_x = max_x.max(e.x0).max(e.x1);
+        max_y = max_y.max(e.y0).max(e.y1);
+    }
+
+    // Everything left from the canvas still affects the winding,
+    // so only the right and bottom sides can be cropped by the shape bbox.
+    let x = cmp::max(0, min_x.floor() as i64) as u32;
+    let y = cmp::max(0, min_y.floor() as i64) as u32;
+    let right = cmp::min(canvas_width as i64, max_x.ceil() as i64);
+    let bottom = cmp::min(canvas_height as i64, max_y.ceil() as i64);
+    if right <= x as i64 || bottom <= y as i64 {
+        return None;
+    }
+
+    let width = (right - x as i64) as u32;
+    let height = (bottom - y as i64) as u32;
+
+    let mut acc = Accumulator::new(width as usize, height as usize);
+    for e in edges {
+        acc.add_edge(e.x0 - x as f64, e.y0 - y as f64, e.x1 - x as f64, e.y1 - y as f64);
+    }
+
+    let data = acc.into_coverage(rule, anti_alias);
+
+    Some(Coverage { x, y, width, height, data })
+}
+
+
+struct Accumulator {
+    width: usize,
+    height: usize,
+    stride: usize,
+    data: Vec<f32>,
+}
+
+impl Accumulator {
+    fn new(width: usize, height: usize) -> Self {
+        let stride = width + 2;
+        Accumulator {
+            width,
+            height,
+            stride,
+            data: vec![0.0; stride * height],
+        }
+    }
+
+    fn add_edge(&mut self, x0: f64, y0: f64, x1: f64, y1: f64) {
+        // Split the edge on the left and right region borders.
+        // Parts outside are projected onto the border,
+        // which preserves the winding without affecting the visible area.
+        let w = self.width as f64;
+        let mut xs = [(x0, y0), (0.0, 0.0), (0.0, 0.0), (x1, y1)];
+        let mut count = 1;
+        let mut ts = [0.0f64; 2];
+        let mut ts_count = 0;
+        for &bx in &[0.0, w] {
+            if (x0 < bx && x1 > bx) || (x0 > bx && x1 < bx) {
+                ts[ts_count] = (bx - x0) / (x1 - x0);
+                ts_count += 1;
+            }
+        }
+
+        if ts_count == 2 && ts[0] > ts[1] {
+            ts.swap(0, 1);
+        }
+
+        for t in &ts[..ts_count] {
+            xs[count] = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+            count += 1;
+        }
+        xs[count] = (x1, y1);
+        count += 1;
+
+        for i in 1..count {
+            let (ax, ay) = xs[i - 1];
+            let (bx, by) = xs[i];
+            let ax = ax.max(0.0).min(w);
+            let bx = bx.max(0.0).min(w);
+            self.add_line(ax as f32, ay as f32, bx as f32, by as f32);
+        }
+    }
+
+    fn add_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) {
+        if (y0 - y1).abs() <= std::f32::EPSILON {
+            return;
+        }
+
+        let (dir, x0, y0, x1, y1) = if y0 < y1 {
+            (1.0, x0, y0, x1, y1)
+        } else {
+            (-1.0, x1, y1, x0, y0)
+        };
+
+        let h = self.height as f32;
+        if y1 <= 0.0 || y0 >= h {
+            return;
+        }
+
+        let dxdy = (x1 - x0) / (y1 - y0);
+        let mut x = x0;
+        if y0 < 0.0 {
+            x -= y0 * dxdy;
+        }
+
+        let y_start = y0.max(0.0) as usize;
+        let y_end = cmp::min(self.height, y1.ceil() as usize);
+        let max_x = (self.stride - 1) as f32;
+        for y in y_start..y_end {
+            let row = y * self.stride;
+            let dy = ((y + 1) as f32).min(y1) - (y as f32).max(y0);
+            let xnext = (x + dxdy * dy).max(0.0).min(max_x);
+            let d = dy * dir;
+
+            let (xa, xb) = if x < xnext { (x, xnext) } else { (xnext, x) };
+            let xa_floor = xa.floor();
+            let xai = xa_floor as usize;
+            let xb_ceil = xb.ceil();
+            let xbi = xb_ceil as usize;
+
+            if xbi <= xai + 1 {
+                let xmf = 0.5 * (x + xnext) - xa_floor;
+                self.data[row + xai] += d - d * xmf;
+                self.data[row + xai + 1] += d * xmf;
+            } else {
+                let s = (xb - xa).recip();
+                let xaf = xa - xa_floor;
+                let a0 = 0.5 * s * (1.0 - xaf) * (1.0 - xaf);
+                let xbf = xb - xb_ceil + 1.0;
+                let am = 0.5 * s * xbf * xbf;
+
+                self.data[row + xai] += d * a0;
+                if xbi == xai + 2 {
+                    self.data[row + xai + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - xaf);
+                    self.data[row + xai + 1] += d * (a1 - a0);
+                    for xi in xai + 2..xbi - 1 {
+                        self.data[row + xi] += d * s;
+                    }
+                    let a2 = a1 + (xbi - xai - 3) as f32 * s;
+                    self.data[row + xbi - 1] += d * (1.0 - a2 - am);
+                }
+
+                self.data[row + xbi] += d * am;
+            }
+
+            x = xnext;
+        }
+    }
+
+    fn into_coverage(self, rule: FillRule, anti_alias: bool) -> Vec<u8> {
+        let mut coverage = vec![0u8; self.width * self.height];
+        for y in 0..self.height {
+            let mut acc = 0.0f32;
+            let row = y * self.stride;
+            for x in 0..self.width {
+                acc += self.data[row + x];
+
+                let v = match rule {
+                    FillRule::NonZero => acc.abs().min(1.0),
+                    FillRule::EvenOdd => {
+                        let v = acc.abs() % 2.0;
+                        if v > 1.0 { 2.0 - v } else { v }
+                    }
+                };
+
+                coverage[y * self.width + x] = if anti_alias {
+                    (v * 255.0 + 0.5) as u8
+                } else if v >= 0.5 {
+                    255
+                } else {
+                    0
+                };
+            }
+        }
+
+        coverage
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64, edges: &mut Vec<Edge>) {
+        let points = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        for i in 0..points.len() {
+            let (ax, ay) = points[i];
+            let (bx, by) = points[(i + 1) % points.len()];
+            edges.push(Edge { x0: ax, y0: ay, x1: bx, y1: by });
+        }
+    }
+
+    fn pixel(coverage: &Coverage, x: u32, y: u32) -> u8 {
+        if x < coverage.x || y < coverage.y
+           || x >= coverage.x + coverage.width || y >= coverage.y + coverage.height {
+            return 0;
+        }
+
+        coverage.data[((y - coverage.y) * coverage.width + x - coverage.x) as usize]
+    }
+
+    #[test]
+    fn fill_rules() {
+        // Two nested squares with the same orientation.
+        let mut edges = Vec::new();
+        rect(0.0, 0.0, 8.0, 8.0, &mut edges);
+        rect(2.0, 2.0, 6.0, 6.0, &mut edges);
+
+        let c = rasterize(&edges, FillRule::NonZero, true, 10, 10).unwrap();
+        assert_eq!(pixel(&c, 1, 1), 255);
+        assert_eq!(pixel(&c, 4, 4), 255);
+        assert_eq!(pixel(&c, 8, 8), 0);
+
+        let c = rasterize(&edges, FillRule::EvenOdd, true, 10, 10).unwrap();
+        assert_eq!(pixel(&c, 1, 1), 255);
+        assert_eq!(pixel(&c, 4, 4), 0);
+        assert_eq!(pixel(&c, 8, 8), 0);
+    }
+
+    #[test]
+    fn non_zero_opposite_winding() {
+        // The inner square has an opposite orientation, so it's a hole for both rules.
+        let mut edges = Vec::new();
+        rect(0.0, 0.0, 8.0, 8.0, &mut edges);
+        rect(6.0, 2.0, 2.0, 6.0, &mut edges);
+
+        let c = rasterize(&edges, FillRule::NonZero, true, 10, 10).unwrap();
+        assert_eq!(pixel(&c, 1, 1), 255);
+        assert_eq!(pixel(&c, 4, 4), 0);
+    }
+
+    #[test]
+    fn anti_aliased_edges() {
+        let mut edges = Vec::new();
+        rect(1.25, 0.0, 2.25, 4.0, &mut edges);
+
+        let c = rasterize(&edges, FillRule::NonZero, true, 4, 4).unwrap();
+        assert_eq!(pixel(&c, 0, 1), 0);
+        assert_eq!(pixel(&c, 1, 1), 191);
+        assert_eq!(pixel(&c, 2, 1), 64);
+        assert_eq!(pixel(&c, 3, 1), 0);
+
+        let c = rasterize(&edges, FillRule::NonZero, false, 4, 4).unwrap();
+        assert_eq!(pixel(&c, 1, 1), 255);
+        assert_eq!(pixel(&c, 2, 1), 0);
+    }
+
+    #[test]
+    fn anti_aliased_diagonal() {
+        // A triangle that covers exactly a half of each diagonal pixel.
+        let edges = [
+            Edge { x0: 0.0, y0: 0.0, x1: 4.0, y1: 4.0 },
+            Edge { x0: 4.0, y0: 4.0, x1: 0.0, y1: 4.0 },
+            Edge { x0: 0.0, y0: 4.0, x1: 0.0, y1: 0.0 },
+        ];
+
+        let c = rasterize(&edges, FillRule::NonZero, true, 4, 4).unwrap();
+        for i in 0..4 {
+            assert_eq!(pixel(&c, i, i), 128);
+        }
+        assert_eq!(pixel(&c, 0, 3), 255);
+        assert_eq!(pixel(&c, 3, 0), 0);
+    }
+
+    #[test]
+    fn outside_canvas() {
+        let mut edges = Vec::new();
+        rect(-10.0, -10.0, -2.0, -2.0, &mut edges);
+        assert!(rasterize(&edges, FillRule::NonZero, true, 4, 4).is_none());
+
+        // A shape on the left still affects the visible area.
+        let mut edges = Vec::new();
+        rect(-10.0, 0.0, 2.0, 4.0, &mut edges);
+        let c = rasterize(&edges, FillRule::NonZero, true, 4, 4).unwrap();
+        assert_eq!(pixel(&c, 0, 0), 255);
+        assert_eq!(pixel(&c, 1, 3), 255);
+        assert_eq!(pixel(&c, 2, 0), 0);
+    }
+}
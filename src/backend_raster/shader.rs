@@ -0,0 +1,319 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Pixel color sources.
+
+// external
+use rgb::alt::BGRA8;
+
+// self
+use crate::utils;
+use super::pixmap::{
+    self,
+    Pixmap,
+};
+
+
+const LUT_SIZE: usize = 1024;
+
+
+/// Pixmap sampling mode outside its bounds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Extend {
+    Pad,
+    Repeat,
+}
+
+/// Pixmap sampling quality.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FilterQuality {
+    Nearest,
+    Bilinear,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpreadMethod {
+    Pad,
+    Reflect,
+    Repeat,
+}
+
+
+/// A gradient stop with a non-premultiplied color.
+#[derive(Clone, Copy, Debug)]
+pub struct GradientStop {
+    pub offset: f64,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f64,
+}
+
+
+pub struct Gradient {
+    /// Maps a device position into the gradient coordinates.
+    ts: usvg::Transform,
+    spread: SpreadMethod,
+    lut: Vec<BGRA8>,
+}
+
+impl Gradient {
+    /// Creates a new gradient.
+    ///
+    /// `ts` maps the gradient coordinates into the device ones.
+    pub fn new(
+        ts: &usvg::Transform,
+        spread: SpreadMethod,
+        stops: &[GradientStop],
+    ) -> Option<Self> {
+        if stops.is_empty() {
+            return None;
+        }
+
+        Some(Gradient {
+            ts: utils::invert_transform(ts)?,
+            spread,
+            lut: build_lut(stops),
+        })
+    }
+
+    fn color_at(&self, t: f64) -> BGRA8 {
+        let t = match self.spread {
+            SpreadMethod::Pad => t,
+            SpreadMethod::Repeat => t - t.floor(),
+            SpreadMethod::Reflect => {
+                let t = (t % 2.0).abs();
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        };
+
+        let t = if t.is_finite() { t.max(0.0).min(1.0) } else { 0.0 };
+        self.lut[(t * (LUT_SIZE - 1) as f64 + 0.5) as usize]
+    }
+}
+
+fn build_lut(stops: &[GradientStop]) -> Vec<BGRA8> {
+    let mut lut = Vec::with_capacity(LUT_SIZE);
+    let to_color = |r: f64, g: f64, b: f64, a: f64| {
+        pixmap::premultiply(
+            (r + 0.5) as u8,
+            (g + 0.5) as u8,
+            (b + 0.5) as u8,
+            (a.max(0.0).min(1.0) * 255.0 + 0.5) as u8,
+        )
+    };
+
+    for i in 0..LUT_SIZE {
+        let t = i as f64 / (LUT_SIZE - 1) as f64;
+
+        // Find the last stop with an offset <= t.
+        let idx = stops.iter().rposition(|s| s.offset <= t);
+        let c = match idx {
+            None => {
+                let s = stops[0];
+                to_color(s.r as f64, s.g as f64, s.b as f64, s.a)
+            }
+            Some(idx) if idx + 1 == stops.len() => {
+                let s = stops[idx];
+                to_color(s.r as f64, s.g as f64, s.b as f64, s.a)
+            }
+            Some(idx) => {
+                let s1 = stops[idx];
+                let s2 = stops[idx + 1];
+                let d = s2.offset - s1.offset;
+                let k = if d > 0.0 { (t - s1.offset) / d } else { 1.0 };
+                let mix = |a: f64, b: f64| a + (b - a) * k;
+                to_color(
+                    mix(s1.r as f64, s2.r as f64),
+                    mix(s1.g as f64, s2.g as f64),
+                    mix(s1.b as f64, s2.b as f64),
+                    mix(s1.a, s2.a),
+                )
+            }
+        };
+
+        lut.push(c);
+    }
+
+    lut
+}
+
+
+pub enum Shader<'a> {
+    SolidColor(BGRA8),
+    LinearGradient {
+        base: Gradient,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    /// A two-point conical gradient with a zero focal radius.
+    RadialGradient {
+        base: Gradient,
+        cx: f64,
+        cy: f64,
+        r: f64,
+        fx: f64,
+        fy: f64,
+    },
+    Pattern {
+        pixmap: &'a Pixmap,
+        /// Maps a device position into the pixmap coordinates.
+        ts: usvg::Transform,
+        extend: Extend,
+        quality: FilterQuality,
+    },
+}
+
+impl<'a> Shader<'a> {
+    /// Creates a solid color shader from a non-premultiplied color.
+    pub fn new_color(color: usvg::Color, opacity: f64) -> Self {
+        let a = (opacity.max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+        Shader::SolidColor(pixmap::premultiply(color.red, color.green, color.blue, a))
+    }
+
+    /// Creates a pixmap shader.
+    ///
+    /// `ts` maps the pixmap coordinates into the device ones.
+    pub fn new_pattern(
+        pixmap: &'a Pixmap,
+        ts: &usvg::Transform,
+        extend: Extend,
+        quality: FilterQuality,
+    ) -> Option<Self> {
+        Some(Shader::Pattern {
+            pixmap,
+            ts: utils::invert_transform(ts)?,
+            extend,
+            quality,
+        })
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        match *self {
+            Shader::SolidColor(c) => c.a == 0,
+            _ => false,
+        }
+    }
+
+    /// Returns a premultiplied color of the pixel at the specified device position.
+    pub fn shade(&self, x: u32, y: u32) -> BGRA8 {
+        let px = x as f64 + 0.5;
+        let py = y as f64 + 0.5;
+
+        match *self {
+            Shader::SolidColor(c) => c,
+            Shader::LinearGradient { ref base, x1, y1, x2, y2 } => {
+                let (gx, gy) = base.ts.apply(px, py);
+                let dx = x2 - x1;
+                let dy = y2 - y1;
+                let len2 = dx * dx + dy * dy;
+                let t = if len2 > 0.0 {
+                    ((gx - x1) * dx + (gy - y1) * dy) / len2
+                } else {
+                    1.0
+                };
+
+                base.color_at(t)
+            }
+            Shader::RadialGradient { ref base, cx, cy, r, fx, fy } => {
+                let (gx, gy) = base.ts.apply(px, py);
+                match radial_t(gx - fx, gy - fy, cx - fx, cy - fy, r) {
+                    Some(t) => base.color_at(t),
+                    None => BGRA8 { b: 0, g: 0, r: 0, a: 0 },
+                }
+            }
+            Shader::Pattern { pixmap, ref ts, extend, quality } => {
+                let (u, v) = ts.apply(px, py);
+                sample(pixmap, u, v, extend, quality)
+            }
+        }
+    }
+}
+
+/// Solves `|p - t * c| = t * r` for the largest `t >= 0`.
+///
+/// `p` and `c` are relative to the focal point.
+fn radial_t(px: f64, py: f64, cx: f64, cy: f64, r: f64) -> Option<f64> {
+    let a = cx * cx + cy * cy - r * r;
+    let b = px * cx + py * cy;
+    let c = px * px + py * py;
+
+    if a.abs() < 1e-9 {
+        if b.abs() < 1e-9 {
+            return None;
+        }
+
+        let t = c / (2.0 * b);
+        return if t >= 0.0 { Some(t) } else { None };
+    }
+
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return None;
+    }
+
+    let sq = disc.sqrt();
+    let t1 = (b + sq) / a;
+    let t2 = (b - sq) / a;
+    let t = t1.max(t2);
+    if t >= 0.0 { Some(t) } else { None }
+}
+
+fn sample(
+    pixmap: &Pixmap,
+    u: f64,
+    v: f64,
+    extend: Extend,
+    quality: FilterQuality,
+) -> BGRA8 {
+    let w = pixmap.width() as i64;
+    let h = pixmap.height() as i64;
+
+    let wrap = |n: i64, max: i64| -> u32 {
+        match extend {
+            Extend::Pad => n.max(0).min(max - 1) as u32,
+            Extend::Repeat => (((n % max) + max) % max) as u32,
+        }
+    };
+
+    if !(u.is_finite() && v.is_finite()) {
+        return BGRA8 { b: 0, g: 0, r: 0, a: 0 };
+    }
+
+    match quality {
+        FilterQuality::Nearest => {
+            pixmap.pixel(wrap(u.floor() as i64, w), wrap(v.floor() as i64, h))
+        }
+        FilterQuality::Bilinear => {
+            let u = u - 0.5;
+            let v = v - 0.5;
+            let x0 = u.floor();
+            let y0 = v.floor();
+            let fx = u - x0;
+            let fy = v - y0;
+            let x0 = x0 as i64;
+            let y0 = y0 as i64;
+
+            let p00 = pixmap.pixel(wrap(x0, w), wrap(y0, h));
+            let p10 = pixmap.pixel(wrap(x0 + 1, w), wrap(y0, h));
+            let p01 = pixmap.pixel(wrap(x0, w), wrap(y0 + 1, h));
+            let p11 = pixmap.pixel(wrap(x0 + 1, w), wrap(y0 + 1, h));
+
+            let mix = |c00: u8, c10: u8, c01: u8, c11: u8| -> u8 {
+                let top = c00 as f64 + (c10 as f64 - c00 as f64) * fx;
+                let bottom = c01 as f64 + (c11 as f64 - c01 as f64) * fx;
+                (top + (bottom - top) * fy + 0.5) as u8
+            };
+
+            BGRA8 {
+                b: mix(p00.b, p10.b, p01.b, p11.b),
+                g: mix(p00.g, p10.g, p01.g, p11.g),
+                r: mix(p00.r, p10.r, p01.r, p11.r),
+                a: mix(p00.a, p10.a, p01.a, p11.a),
+            }
+        }
+    }
+}
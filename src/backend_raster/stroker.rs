@@ -0,0 +1,645 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Path flattening, dashing and stroking.
+//!
+//! A stroke is represented as a set of consistently oriented polygons
+//! (segment quads, joins and caps), which can be filled using the non-zero rule.
+
+use std::f64::consts::PI;
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    #[inline]
+    pub fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    #[inline]
+    fn sub(self, p: Point) -> Point {
+        Point::new(self.x - p.x, self.y - p.y)
+    }
+
+    #[inline]
+    fn add(self, p: Point) -> Point {
+        Point::new(self.x + p.x, self.y + p.y)
+    }
+
+    #[inline]
+    fn scale(self, n: f64) -> Point {
+        Point::new(self.x * n, self.y * n)
+    }
+
+    #[inline]
+    fn length(self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    #[inline]
+    fn dot(self, p: Point) -> f64 {
+        self.x * p.x + self.y * p.y
+    }
+
+    #[inline]
+    fn cross(self, p: Point) -> f64 {
+        self.x * p.y - self.y * p.x
+    }
+
+    #[inline]
+    fn lerp(self, p: Point, t: f64) -> Point {
+        Point::new(self.x + (p.x - self.x) * t, self.y + (p.y - self.y) * t)
+    }
+
+    /// Returns a unit vector, rotated by 90 degrees.
+    #[inline]
+    fn normal(self) -> Point {
+        let len = self.length();
+        Point::new(-self.y / len, self.x / len)
+    }
+
+    #[inline]
+    fn is_same(self, p: Point) -> bool {
+        (self.x - p.x).abs() < 1e-9 && (self.y - p.y).abs() < 1e-9
+    }
+}
+
+
+/// A flattened subpath.
+#[derive(Clone, Debug)]
+pub struct Polyline {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Debug)]
+pub struct StrokeProps {
+    pub width: f64,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miterlimit: f64,
+    pub dasharray: Option<Vec<f64>>,
+    pub dashoffset: f64,
+}
+
+
+/// Converts path segments into polylines.
+///
+/// Each point will be transformed via `ts` before flattening,
+/// so `tolerance` is in the transformed coordinates.
+pub fn flatten(
+    segments: &[usvg::PathSegment],
+    ts: &usvg::Transform,
+    tolerance: f64,
+) -> Vec<Polyline> {
+    let mut polylines = Vec::new();
+    let mut points: Vec<Point> = Vec::new();
+    let mut has_segments = false;
+    let mut start = Point::new(0.0, 0.0);
+    let mut prev = Point::new(0.0, 0.0);
+
+    fn finish(polylines: &mut Vec<Polyline>, points: &mut Vec<Point>, has_segments: bool, closed: bool) {
+        if has_segments && !points.is_empty() {
+            polylines.push(Polyline { points: points.clone(), closed });
+        }
+
+        points.clear();
+    }
+
+    let apply = |x: f64, y: f64| {
+        let (x, y) = ts.apply(x, y);
+        Point::new(x, y)
+    };
+
+    let push = |points: &mut Vec<Point>, p: Point| {
+        if points.last().map(|last| !last.is_same(p)).unwrap_or(true) {
+            points.push(p);
+        }
+    };
+
+    for seg in segments {
+        match *seg {
+            usvg::PathSegment::MoveTo { x, y } => {
+                finish(&mut polylines, &mut points, has_segments, false);
+                has_segments = false;
+
+                let p = apply(x, y);
+                points.push(p);
+                start = p;
+                prev = p;
+            }
+            usvg::PathSegment::LineTo { x, y } => {
+                if points.is_empty() {
+                    points.push(prev);
+                }
+
+                let p = apply(x, y);
+                push(&mut points, p);
+                has_segments = true;
+                prev = p;
+            }
+            usvg::PathSegment::CurveTo { x1, y1, x2, y2, x, y } => {
+                if points.is_empty() {
+                    points.push(prev);
+                }
+
+                let p1 = apply(x1, y1);
+                let p2 = apply(x2, y2);
+                let p3 = apply(x, y);
+                flatten_cubic(prev, p1, p2, p3, tolerance, &mut |p| push(&mut points, p));
+                has_segments = true;
+                prev = p3;
+            }
+            usvg::PathSegment::ClosePath => {
+                // A closed zero-length subpath still has caps.
+                if !points.is_empty() {
+                    has_segments = true;
+                }
+
+                if points.len() > 1 && points.last().unwrap().is_same(points[0]) {
+                    points.pop();
+                }
+
+                finish(&mut polylines, &mut points, has_segments, true);
+                has_segments = false;
+                prev = start;
+            }
+        }
+    }
+
+    finish(&mut polylines, &mut points, has_segments, false);
+
+    polylines
+}
+
+fn flatten_cubic<F: FnMut(Point)>(
+    p0: Point,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    tolerance: f64,
+    f: &mut F,
+) {
+    // The flattening error of a cubic is bounded by 3/4 * max|d2| / n^2.
+    let dd1 = p0.sub(p1.scale(2.0)).add(p2).length();
+    let dd2 = p1.sub(p2.scale(2.0)).add(p3).length();
+    let dd = dd1.max(dd2);
+    let n = (0.75 * dd / tolerance).sqrt().ceil();
+    let n = if n.is_finite() { n.max(1.0).min(500.0) as u32 } else { 1 };
+
+    for i in 1..n + 1 {
+        let t = i as f64 / n as f64;
+        let mt = 1.0 - t;
+        let a = mt * mt * mt;
+        let b = 3.0 * mt * mt * t;
+        let c = 3.0 * mt * t * t;
+        let d = t * t * t;
+        f(Point::new(
+            a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+            a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+        ));
+    }
+}
+
+
+/// Splits polylines into dashes.
+pub fn dash(
+    polylines: &[Polyline],
+    dasharray: &[f64],
+    offset: f64,
+) -> Vec<Polyline> {
+    let total: f64 = dasharray.iter().sum();
+    if !(total > 0.0) || dasharray.iter().any(|n| *n < 0.0) {
+        return polylines.to_vec();
+    }
+
+    // An odd list is repeated to yield an even number of values.
+    let mut list = dasharray.to_vec();
+    if list.len() % 2 != 0 {
+        list.extend_from_slice(dasharray);
+    }
+    let dasharray = &list;
+    let total: f64 = dasharray.iter().sum();
+
+    // Find the initial dash state.
+    let mut offset = offset % total;
+    if offset < 0.0 {
+        offset += total;
+    }
+
+    let mut first_idx = 0;
+    while offset >= dasharray[first_idx] {
+        offset -= dasharray[first_idx];
+        first_idx = (first_idx + 1) % dasharray.len();
+    }
+    let first_remaining = dasharray[first_idx] - offset;
+
+    let mut dashes = Vec::new();
+    for polyline in polylines {
+        let mut points = polyline.points.clone();
+        if polyline.closed {
+            points.push(points[0]);
+        }
+
+        if points.len() < 2 {
+            continue;
+        }
+
+        // Dashing restarts on each subpath.
+        let mut idx = first_idx;
+        let mut remaining = first_remaining;
+        let mut is_on = idx % 2 == 0;
+        let mut current = Vec::new();
+        if is_on {
+            current.push(points[0]);
+        }
+
+        for w in points.windows(2) {
+            let (mut a, b) = (w[0], w[1]);
+            let mut seg_len = b.sub(a).length();
+
+            while seg_len > remaining {
+                let p = a.lerp(b, remaining / seg_len);
+                seg_len -= remaining;
+                a = p;
+
+                if is_on {
+                    current.push(p);
+                    dashes.push(Polyline { points: current.clone(), closed: false });
+                    current.clear();
+                } else {
+                    current.push(p);
+                }
+
+                idx = (idx + 1) % dasharray.len();
+                remaining = dasharray[idx];
+                is_on = idx % 2 == 0;
+            }
+
+            remaining -= seg_len;
+            if is_on {
+                current.push(b);
+            }
+        }
+
+        if is_on && current.len() > 1 {
+            dashes.push(Polyline { points: current, closed: false });
+        }
+    }
+
+    dashes
+}
+
+
+/// Builds a stroke outline.
+///
+/// Returns a list of positively oriented polygons.
+pub fn stroke(
+    polylines: &[Polyline],
+    props: &StrokeProps,
+    tolerance: f64,
+) -> Vec<Vec<Point>> {
+    let hw = props.width / 2.0;
+    let mut polygons = Vec::new();
+    if !(hw > 0.0) {
+        return polygons;
+    }
+
+    let dashed;
+    let polylines = match props.dasharray {
+        Some(ref list) => {
+            dashed = dash(polylines, list, props.dashoffset);
+            &dashed
+        }
+        None => polylines,
+    };
+
+    for polyline in polylines {
+        stroke_polyline(polyline, props, hw, tolerance, &mut polygons);
+    }
+
+    polygons
+}
+
+fn stroke_polyline(
+    polyline: &Polyline,
+    props: &StrokeProps,
+    hw: f64,
+    tolerance: f64,
+    polygons: &mut Vec<Vec<Point>>,
+) {
+    let mut points: Vec<Point> = Vec::with_capacity(polyline.points.len());
+    for p in &polyline.points {
+        if points.last().map(|last| !last.is_same(*p)).unwrap_or(true) {
+            points.push(*p);
+        }
+    }
+
+    if polyline.closed && points.len() > 1 && points[points.len() - 1].is_same(points[0]) {
+        points.pop();
+    }
+
+    if points.len() == 1 {
+        // A zero-length subpath is rendered only with round or square caps.
+        let p = points[0];
+        match props.cap {
+            LineCap::Butt => {}
+            LineCap::Round => push_polygon(polygons, circle(p, hw, tolerance)),
+            LineCap::Square => {
+                push_polygon(polygons, vec![
+                    Point::new(p.x - hw, p.y - hw),
+                    Point::new(p.x + hw, p.y - hw),
+                    Point::new(p.x + hw, p.y + hw),
+                    Point::new(p.x - hw, p.y + hw),
+                ]);
+            }
+        }
+
+        return;
+    }
+
+    let count = if polyline.closed { points.len() } else { points.len() - 1 };
+    for i in 0..count {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let n = b.sub(a).normal().scale(hw);
+        push_polygon(polygons, vec![a.add(n), b.add(n), b.sub(n), a.sub(n)]);
+    }
+
+    // Joins.
+    let join_range = if polyline.closed { 0..points.len() } else { 1..points.len() - 1 };
+    for i in join_range {
+        let prev = points[(i + points.len() - 1) % points.len()];
+        let curr = points[i];
+        let next = points[(i + 1) % points.len()];
+
+        add_join(prev, curr, next, props, hw, tolerance, polygons);
+    }
+
+    // Caps.
+    if !polyline.closed {
+        let first = points[0];
+        let second = points[1];
+        let last = points[points.len() - 1];
+        let before_last = points[points.len() - 2];
+        add_cap(first, first.sub(second), props.cap, hw, tolerance, polygons);
+        add_cap(last, last.sub(before_last), props.cap, hw, tolerance, polygons);
+    }
+}
+
+fn add_join(
+    prev: Point,
+    curr: Point,
+    next: Point,
+    props: &StrokeProps,
+    hw: f64,
+    tolerance: f64,
+    polygons: &mut Vec<Vec<Point>>,
+) {
+    let d0 = curr.sub(prev);
+    let d1 = next.sub(curr);
+    let cross = d0.cross(d1);
+
+    let l0 = d0.length();
+    let l1 = d1.length();
+    let cos = d0.dot(d1) / (l0 * l1);
+    if cross.abs() < 1e-12 && cos > 0.0 {
+        // Collinear segments do not need a join.
+        return;
+    }
+
+    if props.join == LineJoin::Round {
+        push_polygon(polygons, circle(curr, hw, tolerance));
+        return;
+    }
+
+    // The outer side of the join.
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let n0 = d0.normal().scale(hw * side);
+    let n1 = d1.normal().scale(hw * side);
+    let p0 = curr.add(n0);
+    let p1 = curr.add(n1);
+
+    if props.join == LineJoin::Miter {
+        // miter_length / stroke_width = 1 / sin(theta / 2)
+        let ratio = (2.0 / (1.0 + cos)).sqrt();
+        if ratio <= props.miterlimit {
+            let mid = n0.add(n1);
+            let mid_len = mid.length();
+            if mid_len > 0.0 {
+                let miter = curr.add(mid.scale(hw * ratio / mid_len));
+                push_polygon(polygons, vec![curr, p0, miter, p1]);
+                return;
+            }
+        }
+    }
+
+    push_polygon(polygons, vec![curr, p0, p1]);
+}
+
+fn add_cap(
+    p: Point,
+    dir: Point,
+    cap: LineCap,
+    hw: f64,
+    tolerance: f64,
+    polygons: &mut Vec<Vec<Point>>,
+) {
+    match cap {
+        LineCap::Butt => {}
+        LineCap::Round => push_polygon(polygons, circle(p, hw, tolerance)),
+        LineCap::Square => {
+            let len = dir.length();
+            if len == 0.0 {
+                return;
+            }
+
+            let d = dir.scale(hw / len);
+            let n = Point::new(-d.y, d.x);
+            push_polygon(polygons, vec![p.add(n), p.add(n).add(d), p.sub(n).add(d), p.sub(n)]);
+        }
+    }
+}
+
+fn circle(center: Point, r: f64, tolerance: f64) -> Vec<Point> {
+    let n = if r > tolerance {
+        (PI / (1.0 - tolerance / r).acos()).ceil()
+    } else {
+        8.0
+    };
+    let n = if n.is_finite() { n.max(8.0).min(360.0) as u32 } else { 8 };
+
+    (0..n).map(|i| {
+        let a = 2.0 * PI * i as f64 / n as f64;
+        Point::new(center.x + r * a.cos(), center.y + r * a.sin())
+    }).collect()
+}
+
+fn push_polygon(polygons: &mut Vec<Vec<Point>>, mut points: Vec<Point>) {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        area += a.cross(b);
+    }
+
+    if area.abs() < 1e-12 || !area.is_finite() {
+        return;
+    }
+
+    if area < 0.0 {
+        points.reverse();
+    }
+
+    polygons.push(points);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend_raster::rasterizer::{self, Coverage, Edge, FillRule};
+
+    fn props(cap: LineCap, join: LineJoin) -> StrokeProps {
+        StrokeProps {
+            width: 2.0,
+            cap,
+            join,
+            miterlimit: 4.0,
+            dasharray: None,
+            dashoffset: 0.0,
+        }
+    }
+
+    fn polyline(points: &[(f64, f64)]) -> Vec<Polyline> {
+        vec![Polyline {
+            points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            closed: false,
+        }]
+    }
+
+    fn render(polylines: &[Polyline], props: &StrokeProps) -> Coverage {
+        let mut edges = Vec::new();
+        for polygon in stroke(polylines, props, 0.01) {
+            for i in 0..polygon.len() {
+                let a = polygon[i];
+                let b = polygon[(i + 1) % polygon.len()];
+                edges.push(Edge { x0: a.x, y0: a.y, x1: b.x, y1: b.y });
+            }
+        }
+
+        rasterizer::rasterize(&edges, FillRule::NonZero, true, 12, 12).unwrap()
+    }
+
+    fn pixel(coverage: &Coverage, x: u32, y: u32) -> u8 {
+        if x < coverage.x || y < coverage.y
+           || x >= coverage.x + coverage.width || y >= coverage.y + coverage.height {
+            return 0;
+        }
+
+        coverage.data[((y - coverage.y) * coverage.width + x - coverage.x) as usize]
+    }
+
+    #[test]
+    fn caps() {
+        let line = polyline(&[(2.0, 5.0), (8.0, 5.0)]);
+
+        let c = render(&line, &props(LineCap::Butt, LineJoin::Miter));
+        assert_eq!(pixel(&c, 5, 4), 255);
+        assert_eq!(pixel(&c, 1, 4), 0);
+        assert_eq!(pixel(&c, 8, 5), 0);
+
+        let c = render(&line, &props(LineCap::Square, LineJoin::Miter));
+        assert_eq!(pixel(&c, 1, 4), 255);
+        assert_eq!(pixel(&c, 8, 5), 255);
+        assert_eq!(pixel(&c, 9, 5), 0);
+
+        // A quarter of a circle.
+        let c = render(&line, &props(LineCap::Round, LineJoin::Miter));
+        assert!((pixel(&c, 1, 4) as i32 - 200).abs() <= 2);
+        assert!((pixel(&c, 8, 5) as i32 - 200).abs() <= 2);
+    }
+
+    #[test]
+    fn joins() {
+        let line = polyline(&[(2.0, 8.0), (8.0, 8.0), (8.0, 2.0)]);
+
+        // The outer corner pixel is covered only by the join.
+        let c = render(&line, &props(LineCap::Butt, LineJoin::Miter));
+        assert_eq!(pixel(&c, 8, 8), 255);
+
+        let c = render(&line, &props(LineCap::Butt, LineJoin::Bevel));
+        assert_eq!(pixel(&c, 8, 8), 128);
+
+        let c = render(&line, &props(LineCap::Butt, LineJoin::Round));
+        assert!((pixel(&c, 8, 8) as i32 - 200).abs() <= 2);
+
+        // The miter ratio of a right angle is sqrt(2), so it will fallback to bevel.
+        let mut p = props(LineCap::Butt, LineJoin::Miter);
+        p.miterlimit = 1.4;
+        let c = render(&line, &p);
+        assert_eq!(pixel(&c, 8, 8), 128);
+    }
+
+    #[test]
+    fn dashes() {
+        fn ranges(dashes: &[Polyline]) -> Vec<(f64, f64)> {
+            dashes.iter().map(|d| (d.points[0].x, d.points[d.points.len() - 1].x)).collect()
+        }
+
+        let line = polyline(&[(0.0, 0.0), (10.0, 0.0)]);
+
+        assert_eq!(ranges(&dash(&line, &[2.0, 3.0], 0.0)),
+                   vec![(0.0, 2.0), (5.0, 7.0)]);
+
+        assert_eq!(ranges(&dash(&line, &[2.0, 3.0], 1.0)),
+                   vec![(0.0, 1.0), (4.0, 6.0), (9.0, 10.0)]);
+
+        // An odd list is repeated.
+        assert_eq!(ranges(&dash(&line, &[2.0], 0.0)),
+                   vec![(0.0, 2.0), (4.0, 6.0), (8.0, 10.0)]);
+
+        // A negative offset.
+        assert_eq!(ranges(&dash(&line, &[2.0, 3.0], -1.0)),
+                   vec![(1.0, 3.0), (6.0, 8.0)]);
+
+        // Invalid lists are ignored.
+        assert_eq!(dash(&line, &[0.0, 0.0], 0.0).len(), 1);
+        assert_eq!(dash(&line, &[2.0, -1.0], 0.0).len(), 1);
+    }
+
+    #[test]
+    fn dashed_stroke() {
+        let line = polyline(&[(0.0, 5.0), (10.0, 5.0)]);
+        let mut p = props(LineCap::Butt, LineJoin::Miter);
+        p.dasharray = Some(vec![2.0, 2.0]);
+
+        let c = render(&line, &p);
+        assert_eq!(pixel(&c, 0, 5), 255);
+        assert_eq!(pixel(&c, 1, 5), 255);
+        assert_eq!(pixel(&c, 2, 5), 0);
+        assert_eq!(pixel(&c, 3, 5), 0);
+        assert_eq!(pixel(&c, 4, 5), 255);
+    }
+}
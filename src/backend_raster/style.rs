@@ -0,0 +1,272 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// external
+use usvg::{
+    try_opt,
+};
+
+// self
+use crate::prelude::*;
+use crate::backend_utils::RenderState;
+use super::rasterizer::FillRule;
+use super::shader::{
+    self,
+    Shader,
+};
+use super::stroker::{
+    LineCap,
+    LineJoin,
+    StrokeProps,
+};
+use super::{
+    Canvas,
+    Pixmap,
+};
+
+
+pub fn fill(
+    tree: &usvg::Tree,
+    segments: &[usvg::PathSegment],
+    fill: &Option<usvg::Fill>,
+    opt: &Options,
+    bbox: Rect,
+    canvas: &mut Canvas,
+) {
+    match *fill {
+        Some(ref fill) => {
+            let rule = match fill.rule {
+                usvg::FillRule::NonZero => FillRule::NonZero,
+                usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+            };
+
+            with_shader(tree, &fill.paint, fill.opacity, opt, bbox, canvas, |canvas, shader| {
+                canvas.fill_path(segments, shader, rule);
+            });
+        }
+        None => {
+            // A transparent fill still affects the canvas when
+            // a non-default blend mode is set. Like in clip paths.
+            let shader = Shader::new_color(usvg::Color::black(), 0.0);
+            canvas.fill_path(segments, &shader, FillRule::NonZero);
+        }
+    }
+}
+
+pub fn stroke(
+    tree: &usvg::Tree,
+    segments: &[usvg::PathSegment],
+    stroke: &usvg::Stroke,
+    opt: &Options,
+    bbox: Rect,
+    canvas: &mut Canvas,
+) {
+    let cap = match stroke.linecap {
+        usvg::LineCap::Butt => LineCap::Butt,
+        usvg::LineCap::Round => LineCap::Round,
+        usvg::LineCap::Square => LineCap::Square,
+    };
+
+    let join = match stroke.linejoin {
+        usvg::LineJoin::Miter => LineJoin::Miter,
+        usvg::LineJoin::Round => LineJoin::Round,
+        usvg::LineJoin::Bevel => LineJoin::Bevel,
+    };
+
+    let props = StrokeProps {
+        width: stroke.width.value(),
+        cap,
+        join,
+        miterlimit: stroke.miterlimit.value(),
+        dasharray: stroke.dasharray.clone(),
+        dashoffset: stroke.dashoffset as f64,
+    };
+
+    with_shader(tree, &stroke.paint, stroke.opacity, opt, bbox, canvas, |canvas, shader| {
+        if stroke.non_scaling {
+            // Stroke the path in the device space, so the transform
+            // will not affect the stroke width and dashes.
+            // The shader is already in the device space.
+            let ts = canvas.get_transform();
+            let mut segments = segments.to_vec();
+            utils::transform_path(&mut segments, &ts);
+
+            canvas.reset_transform();
+            canvas.stroke_path(&segments, shader, &props);
+            canvas.set_transform(ts);
+        } else {
+            canvas.stroke_path(segments, shader, &props);
+        }
+    });
+}
+
+/// Prepares a shader for the paint and passes it to the callback.
+///
+/// The callback will not be called if the paint is invalid.
+fn with_shader<F>(
+    tree: &usvg::Tree,
+    paint: &usvg::Paint,
+    opacity: usvg::Opacity,
+    opt: &Options,
+    bbox: Rect,
+    canvas: &mut Canvas,
+    f: F,
+)
+where
+    F: FnOnce(&mut Canvas, &Shader),
+{
+    match *paint {
+        usvg::Paint::Color(c) => {
+            f(canvas, &Shader::new_color(c, opacity.value()));
+        }
+        usvg::Paint::Link(ref id) => {
+            if let Some(node) = tree.defs_by_id(id) {
+                match *node.borrow() {
+                    usvg::NodeKind::LinearGradient(ref lg) => {
+                        let shader = try_opt!(prepare_linear(lg, opacity, bbox, canvas));
+                        f(canvas, &shader);
+                    }
+                    usvg::NodeKind::RadialGradient(ref rg) => {
+                        let shader = try_opt!(prepare_radial(rg, opacity, bbox, canvas));
+                        f(canvas, &shader);
+                    }
+                    usvg::NodeKind::Pattern(ref pattern) => {
+                        let (pixmap, ts) = try_opt!(
+                            prepare_pattern(&node, pattern, opt, opacity, bbox, canvas)
+                        );
+                        let shader = try_opt!(Shader::new_pattern(
+                            &pixmap, &ts, shader::Extend::Repeat, shader::FilterQuality::Bilinear,
+                        ));
+                        f(canvas, &shader);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn prepare_linear(
+    g: &usvg::LinearGradient,
+    opacity: usvg::Opacity,
+    bbox: Rect,
+    canvas: &Canvas,
+) -> Option<Shader<'static>> {
+    let base = prepare_base_gradient(&g.base, opacity, bbox, canvas)?;
+    Some(Shader::LinearGradient {
+        base,
+        x1: g.x1,
+        y1: g.y1,
+        x2: g.x2,
+        y2: g.y2,
+    })
+}
+
+fn prepare_radial(
+    g: &usvg::RadialGradient,
+    opacity: usvg::Opacity,
+    bbox: Rect,
+    canvas: &Canvas,
+) -> Option<Shader<'static>> {
+    let base = prepare_base_gradient(&g.base, opacity, bbox, canvas)?;
+    Some(Shader::RadialGradient {
+        base,
+        cx: g.cx,
+        cy: g.cy,
+        r: g.r.value(),
+        fx: g.fx,
+        fy: g.fy,
+    })
+}
+
+fn prepare_base_gradient(
+    g: &usvg::BaseGradient,
+    opacity: usvg::Opacity,
+    bbox: Rect,
+    canvas: &Canvas,
+) -> Option<shader::Gradient> {
+    let spread_method = match g.spread_method {
+        usvg::SpreadMethod::Pad => shader::SpreadMethod::Pad,
+        usvg::SpreadMethod::Reflect => shader::SpreadMethod::Reflect,
+        usvg::SpreadMethod::Repeat => shader::SpreadMethod::Repeat,
+    };
+
+    let mut ts = canvas.get_transform();
+
+    if g.units == usvg::Units::ObjectBoundingBox {
+        ts.append(&usvg::Transform::from_bbox(bbox));
+    }
+
+    ts.append(&g.transform);
+
+    let stops: Vec<_> = g.stops.iter().map(|stop| {
+        shader::GradientStop {
+            offset: stop.offset.value(),
+            r: stop.color.red,
+            g: stop.color.green,
+            b: stop.color.blue,
+            a: stop.opacity.value() * opacity.value(),
+        }
+    }).collect();
+
+    shader::Gradient::new(&ts, spread_method, &stops)
+}
+
+/// Renders the pattern tile.
+///
+/// Returns the tile and a tile to device transform.
+fn prepare_pattern(
+    node: &usvg::Node,
+    pattern: &usvg::Pattern,
+    opt: &Options,
+    opacity: usvg::Opacity,
+    bbox: Rect,
+    canvas: &Canvas,
+) -> Option<(Pixmap, usvg::Transform)> {
+    let r = if pattern.units == usvg::Units::ObjectBoundingBox {
+        pattern.rect.bbox_transform(bbox)
+    } else {
+        pattern.rect
+    };
+
+    let global_ts = canvas.get_transform();
+    let (sx, sy) = global_ts.get_scale();
+
+    let img_size = Size::new(r.width() * sx, r.height() * sy)?.to_screen_size();
+    let mut pixmap = try_create_pixmap!(img_size, None);
+
+    {
+        let mut sub_canvas = Canvas::new(&mut pixmap);
+        sub_canvas.transform(usvg::Transform::new(sx, 0.0, 0.0, sy, 0.0, 0.0));
+
+        if let Some(vbox) = pattern.view_box {
+            let ts = utils::view_box_to_transform(vbox.rect, vbox.aspect, r.size());
+            sub_canvas.transform(ts);
+        } else if pattern.content_units == usvg::Units::ObjectBoundingBox {
+            // 'Note that this attribute has no effect if attribute `viewBox` is specified.'
+
+            // We don't use Transform::from_bbox(bbox) because `x` and `y` should be
+            // ignored for some reasons...
+            sub_canvas.transform(usvg::Transform::new(bbox.width(), 0.0, 0.0, bbox.height(), 0.0, 0.0));
+        }
+
+        let mut layers = super::create_layers(img_size, opt);
+        super::render_group(node, opt, &mut RenderState::Ok, &mut layers, &mut sub_canvas);
+    }
+
+    if !opacity.is_default() {
+        // Pixels are premultiplied, so we can simply scale all the channels.
+        let a = opacity.value();
+        for c in pixmap.data_mut() {
+            *c = (*c as f64 * a + 0.5) as u8;
+        }
+    }
+
+    let mut ts = global_ts;
+    ts.append(&pattern.transform);
+    ts.translate(r.x(), r.y());
+    ts.scale(1.0 / sx, 1.0 / sy);
+
+    Some((pixmap, ts))
+}
@@ -0,0 +1,256 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A software implementation of the `mix-blend-mode`.
+//!
+//! Used by backends that do not support all blending modes natively.
+//!
+//! https://www.w3.org/TR/compositing-1/#blending
+
+use rgb::alt::BGRA8;
+
+
+/// Blends a premultiplied BGRA8 `src` image into the `dst` one.
+///
+/// `mask` is an optional per-pixel coverage, like a clip mask.
+///
+/// All images must have the same size.
+pub fn blend_image(
+    mode: usvg::BlendMode,
+    src: &[BGRA8],
+    dst: &mut [BGRA8],
+    mask: Option<&[u8]>,
+    opacity: f64,
+) {
+    debug_assert_eq!(src.len(), dst.len());
+
+    let opacity = opacity as f32;
+    match mask {
+        Some(mask) => {
+            debug_assert_eq!(src.len(), mask.len());
+
+            // The result is linear in the source alpha,
+            // so the coverage can be applied via opacity.
+            for ((s, d), m) in src.iter().zip(dst.iter_mut()).zip(mask) {
+                *d = blend_pixel(mode, *s, *d, opacity * *m as f32 / 255.0);
+            }
+        }
+        None => {
+            for (s, d) in src.iter().zip(dst.iter_mut()) {
+                *d = blend_pixel(mode, *s, *d, opacity);
+            }
+        }
+    }
+}
+
+fn blend_pixel(
+    mode: usvg::BlendMode,
+    s: BGRA8,
+    d: BGRA8,
+    opacity: f32,
+) -> BGRA8 {
+    if s.a == 0 || opacity <= 0.0 {
+        return d;
+    }
+
+    let sa = s.a as f32 / 255.0 * opacity;
+    let da = d.a as f32 / 255.0;
+    let cs = demultiply(s);
+    let cb = demultiply(d);
+
+    let b = match mode {
+          usvg::BlendMode::Hue
+        | usvg::BlendMode::Saturation
+        | usvg::BlendMode::Color
+        | usvg::BlendMode::Luminosity => blend_non_separable(mode, cs, cb),
+        _ => [
+            blend_separable(mode, cs[0], cb[0]),
+            blend_separable(mode, cs[1], cb[1]),
+            blend_separable(mode, cs[2], cb[2]),
+        ],
+    };
+
+    // https://www.w3.org/TR/compositing-1/#generalformula
+    let ra = sa + da - sa * da;
+    let channel = |i: usize| -> f32 {
+        let c = sa * (1.0 - da) * cs[i] + sa * da * b[i] + (1.0 - sa) * da * cb[i];
+        c.min(ra)
+    };
+
+    let to_u8 = |v: f32| (v.max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+    BGRA8 {
+        r: to_u8(channel(0)),
+        g: to_u8(channel(1)),
+        b: to_u8(channel(2)),
+        a: to_u8(ra),
+    }
+}
+
+/// Returns a non-premultiplied RGB color in a 0..1 range.
+fn demultiply(c: BGRA8) -> [f32; 3] {
+    if c.a == 0 {
+        return [0.0; 3];
+    }
+
+    let a = c.a as f32;
+    [c.r as f32 / a, c.g as f32 / a, c.b as f32 / a]
+}
+
+fn blend_separable(
+    mode: usvg::BlendMode,
+    cs: f32,
+    cb: f32,
+) -> f32 {
+    fn hard_light(cs: f32, cb: f32) -> f32 {
+        if cs <= 0.5 {
+            cb * 2.0 * cs
+        } else {
+            let cs = 2.0 * cs - 1.0;
+            cb + cs - cb * cs
+        }
+    }
+
+    match mode {
+        usvg::BlendMode::Multiply => cs * cb,
+        usvg::BlendMode::Screen => cb + cs - cb * cs,
+        usvg::BlendMode::Overlay => hard_light(cb, cs),
+        usvg::BlendMode::Darken => cs.min(cb),
+        usvg::BlendMode::Lighten => cs.max(cb),
+        usvg::BlendMode::ColorDodge => {
+            if cb <= 0.0 {
+                0.0
+            } else if cs >= 1.0 {
+                1.0
+            } else {
+                (cb / (1.0 - cs)).min(1.0)
+            }
+        }
+        usvg::BlendMode::ColorBurn => {
+            if cb >= 1.0 {
+                1.0
+            } else if cs <= 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - cb) / cs).min(1.0)
+            }
+        }
+        usvg::BlendMode::HardLight => hard_light(cs, cb),
+        usvg::BlendMode::SoftLight => {
+            if cs <= 0.5 {
+                cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+            } else {
+                let d = if cb <= 0.25 {
+                    ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                } else {
+                    cb.sqrt()
+                };
+
+                cb + (2.0 * cs - 1.0) * (d - cb)
+            }
+        }
+        usvg::BlendMode::Difference => (cb - cs).abs(),
+        usvg::BlendMode::Exclusion => cb + cs - 2.0 * cb * cs,
+        _ => cs,
+    }
+}
+
+fn blend_non_separable(
+    mode: usvg::BlendMode,
+    cs: [f32; 3],
+    cb: [f32; 3],
+) -> [f32; 3] {
+    match mode {
+        usvg::BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+        usvg::BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+        usvg::BlendMode::Color => set_lum(cs, lum(cb)),
+        usvg::BlendMode::Luminosity => set_lum(cb, lum(cs)),
+        _ => cs,
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+fn clip_color(c: [f32; 3]) -> [f32; 3] {
+    let l = lum(c);
+    let n = c[0].min(c[1]).min(c[2]);
+    let x = c[0].max(c[1]).max(c[2]);
+
+    let mut c = c;
+    if n < 0.0 {
+        for v in &mut c {
+            *v = l + (*v - l) * l / (l - n);
+        }
+    }
+
+    if x > 1.0 {
+        for v in &mut c {
+            *v = l + (*v - l) * (1.0 - l) / (x - l);
+        }
+    }
+
+    c
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);
+    clip_color([c[0] + d, c[1] + d, c[2] + d])
+}
+
+fn sat(c: [f32; 3]) -> f32 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let min = c[0].min(c[1]).min(c[2]);
+    let max = c[0].max(c[1]).max(c[2]);
+    if max > min {
+        let f = |v: f32| (v - min) * s / (max - min);
+        [f(c[0]), f(c[1]), f(c[2])]
+    } else {
+        [0.0; 3]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(r: u8, g: u8, b: u8, a: u8) -> BGRA8 {
+        BGRA8 { b, g, r, a }
+    }
+
+    #[test]
+    fn multiply() {
+        let c = blend_pixel(usvg::BlendMode::Multiply,
+                            pixel(255, 128, 0, 255), pixel(128, 255, 255, 255), 1.0);
+        assert_eq!(c, pixel(128, 128, 0, 255));
+    }
+
+    #[test]
+    fn transparent_backdrop() {
+        // Without a backdrop, the source is used as is.
+        let s = pixel(50, 100, 150, 255);
+        let c = blend_pixel(usvg::BlendMode::Difference, s, pixel(0, 0, 0, 0), 1.0);
+        assert_eq!(c, s);
+    }
+
+    #[test]
+    fn luminosity() {
+        // A gray source sets the luminosity of a gray backdrop.
+        let c = blend_pixel(usvg::BlendMode::Luminosity,
+                            pixel(200, 200, 200, 255), pixel(50, 50, 50, 255), 1.0);
+        assert_eq!(c, pixel(200, 200, 200, 255));
+    }
+
+    #[test]
+    fn mask() {
+        let src = [pixel(255, 0, 0, 255); 3];
+        let mut dst = [pixel(0, 0, 255, 255); 3];
+        blend_image(usvg::BlendMode::Screen, &src, &mut dst, Some(&[0, 128, 255]), 1.0);
+        assert_eq!(dst, [pixel(0, 0, 255, 255), pixel(128, 0, 255, 255), pixel(255, 0, 255, 255)]);
+    }
+}
@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::cmp;
 use std::rc::Rc;
 
 // external
@@ -15,6 +16,7 @@ use crate::prelude::*;
 pub enum Error {
     AllocFailed,
     InvalidRegion,
+    RegionLimitExceeded,
 }
 
 
@@ -34,6 +36,39 @@ where
 }
 
 
+/// A pixel with a known channels order.
+///
+/// Cairo and raster backends are using BGRA, while Qt is using RGBA.
+pub trait Pixel {
+    fn rgba(&self) -> (u8, u8, u8, u8);
+    fn set_rgba(&mut self, r: u8, g: u8, b: u8, a: u8);
+}
+
+impl Pixel for rgb::RGBA8 {
+    #[inline]
+    fn rgba(&self) -> (u8, u8, u8, u8) {
+        (self.r, self.g, self.b, self.a)
+    }
+
+    #[inline]
+    fn set_rgba(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        *self = rgb::RGBA8 { r, g, b, a };
+    }
+}
+
+impl Pixel for rgb::alt::BGRA8 {
+    #[inline]
+    fn rgba(&self) -> (u8, u8, u8, u8) {
+        (self.r, self.g, self.b, self.a)
+    }
+
+    #[inline]
+    fn set_rgba(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        *self = rgb::alt::BGRA8 { b, g, r, a };
+    }
+}
+
+
 pub struct Image<T: ImageExt> {
     /// Filter primitive result.
     ///
@@ -122,19 +157,117 @@ pub struct FilterResult<T: ImageExt> {
 }
 
 
+/// Images used as filter inputs.
+///
+/// All images have the same size as the canvas.
+pub struct FilterInputs<'a, T: 'a> {
+    /// A canvas with the filtered element. Used by `SourceGraphic` and `SourceAlpha`.
+    ///
+    /// The filter result will be rendered onto it.
+    pub source: &'a mut T,
+
+    /// Used by `BackgroundImage` and `BackgroundAlpha`.
+    pub background: Option<&'a T>,
+
+    /// Used by `FillPaint`.
+    pub fill_paint: Option<&'a T>,
+
+    /// Used by `StrokePaint`.
+    pub stroke_paint: Option<&'a T>,
+}
+
+/// Describes how the `BackgroundImage` and `BackgroundAlpha` inputs should be rendered.
+///
+/// The image contains all children of the nearest ancestor with `enable-background=new`
+/// that were rendered before the current node.
+pub struct BackgroundTask {
+    /// The nearest ancestor with `enable-background=new`.
+    ///
+    /// Should be rendered until the current node.
+    pub start_node: usvg::Node,
+
+    /// A canvas transform for the start node.
+    pub ts: usvg::Transform,
+
+    /// A region the image should be clipped to.
+    pub clip: Option<ScreenRect>,
+}
+
+/// Prepares the `BackgroundImage` and `BackgroundAlpha` inputs rendering.
+///
+/// `ts` is the current canvas transform.
+pub fn prepare_background(
+    node: &usvg::Node,
+    filter: &usvg::Filter,
+    ts: &usvg::Transform,
+) -> Option<BackgroundTask> {
+    let start_node = node.filter_background_start_node(filter)?;
+
+    // Replace the current node transform with the start node one.
+    let mut node_ts = utils::abs_transform(node);
+    node_ts.append(&node.transform());
+
+    let mut start_ts = *ts;
+    start_ts.append(&utils::invert_transform(&node_ts)?);
+    start_ts.append(&utils::abs_transform(&start_node));
+    start_ts.append(&start_node.transform());
+
+    let mut clip = None;
+    if let usvg::NodeKind::Group(ref g) = *start_node.borrow() {
+        if let Some(usvg::EnableBackground(Some(rect))) = g.enable_background {
+            clip = Some(rect.transform(&start_ts)?.to_screen_rect());
+        }
+    }
+
+    Some(BackgroundTask {
+        start_node,
+        ts: start_ts,
+        clip,
+    })
+}
+
+/// Prepares a path used to render the `FillPaint` and `StrokePaint` inputs.
+///
+/// There are no strict rules on how these inputs should be rendered,
+/// so we simply fill the filter region with the specified paint.
+/// The path is in the canvas coordinates.
+pub fn prepare_paint_path(
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    paint: &Option<usvg::Paint>,
+    img_size: ScreenSize,
+) -> Option<usvg::Path> {
+    let paint = paint.as_ref()?;
+
+    let canvas_rect = ScreenRect::new(0, 0, img_size.width(), img_size.height())?;
+    let region = calc_region(filter, bbox, ts, canvas_rect).ok()?;
+
+    Some(usvg::Path {
+        fill: Some(usvg::Fill {
+            paint: paint.clone(),
+            ..usvg::Fill::default()
+        }),
+        segments: utils::rect_to_path(region.to_rect()),
+        ..usvg::Path::default()
+    })
+}
+
+
 pub trait Filter<T: ImageExt> {
     fn apply(
         filter: &usvg::Filter,
         bbox: Rect,
         ts: &usvg::Transform,
         opt: &Options,
-        canvas: &mut T,
+        tree: &usvg::Tree,
+        inputs: &mut FilterInputs<T>,
     ) {
-        let res = Self::_apply(filter, bbox, ts, opt, canvas);
+        let res = Self::_apply(filter, bbox, ts, opt, tree, inputs);
 
         // Clear on error.
         if res.is_err() {
-            canvas.clear();
+            inputs.source.clear();
         }
 
         match res {
@@ -148,6 +281,10 @@ pub trait Filter<T: ImageExt> {
             Err(Error::InvalidRegion) => {
                 warn!("Filter '{}' has an invalid region.", filter.id);
             }
+            Err(Error::RegionLimitExceeded) => {
+                warn!("Filter '{}' region exceeds the limit. Skipped.", filter.id);
+                super::report_exceeded_limit(usvg::LimitKind::FilterRegion);
+            }
         }
     }
 
@@ -156,48 +293,88 @@ pub trait Filter<T: ImageExt> {
         bbox: Rect,
         ts: &usvg::Transform,
         opt: &Options,
-        canvas: &mut T,
+        tree: &usvg::Tree,
+        inputs: &mut FilterInputs<T>,
     ) -> Result<(), Error> {
         let mut results = Vec::new();
 
-        let canvas_rect = ScreenRect::new(0, 0, canvas.width(), canvas.height()).unwrap();
+        let canvas_rect = ScreenRect::new(0, 0, inputs.source.width(), inputs.source.height()).unwrap();
         let region = calc_region(filter, bbox, ts, canvas_rect)?;
 
+        if let Some(max) = opt.usvg.limits.max_filter_region {
+            if region.width() as u64 * region.height() as u64 > max {
+                return Err(Error::RegionLimitExceeded);
+            }
+        }
+
         for primitive in &filter.children {
             let cs = primitive.color_interpolation;
             let subregion = calc_subregion(filter, primitive, bbox, region, ts, &results)?;
 
             let mut result = match primitive.kind {
                 usvg::FilterKind::FeBlend(ref fe) => {
-                    let input1 = Self::get_input(&fe.input1, region, &results, canvas)?;
-                    let input2 = Self::get_input(&fe.input2, region, &results, canvas)?;
+                    let input1 = Self::get_input(&fe.input1, region, inputs, &results)?;
+                    let input2 = Self::get_input(&fe.input2, region, inputs, &results)?;
                     Self::apply_blend(fe, cs, region, input1, input2)
                 }
                 usvg::FilterKind::FeFlood(ref fe) => {
                     Self::apply_flood(fe, region)
                 }
                 usvg::FilterKind::FeGaussianBlur(ref fe) => {
-                    let input = Self::get_input(&fe.input, region, &results, canvas)?;
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
                     Self::apply_blur(fe, filter.primitive_units, cs, bbox, ts, input)
                 }
                 usvg::FilterKind::FeOffset(ref fe) => {
-                    let input = Self::get_input(&fe.input, region, &results, canvas)?;
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
                     Self::apply_offset(fe, filter.primitive_units, bbox, ts, input)
                 }
+                usvg::FilterKind::FeColorMatrix(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_color_matrix(fe, cs, input)
+                }
+                usvg::FilterKind::FeComponentTransfer(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_component_transfer(fe, cs, input)
+                }
                 usvg::FilterKind::FeComposite(ref fe) => {
-                    let input1 = Self::get_input(&fe.input1, region, &results, canvas)?;
-                    let input2 = Self::get_input(&fe.input2, region, &results, canvas)?;
+                    let input1 = Self::get_input(&fe.input1, region, inputs, &results)?;
+                    let input2 = Self::get_input(&fe.input2, region, inputs, &results)?;
                     Self::apply_composite(fe, cs, region, input1, input2)
                 }
+                usvg::FilterKind::FeConvolveMatrix(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_convolve_matrix(fe, cs, input)
+                }
+                usvg::FilterKind::FeDiffuseLighting(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_diffuse_lighting(fe, filter.primitive_units, cs, bbox, region, ts, input)
+                }
+                usvg::FilterKind::FeDisplacementMap(ref fe) => {
+                    let input1 = Self::get_input(&fe.input1, region, inputs, &results)?;
+                    let input2 = Self::get_input(&fe.input2, region, inputs, &results)?;
+                    let (sx, sy) = Self::resolve_displacement_scale(fe, filter.primitive_units, bbox, ts);
+                    Self::apply_displacement_map(fe, region, cs, sx, sy, input1, input2)
+                }
                 usvg::FilterKind::FeMerge(ref fe) => {
-                    Self::apply_merge(fe, cs, region, &results, canvas)
+                    Self::apply_merge(fe, cs, region, inputs, &results)
+                }
+                usvg::FilterKind::FeMorphology(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_morphology(fe, filter.primitive_units, cs, bbox, ts, input)
+                }
+                usvg::FilterKind::FeSpecularLighting(ref fe) => {
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
+                    Self::apply_specular_lighting(fe, filter.primitive_units, cs, bbox, region, ts, input)
                 }
                 usvg::FilterKind::FeTile(ref fe) => {
-                    let input = Self::get_input(&fe.input, region, &results, canvas)?;
+                    let input = Self::get_input(&fe.input, region, inputs, &results)?;
                     Self::apply_tile(input, region)
                 }
                 usvg::FilterKind::FeImage(ref fe) => {
-                    Self::apply_image(fe, region, subregion, opt)
+                    Self::apply_image(fe, region, subregion, ts, opt, tree)
+                }
+                usvg::FilterKind::FeTurbulence(ref fe) => {
+                    Self::apply_turbulence(fe, cs, region, subregion, ts)
                 }
             }?;
 
@@ -230,7 +407,7 @@ pub trait Filter<T: ImageExt> {
         }
 
         if let Some(res) = results.pop() {
-            Self::apply_to_canvas(res.image, region, canvas)?;
+            Self::apply_to_canvas(res.image, region, inputs.source)?;
         }
 
         Ok(())
@@ -239,8 +416,8 @@ pub trait Filter<T: ImageExt> {
     fn get_input(
         input: &usvg::FilterInput,
         region: ScreenRect,
+        inputs: &FilterInputs<T>,
         results: &[FilterResult<T>],
-        canvas: &T,
     ) -> Result<Image<T>, Error>;
 
     fn apply_blur(
@@ -276,12 +453,69 @@ pub trait Filter<T: ImageExt> {
         input2: Image<T>,
     ) -> Result<Image<T>, Error>;
 
+    fn apply_color_matrix(
+        fe: &usvg::FeColorMatrix,
+        cs: ColorSpace,
+        input: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_component_transfer(
+        fe: &usvg::FeComponentTransfer,
+        cs: ColorSpace,
+        input: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_convolve_matrix(
+        fe: &usvg::FeConvolveMatrix,
+        cs: ColorSpace,
+        input: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_diffuse_lighting(
+        fe: &usvg::FeDiffuseLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_specular_lighting(
+        fe: &usvg::FeSpecularLighting,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+        input: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_displacement_map(
+        fe: &usvg::FeDisplacementMap,
+        region: ScreenRect,
+        cs: ColorSpace,
+        sx: f64,
+        sy: f64,
+        input1: Image<T>,
+        input2: Image<T>,
+    ) -> Result<Image<T>, Error>;
+
     fn apply_merge(
         fe: &usvg::FeMerge,
         cs: ColorSpace,
         region: ScreenRect,
+        inputs: &FilterInputs<T>,
         results: &[FilterResult<T>],
-        canvas: &T,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_morphology(
+        fe: &usvg::FeMorphology,
+        units: usvg::Units,
+        cs: ColorSpace,
+        bbox: Rect,
+        ts: &usvg::Transform,
+        input: Image<T>,
     ) -> Result<Image<T>, Error>;
 
     fn apply_flood(
@@ -298,7 +532,17 @@ pub trait Filter<T: ImageExt> {
         fe: &usvg::FeImage,
         region: ScreenRect,
         subregion: ScreenRect,
+        ts: &usvg::Transform,
         opt: &Options,
+        tree: &usvg::Tree,
+    ) -> Result<Image<T>, Error>;
+
+    fn apply_turbulence(
+        fe: &usvg::FeTurbulence,
+        cs: ColorSpace,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
     ) -> Result<Image<T>, Error>;
 
     fn apply_to_canvas(
@@ -340,6 +584,42 @@ pub trait Filter<T: ImageExt> {
         }
     }
 
+    fn resolve_morphology_radius(
+        fe: &usvg::FeMorphology,
+        units: usvg::Units,
+        bbox: Rect,
+        ts: &usvg::Transform,
+    ) -> Option<(u32, u32)> {
+        // 'A negative or zero value disables the effect of the given filter primitive
+        // (i.e., the result is the filter input image).'
+        if fe.radius_x.is_zero() || fe.radius_y.is_zero() {
+            return None;
+        }
+
+        let (sx, sy) = ts.get_scale();
+
+        let (rx, ry) = if units == usvg::Units::ObjectBoundingBox {
+            (
+                fe.radius_x.value() * sx * bbox.width(),
+                fe.radius_y.value() * sy * bbox.height()
+            )
+        } else {
+            (
+                fe.radius_x.value() * sx,
+                fe.radius_y.value() * sy
+            )
+        };
+
+        let rx = rx.round() as u32;
+        let ry = ry.round() as u32;
+
+        if rx == 0 && ry == 0 {
+            None
+        } else {
+            Some((rx, ry))
+        }
+    }
+
     fn resolve_offset(
         fe: &usvg::FeOffset,
         units: usvg::Units,
@@ -366,6 +646,100 @@ pub trait Filter<T: ImageExt> {
             Some((dx, dy))
         }
     }
+
+    /// Returns the displacement scale in the device coordinates.
+    fn resolve_displacement_scale(
+        fe: &usvg::FeDisplacementMap,
+        units: usvg::Units,
+        bbox: Rect,
+        ts: &usvg::Transform,
+    ) -> (f64, f64) {
+        let (sx, sy) = ts.get_scale();
+
+        if units == usvg::Units::ObjectBoundingBox {
+            (fe.scale * sx * bbox.width(), fe.scale * sy * bbox.height())
+        } else {
+            (fe.scale * sx, fe.scale * sy)
+        }
+    }
+
+    /// Converts a light source position into the filter region coordinates.
+    fn transform_light_source(
+        mut source: usvg::FeLightSource,
+        units: usvg::Units,
+        bbox: Rect,
+        region: ScreenRect,
+        ts: &usvg::Transform,
+    ) -> usvg::FeLightSource {
+        use std::f64::consts::SQRT_2;
+
+        // TODO: Does not account rotate and skew.
+        let transform = |x: f64, y: f64, z: f64| {
+            let (x, y, z) = if units == usvg::Units::ObjectBoundingBox {
+                // 'The z coordinate is relative to the normalized diagonal of the bounding box.'
+                let diagonal = (bbox.width().powi(2) + bbox.height().powi(2)).sqrt() / SQRT_2;
+                (
+                    bbox.x() + x * bbox.width(),
+                    bbox.y() + y * bbox.height(),
+                    z * diagonal,
+                )
+            } else {
+                (x, y, z)
+            };
+
+            let (x, y) = ts.apply(x, y);
+            let (sx, sy) = ts.get_scale();
+            let z = z * (sx * sx + sy * sy).sqrt() / SQRT_2;
+
+            (x - region.x() as f64, y - region.y() as f64, z)
+        };
+
+        match source {
+            usvg::FeLightSource::FeDistantLight(..) => {}
+            usvg::FeLightSource::FePointLight(ref mut light) => {
+                let (x, y, z) = transform(light.x, light.y, light.z);
+                light.x = x;
+                light.y = y;
+                light.z = z;
+            }
+            usvg::FeLightSource::FeSpotLight(ref mut light) => {
+                let (x, y, z) = transform(light.x, light.y, light.z);
+                light.x = x;
+                light.y = y;
+                light.z = z;
+
+                let (x, y, z) = transform(light.points_at_x, light.points_at_y, light.points_at_z);
+                light.points_at_x = x;
+                light.points_at_y = y;
+                light.points_at_z = z;
+            }
+        }
+
+        source
+    }
+
+    /// Converts `kernelUnitLength` into a distance between samples in pixels.
+    fn resolve_kernel_unit_length(
+        len: Option<(f64, f64)>,
+        units: usvg::Units,
+        bbox: Rect,
+        ts: &usvg::Transform,
+    ) -> (u32, u32) {
+        let (x, y) = match len {
+            Some(v) => v,
+            None => return (1, 1),
+        };
+
+        let (sx, sy) = ts.get_scale();
+
+        let (x, y) = if units == usvg::Units::ObjectBoundingBox {
+            (x * sx * bbox.width(), y * sy * bbox.height())
+        } else {
+            (x * sx, y * sy)
+        };
+
+        (cmp::max(1, x.round() as u32), cmp::max(1, y.round() as u32))
+    }
 }
 
 
@@ -587,108 +961,1117 @@ pub mod blur {
     }
 }
 
-fn calc_region(
-    filter: &usvg::Filter,
-    bbox: Rect,
-    ts: &usvg::Transform,
-    canvas_rect: ScreenRect,
-) -> Result<ScreenRect, Error> {
-    let path = utils::rect_to_path(filter.rect);
-
-    let region_ts = if filter.units == usvg::Units::ObjectBoundingBox {
-        let bbox_ts = usvg::Transform::from_bbox(bbox);
-        let mut ts2 = ts.clone();
-        ts2.append(&bbox_ts);
-        ts2
-    } else {
-        *ts
-    };
-
-    let region = utils::path_bbox(&path, None, Some(region_ts))
-        .ok_or_else(|| Error::InvalidRegion)?
-        .to_screen_rect()
-        .fit_to_rect(canvas_rect);
+pub mod color_matrix {
+    // self
+    use crate::prelude::*;
+    use super::Pixel;
 
-    Ok(region)
-}
+    /// Applies a color matrix to a non-premultiplied image.
+    pub fn apply<P: Pixel>(
+        kind: &usvg::FeColorMatrixKind,
+        data: &mut [P],
+    ) {
+        match *kind {
+            usvg::FeColorMatrixKind::Matrix(ref m) => {
+                for p in data {
+                    let (r, g, b, a) = to_normalized(p);
 
-/// Returns filter primitive region.
-fn calc_subregion<T: ImageExt>(
-    filter: &usvg::Filter,
-    primitive: &usvg::FilterPrimitive,
-    bbox: Rect,
-    filter_region: ScreenRect,
-    ts: &usvg::Transform,
-    results: &[FilterResult<T>],
-) -> Result<ScreenRect, Error> {
-    // TODO: rewrite/simplify/explain/whatever
+                    let new_r = r * m[0]  + g * m[1]  + b * m[2]  + a * m[3]  + m[4];
+                    let new_g = r * m[5]  + g * m[6]  + b * m[7]  + a * m[8]  + m[9];
+                    let new_b = r * m[10] + g * m[11] + b * m[12] + a * m[13] + m[14];
+                    let new_a = r * m[15] + g * m[16] + b * m[17] + a * m[18] + m[19];
 
-    let region = match primitive.kind {
-        usvg::FilterKind::FeOffset(ref fe) => {
-            // `feOffset` inherits it's region from the input.
-            match fe.input {
-                usvg::FilterInput::Reference(ref name) => {
-                    match results.iter().rev().find(|v| v.name == *name) {
-                        Some(ref res) => res.image.region,
-                        None => filter_region,
-                    }
+                    set_normalized(p, new_r, new_g, new_b, new_a);
                 }
-                _ => {
-                    filter_region
+            }
+            usvg::FeColorMatrixKind::Saturate(v) => {
+                let v = v.value();
+                let m = [
+                    0.213 + 0.787 * v, 0.715 - 0.715 * v, 0.072 - 0.072 * v,
+                    0.213 - 0.213 * v, 0.715 + 0.285 * v, 0.072 - 0.072 * v,
+                    0.213 - 0.213 * v, 0.715 - 0.715 * v, 0.072 + 0.928 * v,
+                ];
+
+                apply_rgb_matrix(&m, data);
+            }
+            usvg::FeColorMatrixKind::HueRotate(angle) => {
+                let angle = angle.to_radians();
+                let a1 = angle.cos();
+                let a2 = angle.sin();
+                let m = [
+                    0.213 + 0.787 * a1 - 0.213 * a2,
+                    0.715 - 0.715 * a1 - 0.715 * a2,
+                    0.072 - 0.072 * a1 + 0.928 * a2,
+                    0.213 - 0.213 * a1 + 0.143 * a2,
+                    0.715 + 0.285 * a1 + 0.140 * a2,
+                    0.072 - 0.072 * a1 - 0.283 * a2,
+                    0.213 - 0.213 * a1 - 0.787 * a2,
+                    0.715 - 0.715 * a1 + 0.715 * a2,
+                    0.072 + 0.928 * a1 + 0.072 * a2,
+                ];
+
+                apply_rgb_matrix(&m, data);
+            }
+            usvg::FeColorMatrixKind::LuminanceToAlpha => {
+                for p in data {
+                    let (r, g, b, _) = to_normalized(p);
+                    let new_a = r * 0.2125 + g * 0.7154 + b * 0.0721;
+                    set_normalized(p, 0.0, 0.0, 0.0, new_a);
                 }
             }
         }
-        usvg::FilterKind::FeImage(..) => {
-            // `feImage` uses the object bbox.
-            if filter.primitive_units == usvg::Units::ObjectBoundingBox {
-                // TODO: wrong
-                let ts_bbox = Rect::new(ts.e, ts.f, ts.a, ts.d).unwrap();
+    }
 
-                let r = Rect::new(
-                    primitive.x.unwrap_or(0.0),
-                    primitive.y.unwrap_or(0.0),
-                    primitive.width.unwrap_or(1.0),
-                    primitive.height.unwrap_or(1.0),
-                ).ok_or_else(|| Error::InvalidRegion)?;
+    /// Applies a 3x3 matrix to the RGB channels. Alpha is preserved.
+    fn apply_rgb_matrix<P: Pixel>(m: &[f64; 9], data: &mut [P]) {
+        for p in data {
+            let (r, g, b, a) = to_normalized(p);
 
-                let r = r
-                    .bbox_transform(bbox)
-                    .bbox_transform(ts_bbox)
-                    .to_screen_rect();
+            let new_r = r * m[0] + g * m[1] + b * m[2];
+            let new_g = r * m[3] + g * m[4] + b * m[5];
+            let new_b = r * m[6] + g * m[7] + b * m[8];
 
-                return Ok(r);
-            } else {
-                filter_region
-            }
+            set_normalized(p, new_r, new_g, new_b, a);
         }
-        _ => filter_region,
-    };
-
-    // TODO: Wrong! Does not account rotate and skew.
-    let subregion = if filter.primitive_units == usvg::Units::ObjectBoundingBox {
-        let subregion_bbox = Rect::new(
-            primitive.x.unwrap_or(0.0),
-            primitive.y.unwrap_or(0.0),
-            primitive.width.unwrap_or(1.0),
-            primitive.height.unwrap_or(1.0),
-        ).ok_or_else(|| Error::InvalidRegion)?;
+    }
 
-        region.to_rect().bbox_transform(subregion_bbox)
-    } else {
-        let (dx, dy) = ts.get_translate();
-        let (sx, sy) = ts.get_scale();
-        Rect::new(
-            primitive.x.map(|n| n * sx + dx).unwrap_or(region.x() as f64),
-            primitive.y.map(|n| n * sy + dy).unwrap_or(region.y() as f64),
-            primitive.width.map(|n| n * sx).unwrap_or(region.width() as f64),
-            primitive.height.map(|n| n * sy).unwrap_or(region.height() as f64),
-        ).ok_or_else(|| Error::InvalidRegion)?
-    };
+    #[inline]
+    fn to_normalized<P: Pixel>(p: &P) -> (f64, f64, f64, f64) {
+        let (r, g, b, a) = p.rgba();
+        (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0, a as f64 / 255.0)
+    }
 
-    Ok(subregion.to_screen_rect())
+    #[inline]
+    fn set_normalized<P: Pixel>(p: &mut P, r: f64, g: f64, b: f64, a: f64) {
+        let conv = |n: f64| (f64_bound(0.0, n, 1.0) * 255.0 + 0.5) as u8;
+        p.set_rgba(conv(r), conv(g), conv(b), conv(a));
+    }
 }
 
-/// Precomputed sRGB to LinearRGB table.
+pub mod component_transfer {
+    use std::cmp;
+
+    // self
+    use crate::prelude::*;
+    use super::Pixel;
+
+    /// Applies component transfer functions to a non-premultiplied image.
+    pub fn apply<P: Pixel>(
+        fe: &usvg::FeComponentTransfer,
+        data: &mut [P],
+    ) {
+        let funcs = [&fe.func_r, &fe.func_g, &fe.func_b, &fe.func_a];
+        if funcs.iter().all(|f| f.is_dummy()) {
+            return;
+        }
+
+        for p in data {
+            let (r, g, b, a) = p.rgba();
+            p.set_rgba(
+                transfer(&fe.func_r, r),
+                transfer(&fe.func_g, g),
+                transfer(&fe.func_b, b),
+                transfer(&fe.func_a, a),
+            );
+        }
+    }
+
+    fn transfer(func: &usvg::TransferFunction, c: u8) -> u8 {
+        let c = c as f64 / 255.0;
+        let c = match *func {
+            usvg::TransferFunction::Identity => c,
+            usvg::TransferFunction::Table(ref values) => {
+                match values.len() {
+                    0 => c,
+                    1 => values[0],
+                    _ => {
+                        let n = values.len() - 1;
+                        let k = cmp::min((c * n as f64) as usize, n - 1);
+                        let v1 = values[k];
+                        let v2 = values[k + 1];
+                        v1 + (c - k as f64 / n as f64) * n as f64 * (v2 - v1)
+                    }
+                }
+            }
+            usvg::TransferFunction::Discrete(ref values) => {
+                if values.is_empty() {
+                    c
+                } else {
+                    let n = values.len();
+                    let k = cmp::min((c * n as f64) as usize, n - 1);
+                    values[k]
+                }
+            }
+            usvg::TransferFunction::Linear { slope, intercept } => {
+                slope * c + intercept
+            }
+            usvg::TransferFunction::Gamma { amplitude, exponent, offset } => {
+                amplitude * c.powf(exponent) + offset
+            }
+        };
+
+        (f64_bound(0.0, c, 1.0) * 255.0 + 0.5) as u8
+    }
+}
+
+pub mod convolve_matrix {
+    use std::cmp;
+
+    // self
+    use crate::prelude::*;
+    use super::Pixel;
+
+    /// Applies a convolve matrix to a premultiplied image.
+    pub fn apply<P: Pixel + Copy>(
+        fe: &usvg::FeConvolveMatrix,
+        width: u32,
+        height: u32,
+        data: &mut [P],
+    ) {
+        let matrix = &fe.matrix;
+        let (width, height) = (width as i32, height as i32);
+
+        // Source pixels in a 0..1 range. Color channels are demultiplied when alpha is preserved.
+        let src: Vec<[f64; 4]> = data.iter().map(|p| {
+            let (r, g, b, a) = p.rgba();
+            let (r, g, b, a) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0, a as f64 / 255.0);
+            if fe.preserve_alpha && !a.is_fuzzy_zero() {
+                [r / a, g / a, b / a, a]
+            } else {
+                [r, g, b, a]
+            }
+        }).collect();
+
+        let mut x = 0;
+        let mut y = 0;
+        for out in data.iter_mut() {
+            let mut sum = [0.0; 4];
+
+            for oy in 0..matrix.rows() {
+                for ox in 0..matrix.columns() {
+                    let mut tx = x - matrix.target_x() as i32 + ox as i32;
+                    let mut ty = y - matrix.target_y() as i32 + oy as i32;
+
+                    match fe.edge_mode {
+                        usvg::EdgeMode::None => {
+                            if tx < 0 || tx >= width || ty < 0 || ty >= height {
+                                continue;
+                            }
+                        }
+                        usvg::EdgeMode::Duplicate => {
+                            tx = cmp::max(0, cmp::min(width - 1, tx));
+                            ty = cmp::max(0, cmp::min(height - 1, ty));
+                        }
+                        usvg::EdgeMode::Wrap => {
+                            tx = ((tx % width) + width) % width;
+                            ty = ((ty % height) + height) % height;
+                        }
+                    }
+
+                    // The kernel is rotated by 180 degrees.
+                    let k = matrix.get(matrix.columns() - ox - 1, matrix.rows() - oy - 1);
+
+                    let p = &src[(ty * width + tx) as usize];
+                    for (s, c) in sum.iter_mut().zip(p.iter()) {
+                        *s += c * k;
+                    }
+                }
+            }
+
+            let calc = |n: f64| f64_bound(0.0, n / fe.divisor + fe.bias, 1.0);
+
+            let idx = (y * width + x) as usize;
+            let (r, g, b, a) = if fe.preserve_alpha {
+                let a = src[idx][3];
+                (calc(sum[0]) * a, calc(sum[1]) * a, calc(sum[2]) * a, a)
+            } else {
+                // Color channels in a premultiplied image cannot be bigger than alpha.
+                let a = calc(sum[3]);
+                (calc(sum[0]).min(a), calc(sum[1]).min(a), calc(sum[2]).min(a), a)
+            };
+
+            let conv = |n: f64| (n * 255.0 + 0.5) as u8;
+            out.set_rgba(conv(r), conv(g), conv(b), conv(a));
+
+            x += 1;
+            if x == width {
+                x = 0;
+                y += 1;
+            }
+        }
+    }
+}
+
+pub mod turbulence {
+    // self
+    use crate::prelude::*;
+    use super::Pixel;
+
+    const RAND_M: i64 = 2147483647; // 2**31 - 1
+    const RAND_A: i64 = 16807; // 7**5; primitive root of m
+    const RAND_Q: i64 = 127773; // m / a
+    const RAND_R: i64 = 2836; // m % a
+    const B_SIZE: usize = 0x100;
+    const B_LEN: usize = B_SIZE + B_SIZE + 2;
+    const BM: i32 = 0xff;
+    const PERLIN_N: i32 = 0x1000;
+
+    #[derive(Clone, Copy)]
+    struct StitchInfo {
+        width: i32, // How much to subtract to wrap for stitching.
+        height: i32,
+        wrap_x: i32, // Minimum value to wrap.
+        wrap_y: i32,
+    }
+
+    /// A filter tile in the user space. Used for stitching.
+    #[derive(Clone, Copy)]
+    struct Tile {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    /// Fills an image with a turbulence.
+    ///
+    /// This is a port of the reference implementation from the SVG 1.1 spec.
+    /// The result is not premultiplied.
+    ///
+    /// `region` is the filter region and `subregion` is the primitive subregion.
+    /// Both in the device coordinates.
+    pub fn apply<P: Pixel>(
+        fe: &usvg::FeTurbulence,
+        region: ScreenRect,
+        subregion: ScreenRect,
+        ts: &usvg::Transform,
+        data: &mut [P],
+    ) {
+        // TODO: Does not account rotate and skew, just like the primitive subregion.
+        let (sx, sy) = ts.get_scale();
+        let (dx, dy) = ts.get_translate();
+        if sx.is_fuzzy_zero() || sy.is_fuzzy_zero() {
+            return;
+        }
+
+        let tile = Tile {
+            x: (subregion.x() as f64 - dx) / sx,
+            y: (subregion.y() as f64 - dy) / sy,
+            width: subregion.width() as f64 / sx,
+            height: subregion.height() as f64 / sy,
+        };
+
+        let noise = Noise::new(fe.seed);
+
+        let mut x = 0;
+        let mut y = 0;
+        for pixel in data.iter_mut() {
+            let point = (
+                ((region.x() + x) as f64 - dx) / sx,
+                ((region.y() + y) as f64 - dy) / sy,
+            );
+
+            let calc = |channel| {
+                let n = noise.turbulence(channel, point, fe, tile);
+                let n = match fe.kind {
+                    usvg::FeTurbulenceKind::FractalNoise => (n * 255.0 + 255.0) / 2.0,
+                    usvg::FeTurbulenceKind::Turbulence => n * 255.0,
+                };
+
+                (f64_bound(0.0, n, 255.0) + 0.5) as u8
+            };
+
+            pixel.set_rgba(calc(0), calc(1), calc(2), calc(3));
+
+            x += 1;
+            if x == region.width() as i32 {
+                x = 0;
+                y += 1;
+            }
+        }
+    }
+
+    struct Noise {
+        lattice_selector: Vec<usize>,
+        gradient: Vec<Vec<[f64; 2]>>,
+    }
+
+    impl Noise {
+        fn new(seed: i32) -> Self {
+            let mut lattice_selector = vec![0; B_LEN];
+            let mut gradient = vec![vec![[0.0; 2]; B_LEN]; 4];
+
+            let mut seed = setup_seed(seed as i64);
+
+            for channel in gradient.iter_mut() {
+                for i in 0..B_SIZE {
+                    lattice_selector[i] = i;
+
+                    let g = &mut channel[i];
+                    for v in g.iter_mut() {
+                        seed = random(seed);
+                        *v = ((seed % (B_SIZE + B_SIZE) as i64) - B_SIZE as i64) as f64
+                            / B_SIZE as f64;
+                    }
+
+                    let s = (g[0] * g[0] + g[1] * g[1]).sqrt();
+                    g[0] /= s;
+                    g[1] /= s;
+                }
+            }
+
+            for i in (1..B_SIZE).rev() {
+                let k = lattice_selector[i];
+                seed = random(seed);
+                let j = (seed % B_SIZE as i64) as usize;
+                lattice_selector[i] = lattice_selector[j];
+                lattice_selector[j] = k;
+            }
+
+            for i in 0..B_SIZE + 2 {
+                lattice_selector[B_SIZE + i] = lattice_selector[i];
+                for channel in gradient.iter_mut() {
+                    channel[B_SIZE + i] = channel[i];
+                }
+            }
+
+            Noise {
+                lattice_selector,
+                gradient,
+            }
+        }
+
+        fn turbulence(
+            &self,
+            color_channel: usize,
+            mut point: (f64, f64),
+            fe: &usvg::FeTurbulence,
+            tile: Tile,
+        ) -> f64 {
+            let mut base_freq_x = fe.base_frequency_x.value();
+            let mut base_freq_y = fe.base_frequency_y.value();
+
+            // Adjust the base frequencies if necessary for stitching.
+            let mut stitch = if fe.stitch_tiles {
+                // When stitching tiled turbulence, the frequencies must be adjusted
+                // so that the tile borders will be continuous.
+                if !base_freq_x.is_fuzzy_zero() {
+                    let lo_freq = (tile.width * base_freq_x).floor() / tile.width;
+                    let hi_freq = (tile.width * base_freq_x).ceil() / tile.width;
+                    if base_freq_x / lo_freq < hi_freq / base_freq_x {
+                        base_freq_x = lo_freq;
+                    } else {
+                        base_freq_x = hi_freq;
+                    }
+                }
+
+                if !base_freq_y.is_fuzzy_zero() {
+                    let lo_freq = (tile.height * base_freq_y).floor() / tile.height;
+                    let hi_freq = (tile.height * base_freq_y).ceil() / tile.height;
+                    if base_freq_y / lo_freq < hi_freq / base_freq_y {
+                        base_freq_y = lo_freq;
+                    } else {
+                        base_freq_y = hi_freq;
+                    }
+                }
+
+                // Set up initial stitch values.
+                let width = (tile.width * base_freq_x + 0.5) as i32;
+                let height = (tile.height * base_freq_y + 0.5) as i32;
+                Some(StitchInfo {
+                    width,
+                    height,
+                    wrap_x: (tile.x * base_freq_x + PERLIN_N as f64 + width as f64) as i32,
+                    wrap_y: (tile.y * base_freq_y + PERLIN_N as f64 + height as f64) as i32,
+                })
+            } else {
+                None
+            };
+
+            let fractal_sum = fe.kind == usvg::FeTurbulenceKind::FractalNoise;
+
+            let mut sum = 0.0;
+            point.0 *= base_freq_x;
+            point.1 *= base_freq_y;
+            let mut ratio = 1.0;
+            for _ in 0..fe.num_octaves {
+                let n = self.noise2(color_channel, point, stitch);
+                if fractal_sum {
+                    sum += n / ratio;
+                } else {
+                    sum += n.abs() / ratio;
+                }
+
+                point.0 *= 2.0;
+                point.1 *= 2.0;
+                ratio *= 2.0;
+
+                if let Some(ref mut stitch) = stitch {
+                    // Update stitch values. Subtracting PerlinN before the multiplication and
+                    // adding it afterward simplifies to subtracting it once.
+                    stitch.width *= 2;
+                    stitch.wrap_x = 2 * stitch.wrap_x - PERLIN_N;
+                    stitch.height *= 2;
+                    stitch.wrap_y = 2 * stitch.wrap_y - PERLIN_N;
+                }
+            }
+
+            sum
+        }
+
+        fn noise2(
+            &self,
+            color_channel: usize,
+            vec: (f64, f64),
+            stitch_info: Option<StitchInfo>,
+        ) -> f64 {
+            let t = vec.0 + PERLIN_N as f64;
+            let mut bx0 = (t as i32) & BM;
+            let mut bx1 = (bx0 + 1) & BM;
+            let rx0 = t - (t as i32) as f64;
+            let rx1 = rx0 - 1.0;
+            let t = vec.1 + PERLIN_N as f64;
+            let mut by0 = (t as i32) & BM;
+            let mut by1 = (by0 + 1) & BM;
+            let ry0 = t - (t as i32) as f64;
+            let ry1 = ry0 - 1.0;
+
+            // If stitching, adjust lattice points accordingly.
+            if let Some(info) = stitch_info {
+                if bx0 >= info.wrap_x {
+                    bx0 -= info.width;
+                }
+
+                if bx1 >= info.wrap_x {
+                    bx1 -= info.width;
+                }
+
+                if by0 >= info.wrap_y {
+                    by0 -= info.height;
+                }
+
+                if by1 >= info.wrap_y {
+                    by1 -= info.height;
+                }
+            }
+
+            bx0 &= BM;
+            bx1 &= BM;
+            by0 &= BM;
+            by1 &= BM;
+
+            let lattice = &self.lattice_selector;
+            let gradient = &self.gradient[color_channel];
+
+            let i = lattice[bx0 as usize];
+            let j = lattice[bx1 as usize];
+            let b00 = lattice[i + by0 as usize];
+            let b10 = lattice[j + by0 as usize];
+            let b01 = lattice[i + by1 as usize];
+            let b11 = lattice[j + by1 as usize];
+            let sx = s_curve(rx0);
+            let sy = s_curve(ry0);
+
+            let q = gradient[b00];
+            let u = rx0 * q[0] + ry0 * q[1];
+            let q = gradient[b10];
+            let v = rx1 * q[0] + ry0 * q[1];
+            let a = lerp(sx, u, v);
+
+            let q = gradient[b01];
+            let u = rx0 * q[0] + ry1 * q[1];
+            let q = gradient[b11];
+            let v = rx1 * q[0] + ry1 * q[1];
+            let b = lerp(sx, u, v);
+
+            lerp(sy, a, b)
+        }
+    }
+
+    fn setup_seed(mut seed: i64) -> i64 {
+        if seed <= 0 {
+            seed = -(seed % (RAND_M - 1)) + 1;
+        }
+
+        if seed > RAND_M - 1 {
+            seed = RAND_M - 1;
+        }
+
+        seed
+    }
+
+    fn random(seed: i64) -> i64 {
+        let mut result = RAND_A * (seed % RAND_Q) - RAND_R * (seed / RAND_Q);
+        if result <= 0 {
+            result += RAND_M;
+        }
+
+        result
+    }
+
+    #[inline]
+    fn s_curve(t: f64) -> f64 {
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    #[inline]
+    fn lerp(t: f64, a: f64, b: f64) -> f64 {
+        a + t * (b - a)
+    }
+}
+
+pub mod morphology {
+    use std::cmp;
+
+    /// Applies an erode or dilate operation to an image.
+    ///
+    /// The operation is separable, so we are processing rows first and columns after.
+    /// Each line is processed in a linear time regardless of the radius,
+    /// using the van Herk/Gil-Werman algorithm.
+    ///
+    /// Pixels outside the image are ignored.
+    ///
+    /// Can be applied to both premultiplied and non-premultiplied images.
+    pub fn apply(
+        operator: usvg::FeMorphologyOperator,
+        rx: u32,
+        ry: u32,
+        data: &mut [u8],
+        width: u32,
+        height: u32,
+    ) {
+        let (width, height) = (width as usize, height as usize);
+
+        // A window larger than the image will not affect the result.
+        let rx = cmp::min(rx as usize, width);
+        let ry = cmp::min(ry as usize, height);
+
+        let (op, neutral): (fn(u8, u8) -> u8, u8) = match operator {
+            usvg::FeMorphologyOperator::Erode => (cmp::min, 255),
+            usvg::FeMorphologyOperator::Dilate => (cmp::max, 0),
+        };
+
+        let mut line = Line {
+            op,
+            neutral,
+            padded: Vec::new(),
+            forward: Vec::new(),
+            backward: Vec::new(),
+        };
+
+        if rx != 0 {
+            for y in 0..height {
+                for channel in 0..4 {
+                    line.process(data, y * width * 4 + channel, 4, width, rx);
+                }
+            }
+        }
+
+        if ry != 0 {
+            for x in 0..width {
+                for channel in 0..4 {
+                    line.process(data, x * 4 + channel, width * 4, height, ry);
+                }
+            }
+        }
+    }
+
+    struct Line {
+        op: fn(u8, u8) -> u8,
+        /// An identity element of the operation. Used for padding.
+        neutral: u8,
+        padded: Vec<u8>,
+        forward: Vec<u8>,
+        backward: Vec<u8>,
+    }
+
+    impl Line {
+        fn process(
+            &mut self,
+            data: &mut [u8],
+            start: usize,
+            step: usize,
+            len: usize,
+            radius: usize,
+        ) {
+            let op = self.op;
+            let window = radius * 2 + 1;
+
+            // Pad the line by `radius` on both sides and align it to the window size.
+            let padded_len = (len + radius * 2 + window - 1) / window * window;
+
+            self.padded.clear();
+            self.padded.resize(padded_len, self.neutral);
+            for i in 0..len {
+                self.padded[radius + i] = data[start + i * step];
+            }
+
+            self.forward.resize(padded_len, 0);
+            self.backward.resize(padded_len, 0);
+
+            let p = &self.padded;
+
+            // Accumulate from the start of each block.
+            for i in 0..padded_len {
+                self.forward[i] = if i % window == 0 { p[i] } else { op(self.forward[i - 1], p[i]) };
+            }
+
+            // Accumulate from the end of each block.
+            for i in (0..padded_len).rev() {
+                self.backward[i] = if i % window == window - 1 {
+                    p[i]
+                } else {
+                    op(self.backward[i + 1], p[i])
+                };
+            }
+
+            // Each window covers at most two adjacent blocks.
+            for i in 0..len {
+                data[start + i * step] = op(self.backward[i], self.forward[i + window - 1]);
+            }
+        }
+    }
+}
+
+pub mod displacement_map {
+    use super::Pixel;
+
+    /// Displaces an image using a map.
+    ///
+    /// - `src` pixels can be stored in any format.
+    /// - `displace` pixels must be not premultiplied.
+    /// - `sx` and `sy` indicate the displacement scale in the device coordinates.
+    ///
+    /// All images must have the same size.
+    pub fn apply<P: Pixel + Copy>(
+        fe: &usvg::FeDisplacementMap,
+        sx: f64,
+        sy: f64,
+        src: &[P],
+        displace: &[P],
+        dest: &mut [P],
+        width: u32,
+    ) {
+        assert!(src.len() == displace.len() && src.len() == dest.len());
+
+        let w = width as i32;
+        let h = src.len() as i32 / w;
+
+        let calc_offset = |channel: usvg::ColorChannel, pixel: &P| {
+            let (r, g, b, a) = pixel.rgba();
+            let c = match channel {
+                usvg::ColorChannel::R => r,
+                usvg::ColorChannel::G => g,
+                usvg::ColorChannel::B => b,
+                usvg::ColorChannel::A => a,
+            };
+
+            c as f64 / 255.0 - 0.5
+        };
+
+        let mut x = 0;
+        let mut y = 0;
+        for (pixel, map) in dest.iter_mut().zip(displace.iter()) {
+            // We are using the nearest neighbor sampling, so we can simply copy pixels
+            // without any premultiplication.
+            let ox = (x as f64 + calc_offset(fe.x_channel_selector, map) * sx).round() as i32;
+            let oy = (y as f64 + calc_offset(fe.y_channel_selector, map) * sy).round() as i32;
+
+            if ox >= 0 && ox < w && oy >= 0 && oy < h {
+                *pixel = src[(w * oy + ox) as usize];
+            } else {
+                pixel.set_rgba(0, 0, 0, 0);
+            }
+
+            x += 1;
+            if x == w {
+                x = 0;
+                y += 1;
+            }
+        }
+    }
+}
+
+pub mod lighting {
+    use std::cmp;
+
+    // self
+    use crate::prelude::*;
+    use super::{
+        ColorSpace,
+        Pixel,
+        SRGB_TO_LINEAR_RGB_TABLE,
+    };
+
+    #[derive(Clone, Copy, Debug)]
+    struct Vector3 {
+        x: f64,
+        y: f64,
+        z: f64,
+    }
+
+    impl Vector3 {
+        #[inline]
+        fn new(x: f64, y: f64, z: f64) -> Self {
+            Vector3 { x, y, z }
+        }
+
+        #[inline]
+        fn dot(&self, other: Vector3) -> f64 {
+            self.x * other.x + self.y * other.y + self.z * other.z
+        }
+
+        #[inline]
+        fn normalized(&self) -> Self {
+            let len = self.dot(*self).sqrt();
+            if len.is_fuzzy_zero() {
+                *self
+            } else {
+                Vector3::new(self.x / len, self.y / len, self.z / len)
+            }
+        }
+    }
+
+    /// An alpha channel of the input image, that is used as a surface height map.
+    struct HeightMap {
+        data: Vec<f64>,
+        width: i32,
+        height: i32,
+    }
+
+    impl HeightMap {
+        #[inline]
+        fn get(&self, x: i32, y: i32) -> f64 {
+            self.data[(self.width * y + x) as usize]
+        }
+
+        /// Calculates a surface normal using the Sobel operator.
+        ///
+        /// This is a generalized version of the kernels from the spec,
+        /// which are different for edges and corners.
+        /// `dx` and `dy` are the distances between the samples.
+        fn normal(&self, x: i32, y: i32, dx: i32, dy: i32, surface_scale: f64) -> Vector3 {
+            let x0 = if x - dx >= 0 { x - dx } else { x };
+            let x1 = if x + dx < self.width { x + dx } else { x };
+            let y0 = if y - dy >= 0 { y - dy } else { y };
+            let y1 = if y + dy < self.height { y + dy } else { y };
+
+            let mut nx = 0.0;
+            if x0 != x1 {
+                let mut sum = 2.0 * (self.get(x1, y) - self.get(x0, y));
+                let mut weight = 2.0;
+                for &row in &[y0, y1] {
+                    if row != y {
+                        sum += self.get(x1, row) - self.get(x0, row);
+                        weight += 1.0;
+                    }
+                }
+
+                let span = if x1 - x0 > dx { 2.0 } else { 1.0 };
+                nx = -surface_scale * 2.0 / (span * weight) * sum;
+            }
+
+            let mut ny = 0.0;
+            if y0 != y1 {
+                let mut sum = 2.0 * (self.get(x, y1) - self.get(x, y0));
+                let mut weight = 2.0;
+                for &column in &[x0, x1] {
+                    if column != x {
+                        sum += self.get(column, y1) - self.get(column, y0);
+                        weight += 1.0;
+                    }
+                }
+
+                let span = if y1 - y0 > dy { 2.0 } else { 1.0 };
+                ny = -surface_scale * 2.0 / (span * weight) * sum;
+            }
+
+            Vector3::new(nx, ny, 1.0).normalized()
+        }
+    }
+
+    struct Params {
+        surface_scale: f64,
+        lighting_color: usvg::Color,
+        light_source: usvg::FeLightSource,
+        kernel_unit_length: (u32, u32),
+    }
+
+    /// Renders a diffuse lighting.
+    ///
+    /// - `light_source` must be already converted into the image coordinates.
+    /// - `kernel_unit_length` is in pixels.
+    ///
+    /// Only the alpha channel of the input image is used.
+    /// The result is premultiplied.
+    pub fn diffuse_lighting<P: Pixel>(
+        fe: &usvg::FeDiffuseLighting,
+        light_source: usvg::FeLightSource,
+        kernel_unit_length: (u32, u32),
+        cs: ColorSpace,
+        width: u32,
+        height: u32,
+        data: &mut [P],
+    ) {
+        let params = Params {
+            surface_scale: fe.surface_scale,
+            lighting_color: convert_color(fe.lighting_color, cs),
+            light_source,
+            kernel_unit_length,
+        };
+
+        let light_factor = |normal: Vector3, light_vector: Vector3| {
+            fe.diffuse_constant.value() * normal.dot(light_vector)
+        };
+
+        apply(&params, light_factor, |_, _, _| 255, width, height, data);
+    }
+
+    /// Renders a specular lighting.
+    ///
+    /// - `light_source` must be already converted into the image coordinates.
+    /// - `kernel_unit_length` is in pixels.
+    ///
+    /// Only the alpha channel of the input image is used.
+    /// The result is premultiplied.
+    pub fn specular_lighting<P: Pixel>(
+        fe: &usvg::FeSpecularLighting,
+        light_source: usvg::FeLightSource,
+        kernel_unit_length: (u32, u32),
+        cs: ColorSpace,
+        width: u32,
+        height: u32,
+        data: &mut [P],
+    ) {
+        let params = Params {
+            surface_scale: fe.surface_scale,
+            lighting_color: convert_color(fe.lighting_color, cs),
+            light_source,
+            kernel_unit_length,
+        };
+
+        let light_factor = |normal: Vector3, light_vector: Vector3| {
+            let h = Vector3::new(light_vector.x, light_vector.y, light_vector.z + 1.0).normalized();
+            let n_dot_h = normal.dot(h);
+            if n_dot_h <= 0.0 {
+                return 0.0;
+            }
+
+            fe.specular_constant.value() * n_dot_h.powf(fe.specular_exponent)
+        };
+
+        let calc_alpha = |r: u8, g: u8, b: u8| cmp::max(cmp::max(r, g), b);
+
+        apply(&params, light_factor, calc_alpha, width, height, data);
+    }
+
+    fn apply<P, F, A>(
+        params: &Params,
+        light_factor: F,
+        calc_alpha: A,
+        width: u32,
+        height: u32,
+        data: &mut [P],
+    )
+        where P: Pixel, F: Fn(Vector3, Vector3) -> f64, A: Fn(u8, u8, u8) -> u8
+    {
+        let map = HeightMap {
+            data: data.iter().map(|p| p.rgba().3 as f64 / 255.0).collect(),
+            width: width as i32,
+            height: height as i32,
+        };
+
+        let (dx, dy) = (params.kernel_unit_length.0 as i32, params.kernel_unit_length.1 as i32);
+        let surface_scale = params.surface_scale;
+        let color = params.lighting_color;
+        let color = (color.red as f64, color.green as f64, color.blue as f64);
+
+        let light_vector = |x: i32, y: i32| {
+            match params.light_source {
+                usvg::FeLightSource::FeDistantLight(ref light) => {
+                    let azimuth = light.azimuth.to_radians();
+                    let elevation = light.elevation.to_radians();
+                    Vector3::new(
+                        azimuth.cos() * elevation.cos(),
+                        azimuth.sin() * elevation.cos(),
+                        elevation.sin(),
+                    )
+                }
+                usvg::FeLightSource::FePointLight(ref light) => {
+                    let z = surface_scale * map.get(x, y);
+                    Vector3::new(light.x - x as f64, light.y - y as f64, light.z - z).normalized()
+                }
+                usvg::FeLightSource::FeSpotLight(ref light) => {
+                    let z = surface_scale * map.get(x, y);
+                    Vector3::new(light.x - x as f64, light.y - y as f64, light.z - z).normalized()
+                }
+            }
+        };
+
+        let light_color = |light_vector: Vector3| {
+            match params.light_source {
+                usvg::FeLightSource::FeSpotLight(ref light) => {
+                    let s = Vector3::new(
+                        light.points_at_x - light.x,
+                        light.points_at_y - light.y,
+                        light.points_at_z - light.z,
+                    ).normalized();
+
+                    let minus_l_dot_s = -light_vector.dot(s);
+                    if minus_l_dot_s <= 0.0 {
+                        return (0.0, 0.0, 0.0);
+                    }
+
+                    if let Some(angle) = light.limiting_cone_angle {
+                        if minus_l_dot_s < angle.to_radians().cos() {
+                            return (0.0, 0.0, 0.0);
+                        }
+                    }
+
+                    let factor = minus_l_dot_s.powf(light.specular_exponent.value());
+                    (color.0 * factor, color.1 * factor, color.2 * factor)
+                }
+                _ => color,
+            }
+        };
+
+        let mut i = 0;
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let normal = map.normal(x, y, dx, dy, surface_scale);
+                let light_vector = light_vector(x, y);
+                let (lr, lg, lb) = light_color(light_vector);
+                let factor = light_factor(normal, light_vector);
+
+                let compute = |c: f64| (f64_bound(0.0, factor * c, 255.0) + 0.5) as u8;
+                let r = compute(lr);
+                let g = compute(lg);
+                let b = compute(lb);
+                let a = calc_alpha(r, g, b);
+
+                data[i].set_rgba(r, g, b, a);
+                i += 1;
+            }
+        }
+    }
+
+    fn convert_color(color: usvg::Color, cs: ColorSpace) -> usvg::Color {
+        match cs {
+            ColorSpace::SRGB => color,
+            ColorSpace::LinearRGB => {
+                usvg::Color::new(
+                    SRGB_TO_LINEAR_RGB_TABLE[color.red as usize],
+                    SRGB_TO_LINEAR_RGB_TABLE[color.green as usize],
+                    SRGB_TO_LINEAR_RGB_TABLE[color.blue as usize],
+                )
+            }
+        }
+    }
+}
+
+/// Returns filter region.
+pub fn calc_region(
+    filter: &usvg::Filter,
+    bbox: Rect,
+    ts: &usvg::Transform,
+    canvas_rect: ScreenRect,
+) -> Result<ScreenRect, Error> {
+    let path = utils::rect_to_path(filter.rect);
+
+    let region_ts = if filter.units == usvg::Units::ObjectBoundingBox {
+        let bbox_ts = usvg::Transform::from_bbox(bbox);
+        let mut ts2 = ts.clone();
+        ts2.append(&bbox_ts);
+        ts2
+    } else {
+        *ts
+    };
+
+    let region = utils::path_bbox(&path, None, Some(region_ts))
+        .ok_or_else(|| Error::InvalidRegion)?
+        .to_screen_rect()
+        .fit_to_rect(canvas_rect);
+
+    Ok(region)
+}
+
+/// Returns filter primitive region.
+fn calc_subregion<T: ImageExt>(
+    filter: &usvg::Filter,
+    primitive: &usvg::FilterPrimitive,
+    bbox: Rect,
+    filter_region: ScreenRect,
+    ts: &usvg::Transform,
+    results: &[FilterResult<T>],
+) -> Result<ScreenRect, Error> {
+    // TODO: rewrite/simplify/explain/whatever
+
+    let region = match primitive.kind {
+        usvg::FilterKind::FeOffset(ref fe) => {
+            // `feOffset` inherits it's region from the input.
+            match fe.input {
+                usvg::FilterInput::Reference(ref name) => {
+                    match results.iter().rev().find(|v| v.name == *name) {
+                        Some(ref res) => res.image.region,
+                        None => filter_region,
+                    }
+                }
+                _ => {
+                    filter_region
+                }
+            }
+        }
+        usvg::FilterKind::FeImage(..) => {
+            // `feImage` uses the object bbox.
+            if filter.primitive_units == usvg::Units::ObjectBoundingBox {
+                // TODO: wrong
+                let ts_bbox = Rect::new(ts.e, ts.f, ts.a, ts.d).unwrap();
+
+                let r = Rect::new(
+                    primitive.x.unwrap_or(0.0),
+                    primitive.y.unwrap_or(0.0),
+                    primitive.width.unwrap_or(1.0),
+                    primitive.height.unwrap_or(1.0),
+                ).ok_or_else(|| Error::InvalidRegion)?;
+
+                let r = r
+                    .bbox_transform(bbox)
+                    .bbox_transform(ts_bbox)
+                    .to_screen_rect();
+
+                return Ok(r);
+            } else {
+                filter_region
+            }
+        }
+        _ => filter_region,
+    };
+
+    // TODO: Wrong! Does not account rotate and skew.
+    let subregion = if filter.primitive_units == usvg::Units::ObjectBoundingBox {
+        let subregion_bbox = Rect::new(
+            primitive.x.unwrap_or(0.0),
+            primitive.y.unwrap_or(0.0),
+            primitive.width.unwrap_or(1.0),
+            primitive.height.unwrap_or(1.0),
+        ).ok_or_else(|| Error::InvalidRegion)?;
+
+        region.to_rect().bbox_transform(subregion_bbox)
+    } else {
+        let (dx, dy) = ts.get_translate();
+        let (sx, sy) = ts.get_scale();
+        Rect::new(
+            primitive.x.map(|n| n * sx + dx).unwrap_or(region.x() as f64),
+            primitive.y.map(|n| n * sy + dy).unwrap_or(region.y() as f64),
+            primitive.width.map(|n| n * sx).unwrap_or(region.width() as f64),
+            primitive.height.map(|n| n * sy).unwrap_or(region.height() as f64),
+        ).ok_or_else(|| Error::InvalidRegion)?
+    };
+
+    Ok(subregion.to_screen_rect())
+}
+
+/// Precomputed sRGB to LinearRGB table.
 ///
 /// Since we are storing the result in `u8`, there is no need to compute those
 /// values each time. Mainly because it's very expensive.
@@ -751,3 +2134,354 @@ pub const LINEAR_RGB_TO_SRGB_TABLE: &[u8; 256] = &[
     241, 241, 242, 242, 243, 243, 244, 244, 245, 245, 246, 246, 246, 247, 247, 248,
     248, 249, 249, 250, 250, 251, 251, 251, 252, 252, 253, 253, 254, 254, 255, 255,
 ];
+
+
+#[cfg(test)]
+mod tests {
+    use rgb::RGBA8;
+    use super::*;
+
+    fn pixel(r: u8, g: u8, b: u8, a: u8) -> RGBA8 {
+        RGBA8 { r, g, b, a }
+    }
+
+    #[test]
+    fn color_matrix_matrix() {
+        // Swaps red and blue channels and halves the alpha.
+        let kind = usvg::FeColorMatrixKind::Matrix(vec![
+            0.0, 0.0, 1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0,
+            1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.5, 0.0,
+        ]);
+
+        let mut data = [pixel(255, 128, 0, 255)];
+        color_matrix::apply(&kind, &mut data);
+        assert_eq!(data, [pixel(0, 128, 255, 128)]);
+    }
+
+    #[test]
+    fn color_matrix_saturate() {
+        let mut data = [pixel(255, 0, 0, 255), pixel(10, 20, 30, 40)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::Saturate(0.0.into()), &mut data);
+        assert_eq!(data, [pixel(54, 54, 54, 255), pixel(19, 19, 19, 40)]);
+
+        let mut data = [pixel(10, 20, 30, 40)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::Saturate(1.0.into()), &mut data);
+        assert_eq!(data, [pixel(10, 20, 30, 40)]);
+    }
+
+    #[test]
+    fn color_matrix_hue_rotate() {
+        let mut data = [pixel(10, 20, 30, 40)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::HueRotate(0.0), &mut data);
+        assert_eq!(data, [pixel(10, 20, 30, 40)]);
+
+        // A gray color is not affected.
+        let mut data = [pixel(100, 100, 100, 255)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::HueRotate(90.0), &mut data);
+        assert_eq!(data, [pixel(100, 100, 100, 255)]);
+
+        let mut data = [pixel(255, 0, 0, 255)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::HueRotate(180.0), &mut data);
+        assert_eq!(data, [pixel(0, 109, 109, 255)]);
+    }
+
+    #[test]
+    fn color_matrix_luminance_to_alpha() {
+        let mut data = [pixel(255, 0, 0, 100), pixel(255, 255, 255, 0)];
+        color_matrix::apply(&usvg::FeColorMatrixKind::LuminanceToAlpha, &mut data);
+        assert_eq!(data, [pixel(0, 0, 0, 54), pixel(0, 0, 0, 255)]);
+    }
+
+    #[test]
+    fn component_transfer() {
+        let fe = usvg::FeComponentTransfer {
+            input: usvg::FilterInput::SourceGraphic,
+            func_r: usvg::TransferFunction::Table(vec![1.0, 0.0]),
+            func_g: usvg::TransferFunction::Discrete(vec![0.0, 0.5, 1.0]),
+            func_b: usvg::TransferFunction::Linear { slope: 0.5, intercept: 0.25 },
+            func_a: usvg::TransferFunction::Gamma { amplitude: 1.0, exponent: 2.0, offset: 0.0 },
+        };
+
+        let mut data = [pixel(0, 0, 0, 0), pixel(64, 100, 255, 128), pixel(255, 255, 255, 255)];
+        component_transfer::apply(&fe, &mut data);
+        assert_eq!(data, [pixel(255, 0, 64, 0), pixel(191, 128, 191, 64), pixel(0, 255, 191, 255)]);
+    }
+
+    #[test]
+    fn component_transfer_dummy() {
+        let fe = usvg::FeComponentTransfer {
+            input: usvg::FilterInput::SourceGraphic,
+            func_r: usvg::TransferFunction::Identity,
+            func_g: usvg::TransferFunction::Table(Vec::new()),
+            func_b: usvg::TransferFunction::Discrete(vec![0.5]),
+            func_a: usvg::TransferFunction::Linear { slope: 1.0, intercept: 0.0 },
+        };
+
+        let mut data = [pixel(10, 20, 30, 40)];
+        component_transfer::apply(&fe, &mut data);
+        assert_eq!(data, [pixel(10, 20, 128, 40)]);
+    }
+
+    #[test]
+    fn morphology() {
+        fn image(pixels: &[u8]) -> Vec<u8> {
+            pixels.iter().flat_map(|&c| vec![c, c, c, c]).collect()
+        }
+
+        let src = image(&[
+            0, 0,   0, 0, 0,
+            0, 0,   0, 0, 0,
+            0, 0, 255, 0, 0,
+            0, 0,   0, 0, 0,
+            0, 0,   0, 0, 0,
+        ]);
+
+        let mut data = src.clone();
+        morphology::apply(usvg::FeMorphologyOperator::Dilate, 1, 1, &mut data, 5, 5);
+        assert_eq!(data, image(&[
+            0,   0,   0,   0, 0,
+            0, 255, 255, 255, 0,
+            0, 255, 255, 255, 0,
+            0, 255, 255, 255, 0,
+            0,   0,   0,   0, 0,
+        ]));
+
+        morphology::apply(usvg::FeMorphologyOperator::Erode, 1, 1, &mut data, 5, 5);
+        assert_eq!(data, src);
+
+        let mut data = src.clone();
+        morphology::apply(usvg::FeMorphologyOperator::Dilate, 2, 0, &mut data, 5, 5);
+        assert_eq!(data, image(&[
+              0,   0,   0,   0,   0,
+              0,   0,   0,   0,   0,
+            255, 255, 255, 255, 255,
+              0,   0,   0,   0,   0,
+              0,   0,   0,   0,   0,
+        ]));
+
+        // Pixels outside the image are ignored.
+        let mut data = image(&[255; 4]);
+        morphology::apply(usvg::FeMorphologyOperator::Erode, 3, 3, &mut data, 2, 2);
+        assert_eq!(data, image(&[255; 4]));
+    }
+
+    #[test]
+    fn convolve_matrix_edge_mode() {
+        fn convolve(edge_mode: usvg::EdgeMode) -> Vec<RGBA8> {
+            // Shifts an image by one pixel to the left, since the kernel is rotated.
+            let fe = usvg::FeConvolveMatrix {
+                input: usvg::FilterInput::SourceGraphic,
+                matrix: usvg::ConvolveMatrix::new(1, 0, 3, 1, vec![1.0, 0.0, 0.0]).unwrap(),
+                divisor: 1.0,
+                bias: 0.0,
+                edge_mode,
+                preserve_alpha: false,
+            };
+
+            let mut data = vec![pixel(255, 0, 0, 255), pixel(0, 255, 0, 255), pixel(0, 0, 255, 255)];
+            convolve_matrix::apply(&fe, 3, 1, &mut data);
+            data
+        }
+
+        assert_eq!(convolve(usvg::EdgeMode::None),
+                   [pixel(0, 255, 0, 255), pixel(0, 0, 255, 255), pixel(0, 0, 0, 0)]);
+        assert_eq!(convolve(usvg::EdgeMode::Duplicate),
+                   [pixel(0, 255, 0, 255), pixel(0, 0, 255, 255), pixel(0, 0, 255, 255)]);
+        assert_eq!(convolve(usvg::EdgeMode::Wrap),
+                   [pixel(0, 255, 0, 255), pixel(0, 0, 255, 255), pixel(255, 0, 0, 255)]);
+    }
+
+    #[test]
+    fn convolve_matrix_preserve_alpha() {
+        fn convolve(preserve_alpha: bool) -> Vec<RGBA8> {
+            let fe = usvg::FeConvolveMatrix {
+                input: usvg::FilterInput::SourceGraphic,
+                matrix: usvg::ConvolveMatrix::new(0, 0, 1, 1, vec![2.0]).unwrap(),
+                divisor: 1.0,
+                bias: 0.0,
+                edge_mode: usvg::EdgeMode::None,
+                preserve_alpha,
+            };
+
+            let mut data = vec![pixel(64, 32, 0, 128)];
+            convolve_matrix::apply(&fe, 1, 1, &mut data);
+            data
+        }
+
+        assert_eq!(convolve(true), [pixel(128, 64, 0, 128)]);
+        assert_eq!(convolve(false), [pixel(128, 64, 0, 255)]);
+    }
+
+    #[test]
+    fn convolve_matrix_divisor_and_bias() {
+        let fe = usvg::FeConvolveMatrix {
+            input: usvg::FilterInput::SourceGraphic,
+            matrix: usvg::ConvolveMatrix::new(1, 0, 3, 1, vec![1.0, 1.0, 1.0]).unwrap(),
+            divisor: 3.0,
+            bias: 0.25,
+            edge_mode: usvg::EdgeMode::Duplicate,
+            preserve_alpha: true,
+        };
+
+        let mut data = vec![pixel(0, 0, 0, 255), pixel(255, 0, 0, 255), pixel(0, 0, 0, 255)];
+        convolve_matrix::apply(&fe, 3, 1, &mut data);
+        assert_eq!(data, [pixel(149, 64, 64, 255), pixel(149, 64, 64, 255),
+                          pixel(149, 64, 64, 255)]);
+    }
+
+    #[test]
+    fn turbulence() {
+        fn turbulence(kind: usvg::FeTurbulenceKind, freq: f64, seed: i32) -> Vec<RGBA8> {
+            let fe = usvg::FeTurbulence {
+                base_frequency_x: freq.into(),
+                base_frequency_y: freq.into(),
+                num_octaves: 1,
+                seed,
+                stitch_tiles: false,
+                kind,
+            };
+
+            let region = ScreenRect::new(0, 0, 8, 8).unwrap();
+            let mut data = vec![RGBA8::default(); 64];
+            turbulence::apply(&fe, region, region, &usvg::Transform::default(), &mut data);
+            data
+        }
+
+        // The noise is zero at lattice points.
+        let data = turbulence(usvg::FeTurbulenceKind::FractalNoise, 1.0, 0);
+        assert!(data.iter().all(|p| *p == pixel(128, 128, 128, 128)));
+
+        let data = turbulence(usvg::FeTurbulenceKind::Turbulence, 1.0, 0);
+        assert!(data.iter().all(|p| *p == pixel(0, 0, 0, 0)));
+
+        // The result depends only on the seed.
+        let data1 = turbulence(usvg::FeTurbulenceKind::Turbulence, 0.3, 1);
+        let data2 = turbulence(usvg::FeTurbulenceKind::Turbulence, 0.3, 1);
+        let data3 = turbulence(usvg::FeTurbulenceKind::Turbulence, 0.3, 2);
+        assert_eq!(data1, data2);
+        assert_ne!(data1, data3);
+        assert!(data1.iter().any(|p| p.a != 0));
+    }
+
+    fn distant_light(elevation: f64) -> usvg::FeLightSource {
+        usvg::FeLightSource::FeDistantLight(usvg::FeDistantLight { azimuth: 0.0, elevation })
+    }
+
+    fn diffuse_lighting(
+        color: usvg::Color,
+        light_source: usvg::FeLightSource,
+        cs: ColorSpace,
+        data: &mut [RGBA8],
+    ) {
+        let fe = usvg::FeDiffuseLighting {
+            input: usvg::FilterInput::SourceGraphic,
+            surface_scale: 1.0,
+            diffuse_constant: 1.0.into(),
+            kernel_unit_length: None,
+            lighting_color: color,
+            light_source,
+        };
+
+        let width = data.len() as u32;
+        lighting::diffuse_lighting(&fe, light_source, (1, 1), cs, width, 1, data);
+    }
+
+    #[test]
+    fn diffuse_lighting_distant() {
+        let white = usvg::Color::new(255, 255, 255);
+
+        // A flat surface lit from above.
+        let mut data = [pixel(0, 0, 0, 255); 2];
+        diffuse_lighting(white, distant_light(90.0), ColorSpace::SRGB, &mut data);
+        assert_eq!(data, [pixel(255, 255, 255, 255); 2]);
+
+        // sin(30deg) = 0.5
+        let color = usvg::Color::new(200, 100, 50);
+        let mut data = [pixel(0, 0, 0, 255); 2];
+        diffuse_lighting(color, distant_light(30.0), ColorSpace::SRGB, &mut data);
+        assert_eq!(data, [pixel(100, 50, 25, 255); 2]);
+
+        // The lighting color is converted into the filter color space.
+        let gray = usvg::Color::new(128, 128, 128);
+        let mut data = [pixel(0, 0, 0, 255); 2];
+        diffuse_lighting(gray, distant_light(90.0), ColorSpace::LinearRGB, &mut data);
+        assert_eq!(data, [pixel(55, 55, 55, 255); 2]);
+    }
+
+    #[test]
+    fn diffuse_lighting_spot() {
+        let light = usvg::FeLightSource::FeSpotLight(usvg::FeSpotLight {
+            x: 0.0,
+            y: 0.0,
+            z: 10.0,
+            points_at_x: 0.0,
+            points_at_y: 0.0,
+            points_at_z: 0.0,
+            specular_exponent: 1.0.into(),
+            limiting_cone_angle: Some(10.0),
+        });
+
+        let mut data = [pixel(0, 0, 0, 255); 4];
+        diffuse_lighting(usvg::Color::new(255, 255, 255), light, ColorSpace::SRGB, &mut data);
+        assert_eq!(data[0], pixel(255, 255, 255, 255));
+        assert!(data[1].r > 0);
+        assert_eq!(data[2], pixel(0, 0, 0, 255));
+        assert_eq!(data[3], pixel(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn specular_lighting() {
+        let fe = usvg::FeSpecularLighting {
+            input: usvg::FilterInput::SourceGraphic,
+            surface_scale: 1.0,
+            specular_constant: 0.5.into(),
+            specular_exponent: 1.0,
+            kernel_unit_length: None,
+            lighting_color: usvg::Color::new(200, 100, 0),
+            light_source: distant_light(90.0),
+        };
+
+        // The alpha is a maximum of the color channels.
+        let mut data = [pixel(0, 0, 0, 255); 2];
+        lighting::specular_lighting(&fe, fe.light_source, (1, 1), ColorSpace::SRGB, 2, 1, &mut data);
+        assert_eq!(data, [pixel(100, 50, 0, 100); 2]);
+
+        // Light from below.
+        let mut data = [pixel(0, 0, 0, 255); 2];
+        lighting::specular_lighting(&fe, distant_light(-90.0), (1, 1), ColorSpace::SRGB, 2, 1, &mut data);
+        assert_eq!(data, [pixel(0, 0, 0, 0); 2]);
+    }
+
+    #[test]
+    fn displacement_map() {
+        fn displace(map: RGBA8) -> Vec<RGBA8> {
+            let fe = usvg::FeDisplacementMap {
+                input1: usvg::FilterInput::SourceGraphic,
+                input2: usvg::FilterInput::SourceGraphic,
+                scale: 2.0,
+                x_channel_selector: usvg::ColorChannel::R,
+                y_channel_selector: usvg::ColorChannel::G,
+            };
+
+            let src = [pixel(255, 0, 0, 255), pixel(0, 255, 0, 255), pixel(0, 0, 255, 255)];
+            let mut dest = [RGBA8::default(); 3];
+            displacement_map::apply(&fe, 2.0, 2.0, &src, &[map; 3], &mut dest, 3);
+            dest.to_vec()
+        }
+
+        // A neutral map.
+        assert_eq!(displace(pixel(128, 128, 0, 0)),
+                   [pixel(255, 0, 0, 255), pixel(0, 255, 0, 255), pixel(0, 0, 255, 255)]);
+
+        // Shifts by +1 and -1 pixels. Pixels outside the image are transparent.
+        assert_eq!(displace(pixel(255, 128, 0, 0)),
+                   [pixel(0, 255, 0, 255), pixel(0, 0, 255, 255), pixel(0, 0, 0, 0)]);
+        assert_eq!(displace(pixel(0, 128, 0, 0)),
+                   [pixel(0, 0, 0, 0), pixel(255, 0, 0, 255), pixel(0, 255, 0, 255)]);
+
+        // There is only one row.
+        assert_eq!(displace(pixel(128, 255, 0, 0)), [pixel(0, 0, 0, 0); 3]);
+    }
+}
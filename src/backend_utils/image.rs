@@ -23,6 +23,11 @@ pub fn load_sub_svg(
             text_rendering: opt.usvg.text_rendering,
             image_rendering: opt.usvg.image_rendering,
             keep_named_groups: false,
+            strict: false,
+            resource_loader: opt.usvg.resource_loader.clone(),
+            limits: opt.usvg.limits,
+            style_sheet: None,
+            css_variables: Vec::new(),
         },
         fit_to: FitTo::Original,
         background: None,
@@ -2,11 +2,119 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::ScreenSize;
+use std::cell::Cell;
 
+use crate::prelude::*;
+use crate::{
+    Options,
+    RenderError,
+    ScreenSize,
+};
+
+#[cfg(any(feature = "qt-backend", feature = "raster-backend"))]
+pub mod blend;
 pub mod filter;
 pub mod image;
 
+/// Indicates the current rendering state.
+pub enum RenderState {
+    /// A default value. Doesn't indicate anything.
+    Ok,
+    /// Indicates that the current rendering task should stop after reaching the specified node.
+    RenderUntil(usvg::Node),
+    /// Indicates that the `BackgroundImage` rendering task was finished.
+    BackgroundFinished,
+}
+
+thread_local! {
+    static EXCEEDED_LIMIT: Cell<Option<usvg::LimitKind>> = Cell::new(None);
+}
+
+/// Fits the output image size and checks that it doesn't exceed `usvg::Limits::max_pixels`.
+pub fn prepare_image_size(
+    size: ScreenSize,
+    opt: &Options,
+) -> Result<ScreenSize, RenderError> {
+    let size = utils::fit_to(size, opt.fit_to).ok_or(RenderError::InvalidSize)?;
+
+    if let Some(max) = opt.usvg.limits.max_pixels {
+        if size.width() as u64 * size.height() as u64 > max {
+            return Err(RenderError::LimitExceeded(usvg::LimitKind::Pixels));
+        }
+    }
+
+    Ok(size)
+}
+
+/// Reports a limit that was exceeded during rendering.
+///
+/// Rendering itself will continue, but `check_render_limits` will return an error.
+pub fn report_exceeded_limit(kind: usvg::LimitKind) {
+    EXCEEDED_LIMIT.with(|c| c.set(Some(kind)));
+}
+
+/// Renders an image using `f` and checks that no limits were exceeded during it.
+pub fn check_render_limits<F, R>(f: F) -> Result<R, RenderError>
+    where F: FnOnce() -> R
+{
+    let prev = EXCEEDED_LIMIT.with(|c| c.replace(None));
+    let res = f();
+    let kind = EXCEEDED_LIMIT.with(|c| {
+        let kind = c.get();
+        // Propagate to an outer call.
+        c.set(prev.or(kind));
+        kind
+    });
+
+    match kind {
+        Some(kind) => Err(RenderError::LimitExceeded(kind)),
+        None => Ok(res),
+    }
+}
+
+/// Calculates a path bounding box, including the stroke.
+///
+/// `ts` is a transform from the path user space to the root user space.
+pub fn calc_path_bbox(
+    path: &usvg::Path,
+    ts: usvg::Transform,
+    tree: &usvg::Tree,
+    opt: &Options,
+) -> Option<Rect> {
+    match path.stroke {
+        Some(ref stroke) if stroke.non_scaling => {
+            let bbox = utils::path_bbox(&path.segments, None, Some(ts))?;
+
+            // A non-scaling stroke width is set in the device space,
+            // so we have to convert it into the root user space first.
+            let (sx, sy) = root_transform(tree, opt).get_scale();
+            let hw = stroke.width.value() / sx.max(sy) / 2.0;
+            if !hw.is_finite() {
+                return Some(bbox);
+            }
+
+            Rect::new(bbox.x() - hw, bbox.y() - hw, bbox.width() + hw * 2.0, bbox.height() + hw * 2.0)
+        }
+        _ => {
+            utils::path_bbox(&path.segments, path.stroke.as_ref(), Some(ts))
+        }
+    }
+}
+
+/// Returns a transform from the root user space to the device space.
+fn root_transform(
+    tree: &usvg::Tree,
+    opt: &Options,
+) -> usvg::Transform {
+    let svg = tree.svg_node();
+    match utils::fit_to(svg.size.to_screen_size(), opt.fit_to) {
+        Some(img_size) => {
+            utils::view_box_to_transform(svg.view_box.rect, svg.view_box.aspect, img_size.to_size())
+        }
+        None => usvg::Transform::default(),
+    }
+}
+
 pub fn use_shape_antialiasing(
     mode: usvg::ShapeRendering,
 ) -> bool {
@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::error;
+use std::fmt;
+
+/// List of all rendering errors.
+#[derive(Debug)]
+pub enum RenderError {
+    /// The output image doesn't have a valid size.
+    ///
+    /// Occurs when `FitTo` produces a zero size
+    /// or when a rendered node has a zero size.
+    InvalidSize,
+
+    /// One of the `usvg::Limits` was exceeded.
+    LimitExceeded(usvg::LimitKind),
+
+    /// Failed to allocate an output image.
+    ImageAllocationFailed,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderError::InvalidSize => {
+                write!(f, "the output image has an invalid size")
+            }
+            RenderError::LimitExceeded(kind) => {
+                write!(f, "the {} limit was exceeded", kind)
+            }
+            RenderError::ImageAllocationFailed => {
+                write!(f, "failed to allocate an image")
+            }
+        }
+    }
+}
+
+impl error::Error for RenderError {
+    fn description(&self) -> &str {
+        "an SVG rendering error"
+    }
+}
@@ -38,8 +38,12 @@ pub mod backend_cairo;
 #[cfg(feature = "qt-backend")]
 pub mod backend_qt;
 
+#[cfg(feature = "raster-backend")]
+pub mod backend_raster;
+
 pub mod utils;
 mod backend_utils;
+mod error;
 mod geom;
 mod layers;
 mod options;
@@ -55,6 +59,7 @@ pub mod prelude {
     pub use crate::Render;
 }
 
+pub use crate::error::*;
 pub use crate::geom::*;
 pub use crate::options::*;
 
@@ -66,21 +71,23 @@ pub use crate::options::*;
 pub trait Render {
     /// Renders SVG to image.
     ///
-    /// Returns `None` if an image allocation failed.
+    /// Returns an error if an image allocation failed
+    /// or when `usvg::Limits::max_pixels` or `max_filter_region` was exceeded.
     fn render_to_image(
         &self,
         tree: &usvg::Tree,
         opt: &Options,
-    ) -> Option<Box<OutputImage>>;
+    ) -> Result<Box<OutputImage>, RenderError>;
 
     /// Renders SVG node to image.
     ///
-    /// Returns `None` if an image allocation failed.
+    /// Returns an error if an image allocation failed
+    /// or when `usvg::Limits::max_pixels` or `max_filter_region` was exceeded.
     fn render_node_to_image(
         &self,
         node: &usvg::Node,
         opt: &Options,
-    ) -> Option<Box<OutputImage>>;
+    ) -> Result<Box<OutputImage>, RenderError>;
 
     /// Calculates node's absolute bounding box.
     ///
@@ -104,7 +111,8 @@ pub trait OutputImage {
 
 /// Returns default backend.
 ///
-/// - If both backends are enabled - cairo backend will be returned.
+/// - If multiple backends are enabled - the first one from cairo, Qt and raster
+///   will be returned.
 /// - If no backends are enabled - will panic.
 /// - Otherwise will return a corresponding backend.
 #[allow(unreachable_code)]
@@ -119,5 +127,10 @@ pub fn default_backend() -> Box<Render> {
         return Box::new(backend_qt::Backend);
     }
 
+    #[cfg(feature = "raster-backend")]
+    {
+        return Box::new(backend_raster::Backend);
+    }
+
     unreachable!("at least one backend must be enabled")
 }
@@ -73,3 +73,23 @@ pub fn abs_transform(
 
     root_ts
 }
+
+/// Returns an inverted transform.
+pub(crate) fn invert_transform(
+    ts: &usvg::Transform,
+) -> Option<usvg::Transform> {
+    let det = ts.a * ts.d - ts.b * ts.c;
+    if det.abs() < 1e-12 || !det.is_finite() {
+        return None;
+    }
+
+    let inv = 1.0 / det;
+    Some(usvg::Transform::new(
+        ts.d * inv,
+        -ts.b * inv,
+        -ts.c * inv,
+        ts.a * inv,
+        (ts.c * ts.f - ts.d * ts.e) * inv,
+        (ts.b * ts.e - ts.a * ts.f) * inv,
+    ))
+}
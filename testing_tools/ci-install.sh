@@ -17,6 +17,11 @@ if [ "$RESVG_CAIRO_BACKEND" = true ]; then
     sudo apt-get install -y libgtk-3-dev
 fi
 
+# the raster backend regression testing uses the cairo backend as a reference
+if [ "$RESVG_RASTER_BACKEND" = true ]; then
+    sudo apt-get install -y libcairo2-dev libgdk-pixbuf2.0-dev
+fi
+
 if [ "$RESVG_QT_BACKEND" = true ]; then
     sudo add-apt-repository ppa:beineri/opt-qt563-xenial -y
     sudo apt-get update -qq
@@ -16,6 +16,14 @@ CRASH_ALLOWED = [
     'e-svg-036.svg',
 ]
 
+# Backends that are not available in the previous version are compared with another backend.
+REFERENCE_BACKEND = {
+    'raster': 'cairo',
+}
+
+# Anti-aliasing differs between backends, so a small difference per pixel is allowed.
+CROSS_BACKEND_FUZZ = '10%'
+
 
 def build_prev_version():
     prev_resvg_dir = args.work_dir / 'resvg'
@@ -29,7 +37,7 @@ def build_prev_version():
         # TODO: maybe there is a better way
         run(['git', 'reset', '--hard', 'HEAD~1'], cwd=prev_resvg_dir, check=True)
 
-    run(['cargo', 'build', '--features', args.backend + '-backend'],
+    run(['cargo', 'build', '--features', ref_backend + '-backend'],
         cwd=prev_resvg_dir / 'tools/rendersvg', check=True)
 
     return prev_resvg_dir / 'target/debug/rendersvg'
@@ -39,10 +47,10 @@ def change_ext(path, suffix, new_ext):
     return Path(path).stem + suffix + '.' + new_ext
 
 
-def render_svg(render_path, in_svg, out_png):
+def render_svg(render_path, backend, in_svg, out_png):
     # Render with zoom by default to test scaling.
     # Images may render differently depending on scale.
-    return run([render_path, '--backend', args.backend, '--zoom', '2', in_svg, out_png],
+    return run([render_path, '--backend', backend, '--zoom', '2', in_svg, out_png],
                cwd=args.work_dir).returncode
 
 
@@ -67,7 +75,7 @@ def rm_file(file_path):
 
 if __name__ == '__main__':
     parser = argparse.ArgumentParser()
-    parser.add_argument('--backend', help='Sets resvg backend', choices=['qt', 'cairo'])
+    parser.add_argument('--backend', help='Sets resvg backend', choices=['qt', 'cairo', 'raster'])
     parser.add_argument('--use-prev-commit', help='Use previous git commit as a reference',
                         action='store_true')
     parser.add_argument('in_dir', type=Path, help='Sets input directory')
@@ -82,8 +90,13 @@ if __name__ == '__main__':
         allowed_files_list = f.read().splitlines()
     allowed_files_list.extend(CRASH_ALLOWED)
 
+    ref_backend = REFERENCE_BACKEND.get(args.backend, args.backend)
     prev_render_path = build_prev_version()
 
+    compare_args = ['compare', '-metric', 'AE']
+    if ref_backend != args.backend:
+        compare_args.extend(['-fuzz', CROSS_BACKEND_FUZZ])
+
     start_idx = load_last_pos()
     files = os.listdir(args.in_dir)
     files = fnmatch.filter(files, '*.svg')
@@ -107,16 +120,16 @@ if __name__ == '__main__':
         if file in allowed_files_list:
             continue
 
-        if render_svg(prev_render_path, svg_path, png_path_prev) != 0:
+        if render_svg(prev_render_path, ref_backend, svg_path, png_path_prev) != 0:
             continue
 
-        if render_svg(render_path, svg_path, png_path_curr) != 0:
+        if render_svg(render_path, args.backend, svg_path, png_path_curr) != 0:
             print('Error: rendersvg returned non-zero exit status.')
             save_last_pos(idx)
             exit(1)
 
         try:
-            diff_val = run(['compare', '-metric', 'AE', png_path_prev, png_path_curr, diff_path],
+            diff_val = run(compare_args + [png_path_prev, png_path_curr, diff_path],
                            check=True, stdout=proc.PIPE, stderr=proc.STDOUT).stdout
         except proc.CalledProcessError as e:
             print('Error: images are different by {} pixels.'.format(e.stdout.decode('ascii')))
@@ -98,6 +98,23 @@ if 'RESVG_CAIRO_BACKEND' in os.environ:
                 exit(1)
 
 
+if 'RESVG_RASTER_BACKEND' in os.environ:
+    # build raster backend
+    with cd('tools/rendersvg'):
+        run(['cargo', 'build', '--features', 'raster-backend'], check=True)
+
+    # run tests
+    run(['cargo', 'test', '--features', 'raster-backend'], check=True)
+
+    # regression testing of the raster backend
+    if not args.no_regression:
+        with cd('testing_tools/regression'):
+            try:
+                regression_testing('raster')
+            except subprocess.CalledProcessError:
+                exit(1)
+
+
 # # try to build with all backends
 # with cd('tools/rendersvg'):
 #     run(['cargo', 'build', '--all-features'], check=True)
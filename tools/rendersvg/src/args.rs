@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fs;
 use std::path;
 use std::process;
 
@@ -58,6 +59,12 @@ OPTIONS:
                                 [default: optimizeQuality]
                                 [possible values: optimizeQuality,
                                 optimizeSpeed]
+        --style-sheet PATH      Applies a user CSS style sheet
+        --css-var NAME=VALUE    Sets a CSS custom property on the root element.
+                                Can be set multiple times.
+                                Example: '--css-var accent=#ff0000'
+
+        --strict                Fails on any unsupported or lossy SVG feature
 
         --query-all             Queries all valid SVG ids with bounding boxes
         --export-id ID          Renders an object only with a specified ID
@@ -66,6 +73,8 @@ OPTIONS:
         --pretend               Does all the steps except rendering
         --quiet                 Disables warnings
         --dump-svg <PATH>       Saves the preprocessed SVG to the selected file
+        --diagnostics <PATH>    Saves conversion diagnostics as JSON
+                                to the selected file
 
 ARGS:
     <in-svg>                    Input file
@@ -118,6 +127,15 @@ struct CliArgs {
     #[options(no_short, meta = "HINT", default = "optimizeQuality", parse(try_from_str))]
     image_rendering: usvg::ImageRendering,
 
+    #[options(no_short, meta = "PATH")]
+    style_sheet: Option<String>,
+
+    #[options(no_short, meta = "NAME=VALUE")]
+    css_var: Vec<String>,
+
+    #[options(no_short)]
+    strict: bool,
+
     #[options(no_short)]
     query_all: bool,
 
@@ -136,6 +154,9 @@ struct CliArgs {
     #[options(no_short, meta = "PATH")]
     dump_svg: Option<String>,
 
+    #[options(no_short, meta = "PATH")]
+    diagnostics: Option<String>,
+
     #[options(free)]
     free: Vec<String>,
 }
@@ -204,6 +225,7 @@ pub struct Args {
     pub query_all: bool,
     pub export_id: Option<String>,
     pub dump: Option<path::PathBuf>,
+    pub diagnostics: Option<path::PathBuf>,
     pub pretend: bool,
     pub perf: bool,
     pub quiet: bool,
@@ -242,6 +264,7 @@ pub fn parse() -> Result<(Args, resvg::Options), String> {
 
     let backend_name = args.backend.unwrap_or(default_backend().to_string());
     let dump = args.dump_svg.map(|v| v.into());
+    let diagnostics = args.diagnostics.map(|v| v.into());
     let export_id = args.export_id.map(|v| v.to_string());
 
     let app_args = Args {
@@ -251,6 +274,7 @@ pub fn parse() -> Result<(Args, resvg::Options), String> {
         query_all: args.query_all,
         export_id,
         dump,
+        diagnostics,
         pretend: args.pretend,
         perf: args.perf,
         quiet: args.quiet,
@@ -274,6 +298,24 @@ pub fn parse() -> Result<(Args, resvg::Options), String> {
         None => vec!["en".to_string()], // TODO: use system language
     };
 
+    let style_sheet = match args.style_sheet {
+        Some(ref path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|_| format!("failed to read the style sheet"))?;
+            Some(text)
+        }
+        None => None,
+    };
+
+    let mut css_variables = Vec::new();
+    for s in &args.css_var {
+        match s.find('=') {
+            Some(idx) => css_variables.push((s[..idx].trim().to_string(),
+                                             s[(idx + 1)..].trim().to_string())),
+            None => return Err(format!("invalid CSS variable: '{}'", s)),
+        }
+    }
+
     let opt = resvg::Options {
         usvg: usvg::Options {
             path: Some(in_svg.into()),
@@ -285,6 +327,11 @@ pub fn parse() -> Result<(Args, resvg::Options), String> {
             text_rendering: args.text_rendering,
             image_rendering: args.image_rendering,
             keep_named_groups,
+            strict: args.strict,
+            resource_loader: None,
+            limits: usvg::Limits::default(),
+            style_sheet,
+            css_variables,
         },
         fit_to,
         background: args.background,
@@ -301,6 +348,9 @@ fn default_backend() -> &'static str {
     #[cfg(feature = "qt-backend")]
     { return "qt" }
 
+    #[cfg(feature = "raster-backend")]
+    { return "raster" }
+
     unreachable!();
 }
 
@@ -313,5 +363,8 @@ fn backends() -> Vec<&'static str> {
     #[cfg(feature = "qt-backend")]
     { list.push("qt"); }
 
+    #[cfg(feature = "raster-backend")]
+    { list.push("raster"); }
+
     list
 }
@@ -33,7 +33,11 @@ fn main() {
 }
 
 fn process() -> Result<(), String> {
-    #[cfg(all(not(feature = "cairo-backend"), not(feature = "qt-backend")))]
+    #[cfg(all(
+        not(feature = "cairo-backend"),
+        not(feature = "qt-backend"),
+        not(feature = "raster-backend"),
+    ))]
     {
         bail!("rendersvg has been built without any backends")
     }
@@ -64,6 +68,8 @@ fn process() -> Result<(), String> {
         "cairo" => Box::new(resvg::backend_cairo::Backend),
         #[cfg(feature = "qt-backend")]
         "qt" => Box::new(resvg::backend_qt::Backend),
+        #[cfg(feature = "raster-backend")]
+        "raster" => Box::new(resvg::backend_raster::Backend),
         _ => bail!("unknown backend"),
     };
 
@@ -72,9 +78,15 @@ fn process() -> Result<(), String> {
     }
 
     // Load file.
-    let tree = timed!("Preprocessing", {
-        usvg::Tree::from_file(&args.in_svg, &opt.usvg).map_err(|e| e.to_string())
-    })?;
+    let (tree, diagnostics) = timed!("Preprocessing", {
+        usvg::collect_diagnostics(|| usvg::Tree::from_file(&args.in_svg, &opt.usvg))
+    });
+
+    if let Some(ref path) = args.diagnostics {
+        save_diagnostics(&diagnostics, path)?;
+    }
+
+    let tree = tree.map_err(|e| e.to_string())?;
 
     if args.query_all {
         return query_all(backend, &tree, &opt);
@@ -102,8 +114,8 @@ fn process() -> Result<(), String> {
         };
 
         match img {
-            Some(img) => { timed!("Saving", img.save(out_png)); }
-            None => { bail!("failed to allocate an image") }
+            Ok(img) => { timed!("Saving", img.save(out_png)); }
+            Err(e) => { bail!(e) }
         }
     };
 
@@ -181,6 +193,16 @@ fn dump_svg(tree: &usvg::Tree, path: &path::Path) -> Result<(), String> {
     Ok(())
 }
 
+fn save_diagnostics(list: &[usvg::Diagnostic], path: &path::Path) -> Result<(), String> {
+    let mut f = fs::File::create(path)
+        .map_err(|_| format!("failed to create a file {:?}", path))?;
+
+    f.write_all(usvg::diagnostics_to_json(list).as_bytes())
+        .map_err(|_| format!("failed to write a file {:?}", path))?;
+
+    Ok(())
+}
+
 fn log_format(
     out: fern::FormatCallback,
     message: &fmt::Arguments,
@@ -3,7 +3,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Read, Write};
 use std::path::Path;
 use std::process;
@@ -30,6 +30,7 @@ OPTIONS:
     -V, --version               Prints version information
     -c                          Prints the output SVG to the stdout
         --keep-named-groups     Disables removing of groups with non-empty ID
+        --strict                Fails on any unsupported or lossy SVG feature
         --dpi DPI               Sets the resolution
                                 [default: 96] [possible values: 10..4000]
         --font-family FAMILY    Sets the default font family
@@ -53,11 +54,14 @@ OPTIONS:
                                 [default: optimizeQuality]
                                 [possible values: optimizeQuality,
                                 optimizeSpeed]
+        --style-sheet PATH      Applies a user CSS style sheet
         --indent INDENT         Sets the XML nodes indent
                                 [values: none, 0, 1, 2, 3, 4, tabs] [default: 4]
         --attrs-indent INDENT   Sets the XML attributes indent
                                 [values: none, 0, 1, 2, 3, 4, tabs] [default: none]
         --quiet                 Disables warnings
+        --diagnostics PATH      Saves conversion diagnostics as JSON
+                                to the selected file
 
 ARGS:
     <in-svg>                    Input file
@@ -79,6 +83,9 @@ struct Args {
     #[options(no_short)]
     keep_named_groups: bool,
 
+    #[options(no_short)]
+    strict: bool,
+
     #[options(no_short, meta = "DPI", default = "96", parse(try_from_str = "parse_dpi"))]
     dpi: u32,
 
@@ -100,6 +107,9 @@ struct Args {
     #[options(no_short, meta = "HINT", default = "optimizeQuality", parse(try_from_str))]
     image_rendering: usvg::ImageRendering,
 
+    #[options(no_short, meta = "PATH")]
+    style_sheet: Option<String>,
+
     #[options(no_short, meta = "INDENT", default = "4", parse(try_from_str = "parse_indent"))]
     indent: svgdom::Indent,
 
@@ -109,6 +119,9 @@ struct Args {
     #[options(no_short)]
     quiet: bool,
 
+    #[options(no_short, meta = "PATH")]
+    diagnostics: Option<String>,
+
     #[options(free)]
     free: Vec<String>,
 }
@@ -243,6 +256,15 @@ fn process(args: &Args) -> Result<(), String> {
         None => vec!["en".to_string()], // TODO: use system language
     };
 
+    let style_sheet = match args.style_sheet {
+        Some(ref path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|_| format!("failed to read the style sheet"))?;
+            Some(text)
+        }
+        None => None,
+    };
+
     let re_opt = usvg::Options {
         path: match in_svg {
             InputFrom::Stdin => None,
@@ -256,6 +278,11 @@ fn process(args: &Args) -> Result<(), String> {
         text_rendering: args.text_rendering,
         image_rendering: args.image_rendering,
         keep_named_groups: args.keep_named_groups,
+        strict: args.strict,
+        resource_loader: None,
+        limits: usvg::Limits::default(),
+        style_sheet,
+        css_variables: Vec::new(),
     };
 
     let input_str = match in_svg {
@@ -265,7 +292,18 @@ fn process(args: &Args) -> Result<(), String> {
         }
     }?;
 
-    let tree = usvg::Tree::from_str(&input_str, &re_opt).map_err(|e| format!("{}", e))?;
+    let (tree, diagnostics) = usvg::collect_diagnostics(|| {
+        usvg::Tree::from_str(&input_str, &re_opt)
+    });
+
+    if let Some(ref path) = args.diagnostics {
+        let mut f = File::create(path)
+            .map_err(|_| format!("failed to create the diagnostics file"))?;
+        f.write_all(usvg::diagnostics_to_json(&diagnostics).as_bytes())
+            .map_err(|_| format!("failed to write to the diagnostics file"))?;
+    }
+
+    let tree = tree.map_err(|e| format!("{}", e))?;
 
     let dom_opt = svgdom::WriteOptions {
         indent: args.indent,
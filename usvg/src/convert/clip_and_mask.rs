@@ -2,12 +2,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::f64;
+
 // external
 use svgdom;
 
 // self
 use crate::tree;
 use super::prelude::*;
+use super::{
+    shapes,
+    units,
+};
 
 
 pub fn convert_clip(
@@ -45,7 +51,7 @@ pub fn convert_clip(
         tree::NodeKind::ClipPath(tree::ClipPath {
             id: node.id().clone(),
             units,
-            transform: attrs.get_transform(AId::Transform),
+            transform: node.resolve_transform(state),
             clip_path,
         })
     );
@@ -81,7 +87,10 @@ pub fn convert_mask(
         node.convert_user_length(AId::Width, state, Length::new(120.0, Unit::Percent)),
         node.convert_user_length(AId::Height, state, Length::new(120.0, Unit::Percent)),
     );
-    let rect = try_opt_warn_or!(rect, None, "Mask '{}' has an invalid size. Skipped.", node.id());
+    let rect = try_opt_diag_or!(
+        rect, None, InvalidAttribute, node.id(),
+        "Mask '{}' has an invalid size. Skipped.", node.id()
+    );
 
     let ref attrs = node.attributes();
 
@@ -120,6 +129,304 @@ pub fn convert_mask(
     }
 }
 
+/// A CSS basic shape reference box.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ReferenceBox {
+    Fill,
+    Stroke,
+    View,
+}
+
+/// Converts a CSS basic shape from the `clip-path` property into a `clipPath` element.
+///
+/// Shapes are relative to the group content, so this method must be called
+/// after the group children conversion.
+pub fn convert_basic_shape(
+    node: &svgdom::Node,
+    g: &mut tree::Node,
+    state: &State,
+    tree: &mut tree::Tree,
+) {
+    let value = match node.attributes().get_str("usvg-clip-path") {
+        Some(v) => v.to_string(),
+        None => return,
+    };
+
+    // Nothing to clip.
+    if !g.has_children() {
+        return;
+    }
+
+    let (segments, rule) = match resolve_basic_shape(node, &value, g, state) {
+        Some(v) => v,
+        None => {
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "Invalid 'clip-path' value: '{}'. Skipped.", value);
+            return;
+        }
+    };
+
+    let id = super::gen_unique_id(node, tree, "clipPath");
+
+    let mut clip_path = tree.append_to_defs(
+        tree::NodeKind::ClipPath(tree::ClipPath {
+            id: id.clone(),
+            units: tree::Units::UserSpaceOnUse,
+            transform: tree::Transform::default(),
+            clip_path: None,
+        })
+    );
+
+    clip_path.append_kind(tree::NodeKind::Path(tree::Path {
+        fill: Some(tree::Fill { rule, ..tree::Fill::default() }),
+        segments,
+        ..tree::Path::default()
+    }));
+
+    if let tree::NodeKind::Group(ref mut g) = *g.borrow_mut() {
+        g.clip_path = Some(id);
+    }
+}
+
+fn resolve_basic_shape(
+    node: &svgdom::Node,
+    value: &str,
+    g: &tree::Node,
+    state: &State,
+) -> Option<(Vec<tree::PathSegment>, tree::FillRule)> {
+    // A shape function and a reference box can be set in any order.
+    let (func, args, box_name) = match (value.find('('), value.rfind(')')) {
+        (Some(open), Some(close)) if open < close => {
+            let name_start = value[..open].rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+            let box_name = format!("{} {}", &value[..name_start], &value[(close + 1)..]);
+            (&value[name_start..open], &value[(open + 1)..close], box_name)
+        }
+        (None, None) => ("inset", "0", value.to_string()),
+        _ => return None,
+    };
+
+    // SVG elements don't have a CSS layout box, so we have to fallback to the closest one.
+    let ref_box = match box_name.trim() {
+        "fill-box" | "content-box" | "padding-box" => ReferenceBox::Fill,
+        "stroke-box" | "border-box" | "margin-box" | "" => ReferenceBox::Stroke,
+        "view-box" => ReferenceBox::View,
+        _ => return None,
+    };
+
+    let rect = match ref_box {
+        ReferenceBox::View => {
+            Rect::new(0.0, 0.0, state.view_box.width(), state.view_box.height())?
+        }
+        ReferenceBox::Fill | ReferenceBox::Stroke => {
+            let mut bbox = Rect::new_bbox();
+            for child in g.children() {
+                let ts = tree::Transform::default();
+                if let Some(r) = super::calc_node_bbox(&child, ts, ref_box == ReferenceBox::Stroke) {
+                    bbox = bbox.expand(r);
+                }
+            }
+
+            if bbox.fuzzy_eq(&Rect::new_bbox()) {
+                return None;
+            }
+
+            bbox
+        }
+    };
+
+    let convert = |length: Length, base: f64| -> f64 {
+        if length.unit == Unit::Percent {
+            base * length.num / 100.0
+        } else {
+            units::convert_length(length, node, AId::ClipPath, tree::Units::UserSpaceOnUse, state)
+        }
+    };
+
+    // Relative to the reference box diagonal, like in SVG.
+    let diagonal = (rect.width() * rect.width() + rect.height() * rect.height()).sqrt()
+                   / 2.0_f64.sqrt();
+
+    let mut rule = tree::FillRule::NonZero;
+    let segments = match func.trim() {
+        "circle" | "ellipse" => {
+            let list: Vec<&str> = args.split_whitespace().collect();
+            let (radii, position) = match list.iter().position(|v| *v == "at") {
+                Some(idx) => (&list[..idx], &list[(idx + 1)..]),
+                None => (&list[..], &[][..]),
+            };
+
+            let (cx, cy) = if position.is_empty() {
+                (rect.x() + rect.width() / 2.0, rect.y() + rect.height() / 2.0)
+            } else {
+                let (x, y) = units::parse_position(position)?;
+                (rect.x() + convert(x, rect.width()), rect.y() + convert(y, rect.height()))
+            };
+
+            // Distances from the center to the reference box sides.
+            let dx = [(cx - rect.x()).abs(), (rect.right() - cx).abs()];
+            let dy = [(cy - rect.y()).abs(), (rect.bottom() - cy).abs()];
+
+            let radius = |s: Option<&&str>, sides: &[f64], base: f64| -> Option<f64> {
+                let closest = sides.iter().fold(f64::MAX, |a, b| a.min(*b));
+                let farthest = sides.iter().fold(0.0, |a: f64, b| a.max(*b));
+                match s.map(|s| *s) {
+                    None | Some("closest-side") => Some(closest),
+                    Some("farthest-side") => Some(farthest),
+                    Some(s) => s.parse().ok().map(|v| convert(v, base)),
+                }
+            };
+
+            if func.trim() == "circle" {
+                if radii.len() > 1 {
+                    return None;
+                }
+
+                let r = radius(radii.first(), &[dx[0], dx[1], dy[0], dy[1]], diagonal)?;
+                shapes::ellipse_to_path(cx, cy, r, r)
+            } else {
+                if !radii.is_empty() && radii.len() != 2 {
+                    return None;
+                }
+
+                let rx = radius(radii.get(0), &dx, rect.width())?;
+                let ry = radius(radii.get(1), &dy, rect.height())?;
+                shapes::ellipse_to_path(cx, cy, rx, ry)
+            }
+        }
+        "inset" => {
+            let list: Vec<&str> = args.split_whitespace().collect();
+            let (offsets, radii) = match list.iter().position(|v| *v == "round") {
+                Some(idx) => (&list[..idx], Some(&list[(idx + 1)..])),
+                None => (&list[..], None),
+            };
+
+            let [top, right, bottom, left] = parse_sides(offsets)?;
+            let x = rect.x() + convert(left, rect.width());
+            let y = rect.y() + convert(top, rect.height());
+            let w = rect.right() - convert(right, rect.width()) - x;
+            let h = rect.bottom() - convert(bottom, rect.height()) - y;
+            let inset_rect = Rect::new(x, y, w, h)?;
+
+            // The same rules as for the CSS `border-radius`.
+            // Horizontal and vertical radii can be separated by a slash.
+            let mut corners = [(0.0, 0.0); 4];
+            if let Some(radii) = radii {
+                let radii = radii.join(" ");
+                let mut parts = radii.splitn(2, '/');
+                let horizontal: Vec<&str> = parts.next()?.split_whitespace().collect();
+                let horizontal = parse_sides(&horizontal)?;
+                let vertical = match parts.next() {
+                    Some(s) => parse_sides(&s.split_whitespace().collect::<Vec<_>>())?,
+                    None => horizontal,
+                };
+
+                for (i, corner) in corners.iter_mut().enumerate() {
+                    if horizontal[i].num < 0.0 || vertical[i].num < 0.0 {
+                        return None;
+                    }
+
+                    *corner = (convert(horizontal[i], rect.width()),
+                               convert(vertical[i], rect.height()));
+                }
+            }
+
+            shapes::rounded_rect_corners_to_path(inset_rect, clamp_corner_radii(inset_rect, corners))
+        }
+        "polygon" => {
+            let mut segments = Vec::new();
+            for (i, point) in args.split(',').enumerate() {
+                let point = point.trim();
+                if i == 0 && (point == "nonzero" || point == "evenodd") {
+                    if point == "evenodd" {
+                        rule = tree::FillRule::EvenOdd;
+                    }
+
+                    continue;
+                }
+
+                let mut iter = point.split_whitespace();
+                let x: Length = iter.next()?.parse().ok()?;
+                let y: Length = iter.next()?.parse().ok()?;
+                if iter.next().is_some() {
+                    return None;
+                }
+
+                let x = rect.x() + convert(x, rect.width());
+                let y = rect.y() + convert(y, rect.height());
+                if segments.is_empty() {
+                    segments.push(tree::PathSegment::MoveTo { x, y });
+                } else {
+                    segments.push(tree::PathSegment::LineTo { x, y });
+                }
+            }
+
+            if segments.len() < 2 {
+                return None;
+            }
+
+            segments.push(tree::PathSegment::ClosePath);
+            segments
+        }
+        _ => return None,
+    };
+
+    Some((segments, rule))
+}
+
+/// Parses a list of 1-4 lengths using the CSS `margin` shorthand rules.
+///
+/// Values are in the top, right, bottom, left order.
+fn parse_sides(list: &[&str]) -> Option<[Length; 4]> {
+    let mut lengths = Vec::with_capacity(4);
+    for v in list {
+        lengths.push(v.parse::<Length>().ok()?);
+    }
+
+    match *lengths.as_slice() {
+        [a] => Some([a, a, a, a]),
+        [a, b] => Some([a, b, a, b]),
+        [a, b, c] => Some([a, b, c, b]),
+        [a, b, c, d] => Some([a, b, c, d]),
+        _ => None,
+    }
+}
+
+/// Scales down corner radii so adjacent corners do not overlap.
+///
+/// https://www.w3.org/TR/css-backgrounds-3/#corner-overlap
+fn clamp_corner_radii(rect: Rect, mut corners: [(f64, f64); 4]) -> [(f64, f64); 4] {
+    // A corner with a zero radius is a regular one.
+    for corner in corners.iter_mut() {
+        if corner.0.fuzzy_eq(&0.0) || corner.1.fuzzy_eq(&0.0) {
+            *corner = (0.0, 0.0);
+        }
+    }
+
+    let [tl, tr, br, bl] = corners;
+    let sides = [
+        (rect.width(), tl.0 + tr.0),
+        (rect.width(), bl.0 + br.0),
+        (rect.height(), tl.1 + bl.1),
+        (rect.height(), tr.1 + br.1),
+    ];
+
+    let mut f = 1.0_f64;
+    for &(len, sum) in &sides {
+        if sum > 0.0 {
+            f = f.min(len / sum);
+        }
+    }
+
+    if f < 1.0 {
+        for corner in corners.iter_mut() {
+            corner.0 *= f;
+            corner.1 *= f;
+        }
+    }
+
+    corners
+}
+
 fn convert_element_units(
     attrs: &svgdom::Attributes,
     aid: AId,
@@ -14,6 +14,7 @@ use super::paint_server::{
     resolve_number,
     convert_units,
 };
+use super::units;
 
 
 pub fn convert(
@@ -34,13 +35,13 @@ pub fn convert(
         resolve_number(node, AId::Width, units, state, Length::new(120.0, Unit::Percent)),
         resolve_number(node, AId::Height, units, state, Length::new(120.0, Unit::Percent)),
     );
-    let rect = try_opt_warn_or!(
-        rect, None,
+    let rect = try_opt_diag_or!(
+        rect, None, InvalidAttribute, node.id(),
         "Filter '{}' has an invalid region. Skipped.", node.id(),
     );
 
     let node_with_children = find_filter_with_children(node)?;
-    let children = collect_children(&node_with_children, primitive_units, state);
+    let children = collect_children(&node_with_children, primitive_units, state, tree);
     if children.is_empty() {
         return None;
     }
@@ -63,7 +64,8 @@ fn find_filter_with_children(
 ) -> Option<svgdom::Node> {
     for link in node.href_iter() {
         if !link.is_tag_name(EId::Filter) {
-            warn!(
+            diag!(
+                Warning, InvalidReference, node.id(),
                 "Filter '{}' cannot reference '{}' via 'xlink:href'.",
                 node.id(), link.tag_id().unwrap()
             );
@@ -87,6 +89,7 @@ fn collect_children(
     filter: &svgdom::Node,
     units: tree::Units,
     state: &State,
+    tree: &mut tree::Tree,
 ) -> Vec<tree::FilterPrimitive> {
     let mut primitives = Vec::new();
 
@@ -106,23 +109,48 @@ fn collect_children(
             Some(EId::FeBlend) => {
                 convert_fe_blend(&child, &primitives)
             }
+            Some(EId::FeColorMatrix) => {
+                convert_fe_color_matrix(&child, &primitives)
+            }
             Some(EId::FeFlood) => {
                 convert_fe_flood(&child)
             }
+            Some(EId::FeComponentTransfer) => {
+                convert_fe_component_transfer(&child, &primitives)
+            }
             Some(EId::FeComposite) => {
                 convert_fe_composite(&child, &primitives)
             }
+            Some(EId::FeConvolveMatrix) => {
+                convert_fe_convolve_matrix(&child, &primitives)
+            }
+            Some(EId::FeDiffuseLighting) => {
+                convert_fe_diffuse_lighting(&child, &primitives)
+            }
+            Some(EId::FeDisplacementMap) => {
+                convert_fe_displacement_map(&child, &primitives)
+            }
             Some(EId::FeMerge) => {
                 convert_fe_merge(&child, &primitives)
             }
+            Some(EId::FeMorphology) => {
+                convert_fe_morphology(&child, &primitives)
+            }
+            Some(EId::FeSpecularLighting) => {
+                convert_fe_specular_lighting(&child, &primitives)
+            }
             Some(EId::FeTile) => {
                 convert_fe_tile(&child, &primitives)
             }
             Some(EId::FeImage) => {
-                convert_fe_image(&child, state)
+                convert_fe_image(&child, state, tree)
+            }
+            Some(EId::FeTurbulence) => {
+                convert_fe_turbulence(&child)
             }
             Some(_) => {
-                warn!("Filter with '{}' child is not supported.", child.tag_name());
+                diag!(Warning, UnsupportedElement, child.id(),
+                      "Filter with '{}' child is not supported.", child.tag_name());
                 continue;
             }
             None => continue,
@@ -223,17 +251,134 @@ fn convert_fe_blend(
     })
 }
 
+fn convert_fe_color_matrix(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    let kind = convert_color_matrix_kind(fe).unwrap_or_default();
+    tree::FilterKind::FeColorMatrix(tree::FeColorMatrix {
+        input: resolve_input(fe, AId::In, primitives),
+        kind,
+    })
+}
+
+fn convert_color_matrix_kind(
+    fe: &svgdom::Node,
+) -> Option<tree::FeColorMatrixKind> {
+    let attrs = fe.attributes();
+    let list = attrs.get_number_list(AId::Values);
+
+    // An omitted `values` attribute means identity for every type,
+    // while an invalid one fallbacks to the identity matrix.
+    match attrs.get_str_or(AId::Type, "matrix") {
+        "saturate" => {
+            match list {
+                Some(list) if list.len() == 1 => {
+                    let n = list[0];
+                    if n.is_sign_negative() {
+                        None
+                    } else {
+                        Some(tree::FeColorMatrixKind::Saturate(n.into()))
+                    }
+                }
+                Some(_) => None,
+                None => Some(tree::FeColorMatrixKind::Saturate(1.0.into())),
+            }
+        }
+        "hueRotate" => {
+            match list {
+                Some(list) if list.len() == 1 => Some(tree::FeColorMatrixKind::HueRotate(list[0])),
+                Some(_) => None,
+                None => Some(tree::FeColorMatrixKind::HueRotate(0.0)),
+            }
+        }
+        "luminanceToAlpha" => {
+            Some(tree::FeColorMatrixKind::LuminanceToAlpha)
+        }
+        _ => {
+            match list {
+                Some(list) if list.len() == 20 => Some(tree::FeColorMatrixKind::Matrix(list.to_vec())),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn convert_fe_component_transfer(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    let mut kind = tree::FeComponentTransfer {
+        input: resolve_input(fe, AId::In, primitives),
+        func_r: tree::TransferFunction::Identity,
+        func_g: tree::TransferFunction::Identity,
+        func_b: tree::TransferFunction::Identity,
+        func_a: tree::TransferFunction::Identity,
+    };
+
+    // If there are multiple functions for the same channel, the last one wins.
+    for child in fe.children() {
+        let func = match child.tag_id() {
+            Some(EId::FeFuncR) => &mut kind.func_r,
+            Some(EId::FeFuncG) => &mut kind.func_g,
+            Some(EId::FeFuncB) => &mut kind.func_b,
+            Some(EId::FeFuncA) => &mut kind.func_a,
+            _ => continue,
+        };
+
+        *func = convert_transfer_function(&child);
+    }
+
+    tree::FilterKind::FeComponentTransfer(kind)
+}
+
+fn convert_transfer_function(
+    node: &svgdom::Node,
+) -> tree::TransferFunction {
+    let attrs = node.attributes();
+
+    match attrs.get_str_or(AId::Type, "identity") {
+        "table" => {
+            match attrs.get_number_list(AId::TableValues) {
+                Some(values) => tree::TransferFunction::Table(values.to_vec()),
+                None => tree::TransferFunction::Table(Vec::new()),
+            }
+        }
+        "discrete" => {
+            match attrs.get_number_list(AId::TableValues) {
+                Some(values) => tree::TransferFunction::Discrete(values.to_vec()),
+                None => tree::TransferFunction::Discrete(Vec::new()),
+            }
+        }
+        "linear" => {
+            tree::TransferFunction::Linear {
+                slope: attrs.get_number_or(AId::Slope, 1.0),
+                intercept: attrs.get_number_or(AId::Intercept, 0.0),
+            }
+        }
+        "gamma" => {
+            tree::TransferFunction::Gamma {
+                amplitude: attrs.get_number_or(AId::Amplitude, 1.0),
+                exponent: attrs.get_number_or(AId::Exponent, 1.0),
+                // `offset` is parsed as a length, because of the gradient's `stop` element.
+                offset: get_unitless_number(&attrs, AId::Offset, 0.0),
+            }
+        }
+        _ => tree::TransferFunction::Identity,
+    }
+}
+
 fn convert_fe_flood(
     fe: &svgdom::Node,
 ) -> tree::FilterKind {
     let attrs = fe.attributes();
 
     let color = attrs.get_color(AId::FloodColor).unwrap_or_else(tree::Color::black);
-    let opacity = fe.convert_opacity(AId::FloodOpacity);
+    let opacity = fe.convert_opacity(AId::FloodOpacity).value() * fe.color_alpha(AId::FloodColor);
 
     tree::FilterKind::FeFlood(tree::FeFlood {
         color,
-        opacity,
+        opacity: opacity.into(),
     })
 }
 
@@ -281,6 +426,241 @@ fn convert_fe_composite(
     })
 }
 
+fn convert_fe_convolve_matrix(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    match convert_fe_convolve_matrix_impl(fe, primitives) {
+        Some(kind) => kind,
+        None => {
+            // An invalid `feConvolveMatrix` produces a transparent black image,
+            // just like in Chrome.
+            diag!(Warning, InvalidFilterPrimitive, fe.id(),
+                  "Invalid 'feConvolveMatrix' attributes. Will be replaced with an empty image.");
+            create_dummy_primitive()
+        }
+    }
+}
+
+fn convert_fe_convolve_matrix_impl(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> Option<tree::FilterKind> {
+    fn parse_target(n: Option<f64>, order: u32) -> Option<u32> {
+        match n {
+            Some(n) if n.fract().is_fuzzy_zero() && n >= 0.0 && n < order as f64 => Some(n as u32),
+            Some(_) => None,
+            None => Some(order / 2),
+        }
+    }
+
+    let attrs = fe.attributes();
+
+    let (columns, rows) = match attrs.get_number_list(AId::Order) {
+        Some(list) => {
+            let (x, y) = match list.len() {
+                1 => (list[0], list[0]),
+                2 => (list[0], list[1]),
+                _ => return None,
+            };
+
+            // Only positive integers are allowed.
+            // We are also limiting the order to prevent a huge memory usage.
+            let is_valid = |n: f64| n.fract().is_fuzzy_zero() && n >= 1.0 && n <= 255.0;
+            if !is_valid(x) || !is_valid(y) {
+                return None;
+            }
+
+            (x as u32, y as u32)
+        }
+        None => (3, 3),
+    };
+
+    let data = attrs.get_number_list(AId::KernelMatrix)?.to_vec();
+
+    let target_x = parse_target(attrs.get_number(AId::TargetX), columns)?;
+    let target_y = parse_target(attrs.get_number(AId::TargetY), rows)?;
+
+    let matrix = tree::ConvolveMatrix::new(target_x, target_y, columns, rows, data)?;
+
+    let mut divisor = attrs.get_number(AId::Divisor).unwrap_or(0.0);
+    if divisor.is_fuzzy_zero() {
+        // 'The default value is the sum of all values in kernelMatrix,
+        // with the exception that if the sum is zero, then the divisor is set to 1.'
+        divisor = matrix.data().iter().sum();
+        if divisor.is_fuzzy_zero() {
+            divisor = 1.0;
+        }
+    }
+
+    let edge_mode = match attrs.get_str_or(AId::EdgeMode, "duplicate") {
+        "none" => tree::EdgeMode::None,
+        "wrap" => tree::EdgeMode::Wrap,
+        _ => tree::EdgeMode::Duplicate,
+    };
+
+    let preserve_alpha = attrs.get_str_or(AId::PreserveAlpha, "false") == "true";
+
+    Some(tree::FilterKind::FeConvolveMatrix(tree::FeConvolveMatrix {
+        input: resolve_input(fe, AId::In, primitives),
+        matrix,
+        divisor,
+        bias: attrs.get_number_or(AId::Bias, 0.0),
+        edge_mode,
+        preserve_alpha,
+    }))
+}
+
+fn convert_fe_diffuse_lighting(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    let light_source = match convert_light_source(fe) {
+        Some(v) => v,
+        None => {
+            diag!(Warning, InvalidFilterPrimitive, fe.id(),
+                  "'feDiffuseLighting' without a light source. Will be replaced with an empty image.");
+            return create_dummy_primitive();
+        }
+    };
+
+    let attrs = fe.attributes();
+
+    // 'A negative value is an error.'
+    let diffuse_constant = attrs.get_number_or(AId::DiffuseConstant, 1.0).max(0.0);
+
+    tree::FilterKind::FeDiffuseLighting(tree::FeDiffuseLighting {
+        input: resolve_input(fe, AId::In, primitives),
+        surface_scale: attrs.get_number_or(AId::SurfaceScale, 1.0),
+        diffuse_constant: diffuse_constant.into(),
+        kernel_unit_length: convert_kernel_unit_length(&attrs),
+        lighting_color: attrs.get_color(AId::LightingColor).unwrap_or_else(tree::Color::white),
+        light_source,
+    })
+}
+
+fn convert_fe_specular_lighting(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    let light_source = match convert_light_source(fe) {
+        Some(v) => v,
+        None => {
+            diag!(Warning, InvalidFilterPrimitive, fe.id(),
+                  "'feSpecularLighting' without a light source. Will be replaced with an empty image.");
+            return create_dummy_primitive();
+        }
+    };
+
+    let attrs = fe.attributes();
+
+    // 'A negative value is an error.'
+    let specular_constant = attrs.get_number_or(AId::SpecularConstant, 1.0).max(0.0);
+
+    // 'Range is 1.0 to 128.0.'
+    let specular_exponent = f64_bound(1.0, attrs.get_number_or(AId::SpecularExponent, 1.0), 128.0);
+
+    tree::FilterKind::FeSpecularLighting(tree::FeSpecularLighting {
+        input: resolve_input(fe, AId::In, primitives),
+        surface_scale: attrs.get_number_or(AId::SurfaceScale, 1.0),
+        specular_constant: specular_constant.into(),
+        specular_exponent,
+        kernel_unit_length: convert_kernel_unit_length(&attrs),
+        lighting_color: attrs.get_color(AId::LightingColor).unwrap_or_else(tree::Color::white),
+        light_source,
+    })
+}
+
+fn convert_fe_displacement_map(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    fn parse_channel(attrs: &svgdom::Attributes, aid: AId) -> tree::ColorChannel {
+        match attrs.get_str_or(aid, "A") {
+            "R" => tree::ColorChannel::R,
+            "G" => tree::ColorChannel::G,
+            "B" => tree::ColorChannel::B,
+            _ => tree::ColorChannel::A,
+        }
+    }
+
+    let ref attrs = fe.attributes();
+
+    tree::FilterKind::FeDisplacementMap(tree::FeDisplacementMap {
+        input1: resolve_input(fe, AId::In, primitives),
+        input2: resolve_input(fe, AId::In2, primitives),
+        scale: attrs.get_number_or(AId::Scale, 0.0),
+        x_channel_selector: parse_channel(attrs, AId::XChannelSelector),
+        y_channel_selector: parse_channel(attrs, AId::YChannelSelector),
+    })
+}
+
+fn convert_kernel_unit_length(
+    attrs: &svgdom::Attributes,
+) -> Option<(f64, f64)> {
+    let list = attrs.get_number_list(AId::KernelUnitLength)?;
+    let (x, y) = match list.len() {
+        1 => (list[0], list[0]),
+        2 => (list[0], list[1]),
+        _ => return None,
+    };
+
+    // 'A negative or zero value is an error.'
+    if x > 0.0 && y > 0.0 {
+        Some((x, y))
+    } else {
+        None
+    }
+}
+
+/// Converts the first light source child.
+fn convert_light_source(
+    fe: &svgdom::Node,
+) -> Option<tree::FeLightSource> {
+    let child = fe.children().find(|n| {
+        match n.tag_id() {
+            Some(EId::FeDistantLight) | Some(EId::FePointLight) | Some(EId::FeSpotLight) => true,
+            _ => false,
+        }
+    })?;
+
+    let ref attrs = child.attributes();
+
+    match child.tag_id() {
+        Some(EId::FeDistantLight) => {
+            Some(tree::FeLightSource::FeDistantLight(tree::FeDistantLight {
+                azimuth: attrs.get_number_or(AId::Azimuth, 0.0),
+                elevation: attrs.get_number_or(AId::Elevation, 0.0),
+            }))
+        }
+        Some(EId::FePointLight) => {
+            Some(tree::FeLightSource::FePointLight(tree::FePointLight {
+                x: get_unitless_number(attrs, AId::X, 0.0),
+                y: get_unitless_number(attrs, AId::Y, 0.0),
+                z: get_unitless_number(attrs, AId::Z, 0.0),
+            }))
+        }
+        Some(EId::FeSpotLight) => {
+            let mut specular_exponent = attrs.get_number_or(AId::SpecularExponent, 1.0);
+            if specular_exponent.is_sign_negative() {
+                specular_exponent = 1.0;
+            }
+
+            Some(tree::FeLightSource::FeSpotLight(tree::FeSpotLight {
+                x: get_unitless_number(attrs, AId::X, 0.0),
+                y: get_unitless_number(attrs, AId::Y, 0.0),
+                z: get_unitless_number(attrs, AId::Z, 0.0),
+                points_at_x: attrs.get_number_or(AId::PointsAtX, 0.0),
+                points_at_y: attrs.get_number_or(AId::PointsAtY, 0.0),
+                points_at_z: attrs.get_number_or(AId::PointsAtZ, 0.0),
+                specular_exponent: specular_exponent.into(),
+                limiting_cone_angle: attrs.get_number(AId::LimitingConeAngle),
+            }))
+        }
+        _ => None,
+    }
+}
+
 fn convert_fe_merge(
     fe: &svgdom::Node,
     primitives: &[tree::FilterPrimitive],
@@ -295,9 +675,53 @@ fn convert_fe_merge(
     })
 }
 
+fn convert_fe_morphology(
+    fe: &svgdom::Node,
+    primitives: &[tree::FilterPrimitive],
+) -> tree::FilterKind {
+    let attrs = fe.attributes();
+
+    let operator = match attrs.get_str_or(AId::Operator, "erode") {
+        "dilate" => tree::FeMorphologyOperator::Dilate,
+        _ => tree::FeMorphologyOperator::Erode,
+    };
+
+    let radius_list = attrs.get_number_list(AId::Radius).cloned();
+
+    let (mut radius_x, mut radius_y) = match radius_list {
+        Some(list) => {
+            if list.len() == 1 {
+                (list[0], list[0])
+            } else if list.len() == 2 {
+                (list[0], list[1])
+            } else {
+                (0.0, 0.0)
+            }
+        }
+        None => {
+            (0.0, 0.0)
+        }
+    };
+
+    // 'A negative value is an error. A value of zero disables the effect
+    // of the given filter primitive (i.e., the result is the filter input image).'
+    if radius_x.is_sign_negative() || radius_y.is_sign_negative() {
+        radius_x = 0.0;
+        radius_y = 0.0;
+    }
+
+    tree::FilterKind::FeMorphology(tree::FeMorphology {
+        input: resolve_input(fe, AId::In, primitives),
+        operator,
+        radius_x: radius_x.into(),
+        radius_y: radius_y.into(),
+    })
+}
+
 fn convert_fe_image(
     fe: &svgdom::Node,
     state: &State,
+    tree: &mut tree::Tree,
 ) -> tree::FilterKind {
     let ref attrs = fe.attributes();
 
@@ -306,10 +730,19 @@ fn convert_fe_image(
         .try_find_enum(AId::ImageRendering)
         .unwrap_or(state.opt.image_rendering);
 
+    if let Some(&AValue::Link(ref link)) = attrs.get_value(AId::Href) {
+        return tree::FilterKind::FeImage(tree::FeImage {
+            aspect,
+            rendering_mode,
+            data: convert_fe_image_link(link, state, tree),
+        });
+    }
+
     let href = match attrs.get_value(AId::Href) {
         Some(&AValue::String(ref s)) => s,
         _ => {
-            warn!("The 'feImage' element lacks the 'xlink:href' attribute. Skipped.");
+            diag!(Warning, InvalidAttribute, fe.id(),
+                  "The 'feImage' element lacks the 'xlink:href' attribute. Skipped.");
             return tree::FilterKind::FeImage(tree::FeImage {
                 aspect,
                 rendering_mode,
@@ -318,7 +751,7 @@ fn convert_fe_image(
         }
     };
 
-    let href = super::image::get_href_data(&*fe.id(), href, state.opt.path.as_ref());
+    let href = super::image::get_href_data(&*fe.id(), href, state.opt);
     let (img_data, format) = match href {
         Some((data, format)) => (data, format),
         None => {
@@ -338,6 +771,32 @@ fn convert_fe_image(
     })
 }
 
+/// Converts an element referenced by `feImage` into a group inside `defs`.
+///
+/// The element is rendered just like via the `use` element.
+fn convert_fe_image_link(
+    link: &svgdom::Node,
+    state: &State,
+    tree: &mut tree::Tree,
+) -> tree::FeImageKind {
+    let id = super::gen_unique_id(link, tree, "feImage");
+
+    let mut g = tree.append_to_defs(tree::NodeKind::Group(tree::Group {
+        id: id.clone(),
+        ..tree::Group::default()
+    }));
+
+    super::convert_element(link, state, &mut g, tree);
+
+    if g.has_children() {
+        tree::FeImageKind::Use(id)
+    } else {
+        // Nothing to render.
+        g.detach();
+        tree::FeImageKind::None
+    }
+}
+
 fn convert_fe_tile(
     fe: &svgdom::Node,
     primitives: &[tree::FilterPrimitive],
@@ -347,6 +806,75 @@ fn convert_fe_tile(
     })
 }
 
+fn convert_fe_turbulence(
+    fe: &svgdom::Node,
+) -> tree::FilterKind {
+    let attrs = fe.attributes();
+
+    let mut base_frequency_x = 0.0;
+    let mut base_frequency_y = 0.0;
+    if let Some(list) = attrs.get_number_list(AId::BaseFrequency) {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        if list.len() == 2 {
+            x = list[0];
+            y = list[1];
+        } else if list.len() == 1 {
+            x = list[0];
+            y = list[0]; // The same value should be used for X and Y.
+        }
+
+        // 'A negative value is an error.'
+        if x.is_sign_positive() && y.is_sign_positive() {
+            base_frequency_x = x;
+            base_frequency_y = y;
+        }
+    }
+
+    let num_octaves = attrs.get_number_or(AId::NumOctaves, 1.0);
+    let num_octaves = if num_octaves.is_sign_negative() { 0 } else { num_octaves.round() as u32 };
+
+    // 'If the value is not an integer, it is truncated towards zero.'
+    let seed = attrs.get_number_or(AId::Seed, 0.0).trunc() as i32;
+
+    let kind = match attrs.get_str_or(AId::Type, "turbulence") {
+        "fractalNoise" => tree::FeTurbulenceKind::FractalNoise,
+        _ => tree::FeTurbulenceKind::Turbulence,
+    };
+
+    tree::FilterKind::FeTurbulence(tree::FeTurbulence {
+        base_frequency_x: base_frequency_x.into(),
+        base_frequency_y: base_frequency_y.into(),
+        num_octaves,
+        seed,
+        stitch_tiles: attrs.get_str(AId::StitchTiles) == Some("stitch"),
+        kind,
+    })
+}
+
+/// Creates a primitive that produces a transparent black image.
+///
+/// Used as a replacement for invalid primitives.
+fn create_dummy_primitive() -> tree::FilterKind {
+    tree::FilterKind::FeFlood(tree::FeFlood {
+        color: tree::Color::black(),
+        opacity: 0.0.into(),
+    })
+}
+
+/// Returns a number from an attribute that can be parsed either as a number or a length.
+fn get_unitless_number(
+    attrs: &svgdom::Attributes,
+    aid: AId,
+    def: f64,
+) -> f64 {
+    match attrs.get_value(aid) {
+        Some(AValue::Number(n)) => *n,
+        Some(AValue::Length(len)) if len.unit == Unit::None => len.num,
+        _ => def,
+    }
+}
+
 fn resolve_input(
     node: &svgdom::Node,
     aid: AId,
@@ -425,3 +953,579 @@ fn gen_result(
         }
     }
 }
+
+/// A single item of a `filter` list.
+pub enum FilterListItem<'a> {
+    Link(&'a str),
+    Function {
+        name: &'a str,
+        args: &'a str,
+    },
+}
+
+/// A resolved item of a `filter` list.
+enum FilterValue {
+    Link(String),
+    Function(FilterFunction),
+}
+
+/// A CSS filter function.
+///
+/// https://www.w3.org/TR/filter-effects-1/#filter-functions
+enum FilterFunction {
+    Blur(f64),
+    Brightness(f64),
+    Contrast(f64),
+    DropShadow {
+        dx: f64,
+        dy: f64,
+        std_dev: f64,
+        color: tree::Color,
+        opacity: f64,
+    },
+    Grayscale(f64),
+    HueRotate(f64),
+    Invert(f64),
+    Opacity(f64),
+    Saturate(f64),
+    Sepia(f64),
+}
+
+/// Converts a `filter` list that contains multiple links and CSS filter functions.
+///
+/// Consecutive filter functions are converted into a single `filter` element.
+/// Its region is relative to the group content, so this method must be called
+/// after the group children conversion.
+pub fn convert_list(
+    node: &svgdom::Node,
+    g: &mut tree::Node,
+    state: &State,
+    tree: &mut tree::Tree,
+) {
+    // `filter` cannot be set on `clipPath` children.
+    if state.is_in_clip_path() {
+        return;
+    }
+
+    let value = match node.attributes().get_str("usvg-filter") {
+        Some(v) => v.to_string(),
+        None => return,
+    };
+
+    let list = parse_list(&value).and_then(|list| {
+        list.into_iter().map(|item| match item {
+            FilterListItem::Link(id) => Some(FilterValue::Link(id.to_string())),
+            FilterListItem::Function { name, args } => {
+                parse_function(node, name, args, state).map(FilterValue::Function)
+            }
+        }).collect::<Option<Vec<_>>>()
+    });
+
+    let list = match list {
+        Some(v) => v,
+        None => {
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "Invalid 'filter' value: '{}'. Skipped.", value);
+            return;
+        }
+    };
+
+    let region = calc_region(g, &list);
+
+    let mut ids = Vec::new();
+    let mut functions = Vec::new();
+    for item in list {
+        match item {
+            FilterValue::Link(id) => {
+                if let Some(id) = convert_functions(node, &functions, region, tree) {
+                    ids.push(id);
+                }
+                functions.clear();
+
+                let link = node.root().descendants().find(|n| *n.id() == id);
+                match link.and_then(|link| convert(&link, state, tree)) {
+                    Some(id) => ids.push(id),
+                    None => {
+                        diag!(Warning, InvalidReference, node.id(),
+                              "Filter '{}' is invalid. The element will be skipped.", id);
+
+                        // If `filter` is linked to an invalid element - skip this group completely.
+                        g.detach();
+                        return;
+                    }
+                }
+            }
+            FilterValue::Function(f) => {
+                functions.push(f);
+            }
+        }
+    }
+
+    if let Some(id) = convert_functions(node, &functions, region, tree) {
+        ids.push(id);
+    }
+
+    // Paints are resolved using the same element, so it doesn't matter which filter requested them.
+    let mut filter_fill = None;
+    let mut filter_stroke = None;
+    for id in &ids {
+        let (fill, stroke) = super::resolve_filter_paint(node, id, state, tree);
+        filter_fill = filter_fill.or(fill);
+        filter_stroke = filter_stroke.or(stroke);
+    }
+
+    if let tree::NodeKind::Group(ref mut g) = *g.borrow_mut() {
+        g.filter = ids;
+        g.filter_fill = filter_fill;
+        g.filter_stroke = filter_stroke;
+    }
+}
+
+/// Parses a `filter` list into links and unresolved filter functions.
+pub fn parse_list(
+    value: &str,
+) -> Option<Vec<FilterListItem>> {
+    let mut list = Vec::new();
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let open = rest.find('(')?;
+        let name = rest[..open].trim();
+
+        // Find the matching bracket, since arguments can contain colors.
+        let mut depth = 0;
+        let mut close = None;
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+
+            if depth == 0 {
+                close = Some(open + i);
+                break;
+            }
+        }
+        let close = close?;
+        let args = rest[(open + 1)..close].trim();
+        rest = rest[(close + 1)..].trim_start();
+
+        let item = if name == "url" {
+            let link = args.trim_matches(|c| c == '"' || c == '\'');
+            if !link.starts_with('#') {
+                return None;
+            }
+
+            FilterListItem::Link(link[1..].trim())
+        } else {
+            FilterListItem::Function { name, args }
+        };
+
+        list.push(item);
+    }
+
+    if list.is_empty() {
+        None
+    } else {
+        Some(list)
+    }
+}
+
+fn parse_function(
+    node: &svgdom::Node,
+    name: &str,
+    args: &str,
+    state: &State,
+) -> Option<FilterFunction> {
+    // Only `drop-shadow` accepts multiple arguments.
+    if name != "drop-shadow" && args.contains(char::is_whitespace) {
+        return None;
+    }
+
+    // Amounts are clamped to 1, except for those that can increase a value.
+    let amount = || parse_amount(args);
+    let bounded_amount = || parse_amount(args).map(|n| n.min(1.0));
+
+    let f = match name {
+        "blur" => {
+            let std_dev = if args.is_empty() { 0.0 } else { parse_length(node, args, state)? };
+            if std_dev.is_sign_negative() {
+                return None;
+            }
+
+            FilterFunction::Blur(std_dev)
+        }
+        "brightness" => FilterFunction::Brightness(amount()?),
+        "contrast" => FilterFunction::Contrast(amount()?),
+        "drop-shadow" => parse_drop_shadow(node, args, state)?,
+        "grayscale" => FilterFunction::Grayscale(bounded_amount()?),
+        "hue-rotate" => FilterFunction::HueRotate(parse_angle(args)?),
+        "invert" => FilterFunction::Invert(bounded_amount()?),
+        "opacity" => FilterFunction::Opacity(bounded_amount()?),
+        "saturate" => FilterFunction::Saturate(amount()?),
+        "sepia" => FilterFunction::Sepia(bounded_amount()?),
+        _ => return None,
+    };
+
+    Some(f)
+}
+
+fn parse_drop_shadow(
+    node: &svgdom::Node,
+    args: &str,
+    state: &State,
+) -> Option<FilterFunction> {
+    // Split by spaces outside of the color function.
+    let mut list = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if c.is_whitespace() && depth == 0 => {
+                list.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    list.push(&args[start..]);
+    list.retain(|s| !s.is_empty());
+
+    // A color can be set either before or after the lengths.
+    let is_length = |s: &&str| s.parse::<Length>().is_ok();
+    let (color, lengths) = match list.split_first() {
+        Some((first, rest)) if !is_length(first) => (Some(*first), rest),
+        _ => match list.split_last() {
+            Some((last, rest)) if !is_length(last) => (Some(*last), rest),
+            _ => (None, &list[..]),
+        },
+    };
+
+    if lengths.len() != 2 && lengths.len() != 3 {
+        return None;
+    }
+
+    let dx = parse_length(node, lengths[0], state)?;
+    let dy = parse_length(node, lengths[1], state)?;
+    let std_dev = match lengths.get(2) {
+        Some(s) => parse_length(node, s, state)?,
+        None => 0.0,
+    };
+
+    if std_dev.is_sign_negative() {
+        return None;
+    }
+
+    let (color, opacity) = match color {
+        Some(s) if !s.eq_ignore_ascii_case("currentcolor") => parse_color(s)?,
+        _ => resolve_current_color(node),
+    };
+
+    Some(FilterFunction::DropShadow { dx, dy, std_dev, color, opacity })
+}
+
+fn parse_length(
+    node: &svgdom::Node,
+    text: &str,
+    state: &State,
+) -> Option<f64> {
+    let length: Length = text.parse().ok()?;
+
+    // Percentages are not allowed.
+    if length.unit == Unit::Percent {
+        return None;
+    }
+
+    Some(units::convert_length(length, node, AId::Filter, tree::Units::UserSpaceOnUse, state))
+}
+
+/// Parses a non-negative number or percentage.
+///
+/// An omitted value is `1`.
+fn parse_amount(text: &str) -> Option<f64> {
+    if text.is_empty() {
+        return Some(1.0);
+    }
+
+    let n = if text.ends_with('%') {
+        text.trim_end_matches('%').parse::<f64>().ok()? / 100.0
+    } else {
+        text.parse::<f64>().ok()?
+    };
+
+    if n.is_finite() && !n.is_sign_negative() {
+        Some(n)
+    } else {
+        None
+    }
+}
+
+/// Parses an angle in degrees.
+///
+/// An omitted value and an unitless zero are allowed.
+fn parse_angle(text: &str) -> Option<f64> {
+    if text.is_empty() {
+        return Some(0.0);
+    }
+
+    let units = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f64::consts::PI), ("turn", 360.0)];
+    for &(unit, k) in &units {
+        if text.ends_with(unit) {
+            let n: f64 = text[..(text.len() - unit.len())].parse().ok()?;
+            return if n.is_finite() { Some(n * k) } else { None };
+        }
+    }
+
+    match text.parse::<f64>() {
+        Ok(n) if n == 0.0 => Some(0.0),
+        _ => None,
+    }
+}
+
+fn parse_color(text: &str) -> Option<(tree::Color, f64)> {
+    // Colors with an alpha are not supported by `svgdom`.
+    if let Some(c) = crate::css::color::parse(text) {
+        return Some((tree::Color::new(c.red, c.green, c.blue), c.alpha));
+    }
+
+    text.parse::<tree::Color>().ok().map(|c| (c, 1.0))
+}
+
+fn resolve_current_color(
+    node: &svgdom::Node,
+) -> (tree::Color, f64) {
+    // Filters on graphic elements are moved to a separate group during preprocessing,
+    // so we have to resolve `color` using the original element.
+    let mut color_node = node.clone();
+    if node.has_attribute("usvg-group") {
+        if let Some(child) = node.first_child() {
+            color_node = child;
+        }
+    }
+
+    color_node.find_current_color().unwrap_or((tree::Color::black(), 1.0))
+}
+
+/// Calculates a filter region for filter functions.
+///
+/// Unlike the default `filter` region, it's not limited by the content bbox,
+/// so blurs and shadows will not be clipped, like in browsers.
+fn calc_region(
+    g: &tree::Node,
+    list: &[FilterValue],
+) -> Option<Rect> {
+    let mut bbox = Rect::new_bbox();
+    for child in g.children() {
+        if let Some(r) = super::calc_node_bbox(&child, tree::Transform::default(), true) {
+            bbox = bbox.expand(r);
+        }
+    }
+
+    if bbox.fuzzy_eq(&Rect::new_bbox()) {
+        return None;
+    }
+
+    // A Gaussian blur is almost invisible after three standard deviations.
+    let outset = |r: Rect, std_dev: f64| {
+        let n = std_dev * 3.0;
+        Rect::new(r.x() - n, r.y() - n, r.width() + n * 2.0, r.height() + n * 2.0).unwrap_or(r)
+    };
+
+    let mut region = bbox;
+    for item in list {
+        match *item {
+            FilterValue::Function(FilterFunction::Blur(std_dev)) => {
+                region = outset(region, std_dev);
+            }
+            FilterValue::Function(FilterFunction::DropShadow { dx, dy, std_dev, .. }) => {
+                region = region.expand(outset(region.translate(dx, dy), std_dev));
+            }
+            _ => {}
+        }
+    }
+
+    Some(region)
+}
+
+/// Converts filter functions into a `filter` element.
+///
+/// Returns `None` when there is nothing to apply.
+fn convert_functions(
+    node: &svgdom::Node,
+    functions: &[FilterFunction],
+    region: Option<Rect>,
+    tree: &mut tree::Tree,
+) -> Option<String> {
+    let mut primitives = Vec::new();
+    for f in functions {
+        convert_function(f, &mut primitives);
+    }
+
+    if primitives.is_empty() {
+        return None;
+    }
+
+    // Without a region, the filter will be ignored during rendering anyway.
+    let (units, rect) = match region {
+        Some(r) => (tree::Units::UserSpaceOnUse, r),
+        None => (tree::Units::ObjectBoundingBox, Rect::new(-0.1, -0.1, 1.2, 1.2).unwrap()),
+    };
+
+    let id = super::gen_unique_id(node, tree, "filter");
+    tree.append_to_defs(
+        tree::NodeKind::Filter(tree::Filter {
+            id: id.clone(),
+            units,
+            primitive_units: tree::Units::UserSpaceOnUse,
+            rect,
+            children: primitives,
+        })
+    );
+
+    Some(id)
+}
+
+/// Converts a filter function into equivalent filter primitives.
+///
+/// https://www.w3.org/TR/filter-effects-1/#ShorthandEquivalents
+fn convert_function(
+    f: &FilterFunction,
+    primitives: &mut Vec<tree::FilterPrimitive>,
+) {
+    let input = match primitives.last() {
+        Some(prev) => tree::FilterInput::Reference(prev.result.clone()),
+        None => tree::FilterInput::SourceGraphic,
+    };
+
+    let linear = |slope: f64, intercept: f64| tree::TransferFunction::Linear { slope, intercept };
+    let transfer = |func: tree::TransferFunction| {
+        tree::FilterKind::FeComponentTransfer(tree::FeComponentTransfer {
+            input: input.clone(),
+            func_r: func.clone(),
+            func_g: func.clone(),
+            func_b: func,
+            func_a: tree::TransferFunction::Identity,
+        })
+    };
+    let matrix = |m: Vec<f64>| {
+        tree::FilterKind::FeColorMatrix(tree::FeColorMatrix {
+            input: input.clone(),
+            kind: tree::FeColorMatrixKind::Matrix(m),
+        })
+    };
+
+    match *f {
+        FilterFunction::Blur(std_dev) => {
+            if std_dev.is_fuzzy_zero() {
+                return;
+            }
+
+            push_primitive(primitives, tree::FilterKind::FeGaussianBlur(tree::FeGaussianBlur {
+                input,
+                std_dev_x: std_dev.into(),
+                std_dev_y: std_dev.into(),
+            }));
+        }
+        FilterFunction::Brightness(n) => {
+            push_primitive(primitives, transfer(linear(n, 0.0)));
+        }
+        FilterFunction::Contrast(n) => {
+            push_primitive(primitives, transfer(linear(n, 0.5 - n * 0.5)));
+        }
+        FilterFunction::DropShadow { dx, dy, std_dev, color, opacity } => {
+            push_primitive(primitives, tree::FilterKind::FeFlood(tree::FeFlood {
+                color,
+                opacity: opacity.into(),
+            }));
+
+            let flood = tree::FilterInput::Reference(primitives.last().unwrap().result.clone());
+            push_primitive(primitives, tree::FilterKind::FeComposite(tree::FeComposite {
+                input1: flood,
+                input2: input.clone(),
+                operator: tree::FeCompositeOperator::In,
+            }));
+
+            if !std_dev.is_fuzzy_zero() {
+                let shadow = tree::FilterInput::Reference(primitives.last().unwrap().result.clone());
+                push_primitive(primitives, tree::FilterKind::FeGaussianBlur(tree::FeGaussianBlur {
+                    input: shadow,
+                    std_dev_x: std_dev.into(),
+                    std_dev_y: std_dev.into(),
+                }));
+            }
+
+            let shadow = tree::FilterInput::Reference(primitives.last().unwrap().result.clone());
+            push_primitive(primitives, tree::FilterKind::FeOffset(tree::FeOffset {
+                input: shadow,
+                dx,
+                dy,
+            }));
+
+            let shadow = tree::FilterInput::Reference(primitives.last().unwrap().result.clone());
+            push_primitive(primitives, tree::FilterKind::FeMerge(tree::FeMerge {
+                inputs: vec![shadow, input],
+            }));
+        }
+        FilterFunction::Grayscale(n) => {
+            let n = 1.0 - n;
+            push_primitive(primitives, matrix(vec![
+                0.2126 + 0.7874 * n, 0.7152 - 0.7152 * n, 0.0722 - 0.0722 * n, 0.0, 0.0,
+                0.2126 - 0.2126 * n, 0.7152 + 0.2848 * n, 0.0722 - 0.0722 * n, 0.0, 0.0,
+                0.2126 - 0.2126 * n, 0.7152 - 0.7152 * n, 0.0722 + 0.9278 * n, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ]));
+        }
+        FilterFunction::HueRotate(angle) => {
+            push_primitive(primitives, tree::FilterKind::FeColorMatrix(tree::FeColorMatrix {
+                input,
+                kind: tree::FeColorMatrixKind::HueRotate(angle),
+            }));
+        }
+        FilterFunction::Invert(n) => {
+            push_primitive(primitives, transfer(tree::TransferFunction::Table(vec![n, 1.0 - n])));
+        }
+        FilterFunction::Opacity(n) => {
+            push_primitive(primitives, tree::FilterKind::FeComponentTransfer(tree::FeComponentTransfer {
+                input,
+                func_r: tree::TransferFunction::Identity,
+                func_g: tree::TransferFunction::Identity,
+                func_b: tree::TransferFunction::Identity,
+                func_a: tree::TransferFunction::Table(vec![0.0, n]),
+            }));
+        }
+        FilterFunction::Saturate(n) => {
+            push_primitive(primitives, tree::FilterKind::FeColorMatrix(tree::FeColorMatrix {
+                input,
+                kind: tree::FeColorMatrixKind::Saturate(n.into()),
+            }));
+        }
+        FilterFunction::Sepia(n) => {
+            let n = 1.0 - n;
+            push_primitive(primitives, matrix(vec![
+                0.393 + 0.607 * n, 0.769 - 0.769 * n, 0.189 - 0.189 * n, 0.0, 0.0,
+                0.349 - 0.349 * n, 0.686 + 0.314 * n, 0.168 - 0.168 * n, 0.0, 0.0,
+                0.272 - 0.272 * n, 0.534 - 0.534 * n, 0.131 + 0.869 * n, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ]));
+        }
+    }
+}
+
+fn push_primitive(
+    primitives: &mut Vec<tree::FilterPrimitive>,
+    kind: tree::FilterKind,
+) {
+    let result = format!("result{}", primitives.len() + 1);
+    primitives.push(tree::FilterPrimitive {
+        x: None,
+        y: None,
+        width: None,
+        height: None,
+        // Filter functions are applied in the sRGB color space, like in browsers.
+        color_interpolation: tree::ColorInterpolation::SRGB,
+        result,
+        kind,
+    });
+}
@@ -11,6 +11,10 @@ use svgdom;
 // self
 use crate::tree;
 use crate::tree::prelude::*;
+use crate::{
+    Resource,
+    ResourceKind,
+};
 use super::prelude::*;
 
 
@@ -21,13 +25,16 @@ pub fn convert(
 ) {
     let ref attrs = node.attributes();
 
-    let transform = attrs.get_transform(AId::Transform);
+    let transform = node.resolve_transform(state);
     let visibility = node.find_enum(AId::Visibility);
     let rendering_mode = node
         .try_find_enum(AId::ImageRendering)
         .unwrap_or(state.opt.image_rendering);
 
-    let rect = try_opt_warn!(get_image_rect(node, state), "Image has an invalid size. Skipped.");
+    let rect = try_opt_diag_or!(
+        get_image_rect(node, state), (), InvalidShape, node.id(),
+        "Image has an invalid size. Skipped."
+    );
 
     let view_box = tree::ViewBox {
         rect,
@@ -37,12 +44,13 @@ pub fn convert(
     let href = match attrs.get_value(AId::Href) {
         Some(&AValue::String(ref s)) => s,
         _ => {
-            warn!("The 'image' element lacks the 'xlink:href' attribute. Skipped.");
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "The 'image' element lacks the 'xlink:href' attribute. Skipped.");
             return;
         }
     };
 
-    if let Some((data, format)) = get_href_data(&*node.id(), href, state.opt.path.as_ref()) {
+    if let Some((data, format)) = get_href_data(&*node.id(), href, state.opt) {
         parent.append_kind(tree::NodeKind::Image(tree::Image {
             id: node.id().clone(),
             transform,
@@ -58,8 +66,29 @@ pub fn convert(
 pub fn get_href_data(
     element_id: &str,
     href: &str,
-    path: Option<&path::PathBuf>,
+    opt: &Options,
 ) -> Option<(tree::ImageData, tree::ImageFormat)> {
+    if let Some(ref loader) = opt.resource_loader {
+        match loader.load(href, ResourceKind::Image) {
+            Resource::Data(data) => {
+                return match get_data_format(&data) {
+                    Some(format) => Some((tree::ImageData::Raw(data), format)),
+                    None => {
+                        diag!(Warning, InvalidImage, element_id,
+                              "'{}' is not a PNG, JPEG or SVG(Z) image.", href);
+                        None
+                    }
+                };
+            }
+            Resource::Default => {}
+            Resource::Denied => {
+                diag!(Warning, ResourceDenied, element_id,
+                      "Access to '{}' was denied.", href);
+                return None;
+            }
+        }
+    }
+
     if href.starts_with("data:image/") {
         if let Some(idx) = href.find(',') {
             let start_idx = 11; // data:image/
@@ -86,10 +115,11 @@ pub fn get_href_data(
             }
         }
 
-        warn!("Image '{}' has an invalid 'xlink:href' content.", element_id);
+        diag!(Warning, InvalidImage, element_id,
+              "Image '{}' has an invalid 'xlink:href' content.", element_id);
     } else {
-        let path = match path {
-            Some(path) => path.parent().unwrap().join(href),
+        let path = match opt.path {
+            Some(ref path) => path.parent().unwrap().join(href),
             None => path::PathBuf::from(href),
         };
 
@@ -97,10 +127,11 @@ pub fn get_href_data(
             if let Some(format) = get_image_format(&path) {
                 return Some((tree::ImageData::Path(path::PathBuf::from(href)), format));
             } else {
-                warn!("'{}' is not a PNG, JPEG or SVG(Z) image.", href);
+                diag!(Warning, InvalidImage, element_id,
+                      "'{}' is not a PNG, JPEG or SVG(Z) image.", href);
             }
         } else {
-            warn!("Linked file does not exist: '{}'.", href);
+            diag!(Warning, ImageNotFound, element_id, "Linked file does not exist: '{}'.", href);
         }
     }
 
@@ -141,6 +172,25 @@ fn get_image_format(path: &path::Path) -> Option<tree::ImageFormat> {
     }
 }
 
+/// Checks that data has a PNG or a JPEG magic bytes.
+/// Or looks like an SVG(Z).
+fn get_data_format(data: &[u8]) -> Option<tree::ImageFormat> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(tree::ImageFormat::PNG)
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some(tree::ImageFormat::JPEG)
+    } else if data.starts_with(&[0x1f, 0x8b]) {
+        Some(tree::ImageFormat::SVG)
+    } else {
+        let c = data.iter().find(|c| !c.is_ascii_whitespace())?;
+        if *c == b'<' {
+            Some(tree::ImageFormat::SVG)
+        } else {
+            None
+        }
+    }
+}
+
 fn get_image_rect(
     node: &svgdom::Node,
     state: &State,
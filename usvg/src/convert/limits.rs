@@ -0,0 +1,221 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fs;
+use std::io::{
+    self,
+    Read,
+    Seek,
+    SeekFrom,
+};
+use std::path;
+
+// external
+use svgdom;
+
+// self
+use crate::tree;
+use crate::{
+    Error,
+    LimitKind,
+    Limits,
+    Options,
+};
+
+
+/// Checks the document elements count and nesting depth.
+pub fn check_doc(
+    doc: &svgdom::Document,
+    limits: &Limits,
+) -> Result<(), Error> {
+    if limits.max_elements.is_none() && limits.max_depth.is_none() {
+        return Ok(());
+    }
+
+    let max_elements = limits.max_elements.unwrap_or(usize::max_value());
+    let max_depth = limits.max_depth.unwrap_or(usize::max_value());
+
+    // Do not use recursion, because the depth is not checked yet.
+    let mut count = 0;
+    let mut stack = vec![(doc.root(), 0)];
+    while let Some((node, depth)) = stack.pop() {
+        if depth > max_depth {
+            return Err(Error::LimitExceeded(LimitKind::Depth));
+        }
+
+        // Only elements are counted, just like during the CSS pre-pass.
+        for child in node.children().filter(|n| n.is_element()) {
+            count += 1;
+            if count > max_elements {
+                return Err(Error::LimitExceeded(LimitKind::Elements));
+            }
+
+            stack.push((child, depth + 1));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks the size of all images referenced by the `image` and `feImage` elements.
+///
+/// Raster images are checked against `Limits::max_pixels` too, using dimensions
+/// from the image header, so a small file with a huge decoded size will be rejected.
+/// An image which size cannot be detected is treated as exceeding the limit.
+pub fn check_images(
+    tree: &tree::Tree,
+    opt: &Options,
+) -> Result<(), Error> {
+    if opt.limits.max_image_size.is_none() && opt.limits.max_pixels.is_none() {
+        return Ok(());
+    }
+
+    for node in tree.root().descendants() {
+        match *node.borrow() {
+            tree::NodeKind::Image(ref img) => {
+                check_image_data(&img.data, img.format, opt)?;
+            }
+            tree::NodeKind::Filter(ref filter) => {
+                for primitive in &filter.children {
+                    if let tree::FilterKind::FeImage(ref fe) = primitive.kind {
+                        if let tree::FeImageKind::Image(ref data, format) = fe.data {
+                            check_image_data(data, format, opt)?;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn check_image_data(
+    data: &tree::ImageData,
+    format: tree::ImageFormat,
+    opt: &Options,
+) -> Result<(), Error> {
+    match *data {
+        tree::ImageData::Raw(ref data) => {
+            check_image_size(Some(data.len() as u64), &opt.limits)?;
+            check_image_pixels(io::Cursor::new(data), format, &opt.limits)
+        }
+        tree::ImageData::Path(ref path) => {
+            let path = match opt.path {
+                Some(ref svg_path) => svg_path.parent().unwrap_or(path::Path::new("")).join(path),
+                None => path.clone(),
+            };
+
+            check_image_size(fs::metadata(&path).map(|m| m.len()).ok(), &opt.limits)?;
+
+            match fs::File::open(&path) {
+                Ok(file) => check_image_pixels(io::BufReader::new(file), format, &opt.limits),
+                Err(_) if opt.limits.max_pixels.is_some() => {
+                    Err(Error::LimitExceeded(LimitKind::Pixels))
+                }
+                Err(_) => Ok(()),
+            }
+        }
+    }
+}
+
+fn check_image_size(
+    size: Option<u64>,
+    limits: &Limits,
+) -> Result<(), Error> {
+    if let Some(max) = limits.max_image_size {
+        match size {
+            Some(size) if size <= max => {}
+            _ => return Err(Error::LimitExceeded(LimitKind::ImageSize)),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_image_pixels<R: Read + Seek>(
+    mut reader: R,
+    format: tree::ImageFormat,
+    limits: &Limits,
+) -> Result<(), Error> {
+    let max = match limits.max_pixels {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let size = match format {
+        tree::ImageFormat::PNG => png_size(&mut reader),
+        tree::ImageFormat::JPEG => jpeg_size(&mut reader),
+        // SVG images are rendered using the same limits.
+        tree::ImageFormat::SVG => return Ok(()),
+    };
+
+    match size {
+        Some((w, h)) if w as u64 * h as u64 <= max => Ok(()),
+        _ => Err(Error::LimitExceeded(LimitKind::Pixels)),
+    }
+}
+
+/// Returns PNG dimensions from the `IHDR` chunk.
+fn png_size<R: Read>(reader: &mut R) -> Option<(u32, u32)> {
+    let mut header = [0; 24];
+    reader.read_exact(&mut header).ok()?;
+
+    if !header.starts_with(b"\x89PNG\r\n\x1a\n") || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    Some((read_u32(&header[16..20]), read_u32(&header[20..24])))
+}
+
+/// Returns JPEG dimensions from the first `SOFn` segment.
+fn jpeg_size<R: Read + Seek>(reader: &mut R) -> Option<(u32, u32)> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf).ok()?;
+    if buf != [0xff, 0xd8] {
+        return None;
+    }
+
+    loop {
+        reader.read_exact(&mut buf).ok()?;
+        if buf[0] != 0xff {
+            return None;
+        }
+
+        // Skip fill bytes.
+        let mut marker = buf[1];
+        while marker == 0xff {
+            reader.read_exact(&mut buf[..1]).ok()?;
+            marker = buf[0];
+        }
+
+        // Markers without a length.
+        if marker == 0x01 || (marker >= 0xd0 && marker <= 0xd7) {
+            continue;
+        }
+
+        reader.read_exact(&mut buf).ok()?;
+        let len = (buf[0] as u16) << 8 | buf[1] as u16;
+        if len < 2 {
+            return None;
+        }
+
+        // SOF0-SOF15, except DHT, JPG and DAC.
+        if marker >= 0xc0 && marker <= 0xcf && marker != 0xc4 && marker != 0xc8 && marker != 0xcc {
+            // Precision, height and width.
+            let mut sof = [0; 5];
+            reader.read_exact(&mut sof).ok()?;
+            let h = (sof[1] as u32) << 8 | sof[2] as u32;
+            let w = (sof[3] as u32) << 8 | sof[4] as u32;
+            return Some((w, h));
+        }
+
+        reader.seek(SeekFrom::Current(len as i64 - 2)).ok()?;
+    }
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    (data[0] as u32) << 24 | (data[1] as u32) << 16 | (data[2] as u32) << 8 | data[3] as u32
+}
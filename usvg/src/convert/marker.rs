@@ -10,58 +10,82 @@ use crate::tree;
 use crate::tree::prelude::*;
 use crate::tree::PathSegment as Segment;
 use super::prelude::*;
-use super::use_node;
 
 
-pub fn convert(
+const MARKER_ATTRIBUTES: &[(AId, MarkerKind)] = &[
+    (AId::MarkerStart, MarkerKind::Start),
+    (AId::MarkerMid, MarkerKind::Middle),
+    (AId::MarkerEnd, MarkerKind::End),
+];
+
+
+/// Checks that the shape has any markers.
+pub fn has_markers(
     node: &svgdom::Node,
-    segments: &[tree::PathSegment],
-    state: &State,
-    parent: &mut tree::Node,
-    tree: &mut tree::Tree,
-) {
+) -> bool {
     // `marker-*` attributes can only be set on `path`, `line`, `polyline` and `polygon`.
     match node.tag_id() {
           Some(EId::Path)
         | Some(EId::Line)
         | Some(EId::Polyline)
         | Some(EId::Polygon) => {}
-        _ => return,
+        _ => return false,
     }
 
     // `marker-*` attributes cannot be set on shapes inside the `clipPath`.
     if node.ancestors().any(|n| n.is_tag_name(EId::ClipPath)) {
+        return false;
+    }
+
+    MARKER_ATTRIBUTES.iter().any(|(aid, _)| find_marker(node, *aid).is_some())
+}
+
+pub fn convert(
+    node: &svgdom::Node,
+    path: &tree::Path,
+    state: &State,
+    parent: &mut tree::Node,
+    tree: &mut tree::Tree,
+) {
+    if !has_markers(node) {
         return;
     }
 
-    let list = [
-        (AId::MarkerStart, MarkerKind::Start),
-        (AId::MarkerMid, MarkerKind::Middle),
-        (AId::MarkerEnd, MarkerKind::End),
-    ];
-
-    for (aid, kind) in &list {
-        let mut marker = None;
-        for n in node.ancestors() {
-            let attrs = n.attributes();
-            if let Some(&AValue::FuncLink(ref link)) = attrs.get_value(*aid) {
-                if link.is_tag_name(EId::Marker) {
-                    marker = Some(link.clone());
-                }
-            }
-        }
+    // Markers content can reference the shape paint via `context-fill` and `context-stroke`.
+    let mut marker_state = state.clone();
+    marker_state.context_fill = path.fill.as_ref().map(|f| f.paint.clone());
+    marker_state.context_stroke = path.stroke.as_ref().map(|s| s.paint.clone());
 
-        if let Some(marker) = marker {
+    for (aid, kind) in MARKER_ATTRIBUTES {
+        if let Some(marker) = find_marker(node, *aid) {
             // Check for recursive marker.
             if state.current_root == marker {
                 continue;
             }
 
-            resolve(node, segments, &marker, *kind, state, parent, tree);
+            marker_state.current_root = marker.clone();
+            resolve(node, &path.segments, &marker, *kind, &marker_state, parent, tree);
         }
     }
 }
 
+fn find_marker(
+    node: &svgdom::Node,
+    aid: AId,
+) -> Option<svgdom::Node> {
+    let mut marker = None;
+    for n in node.ancestors() {
+        let attrs = n.attributes();
+        if let Some(&AValue::FuncLink(ref link)) = attrs.get_value(aid) {
+            if link.is_tag_name(EId::Marker) {
+                marker = Some(link.clone());
+            }
+        }
+    }
+
+    marker
+}
+
 #[derive(Clone, Copy)]
 enum MarkerKind {
     Start,
@@ -71,6 +95,7 @@ enum MarkerKind {
 
 enum MarkerOrientation {
     Auto,
+    AutoStartReverse,
     Angle(f64),
 }
 
@@ -107,7 +132,7 @@ fn resolve(
             r.size().to_rect(0.0, 0.0)
         };
 
-        let id = use_node::gen_clip_path_id(shape_node, tree);
+        let id = super::gen_unique_id(shape_node, tree, "clipPath");
 
         let mut clip_path = tree.append_to_defs(
             tree::NodeKind::ClipPath(tree::ClipPath {
@@ -134,6 +159,13 @@ fn resolve(
 
         let angle = match convert_orientation(&*marker_node.attributes()) {
             MarkerOrientation::Auto => calc_vertex_angle(&segments, idx),
+            MarkerOrientation::AutoStartReverse => {
+                let angle = calc_vertex_angle(&segments, idx);
+                match marker_kind {
+                    MarkerKind::Start => angle + 180.0,
+                    _ => angle,
+                }
+            }
             MarkerOrientation::Angle(angle) => angle,
         };
 
@@ -160,12 +192,15 @@ fn resolve(
             opacity: tree::Opacity::default(),
             clip_path: clip_path.clone(),
             mask: None,
-            filter: None,
+            filter: Vec::new(),
+            filter_fill: None,
+            filter_stroke: None,
+            enable_background: None,
+            blend_mode: tree::BlendMode::Normal,
+            isolate: false,
         }));
 
-        let mut marker_state = state.clone();
-        marker_state.current_root = marker_node.clone();
-        super::convert_children(marker_node, &marker_state, &mut g_node, tree);
+        super::convert_children(marker_node, state, &mut g_node, tree);
 
         if !g_node.has_children() {
             g_node.detach();
@@ -490,6 +525,9 @@ fn convert_orientation(
         Some(AValue::String(s)) if s == "auto" => {
             MarkerOrientation::Auto
         }
+        Some(AValue::String(s)) if s == "auto-start-reverse" => {
+            MarkerOrientation::AutoStartReverse
+        }
         _ => {
             MarkerOrientation::Angle(0.0)
         }
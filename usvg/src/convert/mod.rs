@@ -10,9 +10,9 @@ use svgdom::{
     self,
     ElementType,
     FilterSvg,
+    FuzzyEq,
     Length,
 };
-use log::warn;
 
 // self
 use crate::tree;
@@ -20,6 +20,7 @@ use crate::tree::prelude::*;
 use crate::short::*;
 use crate::geom::*;
 use crate::{
+    utils,
     Error,
     Options,
 };
@@ -29,6 +30,7 @@ pub use self::preprocess::prepare_doc;
 mod clip_and_mask;
 mod filter;
 mod image;
+mod limits;
 mod marker;
 mod paint_server;
 mod path;
@@ -42,7 +44,6 @@ mod units;
 mod use_node;
 
 mod prelude {
-    pub use log::warn;
     pub use svgdom::{
         AttributeType,
         ElementType,
@@ -71,6 +72,11 @@ pub struct State<'a> {
     view_box: Rect,
     font_cache: Rc<RefCell<FontCache>>,
     opt: &'a Options,
+    /// Paints used by `context-fill` and `context-stroke`.
+    ///
+    /// Set only during a marker conversion.
+    context_fill: Option<tree::Paint>,
+    context_stroke: Option<tree::Paint>,
 }
 
 impl<'a> State<'a> {
@@ -111,6 +117,9 @@ impl FontCache {
 ///
 /// - If `Document` doesn't have an SVG node - returns an empty tree.
 /// - If `Document` doesn't have a valid size - returns `Error::InvalidSize`.
+///
+/// When the root `svg` element doesn't have a `viewBox` and a relative `width`
+/// or `height`, the size will be detected from the bounding box of all elements.
 pub fn convert_doc(
     svg_doc: &svgdom::Document,
     opt: &Options,
@@ -123,63 +132,95 @@ pub fn convert_doc(
         //
         // Or if someone passed an invalid document directly though API.
 
-        warn!("An invalid SVG structure. An empty tree will be produced.");
+        diag!(Error, InvalidStructure, "", "An invalid SVG structure. An empty tree will be produced.");
         return Ok(gen_empty_tree());
     };
 
-    let size = resolve_svg_size(&svg, opt)?;
+    let aspect = convert_aspect(&svg.attributes());
+    let font_cache = Rc::new(RefCell::new(FontCache::new()));
 
-    let view_box = {
-        let attrs = svg.attributes();
-        tree::ViewBox {
-            rect: get_view_box(&svg, size),
-            aspect: convert_aspect(&attrs),
+    let (size, view_box) = match resolve_svg_size(&svg, opt)? {
+        Some(size) => (size, get_view_box(&svg, size)),
+        None if !style::is_visible_element(&svg, opt) => {
+            let size = Size::new(100.0, 100.0).unwrap();
+            (size, size.to_rect(0.0, 0.0))
         }
+        None => resolve_auto_size(&svg, font_cache.clone(), opt)?,
     };
 
-    if !style::is_visible_element(&svg, opt) {
-        let svg_kind = tree::Svg {
-            size,
-            view_box,
-        };
+    let view_box = tree::ViewBox {
+        rect: view_box,
+        aspect,
+    };
 
-        return Ok(tree::Tree::create(svg_kind));
-    }
+    let tree = convert_content(&svg, size, view_box, font_cache, opt);
+
+    limits::check_images(&tree, opt)?;
+
+    Ok(tree)
+}
 
+fn convert_content(
+    svg: &svgdom::Node,
+    size: Size,
+    view_box: tree::ViewBox,
+    font_cache: Rc<RefCell<FontCache>>,
+    opt: &Options,
+) -> tree::Tree {
     let svg_kind = tree::Svg {
         size,
         view_box,
     };
 
+    if !style::is_visible_element(svg, opt) {
+        return tree::Tree::create(svg_kind);
+    }
+
     let state = State {
         current_root: svg.clone(),
         size,
         view_box: view_box.rect,
-        font_cache: Rc::new(RefCell::new(FontCache::new())),
+        font_cache,
         opt: &opt,
+        context_fill: None,
+        context_stroke: None,
     };
 
     let mut tree = tree::Tree::create(svg_kind);
 
-    convert_children(&svg, &state, &mut tree.root(), &mut tree);
+    let mut root = tree.root();
+    if let Some(enable_background) = convert_enable_background(svg) {
+        // `enable-background` on the root element applies to the whole image.
+        root = root.append_kind(tree::NodeKind::Group(tree::Group {
+            enable_background: Some(enable_background),
+            ..tree::Group::default()
+        }));
+    }
+
+    convert_children(svg, &state, &mut root, &mut tree);
 
     remove_empty_groups(&mut tree);
     ungroup_groups(&mut tree, opt);
     remove_unused_defs(&mut tree);
 
-    Ok(tree)
+    tree
 }
 
+/// Resolves the root `svg` element size.
+///
+/// Returns `None` when the size should be detected from the content bounding box.
 fn resolve_svg_size(
     svg: &svgdom::Node,
     opt: &Options,
-) -> Result<Size, Error> {
+) -> Result<Option<Size>, Error> {
     let mut state = State {
         current_root: svg.clone(),
         size: Size::new(100.0, 100.0).unwrap(),
         view_box: Rect::new(0.0, 0.0, 100.0, 100.0).unwrap(),
         font_cache: Rc::new(RefCell::new(FontCache::new())),
         opt,
+        context_fill: None,
+        context_stroke: None,
     };
 
     let def = Length::new(100.0, Unit::Percent);
@@ -189,9 +230,9 @@ fn resolve_svg_size(
     let view_box = svg.get_viewbox();
 
     if (width.unit == Unit::Percent || height.unit == Unit::Percent) && view_box.is_none() {
-        // TODO: it this case we should detect the bounding box of all elements,
-        //       which is currently impossible
-        return Err(Error::InvalidSize);
+        // In this case we should detect the bounding box of all elements,
+        // which is possible only after the conversion.
+        return Ok(None);
     }
 
     let size = if let Some(vbox) = view_box {
@@ -218,12 +259,216 @@ fn resolve_svg_size(
     };
 
     if let Some(size) = size {
-        Ok(size)
+        Ok(Some(size))
     } else {
         Err(Error::InvalidSize)
     }
 }
 
+/// Detects the root `svg` element size and `viewBox` from the bounding box of all elements.
+///
+/// Absolute `width` and `height` are preserved,
+/// while relative ones are resolved against the bounding box.
+///
+/// Relative lengths inside the document depend on the viewport, which is not known yet,
+/// so the content is measured with a zero-sized viewport first, where they are ignored.
+/// The document should be converted again using the returned size afterwards.
+fn resolve_auto_size(
+    svg: &svgdom::Node,
+    font_cache: Rc<RefCell<FontCache>>,
+    opt: &Options,
+) -> Result<(Size, Rect), Error> {
+    // A tiny size, which makes percentages negligible.
+    let min_size = Size::new(1e-6, 1e-6).unwrap();
+    let view_box = tree::ViewBox {
+        rect: min_size.to_rect(0.0, 0.0),
+        aspect: tree::AspectRatio::default(),
+    };
+
+    // The document will be converted again, so diagnostics will be reported then.
+    let tree = crate::diagnostics::without_diagnostics(|| {
+        convert_content(svg, min_size, view_box, font_cache.clone(), opt)
+    });
+    let bbox = calc_content_bbox(&tree).ok_or(Error::InvalidSize)?;
+
+    let state = State {
+        current_root: svg.clone(),
+        size: bbox.size(),
+        view_box: bbox,
+        font_cache,
+        opt,
+        context_fill: None,
+        context_stroke: None,
+    };
+
+    let def = Length::new(100.0, Unit::Percent);
+    let width = svg.attributes().get_length(AId::Width).unwrap_or(def);
+    let height = svg.attributes().get_length(AId::Height).unwrap_or(def);
+
+    let w = if width.unit == Unit::Percent {
+        bbox.width() * (width.num / 100.0)
+    } else {
+        svg.convert_user_length(AId::Width, &state, def)
+    };
+
+    let h = if height.unit == Unit::Percent {
+        bbox.height() * (height.num / 100.0)
+    } else {
+        svg.convert_user_length(AId::Height, &state, def)
+    };
+
+    let size = Size::new(w, h).ok_or(Error::InvalidSize)?;
+
+    Ok((size, bbox))
+}
+
+/// Calculates the bounding box of all renderable elements, including strokes.
+///
+/// Markers and text are already converted into paths at this point.
+fn calc_content_bbox(
+    tree: &tree::Tree,
+) -> Option<Rect> {
+    let mut bbox = Rect::new_bbox();
+    for node in tree.root().children() {
+        if let Some(r) = calc_node_bbox(&node, tree::Transform::default(), true) {
+            bbox = bbox.expand(r);
+        }
+    }
+
+    if bbox.fuzzy_eq(&Rect::new_bbox()) {
+        None
+    } else {
+        Some(bbox)
+    }
+}
+
+fn calc_node_bbox(
+    node: &tree::Node,
+    ts: tree::Transform,
+    with_stroke: bool,
+) -> Option<Rect> {
+    let mut ts2 = ts;
+    ts2.append(&node.transform());
+
+    match *node.borrow() {
+        tree::NodeKind::Path(ref path) => {
+            let stroke = if with_stroke { path.stroke.as_ref() } else { None };
+            utils::path_bbox(&path.segments, stroke, Some(ts2))
+        }
+        tree::NodeKind::Image(ref img) => {
+            transform_rect(img.view_box.rect, ts2)
+        }
+        tree::NodeKind::Group(ref g) => {
+            if g.filter.is_empty() && g.clip_path.is_none() && g.mask.is_none() {
+                return calc_children_bbox(node, ts2, with_stroke);
+            }
+
+            // Regions are defined in the group user space.
+            let region = calc_group_region(node, g, with_stroke)?;
+            transform_rect(region, ts2)
+        }
+        _ => None,
+    }
+}
+
+fn calc_children_bbox(
+    node: &tree::Node,
+    ts: tree::Transform,
+    with_stroke: bool,
+) -> Option<Rect> {
+    let mut bbox = Rect::new_bbox();
+    for child in node.children() {
+        if let Some(r) = calc_node_bbox(&child, ts, with_stroke) {
+            bbox = bbox.expand(r);
+        }
+    }
+
+    if bbox.fuzzy_eq(&Rect::new_bbox()) {
+        None
+    } else {
+        Some(bbox)
+    }
+}
+
+/// Calculates the group bounding box in its user space,
+/// limited by its filter, clip path and mask regions.
+fn calc_group_region(
+    node: &tree::Node,
+    g: &tree::Group,
+    with_stroke: bool,
+) -> Option<Rect> {
+    let ts = tree::Transform::default();
+    let tree = node.tree();
+
+    // Used by regions with the `objectBoundingBox` units.
+    let object_bbox = calc_children_bbox(node, ts, false);
+
+    let mut region = calc_children_bbox(node, ts, with_stroke);
+
+    // A filter can draw outside the content, so its region is used instead.
+    if let Some(id) = g.filter.last() {
+        if let Some(filter_node) = tree.defs_by_id(id) {
+            if let tree::NodeKind::Filter(ref filter) = *filter_node.borrow() {
+                region = units_rect(filter.rect, filter.units, object_bbox);
+            }
+        }
+    }
+
+    if let Some(ref id) = g.clip_path {
+        if let Some(clip_node) = tree.defs_by_id(id) {
+            if let tree::NodeKind::ClipPath(ref cp) = *clip_node.borrow() {
+                let mut clip_ts = cp.transform;
+                if cp.units == tree::Units::ObjectBoundingBox {
+                    clip_ts.append(&tree::Transform::from_bbox(object_bbox?));
+                }
+
+                region = intersect_rects(region?, calc_children_bbox(&clip_node, clip_ts, false)?);
+            }
+        }
+    }
+
+    if let Some(ref id) = g.mask {
+        if let Some(mask_node) = tree.defs_by_id(id) {
+            if let tree::NodeKind::Mask(ref mask) = *mask_node.borrow() {
+                let rect = units_rect(mask.rect, mask.units, object_bbox)?;
+                region = intersect_rects(region?, rect);
+            }
+        }
+    }
+
+    region
+}
+
+/// Converts a region rect with the specified units into the user space.
+fn units_rect(
+    rect: Rect,
+    units: tree::Units,
+    object_bbox: Option<Rect>,
+) -> Option<Rect> {
+    if units == tree::Units::ObjectBoundingBox {
+        transform_rect(rect, tree::Transform::from_bbox(object_bbox?))
+    } else {
+        Some(rect)
+    }
+}
+
+fn transform_rect(
+    rect: Rect,
+    ts: tree::Transform,
+) -> Option<Rect> {
+    let segments = utils::rect_to_path(rect);
+    utils::path_bbox(&segments, None, Some(ts))
+}
+
+fn intersect_rects(
+    r1: Rect,
+    r2: Rect,
+) -> Option<Rect> {
+    let x = r1.left().max(r2.left());
+    let y = r1.top().max(r2.top());
+    Rect::new(x, y, r1.right().min(r2.right()) - x, r1.bottom().min(r2.bottom()) - y)
+}
+
 fn get_view_box(
     svg: &svgdom::Node,
     size: Size,
@@ -311,6 +556,8 @@ fn convert_element(
                 match convert_group(&node, state, false, parent, tree) {
                     GroupKind::Keep(mut g) => {
                         convert_children(&node, state, &mut g, tree);
+                        clip_and_mask::convert_basic_shape(&node, &mut g, state, tree);
+                        filter::convert_list(&node, &mut g, state, tree);
                     }
                     GroupKind::Skip => {
                         convert_children(&node, state, parent, tree);
@@ -392,10 +639,29 @@ fn convert_group(
         }
     }
 
+    let (filter_fill, filter_stroke) = match filter {
+        Some(ref id) => resolve_filter_paint(node, id, state, tree),
+        None => (None, None),
+    };
+
+    let enable_background = convert_enable_background(node);
+
+    // Like `opacity`, compositing cannot be applied to `clipPath` children.
+    let (blend_mode, isolate) = if !state.is_in_clip_path() {
+        convert_compositing(node)
+    } else {
+        (tree::BlendMode::Normal, false)
+    };
+
     let required =    opacity.value().fuzzy_ne(&1.0)
                    || clip_path.is_some()
                    || mask.is_some()
                    || filter.is_some()
+                   || enable_background.is_some()
+                   || blend_mode != tree::BlendMode::Normal
+                   || isolate
+                   || node.has_attribute("usvg-clip-path")
+                   || node.has_attribute("usvg-filter")
                    || !node.attributes().get_transform(AId::Transform).is_default()
                    || state.opt.keep_named_groups
                    || force;
@@ -403,11 +669,16 @@ fn convert_group(
     if required {
         let g = parent.append_kind(tree::NodeKind::Group(tree::Group {
             id: node.id().clone(),
-            transform: node.attributes().get_transform(AId::Transform),
+            transform: node.resolve_transform(state),
             opacity: node.convert_opacity(AId::Opacity),
             clip_path,
             mask,
-            filter,
+            filter: filter.into_iter().collect(),
+            filter_fill,
+            filter_stroke,
+            enable_background,
+            blend_mode,
+            isolate,
         }));
 
         GroupKind::Keep(g)
@@ -416,6 +687,98 @@ fn convert_group(
     }
 }
 
+/// Resolves paints used by the `FillPaint` and `StrokePaint` filter inputs.
+fn resolve_filter_paint(
+    node: &svgdom::Node,
+    filter_id: &str,
+    state: &State,
+    tree: &mut tree::Tree,
+) -> (Option<tree::Paint>, Option<tree::Paint>) {
+    let filter_node = try_opt_or!(tree.defs_by_id(filter_id), (None, None));
+    let (has_fill, has_stroke) = match *filter_node.borrow() {
+        tree::NodeKind::Filter(ref filter) => {
+            (filter.has_input(&tree::FilterInput::FillPaint),
+             filter.has_input(&tree::FilterInput::StrokePaint))
+        }
+        _ => (false, false),
+    };
+
+    if !has_fill && !has_stroke {
+        return (None, None);
+    }
+
+    // Filters on graphic elements are moved to a separate group during preprocessing,
+    // so we have to resolve paints using the original element.
+    let mut paint_node = node.clone();
+    if node.has_attribute("usvg-group") {
+        if let Some(child) = node.first_child() {
+            paint_node = child;
+        }
+    }
+
+    let fill = if has_fill {
+        style::resolve_fill(&paint_node, true, state, tree).map(|v| v.paint)
+    } else {
+        None
+    };
+
+    let stroke = if has_stroke {
+        style::resolve_stroke(&paint_node, true, state, tree).map(|v| v.paint)
+    } else {
+        None
+    };
+
+    (fill, stroke)
+}
+
+/// Parses the `enable-background` attribute.
+///
+/// Returns `None` for `accumulate` and invalid values.
+fn convert_enable_background(
+    node: &svgdom::Node,
+) -> Option<tree::EnableBackground> {
+    let attrs = node.attributes();
+    let s = attrs.get_str(AId::EnableBackground)?;
+
+    let mut iter = s.split(|c: char| c.is_whitespace() || c == ',').filter(|s| !s.is_empty());
+    if iter.next() != Some("new") {
+        return None;
+    }
+
+    let nums: Vec<f64> = iter.filter_map(|s| s.parse().ok()).collect();
+    let rect = if nums.len() == 4 {
+        // A zero or negative size is an error and disables the background rect.
+        Rect::new(nums[0], nums[1], nums[2], nums[3])
+    } else {
+        None
+    };
+
+    Some(tree::EnableBackground(rect))
+}
+
+/// Parses the `mix-blend-mode` and `isolation` attributes.
+fn convert_compositing(
+    node: &svgdom::Node,
+) -> (tree::BlendMode, bool) {
+    let attrs = node.attributes();
+
+    let blend_mode = match attrs.get_str("mix-blend-mode") {
+        Some(value) => match value.trim().parse() {
+            Ok(mode) => mode,
+            Err(_) => {
+                diag!(Warning, InvalidAttribute, node.id(),
+                      "Invalid 'mix-blend-mode' value: '{}'. Fallback to 'normal'.", value);
+                tree::BlendMode::Normal
+            }
+        },
+        None => tree::BlendMode::Normal,
+    };
+
+    let isolate = attrs.get_str("isolation").map(|v| v.trim()) == Some("isolate");
+
+    (blend_mode, isolate)
+}
+
 fn remove_empty_groups(
     tree: &mut tree::Tree,
 ) {
@@ -435,7 +798,7 @@ fn remove_empty_groups(
                 //   <feFlood flood-color="green"/>
                 // </filter>
                 // <g filter="url(#filter1)"/>
-                g.filter.is_none()
+                g.filter.is_empty()
             } else {
                 false
             };
@@ -463,6 +826,9 @@ fn ungroup_groups(
     fn ungroup(parent: tree::Node, opt: &Options) -> bool {
         let mut changed = false;
 
+        // Groups inside `defs` are referenced by `feImage` and must be preserved.
+        let is_defs = if let tree::NodeKind::Defs = *parent.borrow() { true } else { false };
+
         let mut curr_node = parent.first_child();
         while let Some(mut node) = curr_node {
             curr_node = node.next_sibling();
@@ -471,10 +837,14 @@ fn ungroup_groups(
             let is_ok = if let tree::NodeKind::Group(ref g) = *node.borrow() {
                 ts = g.transform;
 
-                   g.opacity.is_default()
+                   !is_defs
+                && g.opacity.is_default()
                 && g.clip_path.is_none()
                 && g.mask.is_none()
-                && g.filter.is_none()
+                && g.filter.is_empty()
+                && g.enable_background.is_none()
+                && g.blend_mode == tree::BlendMode::Normal
+                && !g.isolate
                 && !(opt.keep_named_groups && !g.id.is_empty())
             } else {
                 false
@@ -559,7 +929,33 @@ fn remove_unused_defs(
                 tree::NodeKind::Group(ref g) => {
                     check_id!(g.clip_path, id);
                     check_id!(g.mask, id);
-                    check_id!(g.filter, id);
+
+                    if g.filter.iter().any(|v| v == id) {
+                        return true;
+                    }
+
+                    if let Some(tree::Paint::Link(ref link)) = g.filter_fill {
+                        if link == id {
+                            return true;
+                        }
+                    }
+
+                    if let Some(tree::Paint::Link(ref link)) = g.filter_stroke {
+                        if link == id {
+                            return true;
+                        }
+                    }
+                }
+                tree::NodeKind::Filter(ref filter) => {
+                    for fe in &filter.children {
+                        if let tree::FilterKind::FeImage(ref img) = fe.kind {
+                            if let tree::FeImageKind::Use(ref use_id) = img.data {
+                                if id == use_id {
+                                    return true;
+                                }
+                            }
+                        }
+                    }
                 }
                 _ => {}
             }
@@ -600,7 +996,7 @@ fn convert_path(
     let attrs = node.attributes();
     let fill = style::resolve_fill(node, has_bbox, state, tree);
     let stroke = style::resolve_stroke(node, has_bbox, state, tree);
-    let transform = attrs.get_transform(AId::Transform);
+    let transform = node.resolve_transform(state);
     let mut visibility = node.find_enum(AId::Visibility);
     let rendering_mode = node
         .try_find_enum(AId::ShapeRendering)
@@ -612,19 +1008,78 @@ fn convert_path(
         visibility = tree::Visibility::Hidden
     }
 
-    parent.append_kind(tree::NodeKind::Path(tree::Path {
+    let paint_order = style::resolve_paint_order(node);
+
+    let path = tree::Path {
         id: node.id().clone(),
         transform,
         visibility,
         fill,
         stroke,
         rendering_mode,
-        segments: segments.clone(), // TODO: remove
-    }));
+        paint_order: style::fill_and_stroke_order(&paint_order),
+        segments,
+    };
+
+    if visibility != tree::Visibility::Visible || !marker::has_markers(node) {
+        parent.append_kind(tree::NodeKind::Path(path));
+        return;
+    }
+
+    // Markers are rendered as separate elements, so the path should be split
+    // into a part that is rendered before the markers and a part that is rendered after.
+    let index_of = |kind| paint_order.iter().position(|k| *k == kind).unwrap_or(0);
+    let markers_idx = index_of(style::PaintOrderKind::Markers);
+
+    let mut before = path.clone();
+    let mut after = path.clone();
+    if index_of(style::PaintOrderKind::Fill) < markers_idx {
+        after.fill = None;
+    } else {
+        before.fill = None;
+    }
+
+    if index_of(style::PaintOrderKind::Stroke) < markers_idx {
+        after.stroke = None;
+    } else {
+        before.stroke = None;
+    }
 
-    if visibility == tree::Visibility::Visible {
-        marker::convert(node, &segments, state, parent, tree);
+    let has_before = before.fill.is_some() || before.stroke.is_some();
+    if has_before {
+        parent.append_kind(tree::NodeKind::Path(before));
     }
+
+    marker::convert(node, &path, state, parent, tree);
+
+    if after.fill.is_some() || after.stroke.is_some() {
+        // IDs must be unique, so the second part gets a derived one.
+        if has_before && !node.id().is_empty() {
+            after.id = gen_unique_id(node, tree, &format!("{}-after", node.id()));
+        }
+
+        parent.append_kind(tree::NodeKind::Path(after));
+    }
+}
+
+/// Creates a free ID with the specified prefix.
+///
+/// The ID is not used by the source document and by the already converted tree.
+fn gen_unique_id(
+    node: &svgdom::Node,
+    tree: &tree::Tree,
+    prefix: &str,
+) -> String {
+    let mut idx = 1;
+    let mut id = format!("{}{}", prefix, idx);
+    while    node.root().descendants().any(|n| *n.id() == id)
+          || tree.root().descendants().any(|n| *n.id() == id)
+    {
+        idx += 1;
+        id = format!("{}{}", prefix, idx);
+    }
+
+    id
 }
 
 fn convert_aspect(
@@ -187,8 +187,8 @@ fn convert_pattern(
         resolve_number(node, AId::Width, units, state, Length::zero()),
         resolve_number(node, AId::Height, units, state, Length::zero()),
     );
-    let rect = try_opt_warn_or!(
-        rect, None,
+    let rect = try_opt_diag_or!(
+        rect, None, InvalidAttribute, node.id(),
         "Pattern '{}' has an invalid size. Skipped.", node.id()
     );
 
@@ -242,7 +242,8 @@ pub fn convert_units(
 fn find_gradient_with_stops(node: &svgdom::Node) -> Option<svgdom::Node> {
     for link in node.href_iter() {
         if !link.is_gradient() {
-            warn!(
+            diag!(
+                Warning, InvalidReference, node.id(),
                 "Gradient '{}' cannot reference '{}' via 'xlink:href'.",
                 node.id(), link.tag_id().unwrap()
             );
@@ -260,7 +261,8 @@ fn find_gradient_with_stops(node: &svgdom::Node) -> Option<svgdom::Node> {
 fn find_pattern_with_children(node: &svgdom::Node) -> Option<svgdom::Node> {
     for link in node.href_iter() {
         if !link.is_tag_name(EId::Pattern) {
-            warn!(
+            diag!(
+                Warning, InvalidReference, node.id(),
                 "Pattern '{}' cannot reference '{}' via 'xlink:href'.",
                 node.id(), link.tag_id().unwrap()
             );
@@ -282,7 +284,8 @@ fn convert_stops(grad: &svgdom::Node) -> Vec<tree::Stop> {
         let mut prev_offset = Length::zero();
         for stop in grad.children() {
             if !stop.is_tag_name(EId::Stop) {
-                warn!("Invalid gradient child: '{:?}'.", stop.tag_id().unwrap());
+                diag!(Warning, UnsupportedElement, stop.id(),
+                      "Invalid gradient child: '{:?}'.", stop.tag_id().unwrap());
                 continue;
             }
 
@@ -304,10 +307,13 @@ fn convert_stops(grad: &svgdom::Node) -> Vec<tree::Stop> {
                 .get_color(AId::StopColor)
                 .unwrap_or_else(svgdom::Color::black);
 
+            let opacity = stop.convert_opacity(AId::StopOpacity).value()
+                * stop.color_alpha(AId::StopColor);
+
             stops.push(tree::Stop {
                 offset: offset.into(),
                 color,
-                opacity: stop.convert_opacity(AId::StopOpacity),
+                opacity: opacity.into(),
             });
         }
     }
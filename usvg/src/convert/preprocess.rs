@@ -2,10 +2,21 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::{HashMap, HashSet};
+use std::path;
+
 // external
 use svgdom;
 
 // self
+use crate::tree;
+use crate::{
+    Error,
+    LimitKind,
+    Limits,
+    Resource,
+    ResourceKind,
+};
 use super::prelude::*;
 
 
@@ -17,23 +28,28 @@ use super::prelude::*;
 ///
 /// Basically, any error, even a critical one, should be recoverable.
 /// In worst case scenario clear the `doc`.
+///
+/// The only exception is an exceeded resource limit.
 pub fn prepare_doc(
     doc: &mut svgdom::Document,
-) {
+    opt: &Options,
+) -> Result<(), Error> {
+    super::limits::check_doc(doc, &opt.limits)?;
+
     let mut svg = if let Some(svg) = doc.svg_element() {
         svg
     } else {
         // Technically unreachable, because svgdom will return a parser error
         // if input SVG doesn't have an `svg` node.
-        warn!("Invalid SVG structure. The Document will be cleared.");
+        diag!(Error, InvalidStructure, "", "Invalid SVG structure. The Document will be cleared.");
         *doc = svgdom::Document::new();
-        return;
+        return Ok(());
     };
 
     let svg = &mut svg;
 
     resolve_root_style_attributes(doc, svg);
-    resolve_use(doc);
+    resolve_use(doc, opt)?;
     resolve_inherit(doc);
     resolve_current_color(doc);
     fix_recursive_links(doc);
@@ -42,6 +58,8 @@ pub fn prepare_doc(
     prepare_clip_path(doc);
     regroup_elements(doc, svg);
     prepare_text(doc);
+
+    Ok(())
 }
 
 fn fix_recursive_links(
@@ -51,6 +69,7 @@ fn fix_recursive_links(
     fix_func_iri(doc, EId::ClipPath, AId::ClipPath);
     fix_func_iri(doc, EId::Mask, AId::Mask);
     fix_func_iri(doc, EId::Filter, AId::Filter);
+    fix_fe_image(doc);
 }
 
 fn fix_patterns(
@@ -116,6 +135,72 @@ fn fix_func_iri(
     }
 }
 
+/// Removes `feImage` links to elements that are using the same filter.
+///
+/// Otherwise we will get endless loop/recursion and stack overflow.
+fn fix_fe_image(
+    doc: &svgdom::Document,
+) {
+    fn filter_links(node: &svgdom::Node) -> Vec<svgdom::Node> {
+        if let Some(&AValue::FuncLink(ref link)) = node.attributes().get_value(AId::Filter) {
+            return vec![link.clone()];
+        }
+
+        // Filter lists are stored as is by `css::resolve`.
+        let attrs = node.attributes();
+        let list = match attrs.get_str("usvg-filter").and_then(super::filter::parse_list) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+
+        list.iter().filter_map(|item| match *item {
+            super::filter::FilterListItem::Link(id) => {
+                node.root().descendants().find(|n| *n.id() == id)
+            }
+            _ => None,
+        }).collect()
+    }
+
+    fn has_filter_link(node: &svgdom::Node, filter: &svgdom::Node) -> bool {
+        node.descendants().any(|n| filter_links(&n).contains(filter))
+    }
+
+    for mut fe in doc.root().descendants().filter(|n| n.is_tag_name(EId::FeImage)) {
+        let filter = match fe.parent() {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let av = fe.attributes().get_value(AId::Href).cloned();
+        if let Some(AValue::Link(link)) = av {
+            let mut is_recursive = link == filter || has_filter_link(&link, &filter);
+
+            // Check that linked node children doesn't reference this filter
+            // via other `feImage` elements.
+            if !is_recursive {
+                for node in link.descendants() {
+                    for filter2 in filter_links(&node) {
+                        for fe2 in filter2.children().filter(|n| n.is_tag_name(EId::FeImage)) {
+                            let av3 = fe2.attributes().get_value(AId::Href).cloned();
+                            if let Some(AValue::Link(link2)) = av3 {
+                                if has_filter_link(&link2, &filter) {
+                                    is_recursive = true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            if is_recursive {
+                diag!(Warning, RecursiveReference, fe.id(),
+                      "Recursive 'feImage' detected. The 'xlink:href' attribute will be removed.");
+                fe.remove_attribute(AId::Href);
+            }
+        }
+    }
+}
+
 /// `clipPath` can have only shapes and `text` children and not groups.
 /// So instead of creating a separate svgdom::Node to usvg::Node converter
 /// just for `clipPath` we will remove invalid children beforehand.
@@ -188,6 +273,8 @@ fn regroup_elements(
         || node.has_attribute(AId::Filter)
     }
 
+    // Attributes that are not part of SVG 1.1 are matched by name.
+    let named_attrs = ["mix-blend-mode", "isolation", "usvg-clip-path", "usvg-filter"];
     let g_attrs = [AId::ClipPath, AId::Mask, AId::Filter, AId::Opacity];
 
     let mut ids = Vec::new();
@@ -213,7 +300,8 @@ fn regroup_elements(
             f64_bound(0.0, opacity, 1.0)
         };
 
-        if opacity.fuzzy_eq(&1.0) && !has_links(&node) {
+        let has_named = named_attrs.iter().any(|name| node.has_attribute(*name));
+        if opacity.fuzzy_eq(&1.0) && !has_links(&node) && !has_named {
             continue;
         }
 
@@ -233,9 +321,19 @@ fn regroup_elements(
                 }
             }
 
+            for name in &named_attrs {
+                if let Some(attr) = attrs.get(*name) {
+                    g_node.set_attribute(attr.clone());
+                }
+            }
+
             if let Some(ts) = attrs.get(AId::Transform) {
                 g_node.set_attribute(ts.clone());
                 ids.push(AId::Transform);
+
+                if let Some(attr) = attrs.get("transform-origin") {
+                    g_node.set_attribute(attr.clone());
+                }
             }
         }
 
@@ -243,6 +341,14 @@ fn regroup_elements(
             node.remove_attribute(*id);
         }
 
+        if ids.contains(&AId::Transform) {
+            node.remove_attribute("transform-origin");
+        }
+
+        for name in &named_attrs {
+            node.remove_attribute(*name);
+        }
+
         node.insert_before(g_node.clone());
         node.detach();
         g_node.append(node.clone());
@@ -255,15 +361,15 @@ fn regroup_elements(
 fn resolve_current_color(
     doc: &svgdom::Document,
 ) {
-    fn resolve_color(node: &svgdom::Node, aid: AId) -> Option<svgdom::Color> {
-        if let Some(n) = node.ancestors().find(|n| n.has_attribute(AId::Color)) {
-            n.attributes().get_color(AId::Color)
+    fn resolve_color(node: &svgdom::Node, aid: AId) -> Option<(svgdom::Color, f64)> {
+        if node.ancestors().any(|n| n.has_attribute(AId::Color)) {
+            node.find_current_color()
         } else {
             match aid {
                   AId::Fill
                 | AId::FloodColor
-                | AId::StopColor => Some(svgdom::Color::black()),
-                AId::LightingColor => Some(svgdom::Color::white()),
+                | AId::StopColor => Some((svgdom::Color::black(), 1.0)),
+                AId::LightingColor => Some((svgdom::Color::white(), 1.0)),
                 _ => None,
             }
         }
@@ -293,7 +399,10 @@ fn resolve_current_color(
 
         for id in &ids {
             match resolve_color(&node, *id) {
-                Some(v) => {
+                Some((v, alpha)) => {
+                    // The alpha is stored separately, so it has to be copied too.
+                    node.set_color_alpha(*id, alpha);
+
                     let av = node.attributes().get_value(*id).cloned().unwrap();
                     match av {
                         AValue::CurrentColor => {
@@ -307,7 +416,8 @@ fn resolve_current_color(
                     }
                 }
                 None => {
-                    warn!("Failed to resolve currentColor for '{}'. Removing it.", id);
+                    diag!(Warning, InvalidAttribute, node.id(),
+                          "Failed to resolve currentColor for '{}'. Removing it.", id);
                     node.remove_attribute(*id);
                 }
             }
@@ -346,6 +456,7 @@ fn _resolve_inherit(
             let attrs = n.attributes();
             if let Some(attr) = attrs.get(aid) {
                 node.try_set_attribute(attr);
+                node.set_color_alpha(aid, n.color_alpha(aid));
                 return;
             }
         }
@@ -354,15 +465,18 @@ fn _resolve_inherit(
             let attrs = parent.attributes();
             if let Some(attr) = attrs.get(aid) {
                 node.try_set_attribute(attr);
+                node.set_color_alpha(aid, parent.color_alpha(aid));
                 return;
             }
         }
     }
 
+    node.set_color_alpha(aid, 1.0);
     match svgdom::Attribute::new_default(aid) {
         Some(a) => node.set_attribute((aid, a.value)),
         None => {
-            warn!("Failed to resolve attribute: {}. Removing it.",
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "Failed to resolve attribute: {}. Removing it.",
                   node.attributes().get(aid).unwrap());
             node.remove_attribute(aid);
         }
@@ -383,7 +497,9 @@ fn resolve_root_style_attributes(
         || svg.has_attribute(AId::Filter)
         || svg.has_attribute(AId::Mask)
         || svg.has_attribute(AId::Opacity)
-        || svg.has_attribute(AId::Transform);
+        || svg.has_attribute(AId::Transform)
+        || svg.has_attribute("usvg-clip-path")
+        || svg.has_attribute("usvg-filter");
 
     if !has_any {
         return;
@@ -403,6 +519,14 @@ fn resolve_root_style_attributes(
     svg.move_attribute_to(AId::Mask, &mut g);
     svg.move_attribute_to(AId::Opacity, &mut g);
     svg.move_attribute_to(AId::Transform, &mut g);
+
+    for name in &["transform-origin", "usvg-clip-path", "usvg-filter"] {
+        let attr = svg.attributes().get(*name).cloned();
+        if let Some(attr) = attr {
+            g.set_attribute(attr);
+            svg.remove_attribute(*name);
+        }
+    }
 }
 
 fn resolve_tref(
@@ -435,8 +559,13 @@ fn resolve_tref(
 
 fn resolve_use(
     doc: &mut svgdom::Document,
-) {
+    opt: &Options,
+) -> Result<(), Error> {
+    resolve_external_use(doc, opt)?;
+
+    let limits = &opt.limits;
     let mut rm_nodes = Vec::new();
+    let mut expanded = 0;
 
     // 'use' elements can be linked in any order,
     // so we have to process the tree until all 'use' are solved.
@@ -445,7 +574,7 @@ fn resolve_use(
         rm_nodes.clear();
 
         let root = doc.root().clone();
-        is_any_resolved = _resolve_use(doc, root, &mut rm_nodes);
+        is_any_resolved = _resolve_use(doc, root, &mut rm_nodes, limits, &mut expanded)?;
 
         // Remove unresolved 'use' elements, since there is not need
         // to keep them around and they will be skipped anyway.
@@ -455,13 +584,344 @@ fn resolve_use(
     }
 
     remove_invalid_use(doc);
+
+    Ok(())
+}
+
+/// An external SVG document referenced by `use` elements.
+struct ExternalDoc {
+    doc: svgdom::Document,
+    /// A document path relative to the current document.
+    path: path::PathBuf,
+    /// A prefix for the imported elements IDs.
+    prefix: String,
+    /// Already imported elements. External node -> local node.
+    imported: Vec<(svgdom::Node, svgdom::Node)>,
+}
+
+/// External SVG documents import state.
+struct ExternalDocs {
+    docs: Vec<ExternalDoc>,
+    /// Document path -> `docs` index. `None` if the document cannot be loaded.
+    paths: HashMap<path::PathBuf, Option<usize>>,
+    /// All IDs in the current document.
+    ids: HashSet<String>,
+    /// External elements that are being imported via external `use` references.
+    /// Used to detect recursive references between documents.
+    stack: Vec<(usize, svgdom::Node)>,
+    defs: Option<svgdom::Node>,
+}
+
+/// Resolves `use` elements that reference elements in external SVG files, like `icons.svg#star`.
+///
+/// A referenced element and all the elements it depends on (gradients, clip paths, filters, etc.)
+/// are copied into the current document `defs` under new, unique IDs.
+/// After that, `use` elements are resolved as usual.
+///
+/// External documents can reference other external documents.
+/// Such references are resolved relative to the referencing document.
+///
+/// Each external document is loaded and imported only once.
+fn resolve_external_use(
+    doc: &mut svgdom::Document,
+    opt: &Options,
+) -> Result<(), Error> {
+    let mut use_nodes = Vec::new();
+    for node in doc.root().descendants().filter(|n| n.is_tag_name(EId::Use)) {
+        if let Some(&AValue::String(ref href)) = node.attributes().get_value(AId::Href) {
+            use_nodes.push((node.clone(), href.clone()));
+        }
+    }
+
+    if use_nodes.is_empty() {
+        return Ok(());
+    }
+
+    let ids = doc.root().descendants()
+        .map(|n| n.id().clone())
+        .filter(|id| !id.is_empty())
+        .collect();
+
+    let mut ext_docs = ExternalDocs {
+        docs: Vec::new(),
+        paths: HashMap::new(),
+        ids,
+        stack: Vec::new(),
+        defs: None,
+    };
+
+    for (mut node, href) in use_nodes {
+        let base = path::Path::new("");
+        if let Some(link) = import_external_link(doc, &node, base, &href, &mut ext_docs, opt)? {
+            node.set_attribute((AId::Href, AValue::Link(link)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Imports an element referenced by an external `use` element.
+///
+/// `base` is a directory of the document that contains the `use` element.
+fn import_external_link(
+    doc: &mut svgdom::Document,
+    use_node: &svgdom::Node,
+    base: &path::Path,
+    href: &str,
+    ext_docs: &mut ExternalDocs,
+    opt: &Options,
+) -> Result<Option<svgdom::Node>, Error> {
+    // References without a fragment, aka to the whole document, are not supported.
+    let (file, frag) = match href.find('#') {
+        Some(idx) => (&href[..idx], &href[(idx + 1)..]),
+        None => return Ok(None),
+    };
+
+    if file.is_empty() || frag.is_empty() {
+        return Ok(None);
+    }
+
+    let file_path = normalize_path(&base.join(file));
+    if !ext_docs.paths.contains_key(&file_path) {
+        let idx = load_external_doc(&use_node.id(), &file_path, opt)?.map(|ext_doc| {
+            let prefix = file_path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("external")
+                .to_string();
+
+            ext_docs.docs.push(ExternalDoc {
+                doc: ext_doc,
+                path: file_path.clone(),
+                prefix,
+                imported: Vec::new(),
+            });
+
+            ext_docs.docs.len() - 1
+        });
+
+        ext_docs.paths.insert(file_path.clone(), idx);
+    }
+
+    let idx = match ext_docs.paths.get(&file_path) {
+        Some(&Some(idx)) => idx,
+        _ => return Ok(None),
+    };
+
+    let link = match ext_docs.docs[idx].doc.root().descendants().find(|n| *n.id() == frag) {
+        Some(link) => link,
+        None => {
+            diag!(Warning, InvalidReference, use_node.id(),
+                  "'{}' doesn't have an element with the '{}' ID.", file, frag);
+            return Ok(None);
+        }
+    };
+
+    // Check that the referenced element doesn't contain
+    // any of the elements that are being imported right now.
+    //
+    // Example:
+    // icons.svg:  <g id="g1"><use xlink:href="shapes.svg#g2"/></g>
+    // shapes.svg: <g id="g2"><use xlink:href="icons.svg#g1"/></g>
+    let is_recursive = ext_docs.stack.iter()
+        .any(|&(i, ref n)| i == idx && n.ancestors().any(|a| a == link));
+    if is_recursive {
+        diag!(Warning, RecursiveReference, use_node.id(),
+              "Recursive external 'use' detected. '{}' will be deleted.", href);
+        return Ok(None);
+    }
+
+    ext_docs.stack.push((idx, link.clone()));
+    let new_link = import_external_node(doc, &link, idx, ext_docs, opt);
+    ext_docs.stack.pop();
+
+    new_link.map(Some)
+}
+
+/// Removes `.` and `..` components, so the same document will always have the same path.
+///
+/// Leading `..` components are preserved.
+fn normalize_path(path: &path::Path) -> path::PathBuf {
+    use std::path::Component;
+
+    let mut new_path = path::PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                match new_path.components().next_back() {
+                    Some(Component::Normal(_)) => { new_path.pop(); }
+                    Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                    _ => new_path.push(c),
+                }
+            }
+            _ => new_path.push(c),
+        }
+    }
+
+    new_path
+}
+
+fn load_external_doc(
+    element_id: &str,
+    file_path: &path::Path,
+    opt: &Options,
+) -> Result<Option<svgdom::Document>, Error> {
+    let href = file_path.to_string_lossy();
+
+    let mut res = None;
+    if let Some(ref loader) = opt.resource_loader {
+        match loader.load(&href, ResourceKind::Document) {
+            Resource::Data(data) => res = Some(tree::parse_svg_data(&data, opt)),
+            Resource::Default => {}
+            Resource::Denied => {
+                diag!(Warning, ResourceDenied, element_id, "Access to '{}' was denied.", href);
+                return Ok(None);
+            }
+        }
+    }
+
+    let res = match res {
+        Some(res) => res,
+        None => {
+            let path = match opt.path {
+                Some(ref path) => path.parent().unwrap_or(path::Path::new("")).join(file_path),
+                None => file_path.to_path_buf(),
+            };
+
+            tree::load_svg_file(&path).and_then(|text| tree::parse_svg_str(&text, opt))
+        }
+    };
+
+    match res {
+        Ok(ext_doc) => {
+            super::limits::check_doc(&ext_doc, &opt.limits)?;
+            Ok(Some(ext_doc))
+        }
+        Err(e) => {
+            diag!(Warning, InvalidReference, element_id, "Failed to load '{}': {}.", href, e);
+            Ok(None)
+        }
+    }
+}
+
+fn get_or_create_defs(
+    doc: &mut svgdom::Document,
+) -> svgdom::Node {
+    let mut svg = doc.svg_element().unwrap();
+    if let Some(defs) = svg.children().find(|n| n.is_tag_name(EId::Defs)) {
+        return defs;
+    }
+
+    let defs = doc.create_element(EId::Defs);
+    svg.prepend(defs.clone());
+    defs
+}
+
+/// Copies an external node into `defs` with all the elements it references.
+fn import_external_node(
+    doc: &mut svgdom::Document,
+    node: &svgdom::Node,
+    idx: usize,
+    ext_docs: &mut ExternalDocs,
+    opt: &Options,
+) -> Result<svgdom::Node, Error> {
+    {
+        let imported = &ext_docs.docs[idx].imported;
+        if let Some(&(_, ref local)) = imported.iter().find(|&&(ref n, _)| n == node) {
+            return Ok(local.clone());
+        }
+    }
+
+    let new_node = doc.copy_node_deep(node.clone());
+    let mut defs = match ext_docs.defs {
+        Some(ref defs) => defs.clone(),
+        None => get_or_create_defs(doc),
+    };
+    ext_docs.defs = Some(defs.clone());
+    defs.append(new_node.clone());
+
+    // Register all the copied nodes before resolving links,
+    // so links inside the subtree and recursive links will point to the copies.
+    for (ext_node, mut local_node) in node.descendants().zip(new_node.descendants()) {
+        if !local_node.id().is_empty() {
+            let id = gen_unique_id(&ext_docs.docs[idx].prefix, &local_node.id(), &mut ext_docs.ids);
+            local_node.set_id(id);
+        }
+
+        ext_docs.docs[idx].imported.push((ext_node, local_node));
+    }
+
+    for mut local_node in new_node.descendants() {
+        // A `use` element that references yet another external document.
+        if local_node.is_tag_name(EId::Use) {
+            let href = match local_node.attributes().get_value(AId::Href) {
+                Some(&AValue::String(ref href)) => Some(href.clone()),
+                _ => None,
+            };
+
+            if let Some(href) = href {
+                let base = ext_docs.docs[idx].path.parent()
+                    .unwrap_or(path::Path::new("")).to_path_buf();
+                // Unresolved elements will be removed by `remove_invalid_use`.
+                if let Some(link) = import_external_link(doc, &local_node, &base, &href,
+                                                         ext_docs, opt)? {
+                    local_node.set_attribute((AId::Href, AValue::Link(link)));
+                }
+            }
+        }
+
+        let links: Vec<_> = local_node.attributes().iter().svg()
+            .filter(|&(_, attr)| match attr.value {
+                AValue::Link(_) | AValue::FuncLink(_) | AValue::Paint(..) => true,
+                _ => false,
+            })
+            .map(|(aid, attr)| (aid, attr.value.clone()))
+            .collect();
+
+        for (aid, av) in links {
+            let av = match av {
+                AValue::Link(link) => {
+                    AValue::Link(import_external_node(doc, &link, idx, ext_docs, opt)?)
+                }
+                AValue::FuncLink(link) => {
+                    AValue::FuncLink(import_external_node(doc, &link, idx, ext_docs, opt)?)
+                }
+                AValue::Paint(link, fallback) => {
+                    AValue::Paint(import_external_node(doc, &link, idx, ext_docs, opt)?, fallback)
+                }
+                _ => continue,
+            };
+
+            local_node.set_attribute((aid, av));
+        }
+    }
+
+    Ok(new_node)
+}
+
+fn gen_unique_id(
+    prefix: &str,
+    id: &str,
+    ids: &mut HashSet<String>,
+) -> String {
+    let mut new_id = format!("{}-{}", prefix, id);
+    let mut idx = 1;
+    while ids.contains(&new_id) {
+        idx += 1;
+        new_id = format!("{}-{}-{}", prefix, id, idx);
+    }
+
+    ids.insert(new_id.clone());
+    new_id
 }
 
 fn _resolve_use(
     doc: &mut svgdom::Document,
     parent: svgdom::Node,
     rm_nodes: &mut Vec<svgdom::Node>,
-) -> bool {
+    limits: &Limits,
+    expanded: &mut usize,
+) -> Result<bool, Error> {
     let mut is_any_resolved = false;
 
     for mut node in parent.children() {
@@ -476,7 +936,8 @@ fn _resolve_use(
                 // TODO: this
                 // We don't support 'use' elements linked to 'svg' element.
                 if link.is_tag_name(EId::Svg) {
-                    warn!("'use' element linked to an 'svg' element is not supported. Skipped.");
+                    diag!(Warning, UnsupportedElement, node.id(),
+                          "'use' element linked to an 'svg' element is not supported. Skipped.");
                     rm_nodes.push(node.clone());
                     continue;
                 }
@@ -510,22 +971,30 @@ fn _resolve_use(
                 }
 
                 if is_recursive {
-                    warn!("Recursive 'use' detected. '{}' will be deleted.", node.id());
+                    diag!(Warning, RecursiveReference, node.id(),
+                          "Recursive 'use' detected. '{}' will be deleted.", node.id());
                     rm_nodes.push(node.clone());
                     continue;
                 }
 
+                if let Some(max) = limits.max_use_expansion {
+                    *expanded += link.descendants().count();
+                    if *expanded > max {
+                        return Err(Error::LimitExceeded(LimitKind::UseExpansion));
+                    }
+                }
+
                 __resolve_use(doc, &mut node, &mut link);
                 is_any_resolved = true;
             }
         }
 
-        if _resolve_use(doc, node, rm_nodes) {
+        if _resolve_use(doc, node, rm_nodes, limits, expanded)? {
             is_any_resolved = true;
         }
     }
 
-    is_any_resolved
+    Ok(is_any_resolved)
 }
 
 fn __resolve_use(
@@ -560,6 +1029,8 @@ fn remove_invalid_use(
             if n.is_tag_name(EId::Use) {
                 if !n.has_attribute(AId::Href) {
                     // Remove 'use' elements without an 'xlink:href' attribute.
+                    diag!(Warning, InvalidReference, n.id(),
+                          "The 'use' element lacks the 'xlink:href' attribute. Skipped.");
                     return true;
                 } else {
                     // Remove 'use' elements with an invalid 'xlink:href' attribute.
@@ -567,9 +1038,11 @@ fn remove_invalid_use(
                     if let Some(&AValue::Link(_)) = attrs.get_value(AId::Href) {
                         // Nothing.
                     } else {
-                        // NOTE: actually, an attribute with 'String' type is valid
+                        // NOTE: an attribute with 'String' type is valid
                         // if it contain a path to an external file, like '../img.svg#rect1',
-                        // but we don't support external SVG, so we treat them as invalid.
+                        // but at this point all the loadable ones are already resolved.
+                        diag!(Warning, InvalidReference, n.id(),
+                              "The 'use' element has an invalid 'xlink:href' attribute. Skipped.");
                         return true;
                     }
                 }
@@ -54,11 +54,13 @@ pub fn convert_rect(
     let width  = node.convert_user_length(AId::Width, state, Length::zero());
     let height = node.convert_user_length(AId::Height, state, Length::zero());
     if !(width > 0.0) {
-        warn!("Rect '{}' has an invalid 'width' value. Skipped.", node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "Rect '{}' has an invalid 'width' value. Skipped.", node.id());
         return None;
     }
     if !(height > 0.0) {
-        warn!("Rect '{}' has an invalid 'height' value. Skipped.", node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "Rect '{}' has an invalid 'height' value. Skipped.", node.id());
         return None;
     }
 
@@ -101,10 +103,22 @@ pub fn convert_rect(
     if ry > height / 2.0 { ry = height / 2.0; }
 
 
+    Some(rounded_rect_to_path(Rect::new(x, y, width, height)?, rx, ry))
+}
+
+/// Converts a rect with rounded corners into a path.
+///
+/// `rx` and `ry` must be already clamped.
+pub fn rounded_rect_to_path(
+    rect: Rect,
+    rx: f64,
+    ry: f64,
+) -> Vec<tree::PathSegment> {
     // Conversion according to https://www.w3.org/TR/SVG11/shapes.html#RectElement
-    let path = if rx.fuzzy_eq(&0.0) {
-        utils::rect_to_path(Rect::new(x, y, width, height)?)
+    if rx.fuzzy_eq(&0.0) {
+        utils::rect_to_path(rect)
     } else {
+        let (x, y, width, height) = (rect.x(), rect.y(), rect.width(), rect.height());
         let p = svgdom::PathBuilder::with_capacity(9)
             .move_to(x + rx, y)
             .line_to(x + width - rx, y)
@@ -118,9 +132,48 @@ pub fn convert_rect(
             .finalize();
 
         path::convert(p)
-    };
+    }
+}
 
-    Some(path)
+/// Converts a rect with per-corner radii into a path.
+///
+/// Radii are in the top-left, top-right, bottom-right, bottom-left order
+/// and must be already clamped.
+pub fn rounded_rect_corners_to_path(
+    rect: Rect,
+    radii: [(f64, f64); 4],
+) -> Vec<tree::PathSegment> {
+    fn corner(
+        p: svgdom::PathBuilder,
+        (rx, ry): (f64, f64),
+        x: f64,
+        y: f64,
+    ) -> svgdom::PathBuilder {
+        // A corner with a zero radius is a regular one.
+        if rx.fuzzy_eq(&0.0) || ry.fuzzy_eq(&0.0) {
+            p
+        } else {
+            p.arc_to(rx, ry, 0.0, false, true, x, y)
+        }
+    }
+
+    let (x, y, width, height) = (rect.x(), rect.y(), rect.width(), rect.height());
+    let [tl, tr, br, bl] = radii;
+
+    let p = svgdom::PathBuilder::with_capacity(9)
+        .move_to(x + tl.0, y)
+        .line_to(x + width - tr.0, y);
+    let p = corner(p, tr, x + width, y + tr.1)
+        .line_to(x + width, y + height - br.1);
+    let p = corner(p, br, x + width - br.0, y + height)
+        .line_to(x + bl.0, y + height);
+    let p = corner(p, bl, x, y + height - bl.1)
+        .line_to(x, y + tl.1);
+    let p = corner(p, tl, x + tl.0, y)
+        .close_path()
+        .finalize();
+
+    path::convert(p)
 }
 
 pub fn convert_line(
@@ -168,14 +221,16 @@ fn points_to_path(
             points
         }
         _ => {
-            warn!("{} '{}' has an invalid 'points' value. Skipped.", eid, node.id());
+            diag!(Warning, InvalidShape, node.id(),
+                  "{} '{}' has an invalid 'points' value. Skipped.", eid, node.id());
             return None;
         }
     };
 
     // 'polyline' and 'polygon' elements must contain at least 2 points.
     if points.len() < 2 {
-        warn!("{} '{}' has less than 2 points. Skipped.", eid, node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "{} '{}' has less than 2 points. Skipped.", eid, node.id());
         return None;
     }
 
@@ -201,7 +256,8 @@ pub fn convert_circle(
     let r  = node.convert_user_length(AId::R,  state, Length::zero());
 
     if !(r > 0.0) {
-        warn!("Circle '{}' has an invalid 'r' value. Skipped.", node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "Circle '{}' has an invalid 'r' value. Skipped.", node.id());
         return None;
     }
 
@@ -218,19 +274,21 @@ pub fn convert_ellipse(
     let ry = node.convert_user_length(AId::Ry, state, Length::zero());
 
     if !(rx > 0.0) {
-        warn!("Ellipse '{}' has an invalid 'rx' value. Skipped.", node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "Ellipse '{}' has an invalid 'rx' value. Skipped.", node.id());
         return None;
     }
 
     if !(ry > 0.0) {
-        warn!("Ellipse '{}' has an invalid 'ry' value. Skipped.", node.id());
+        diag!(Warning, InvalidShape, node.id(),
+              "Ellipse '{}' has an invalid 'ry' value. Skipped.", node.id());
         return None;
     }
 
     Some(ellipse_to_path(cx, cy, rx, ry))
 }
 
-fn ellipse_to_path(
+pub fn ellipse_to_path(
     cx: f64,
     cy: f64,
     rx: f64,
@@ -95,6 +95,9 @@ pub fn resolve_stroke(
     let linejoin = node.find_enum(AId::StrokeLinejoin);
     let dasharray = conv_dasharray(node, state);
 
+    // `vector-effect` is not inherited.
+    let non_scaling = node.attributes().get_str("vector-effect") == Some("non-scaling-stroke");
+
     let stroke = tree::Stroke {
         paint,
         dasharray,
@@ -104,6 +107,7 @@ pub fn resolve_stroke(
         width,
         linecap,
         linejoin,
+        non_scaling,
     };
 
     Some(stroke)
@@ -120,6 +124,7 @@ fn convert_paint(
     let av = node.attributes().get_value(aid).cloned()?;
     match av {
         AValue::Color(c) => {
+            *opacity = node.color_alpha(aid).into();
             Some(tree::Paint::Color(c))
         }
         AValue::Paint(ref link, fallback) => {
@@ -131,7 +136,7 @@ fn convert_paint(
                         //
                         // See SVG spec 7.11 for details.
                         if !has_bbox && units == tree::Units::ObjectBoundingBox {
-                            from_fallback(fallback)
+                            from_fallback(node, aid, fallback, opacity)
                         } else {
                             Some(tree::Paint::Link(id))
                         }
@@ -141,36 +146,47 @@ fn convert_paint(
                         Some(tree::Paint::Color(color))
                     }
                     None => {
-                        from_fallback(fallback)
+                        from_fallback(node, aid, fallback, opacity)
                     }
                 }
             } else {
-                warn!("'{}' cannot be used to {} a shape.", link.tag_name(), aid);
+                diag!(Warning, InvalidPaint, node.id(),
+                      "'{}' cannot be used to {} a shape.", link.tag_name(), aid);
                 None
             }
         }
+        AValue::String(ref s) if s == "context-fill" => {
+            state.context_fill.clone()
+        }
+        AValue::String(ref s) if s == "context-stroke" => {
+            state.context_stroke.clone()
+        }
         AValue::None => {
             None
         }
         _ => {
-            warn!("An invalid {} value: {}. Skipped.", aid, av);
+            diag!(Warning, InvalidPaint, node.id(), "An invalid {} value: {}. Skipped.", aid, av);
             None
         }
     }
 }
 
 fn from_fallback(
+    node: &svgdom::Node,
+    aid: AId,
     fallback: Option<svgdom::PaintFallback>,
+    opacity: &mut tree::Opacity,
 ) -> Option<tree::Paint> {
     match fallback? {
         svgdom::PaintFallback::None => {
             None
         }
         svgdom::PaintFallback::CurrentColor => {
-            warn!("'currentColor' must be already resolved");
+            diag!(Warning, InvalidPaint, "", "'currentColor' must be already resolved");
             None
         }
         svgdom::PaintFallback::Color(c) => {
+            *opacity = node.color_alpha(aid).into();
             Some(tree::Paint::Color(c))
         }
     }
@@ -216,6 +232,99 @@ fn conv_dasharray(
     Some(list)
 }
 
+/// A `paint-order` component.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PaintOrderKind {
+    Fill,
+    Stroke,
+    Markers,
+}
+
+/// Resolves the `paint-order` property.
+///
+/// Components that were not set will be appended in the default order.
+pub fn resolve_paint_order(
+    node: &svgdom::Node,
+) -> [PaintOrderKind; 3] {
+    const DEFAULT: [PaintOrderKind; 3] = [
+        PaintOrderKind::Fill,
+        PaintOrderKind::Stroke,
+        PaintOrderKind::Markers,
+    ];
+
+    // `paint-order` is inherited.
+    let value = node.ancestors().find_map(|n| {
+        n.attributes().get_str("paint-order").map(|s| s.trim().to_string())
+    });
+
+    let value = match value {
+        Some(v) => v,
+        None => return DEFAULT,
+    };
+
+    if value == "normal" {
+        return DEFAULT;
+    }
+
+    let mut list = match parse_paint_order(&value) {
+        Some(list) => list,
+        None => {
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "Invalid 'paint-order' value: '{}'. Fallback to 'normal'.", value);
+            return DEFAULT;
+        }
+    };
+
+    for kind in &DEFAULT {
+        if !list.contains(kind) {
+            list.push(*kind);
+        }
+    }
+
+    [list[0], list[1], list[2]]
+}
+
+fn parse_paint_order(value: &str) -> Option<Vec<PaintOrderKind>> {
+    let mut list = Vec::with_capacity(3);
+    for name in value.split_whitespace() {
+        let kind = match name {
+            "fill" => PaintOrderKind::Fill,
+            "stroke" => PaintOrderKind::Stroke,
+            "markers" => PaintOrderKind::Markers,
+            _ => return None,
+        };
+
+        // Each keyword can be set only once.
+        if list.contains(&kind) {
+            return None;
+        }
+
+        list.push(kind);
+    }
+
+    Some(list)
+}
+
+/// Resolves the fill and stroke order, ignoring markers.
+pub fn resolve_fill_and_stroke_order(
+    node: &svgdom::Node,
+) -> tree::PaintOrder {
+    fill_and_stroke_order(&resolve_paint_order(node))
+}
+
+/// Returns the fill and stroke order from the `paint-order` components.
+pub fn fill_and_stroke_order(
+    order: &[PaintOrderKind],
+) -> tree::PaintOrder {
+    let fill_idx = order.iter().position(|k| *k == PaintOrderKind::Fill);
+    let stroke_idx = order.iter().position(|k| *k == PaintOrderKind::Stroke);
+    if stroke_idx < fill_idx {
+        tree::PaintOrder::StrokeAndFill
+    } else {
+        tree::PaintOrder::FillAndStroke
+    }
+}
+
 pub fn is_visible_element(
     node: &svgdom::Node,
     opt: &Options,
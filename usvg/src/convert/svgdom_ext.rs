@@ -24,12 +24,16 @@ pub trait SvgNodeExt {
     fn convert_user_length(&self, aid: AId, state: &State, def: Length) -> f64;
     fn try_convert_user_length(&self, aid: AId, state: &State) -> Option<f64>;
     fn convert_opacity(&self, aid: AId) -> tree::Opacity;
+    fn color_alpha(&self, aid: AId) -> f64;
+    fn set_color_alpha(&mut self, aid: AId, alpha: f64);
+    fn find_current_color(&self) -> Option<(svgdom::Color, f64)>;
     fn href_iter(&self) -> HrefIter;
     fn move_attribute_to(&mut self, aid: AId, to: &mut Self);
     fn copy_attribute_to(&self, aid: AId, to: &mut Self);
     fn try_set_attribute(&mut self, attr: &svgdom::Attribute);
     fn get_viewbox(&self) -> Option<Rect>;
     fn is_valid_transform(&self, aid: AId) -> bool;
+    fn resolve_transform(&self, state: &State) -> tree::Transform;
 }
 
 impl SvgNodeExt for svgdom::Node {
@@ -132,6 +136,32 @@ impl SvgNodeExt for svgdom::Node {
         f64_bound(0.0, opacity, 1.0).into()
     }
 
+    fn color_alpha(&self, aid: AId) -> f64 {
+        // Set by `css::resolve`, since `svgdom` doesn't support colors with alpha.
+        let name = format!("usvg-{}-alpha", aid);
+        let alpha = match self.attributes().get_str(name.as_str()) {
+            Some(s) => s.parse().unwrap_or(1.0),
+            None => 1.0,
+        };
+
+        f64_bound(0.0, alpha, 1.0)
+    }
+
+    fn set_color_alpha(&mut self, aid: AId, alpha: f64) {
+        let name = format!("usvg-{}-alpha", aid);
+        if alpha < 1.0 {
+            self.set_attribute((name.as_str(), alpha.to_string()));
+        } else {
+            self.remove_attribute(name.as_str());
+        }
+    }
+
+    fn find_current_color(&self) -> Option<(svgdom::Color, f64)> {
+        let n = self.ancestors().find(|n| n.has_attribute(AId::Color))?;
+        let color = n.attributes().get_color(AId::Color)?;
+        Some((color, n.color_alpha(AId::Color)))
+    }
+
     fn href_iter(&self) -> HrefIter {
         HrefIter {
             origin: self.clone(),
@@ -158,7 +188,8 @@ impl SvgNodeExt for svgdom::Node {
             Ok(_) => {}
             Err(_) => {
                 let id = if self.has_id() { format!("#{}", self.id()) } else { String::new() };
-                warn!("Failed to set {} on {}{}.", attr, self.tag_name(), id);
+                diag!(Warning, InvalidAttribute, self.id(),
+                      "Failed to set {} on {}{}.", attr, self.tag_name(), id);
             }
         }
     }
@@ -178,6 +209,49 @@ impl SvgNodeExt for svgdom::Node {
 
         true
     }
+
+    fn resolve_transform(&self, state: &State) -> tree::Transform {
+        let ts = self.attributes().get_transform(AId::Transform);
+        if ts.is_default() {
+            return ts;
+        }
+
+        match convert_transform_origin(self, state) {
+            Some((x, y)) => {
+                let mut new_ts = tree::Transform::new_translate(x, y);
+                new_ts.append(&ts);
+                new_ts.translate(-x, -y);
+                new_ts
+            }
+            None => ts,
+        }
+    }
+}
+
+/// Converts the `transform-origin` property.
+///
+/// Percentages are relative to the current viewport. The Z offset is ignored.
+fn convert_transform_origin(node: &svgdom::Node, state: &State) -> Option<(f64, f64)> {
+    let attrs = node.attributes();
+    let value = attrs.get_str("transform-origin")?;
+
+    let mut list: Vec<&str> = value.split_whitespace().collect();
+    if list.len() == 3 {
+        list.pop();
+    }
+
+    let (x, y) = match units::parse_position(&list) {
+        Some(v) => v,
+        None => {
+            diag!(Warning, InvalidAttribute, node.id(),
+                  "Invalid 'transform-origin' value: '{}'. Skipped.", value);
+            return None;
+        }
+    };
+
+    let x = units::convert_length(x, node, AId::X, tree::Units::UserSpaceOnUse, state);
+    let y = units::convert_length(y, node, AId::Y, tree::Units::UserSpaceOnUse, state);
+    Some((x, y))
 }
 
 
@@ -204,7 +278,8 @@ impl Iterator for HrefIter {
         let av = self.curr.attributes().get_value(AId::Href).cloned();
         if let Some(AValue::Link(link)) = av {
             if link == self.curr || link == self.origin {
-                warn!("Element '#{}' cannot reference itself via 'xlink:href'.", self.origin.id());
+                diag!(Warning, RecursiveReference, self.origin.id(),
+                      "Element '#{}' cannot reference itself via 'xlink:href'.", self.origin.id());
                 self.is_finished = true;
                 return None;
             }
@@ -232,6 +307,7 @@ macro_rules! impl_from_value {
     )
 }
 
+impl_from_value!(f64, Number);
 impl_from_value!(svgdom::Color, Color);
 impl_from_value!(Length, Length);
 impl_from_value!(svgdom::NumberList, NumberList);
@@ -277,6 +353,14 @@ pub trait GetValue {
         }
     }
 
+    fn get_number(&self, id: AId) -> Option<f64> {
+        self.get_type(id).cloned()
+    }
+
+    fn get_number_or(&self, id: AId, def: f64) -> f64 {
+        self.get_number(id).unwrap_or(def)
+    }
+
     fn get_number_list(&self, id: AId) -> Option<&svgdom::NumberList> {
         self.get_type(id)
     }
@@ -57,6 +57,8 @@ pub fn convert(
     match super::convert_group(&node, state, false, parent, tree) {
         super::GroupKind::Keep(mut g) => {
             super::convert_element(&child, state, &mut g, tree);
+            super::clip_and_mask::convert_basic_shape(&node, &mut g, state, tree);
+            super::filter::convert_list(&node, &mut g, state, tree);
         }
         super::GroupKind::Skip => {
             super::convert_element(&child, state, parent, tree);
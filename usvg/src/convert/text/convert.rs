@@ -5,6 +5,7 @@
 use std::cmp;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 
 // external
 use svgdom;
@@ -20,6 +21,10 @@ mod fk {
 // self
 use crate::tree;
 use crate::utils;
+use crate::{
+    Resource,
+    ResourceKind,
+};
 use crate::convert::prelude::*;
 use crate::convert::{
     style,
@@ -168,6 +173,7 @@ pub struct TextSpan {
     pub end: usize,
     pub fill: Option<tree::Fill>,
     pub stroke: Option<tree::Stroke>,
+    pub paint_order: tree::PaintOrder,
     pub font: Font,
     pub font_size: f64,
     pub decoration: TextDecoration,
@@ -295,6 +301,7 @@ fn collect_text_chunks_impl(
             end: 0,
             fill: style::resolve_fill(parent, true, state, tree),
             stroke: style::resolve_stroke(parent, true, state, tree),
+            paint_order: style::resolve_fill_and_stroke_order(parent),
             font,
             font_size,
             decoration: resolve_decoration(text_node, parent, state, tree),
@@ -469,16 +476,18 @@ fn resolve_font(
                 });
             }
 
-            warn!("No match for '{}' font-family.", families.join(", "));
+            diag!(Warning, FontNotFound, node.id(),
+                  "No match for '{}' font-family.", families.join(", "));
             return None;
         }
     };
 
-    load_font(&handle)
+    load_font(&handle, state.opt)
 }
 
 pub fn load_font(
     handle: &fk::Handle,
+    opt: &Options,
 ) -> Option<Font> {
     let (path, index) = match handle {
         fk::Handle::Path { ref path, font_index } => {
@@ -487,10 +496,24 @@ pub fn load_font(
         _ => return None,
     };
 
-    let font = match handle.load() {
+    let mut resource = Resource::Default;
+    if let Some(ref loader) = opt.resource_loader {
+        resource = loader.load(&path.to_string_lossy(), ResourceKind::Font);
+    }
+
+    let font = match resource {
+        Resource::Data(data) => fk::Font::from_bytes(Arc::new(data), index),
+        Resource::Default => handle.load(),
+        Resource::Denied => {
+            diag!(Warning, ResourceDenied, "", "Access to '{}' was denied.", path.display());
+            return None;
+        }
+    };
+
+    let font = match font {
         Ok(v) => v,
         Err(_) => {
-            warn!("Failed to load '{}'.", path.display());
+            diag!(Warning, FontLoadFailed, "", "Failed to load '{}'.", path.display());
             return None;
         }
     };
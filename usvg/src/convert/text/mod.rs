@@ -111,7 +111,7 @@ fn text_to_paths(
     let pos_list = resolve_positions_list(text_node, state);
     let rotate_list = resolve_rotate_list(text_node);
     let writing_mode = convert_writing_mode(text_node);
-    let mut text_ts = text_node.attributes().get_transform(AId::Transform);
+    let mut text_ts = text_node.resolve_transform(state);
 
     let mut chunks = collect_text_chunks(text_node, &pos_list, state, tree);
     let mut char_offset = 0;
@@ -258,6 +258,7 @@ fn convert_span(
         fill,
         stroke: span.stroke.take(),
         rendering_mode: tree::ShapeRendering::default(),
+        paint_order: span.paint_order,
         segments,
     };
 
@@ -369,6 +370,7 @@ fn convert_decoration(
         fill: decoration.fill.take(),
         stroke: decoration.stroke.take(),
         rendering_mode: tree::ShapeRendering::default(),
+        paint_order: span.paint_order,
         segments,
     }
 }
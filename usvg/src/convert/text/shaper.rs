@@ -184,7 +184,7 @@ pub fn outline_chunk(
         // We assume, that shaping with an any font will produce the same amount of glyphs.
         // Otherwise an error.
         if glyphs.len() != tmp_glyphs.len() {
-            warn!("Text layouting failed.");
+            diag!(Warning, TextLayoutFailed, "", "Text layouting failed.");
             return Vec::new();
         }
 
@@ -261,7 +261,8 @@ fn shape_text(
     for glyph in &glyphs {
         if glyph.is_missing() {
             let c = glyph.byte_idx.char_from(text);
-            warn!("No fonts with a {}/U+{:X} character were found.", c, c as u32);
+            diag!(Warning, MissingGlyph, "",
+                  "No fonts with a {}/U+{:X} character were found.", c, c as u32);
         }
     }
 
@@ -353,7 +354,7 @@ fn outline_cluster(
             }
             Err(_) => {
                 // Technically unreachable.
-                warn!("Glyph {} not found in the font.", glyph.id);
+                diag!(Warning, MissingGlyph, "", "Glyph {} not found in the font.", glyph.id);
                 Vec::new()
             }
         };
@@ -434,7 +435,7 @@ fn find_font_for_char(
             continue;
         }
 
-        if let Some(font) = super::load_font(handle) {
+        if let Some(font) = super::load_font(handle, state.opt) {
             if base_font.handle.properties() != font.handle.properties() {
                 continue;
             }
@@ -443,7 +444,8 @@ fn find_font_for_char(
                 continue;
             }
 
-            warn!("Fallback from {} to {}.", exclude_fonts[0].path.display(), path.display());
+            diag!(Warning, FontFallback, "",
+                  "Fallback from {} to {}.", exclude_fonts[0].path.display(), path.display());
             return Some(font);
         }
     }
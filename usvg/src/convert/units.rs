@@ -70,6 +70,35 @@ pub fn convert_list(
     }
 }
 
+/// Parses a CSS `<position>` from a list of one or two values.
+///
+/// Keywords are converted into percentages.
+pub fn parse_position(list: &[&str]) -> Option<(Length, Length)> {
+    let center = Length::new(50.0, Unit::Percent);
+    let parse = |s: &str, is_x: bool| -> Option<Length> {
+        match s {
+            "left" if is_x => Some(Length::new(0.0, Unit::Percent)),
+            "right" if is_x => Some(Length::new(100.0, Unit::Percent)),
+            "top" if !is_x => Some(Length::new(0.0, Unit::Percent)),
+            "bottom" if !is_x => Some(Length::new(100.0, Unit::Percent)),
+            "center" => Some(center),
+            _ => s.parse().ok(),
+        }
+    };
+
+    match list {
+        [v] if *v == "top" || *v == "bottom" => parse(v, false).map(|y| (center, y)),
+        [v] => parse(v, true).map(|x| (x, center)),
+        [v1, v2] => {
+            // Keywords can be set in any order.
+            let is_swapped = ["top", "bottom"].contains(v1) || ["left", "right"].contains(v2);
+            let (v1, v2) = if is_swapped { (v2, v1) } else { (v1, v2) };
+            parse(v1, true).and_then(|x| parse(v2, false).map(|y| (x, y)))
+        }
+        _ => None,
+    }
+}
+
 fn convert_percent(length: Length, base: f64) -> f64 {
     base * length.num / 100.0
 }
@@ -123,7 +152,7 @@ fn convert_named_font_size(
         "smaller"   => -1,
         "larger"    => 1,
         _ => {
-            warn!("Invalid 'font-size' value: '{}'.", name);
+            diag!(Warning, InvalidAttribute, "", "Invalid 'font-size' value: '{}'.", name);
             0
         }
     };
@@ -21,7 +21,7 @@ pub fn convert(
     debug_assert!(node.has_attribute("usvg-use"));
 
     // We require original transformation to setup 'clipPath'.
-    let mut orig_ts = node.attributes().get_transform(AId::Transform);
+    let mut orig_ts = node.resolve_transform(state);
     let mut new_ts = tree::Transform::default();
 
     {
@@ -62,7 +62,7 @@ pub fn convert_svg(
     tree: &mut tree::Tree,
 ) {
     // We require original transformation to setup 'clipPath'.
-    let mut orig_ts = node.attributes().get_transform(AId::Transform);
+    let mut orig_ts = node.resolve_transform(state);
     let mut new_ts = tree::Transform::default();
 
     {
@@ -111,7 +111,7 @@ fn clip_element(
     //   <elem/>
     // </g>
 
-    let id = gen_clip_path_id(node, tree);
+    let id = super::gen_unique_id(node, tree, "clipPath");
 
     let mut clip_path = tree.append_to_defs(tree::NodeKind::ClipPath(tree::ClipPath {
         id: id.clone(),
@@ -148,6 +148,8 @@ fn convert_children(
             }
 
             super::convert_children(node, state, &mut g, tree);
+            super::clip_and_mask::convert_basic_shape(node, &mut g, state, tree);
+            super::filter::convert_list(node, &mut g, state, tree);
         }
         super::GroupKind::Skip => {
             super::convert_children(node, state, parent, tree);
@@ -191,23 +193,6 @@ fn get_clip_rect(
     Rect::new(x, y, w, h)
 }
 
-/// Creates a free id for `clipPath`.
-pub fn gen_clip_path_id(
-    node: &svgdom::Node,
-    tree: &tree::Tree,
-) -> String {
-    let mut idx = 1;
-    let mut id = format!("clipPath{}", idx);
-    while    node.root().descendants().any(|n| *n.id() == id)
-          || tree.defs().children().any(|n| *n.id() == id)
-    {
-        idx += 1;
-        id = format!("clipPath{}", idx);
-    }
-
-    id
-}
-
 fn viewbox_transform(
     node: &svgdom::Node,
     linked: &svgdom::Node,
@@ -0,0 +1,309 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! CSS Color Module Level 4 colors.
+//!
+//! `svgdom` supports only opaque colors, so `rgba()`, `hsl()`, `hsla()`,
+//! `#rgba`, `#rrggbbaa` and `transparent` are converted into a `#rrggbb` color
+//! and a separate alpha value.
+//!
+//! Named colors are left as is.
+//!
+//! https://www.w3.org/TR/css-color-4/
+
+
+/// Properties that can contain a color.
+pub const PROPERTIES: &[&str] = &[
+    "fill",
+    "stroke",
+    "stop-color",
+    "flood-color",
+    "lighting-color",
+    "color",
+];
+
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// Alpha in a 0..1 range.
+    pub alpha: f64,
+}
+
+impl Color {
+    fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+
+/// Checks that the text may contain colors that should be converted.
+///
+/// False positives are fine, since they will be filtered out by `resolve_paint`.
+pub fn has_colors(text: &str) -> bool {
+    if text.contains("rgb") || text.contains("hsl") || text.contains("transparent") {
+        return true;
+    }
+
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while let Some(idx) = text[pos..].find('#') {
+        let start = pos + idx + 1;
+        let len = bytes[start..].iter().take_while(|c| c.is_ascii_hexdigit()).count();
+        if len == 4 || len == 8 {
+            return true;
+        }
+
+        pos = start + len;
+    }
+
+    false
+}
+
+/// Converts a color or a paint with a color fallback into the one supported by `svgdom`.
+///
+/// Returns a new value and the color alpha.
+/// Returns `None` when the value doesn't contain a color or it's already supported.
+pub fn resolve_paint(value: &str) -> Option<(String, f64)> {
+    let value = value.trim();
+    if value.starts_with("url(") {
+        let idx = value.find(')')?;
+        let (link, fallback) = value.split_at(idx + 1);
+        let color = resolve_color(fallback)?;
+        return Some((format!("{} {}", link, color.to_hex()), color.alpha));
+    }
+
+    let color = resolve_color(value)?;
+    Some((color.to_hex(), color.alpha))
+}
+
+fn resolve_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+
+    // Colors from SVG 1.1 are handled by `svgdom`.
+    let is_supported =
+           (text.starts_with('#') && (text.len() == 4 || text.len() == 7))
+        || (text.starts_with("rgb(") && text.matches(',').count() == 2);
+
+    if is_supported {
+        None
+    } else {
+        parse(text)
+    }
+}
+
+/// Parses a hex, `rgb()`, `rgba()`, `hsl()`, `hsla()` or `transparent` color.
+pub fn parse(text: &str) -> Option<Color> {
+    let text = text.trim().to_ascii_lowercase();
+
+    if text == "transparent" {
+        return Some(Color { red: 0, green: 0, blue: 0, alpha: 0.0 });
+    }
+
+    if text.starts_with('#') {
+        return parse_hex(&text[1..]);
+    }
+
+    let open = text.find('(')?;
+    if !text.ends_with(')') {
+        return None;
+    }
+
+    let name = text[..open].trim();
+    let args = &text[(open + 1)..(text.len() - 1)];
+
+    // Both the legacy comma-separated syntax and the space-separated one are allowed.
+    let (args, alpha) = match args.find('/') {
+        Some(idx) => (&args[..idx], Some(args[(idx + 1)..].trim())),
+        None => (args, None),
+    };
+
+    let mut list: Vec<&str> = if args.contains(',') {
+        args.split(',').map(|s| s.trim()).collect()
+    } else {
+        args.split_whitespace().collect()
+    };
+
+    let alpha = match alpha {
+        Some(alpha) => Some(alpha),
+        None if list.len() == 4 => list.pop(),
+        None => None,
+    };
+
+    if list.len() != 3 {
+        return None;
+    }
+
+    let alpha = match alpha {
+        Some(alpha) => parse_alpha(alpha)?,
+        None => 1.0,
+    };
+
+    let (red, green, blue) = match name {
+        "rgb" | "rgba" => {
+            (parse_channel(list[0])?, parse_channel(list[1])?, parse_channel(list[2])?)
+        }
+        "hsl" | "hsla" => {
+            let hue = parse_hue(list[0])?;
+            let saturation = parse_percent(list[1])?;
+            let lightness = parse_percent(list[2])?;
+            hsl_to_rgb(hue, saturation, lightness)
+        }
+        _ => return None,
+    };
+
+    Some(Color { red, green, blue, alpha })
+}
+
+fn parse_hex(text: &str) -> Option<Color> {
+    if !text.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&text[i..(i + 1)], 16).ok();
+    let byte = |i: usize| u8::from_str_radix(&text[i..(i + 2)], 16).ok();
+
+    let (red, green, blue, alpha) = match text.len() {
+        3 | 4 => {
+            let alpha = if text.len() == 4 { digit(3)? * 17 } else { 255 };
+            (digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, alpha)
+        }
+        6 | 8 => {
+            let alpha = if text.len() == 8 { byte(6)? } else { 255 };
+            (byte(0)?, byte(2)?, byte(4)?, alpha)
+        }
+        _ => return None,
+    };
+
+    Some(Color { red, green, blue, alpha: alpha as f64 / 255.0 })
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let n: f64 = text.parse().ok()?;
+    if n.is_finite() { Some(n) } else { None }
+}
+
+/// Parses a percentage into a 0..1 range.
+///
+/// A plain number is treated as a percentage too.
+fn parse_percent(text: &str) -> Option<f64> {
+    let n = parse_number(text.trim_end_matches('%'))?;
+    Some(bound(n / 100.0))
+}
+
+fn parse_alpha(text: &str) -> Option<f64> {
+    if text.ends_with('%') {
+        parse_percent(text)
+    } else {
+        parse_number(text).map(bound)
+    }
+}
+
+fn parse_channel(text: &str) -> Option<u8> {
+    let n = if text.ends_with('%') {
+        parse_percent(text)?
+    } else {
+        parse_number(text)? / 255.0
+    };
+
+    Some(to_u8(n))
+}
+
+/// Parses a hue in degrees.
+fn parse_hue(text: &str) -> Option<f64> {
+    let units = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f64::consts::PI), ("turn", 360.0)];
+    for &(unit, k) in &units {
+        if text.ends_with(unit) {
+            return parse_number(&text[..(text.len() - unit.len())]).map(|n| n * k);
+        }
+    }
+
+    parse_number(text)
+}
+
+// https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let hue = wrap(hue, 360.0) / 60.0;
+    let t2 = if lightness <= 0.5 {
+        lightness * (saturation + 1.0)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let t1 = lightness * 2.0 - t2;
+
+    let channel = |h: f64| {
+        let h = wrap(h, 6.0);
+        let v = if h < 1.0 {
+            (t2 - t1) * h + t1
+        } else if h < 3.0 {
+            t2
+        } else if h < 4.0 {
+            (t2 - t1) * (4.0 - h) + t1
+        } else {
+            t1
+        };
+
+        to_u8(v)
+    };
+
+    (channel(hue + 2.0), channel(hue), channel(hue - 2.0))
+}
+
+fn wrap(n: f64, max: f64) -> f64 {
+    let n = n % max;
+    if n < 0.0 { n + max } else { n }
+}
+
+fn bound(n: f64) -> f64 {
+    n.max(0.0).min(1.0)
+}
+
+fn to_u8(n: f64) -> u8 {
+    (bound(n) * 255.0 + 0.5) as u8
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test {
+        ($name:ident, $text:expr, $r:expr, $g:expr, $b:expr, $a:expr) => {
+            #[test]
+            fn $name() {
+                let c = parse($text).unwrap();
+                assert_eq!((c.red, c.green, c.blue), ($r, $g, $b));
+                assert!((c.alpha - $a).abs() < 0.01);
+            }
+        };
+    }
+
+    test!(hex_short, "#f00", 255, 0, 0, 1.0);
+    test!(hex_short_alpha, "#f008", 255, 0, 0, 0.53);
+    test!(hex_alpha, "#00ff0080", 0, 255, 0, 0.5);
+    test!(rgba_legacy, "rgba(10, 20, 30, 0.5)", 10, 20, 30, 0.5);
+    test!(rgb_space, "rgb(100% 0% 50% / 25%)", 255, 0, 128, 0.25);
+    test!(hsl, "hsl(120, 100%, 50%)", 0, 255, 0, 1.0);
+    test!(hsla_turn, "HSLA(0.5turn 100% 25% / 0.3)", 0, 128, 128, 0.3);
+    test!(transparent, "transparent", 0, 0, 0, 0.0);
+
+    #[test]
+    fn invalid() {
+        assert_eq!(parse("red"), None);
+        assert_eq!(parse("rgb(1, 2)"), None);
+        assert_eq!(parse("#12345"), None);
+    }
+
+    #[test]
+    fn paint_fallback() {
+        assert_eq!(resolve_paint("url(#lg1) rgba(255, 0, 0, 0.5)"),
+                   Some(("url(#lg1) #ff0000".to_string(), 0.5)));
+        assert_eq!(resolve_paint("url(#lg1)"), None);
+        assert_eq!(resolve_paint("#ff0000"), None);
+        assert_eq!(resolve_paint("rgb(10%, 20%, 30%)"), None);
+        assert_eq!(resolve_paint("none"), None);
+    }
+}
@@ -0,0 +1,625 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! CSS cascade.
+//!
+//! `svgdom` supports only a small subset of CSS, so style sheets are resolved
+//! before the parsing. All matched declarations are written into the `style` attribute
+//! in the cascade order and `style` elements are removed.
+//!
+//! Custom properties, CSS Color Module Level 4 colors and basic shapes
+//! are resolved here too.
+
+use std::borrow::Cow;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::{
+    Error,
+    Options,
+    SourcePos,
+};
+use self::stylesheet::{
+    Declaration,
+    StyleSheet,
+};
+
+pub mod color;
+mod stylesheet;
+mod xml;
+
+
+type Variables = HashMap<String, String>;
+
+
+struct XmlElement<'a, 'b: 'a> {
+    elements: &'a [xml::Element<'b>],
+    idx: usize,
+}
+
+impl<'a, 'b> stylesheet::Element for XmlElement<'a, 'b> {
+    fn parent_element(&self) -> Option<Self> {
+        let idx = self.elements[self.idx].parent?;
+        Some(XmlElement { elements: self.elements, idx })
+    }
+
+    fn prev_sibling_element(&self) -> Option<Self> {
+        let idx = self.elements[self.idx].prev_sibling?;
+        Some(XmlElement { elements: self.elements, idx })
+    }
+
+    fn local_name(&self) -> &str {
+        // Non-SVG elements must not match SVG type selectors.
+        let e = &self.elements[self.idx];
+        if e.is_svg { e.name } else { "" }
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.elements[self.idx].attribute(name).map(|a| a.value.as_str())
+    }
+}
+
+
+/// Applies document style sheets and a user style sheet to the SVG text.
+///
+/// The user style sheet is applied after the document ones,
+/// so it has a higher priority for selectors with the same specificity.
+/// The `style` attribute still has the highest priority, unless `!important` is used.
+///
+/// Custom properties are inherited and `var()` references are substituted
+/// in declarations and presentation attributes.
+///
+/// Colors with an alpha are replaced with opaque ones. The alpha is stored
+/// in a `usvg-{property}-alpha` attribute of the same element.
+///
+/// A `clip-path` with a basic shape is replaced with `none` and stored
+/// in a `usvg-clip-path` attribute.
+///
+/// A `filter` with filter functions or multiple links is replaced with `none`
+/// and stored in a `usvg-filter` attribute.
+///
+/// Returns the original text when there is nothing to apply
+/// or when the text is not a valid XML.
+///
+/// The elements count and nesting depth limits and links are checked here too,
+/// since it's the first pass over the document.
+pub fn resolve<'a>(text: &'a str, opt: &Options) -> Result<Cow<'a, str>, Error> {
+    let has_vars = !opt.css_variables.is_empty() || text.contains("var(");
+    // Values that are not supported by `svgdom`.
+    let has_values = color::has_colors(text) || text.contains("clip-path") || text.contains("filter");
+
+    // The text is always scanned, because limits and links are checked here.
+    let elements = match xml::parse(text, &opt.limits) {
+        Ok(v) => v,
+        Err(xml::Error::LimitExceeded(kind)) => return Err(Error::LimitExceeded(kind)),
+        Err(xml::Error::Invalid) => return Ok(Cow::Borrowed(text)),
+    };
+
+    let mut style_sheet = StyleSheet::new();
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for e in &elements {
+        if !e.is_svg || e.name != "style" {
+            continue;
+        }
+
+        match e.attribute("type").map(|a| a.value.as_str()) {
+            None | Some("") | Some("text/css") => {}
+            _ => continue,
+        }
+
+        style_sheet.parse_more(&xml::text_content(text, e.content.clone()));
+        edits.push((e.range.clone(), String::new()));
+    }
+
+    if let Some(ref text) = opt.style_sheet {
+        style_sheet.parse_more(text);
+    }
+
+    crate::diagnostics::set_positions(|| element_positions(text, &elements));
+    check_links(&elements, &style_sheet);
+
+    if style_sheet.rules.is_empty() && edits.is_empty() && !has_vars && !has_values {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    // Custom properties of each element.
+    let mut variables: Vec<Rc<Variables>> = Vec::with_capacity(elements.len());
+    let mut matched: Vec<(&Declaration, [u16; 3], usize)> = Vec::new();
+    for (idx, e) in elements.iter().enumerate() {
+        let parent_vars = match e.parent {
+            Some(parent) => variables[parent].clone(),
+            None => Rc::new(Variables::new()),
+        };
+
+        // Styles are not applied to non-SVG elements.
+        if !e.is_svg || e.name == "style" {
+            variables.push(parent_vars);
+            continue;
+        }
+
+        matched.clear();
+        let xml_element = XmlElement { elements: &elements, idx };
+        for (order, rule) in style_sheet.rules.iter().enumerate() {
+            if rule.selector.matches(&xml_element) {
+                let specificity = rule.selector.specificity();
+                for d in &rule.declarations {
+                    matched.push((d, specificity, order));
+                }
+            }
+        }
+
+        // A stable sort, so declarations inside a rule will preserve their order.
+        matched.sort_by_key(|&(_, specificity, order)| (specificity, order));
+
+        let inline = match e.attribute("style") {
+            Some(attr) => stylesheet::parse_declarations(&attr.value),
+            None => Vec::new(),
+        };
+
+        // The cascade order: normal style sheet declarations, normal inline declarations,
+        // important style sheet declarations and important inline declarations.
+        let mut list: Vec<(&str, &str)> = Vec::new();
+        for important in &[false, true] {
+            for &(d, _, _) in matched.iter().filter(|v| v.0.important == *important) {
+                push_declaration(d, &mut list);
+            }
+
+            for d in inline.iter().filter(|d| d.important == *important) {
+                push_declaration(d, &mut list);
+            }
+        }
+
+        // Custom properties are inherited and the root element ones
+        // can be overridden by the caller.
+        let is_root = e.parent.is_none();
+        let has_own_vars = list.iter().any(|&(name, _)| name.starts_with("--"));
+        let vars = if has_own_vars || (is_root && !opt.css_variables.is_empty()) {
+            let mut vars = (*parent_vars).clone();
+            for &(name, value) in list.iter().filter(|&&(name, _)| name.starts_with("--")) {
+                if let Some(value) = substitute_vars(value, &vars) {
+                    vars.insert(name.to_string(), value);
+                }
+            }
+
+            if is_root {
+                for (name, value) in &opt.css_variables {
+                    if name.starts_with("--") {
+                        vars.insert(name.clone(), value.clone());
+                    } else {
+                        vars.insert(format!("--{}", name), value.clone());
+                    }
+                }
+            }
+
+            Rc::new(vars)
+        } else {
+            parent_vars
+        };
+
+        // Internal attributes of the current element. Declarations have a higher priority
+        // than presentation attributes, so they will override them.
+        let mut internal_attrs: Vec<(String, Option<String>)> = Vec::new();
+
+        // The `style` attribute is processed separately.
+        for attr in e.attributes.iter().filter(|a| a.name != "style") {
+            let mut value = Cow::Borrowed(attr.value.as_str());
+            if attr.value.contains("var(") {
+                match substitute_vars(&attr.value, &vars) {
+                    Some(v) => value = Cow::Owned(v),
+                    None => {
+                        diag!(Warning, InvalidCss, attr_id(e),
+                              "Failed to resolve '{}' in the '{}' attribute.", attr.value, attr.name);
+                        continue;
+                    }
+                }
+            }
+
+            if let Some(v) = resolve_value(attr.name, &value, &mut internal_attrs) {
+                value = Cow::Owned(v);
+            }
+
+            if let Cow::Owned(ref value) = value {
+                edits.push((attr.value_range.clone(), xml::escape(value)));
+            }
+        }
+
+        let mut needs_update = !matched.is_empty() || inline.iter().any(|d| {
+            d.name.starts_with("--") || d.value.contains("var(")
+        });
+
+        let mut style = Vec::new();
+        for &(name, value) in list.iter().filter(|&&(name, _)| !name.starts_with("--")) {
+            match substitute_vars(value, &vars) {
+                Some(value) => {
+                    match resolve_value(name, &value, &mut internal_attrs) {
+                        Some(v) => {
+                            style.push(format!("{}:{}", name, v));
+                            needs_update = true;
+                        }
+                        None => style.push(format!("{}:{}", name, value)),
+                    }
+                }
+                None => {
+                    diag!(Warning, InvalidCss, attr_id(e),
+                          "Failed to resolve '{}' in the '{}' property.", value, name);
+                }
+            }
+        }
+
+        if needs_update {
+            let style = xml::escape(&style.join(";"));
+            match e.attribute("style") {
+                Some(attr) => edits.push((attr.value_range.clone(), style)),
+                None if style.is_empty() => {}
+                None => edits.push((e.name_end..e.name_end, format!(" style=\"{}\"", style))),
+            }
+        }
+
+        for (name, value) in internal_attrs {
+            if let Some(value) = value {
+                let attr = format!(" {}=\"{}\"", name, xml::escape(&value));
+                edits.push((e.name_end..e.name_end, attr));
+            }
+        }
+
+        variables.push(vars);
+    }
+
+    // Nothing was changed, so there is no need to rewrite the text.
+    if edits.is_empty() {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    edits.sort_by_key(|e| e.0.start);
+
+    let mut s = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (range, value) in edits {
+        s.push_str(&text[pos..range.start]);
+        s.push_str(&value);
+        pos = range.end;
+    }
+    s.push_str(&text[pos..]);
+
+    Ok(Cow::Owned(s))
+}
+
+/// Returns source positions of elements with an ID.
+fn element_positions(text: &str, elements: &[xml::Element]) -> HashMap<String, SourcePos> {
+    let mut positions = HashMap::new();
+    let mut pos = SourcePos { row: 1, col: 1 };
+    let mut offset = 0;
+    // Elements are stored in the document order.
+    for e in elements {
+        let id = match e.attribute("id") {
+            Some(attr) => attr.value.as_str(),
+            None => continue,
+        };
+
+        for c in text[offset..e.range.start].chars() {
+            if c == '\n' {
+                pos.row += 1;
+                pos.col = 1;
+            } else {
+                pos.col += 1;
+            }
+        }
+        offset = e.range.start;
+
+        positions.entry(id.to_string()).or_insert(pos);
+    }
+
+    positions
+}
+
+/// Reports links to non-existing elements.
+///
+/// `svgdom` removes such links silently, so they have to be checked before the parsing.
+fn check_links(elements: &[xml::Element], style_sheet: &StyleSheet) {
+    let ids: HashSet<&str> = elements.iter()
+        .filter_map(|e| e.attribute("id"))
+        .map(|a| a.value.as_str())
+        .collect();
+
+    for e in elements {
+        for attr in &e.attributes {
+            if attr.name == "style" {
+                for d in stylesheet::parse_declarations(&attr.value) {
+                    check_value_links(&d.name, &d.value, &ids, attr_id(e));
+                }
+            } else if attr.name == "href" || attr.name.ends_with(":href") {
+                if attr.value.starts_with('#') && !ids.contains(&attr.value[1..]) {
+                    diag!(Warning, UnresolvedLink, attr_id(e),
+                          "'{}' references a non-existing element '{}'.", attr.name, attr.value);
+                }
+            } else {
+                check_value_links(attr.name, &attr.value, &ids, attr_id(e));
+            }
+        }
+    }
+
+    for rule in &style_sheet.rules {
+        for d in &rule.declarations {
+            check_value_links(&d.name, &d.value, &ids, "");
+        }
+    }
+}
+
+fn check_value_links(name: &str, value: &str, ids: &HashSet<&str>, element_id: &str) {
+    let mut rest = value;
+    while let Some(idx) = rest.find("url(") {
+        rest = &rest[(idx + 4)..];
+        let end = match rest.find(')') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let link = rest[..end].trim_matches(|c: char| c.is_whitespace() || c == '"' || c == '\'');
+        rest = &rest[(end + 1)..];
+
+        // Only local links can be checked.
+        if !link.starts_with('#') || ids.contains(&link[1..]) {
+            continue;
+        }
+
+        // Paint servers can have a fallback, which will be used instead.
+        if (name == "fill" || name == "stroke") && !rest.trim().is_empty() {
+            continue;
+        }
+
+        diag!(Warning, UnresolvedLink, element_id,
+              "'{}' references a non-existing element '{}'.", name, link);
+    }
+}
+
+fn attr_id<'a>(e: &'a xml::Element) -> &'a str {
+    e.attribute("id").map(|a| a.value.as_str()).unwrap_or("")
+}
+
+/// Substitutes all `var()` references.
+///
+/// Returns `None` when a variable is not set and doesn't have a fallback.
+fn substitute_vars(value: &str, vars: &Variables) -> Option<String> {
+    if !value.contains("var(") {
+        return Some(value.to_string());
+    }
+
+    let mut s = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(idx) = rest.find("var(") {
+        s.push_str(&rest[..idx]);
+
+        let args_start = idx + 4;
+        let mut depth = 1;
+        let mut args_end = None;
+        for (i, c) in rest[args_start..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        args_end = Some(args_start + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let args_end = args_end?;
+        let args = &rest[args_start..args_end];
+
+        let (name, fallback) = match args.find(',') {
+            Some(i) => (args[..i].trim(), Some(args[(i + 1)..].trim())),
+            None => (args.trim(), None),
+        };
+
+        match vars.get(name) {
+            Some(v) => s.push_str(v),
+            None => s.push_str(&substitute_vars(fallback?, vars)?),
+        }
+
+        rest = &rest[(args_end + 1)..];
+    }
+    s.push_str(rest);
+
+    Some(s)
+}
+
+/// Converts a value that is not supported by `svgdom`.
+///
+/// The removed part of the value is stored in an internal attribute.
+/// It's updated by any declaration of the same property, so the latest one wins.
+fn resolve_value(
+    name: &str,
+    value: &str,
+    internal_attrs: &mut Vec<(String, Option<String>)>,
+) -> Option<String> {
+    let (new_value, internal_value, internal_name) = if color::PROPERTIES.contains(&name) {
+        let (new_value, alpha) = match color::resolve_paint(value) {
+            Some((value, alpha)) => (Some(value), alpha),
+            None => (None, 1.0),
+        };
+
+        let alpha = if alpha < 1.0 { Some(alpha.to_string()) } else { None };
+        (new_value, alpha, format!("usvg-{}-alpha", name))
+    } else if name == "clip-path" || name == "filter" {
+        // `svgdom` supports only a single link.
+        let value = value.trim();
+        let is_link = if name == "filter" {
+            value.starts_with("url(") && value.find(')') == Some(value.len() - 1)
+        } else {
+            value.starts_with("url(")
+        };
+
+        let internal_name = format!("usvg-{}", name);
+        if value.is_empty() || value == "none" || value == "inherit" || is_link {
+            (None, None, internal_name)
+        } else {
+            (Some("none".to_string()), Some(value.to_string()), internal_name)
+        }
+    } else {
+        return None;
+    };
+
+    match internal_attrs.iter_mut().find(|v| v.0 == internal_name) {
+        Some(v) => v.1 = internal_value,
+        None => internal_attrs.push((internal_name, internal_value)),
+    }
+
+    new_value
+}
+
+/// Appends a declaration or overrides the existing one.
+fn push_declaration<'a>(d: &'a Declaration, list: &mut Vec<(&'a str, &'a str)>) {
+    match list.iter_mut().find(|v| v.0 == d.name) {
+        Some(v) => v.1 = &d.value,
+        None => list.push((&d.name, &d.value)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! test {
+        ($name:ident, $user:expr, $input:expr, $output:expr) => {
+            test!($name, $user, vec![], $input, $output);
+        };
+        ($name:ident, $user:expr, $vars:expr, $input:expr, $output:expr) => {
+            #[test]
+            fn $name() {
+                let opt = Options {
+                    style_sheet: $user.map(|s: &str| s.to_string()),
+                    css_variables: $vars,
+                    .. Options::default()
+                };
+
+                assert_eq!(resolve($input, &opt).unwrap(), $output);
+            }
+        };
+    }
+
+    test!(no_css, None,
+"<svg><rect/></svg>",
+"<svg><rect/></svg>");
+
+    test!(type_and_class, None,
+"<svg><style>rect { fill: red } .a { fill: green; stroke: blue }</style><rect class='a'/><rect/></svg>",
+"<svg><rect style=\"fill:green;stroke:blue\" class='a'/><rect style=\"fill:red\"/></svg>");
+
+    test!(specificity, None,
+"<svg><style>#r1 { fill: red } g > .a { fill: green } .a { fill: blue }</style>\
+<g><rect id='r1' class='a'/><rect class='a'/></g></svg>",
+"<svg><g><rect style=\"fill:red\" id='r1' class='a'/><rect style=\"fill:green\" class='a'/></g></svg>");
+
+    test!(inline_and_important, None,
+"<svg><style>.a { fill: red !important; stroke: red }</style>\
+<rect class='a' style='fill:green;stroke:green'/></svg>",
+"<svg><rect class='a' style='stroke:green;fill:red'/></svg>");
+
+    test!(combinators, None,
+"<svg><style>g rect { fill: red } rect + circle { fill: green } rect ~ path { fill: blue } \
+circle:first-child { fill: gray } [data-x^=ab] { stroke: red }</style>\
+<g><circle/><g><rect data-x='abc'/></g></g><rect/><circle/><circle/><path/></svg>",
+"<svg><g><circle style=\"fill:gray\"/><g><rect style=\"fill:red;stroke:red\" data-x='abc'/></g></g>\
+<rect/><circle style=\"fill:green\"/><circle/><path style=\"fill:blue\"/></svg>");
+
+    test!(user_style_sheet, Some(".primary { fill: red }"),
+"<svg><style><![CDATA[ .primary { fill: green } ]]></style><path class='primary'/></svg>",
+"<svg><path style=\"fill:red\" class='primary'/></svg>");
+
+    test!(unsupported_selector, None,
+"<svg><style>rect:hover, rect { fill: red } @media print { rect { fill: green } }</style>\
+<rect/></svg>",
+"<svg><rect style=\"fill:red\"/></svg>");
+
+    test!(invalid_xml, Some("rect { fill: red }"),
+"<svg><rect></svg>",
+"<svg><rect></svg>");
+
+    test!(variables, None,
+"<svg style='--accent:red'><style>g { --stroke: var(--accent) }</style>\
+<g><rect style='fill:var(--accent);stroke:var(--stroke, blue)'/><rect fill='var(--missing, #333)'/></g></svg>",
+"<svg style=''><g><rect style='fill:red;stroke:red'/><rect fill='#333'/></g></svg>");
+
+    test!(variables_case, None,
+"<svg style='--myColor:red;--mycolor:blue'><rect style='fill:var(--myColor)'/><rect style='FILL:var(--mycolor)'/></svg>",
+"<svg style=''><rect style='fill:red'/><rect style='fill:blue'/></svg>");
+
+    test!(variables_fallback, None,
+"<svg><rect style='fill:var(--accent, var(--other, green))'/><rect style='fill:var(--none);stroke:red'/></svg>",
+"<svg><rect style='fill:green'/><rect style='stroke:red'/></svg>");
+
+    test!(caller_variables, None, vec![("accent".to_string(), "blue".to_string())],
+"<svg style='--accent:red'><rect style='fill:var(--accent)'/></svg>",
+"<svg style=''><rect style='fill:blue'/></svg>");
+
+    test!(colors, None,
+"<svg><rect fill='rgba(255, 0, 0, 0.5)' stroke='#00ff0080'/><rect fill='url(#lg1) hsl(0, 100%, 50%)'/></svg>",
+"<svg><rect usvg-fill-alpha=\"0.5\" usvg-stroke-alpha=\"0.5019607843137255\" fill='#ff0000' stroke='#00ff00'/>\
+<rect fill='url(#lg1) #ff0000'/></svg>");
+
+    test!(colors_override, Some("rect { fill: transparent }"),
+"<svg><rect fill='rgba(0, 0, 255, 0.5)'/><rect fill='rgba(0, 0, 255, 0.5)' style='fill:red'/></svg>",
+"<svg><rect style=\"fill:#000000\" usvg-fill-alpha=\"0\" fill='#0000ff'/>\
+<rect fill='#0000ff' style='fill:red'/></svg>");
+
+    test!(basic_shapes, None,
+"<svg><g clip-path='circle(40%)'/><g style='clip-path:inset(10px round 4px) fill-box'/>\
+<g clip-path='url(#clip1)'/></svg>",
+"<svg><g usvg-clip-path=\"circle(40%)\" clip-path='none'/>\
+<g usvg-clip-path=\"inset(10px round 4px) fill-box\" style='clip-path:none'/><g clip-path='url(#clip1)'/></svg>");
+
+    test!(filter_functions, None,
+"<svg><g filter='blur(2px) url(#filter1)'/><g style='filter:drop-shadow(1px 1px rgba(0, 0, 0, 0.5))'/>\
+<g filter='url(#filter1)'/></svg>",
+"<svg><g usvg-filter=\"blur(2px) url(#filter1)\" filter='none'/>\
+<g usvg-filter=\"drop-shadow(1px 1px rgba(0, 0, 0, 0.5))\" style='filter:none'/><g filter='url(#filter1)'/></svg>");
+
+    test!(entity_refs, None,
+"<svg style='--c:red'><rect fill='url(&ent;) var(--c)' class='var(--c) &lt;'/></svg>",
+"<svg style=''><rect fill='url(&ent;) red' class='red &lt;'/></svg>");
+
+    test!(namespaces, Some("rect { fill: red }"),
+"<svg xmlns='http://www.w3.org/2000/svg' xmlns:foo='http://foo'>\
+<foo:rect/><rect/><g xmlns='http://foo'><rect/></g></svg>",
+"<svg xmlns='http://www.w3.org/2000/svg' xmlns:foo='http://foo'>\
+<foo:rect/><rect style=\"fill:red\"/><g xmlns='http://foo'><rect/></g></svg>");
+
+    test!(namespaces_prefixed, Some("rect { fill: red }"),
+"<svg:svg xmlns:svg='http://www.w3.org/2000/svg'><svg:rect/></svg:svg>",
+"<svg:svg xmlns:svg='http://www.w3.org/2000/svg'><svg:rect style=\"fill:red\"/></svg:svg>");
+
+    #[test]
+    fn limits() {
+        use crate::{Limits, LimitKind};
+
+        let opt = Options {
+            limits: Limits { max_depth: Some(2), .. Limits::default() },
+            .. Options::default()
+        };
+
+        match resolve("<svg><g><rect/></g></svg>", &opt) {
+            Err(Error::LimitExceeded(LimitKind::Depth)) => {}
+            _ => panic!("depth limit must be exceeded"),
+        }
+
+        let opt = Options {
+            limits: Limits { max_elements: Some(2), .. Limits::default() },
+            .. Options::default()
+        };
+
+        match resolve("<svg><rect/><rect/></svg>", &opt) {
+            Err(Error::LimitExceeded(LimitKind::Elements)) => {}
+            _ => panic!("elements limit must be exceeded"),
+        }
+
+        assert!(resolve("<svg><rect/></svg>", &opt).is_ok());
+    }
+}
@@ -0,0 +1,561 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! CSS parsing and selectors matching.
+
+
+/// An element that can be matched by a selector.
+pub trait Element: Sized {
+    fn parent_element(&self) -> Option<Self>;
+    fn prev_sibling_element(&self) -> Option<Self>;
+    fn local_name(&self) -> &str;
+    fn attribute(&self, name: &str) -> Option<&str>;
+}
+
+
+pub struct StyleSheet {
+    pub rules: Vec<Rule>,
+}
+
+pub struct Rule {
+    pub selector: Selector,
+    pub declarations: Vec<Declaration>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Declaration {
+    pub name: String,
+    pub value: String,
+    pub important: bool,
+}
+
+impl StyleSheet {
+    pub fn new() -> Self {
+        StyleSheet {
+            rules: Vec::new(),
+        }
+    }
+
+    /// Parses a style sheet and appends its rules.
+    ///
+    /// Invalid and unsupported rules are skipped.
+    pub fn parse_more(&mut self, text: &str) {
+        let text = remove_comments(text);
+        let mut s = text.as_str();
+
+        loop {
+            s = s.trim_start();
+            if s.is_empty() {
+                break;
+            }
+
+            if s.starts_with('@') {
+                // At-rules like `@media` and `@import` are not supported.
+                let end = s.find(&[';', '{'][..]).unwrap_or(s.len());
+                if s[end..].starts_with('{') {
+                    let block_end = find_block_end(s, end).unwrap_or(s.len());
+                    diag!(Warning, InvalidCss, "", "'{}' at-rule is not supported. Skipped.",
+                          s[..end].trim());
+                    s = &s[block_end..];
+                } else {
+                    s = &s[(end + 1).min(s.len())..];
+                }
+
+                continue;
+            }
+
+            let start = match s.find('{') {
+                Some(idx) => idx,
+                None => break,
+            };
+            let (block, end) = match find_block_end(s, start) {
+                Some(end) => (&s[(start + 1)..(end - 1)], end),
+                None => (&s[(start + 1)..], s.len()),
+            };
+
+            let prelude = &s[..start];
+            let declarations = parse_declarations(block);
+
+            for text in prelude.split(',') {
+                match Selector::parse(text.trim()) {
+                    Some(selector) => {
+                        self.rules.push(Rule {
+                            selector,
+                            declarations: declarations.clone(),
+                        });
+                    }
+                    None => {
+                        diag!(Warning, InvalidCss, "", "'{}' selector is not supported. Skipped.",
+                              text.trim());
+                    }
+                }
+            }
+
+            s = &s[end..];
+        }
+    }
+}
+
+/// Parses a list of declarations, like the `style` attribute value.
+pub fn parse_declarations(text: &str) -> Vec<Declaration> {
+    let mut list = Vec::new();
+    for text in split_declarations(text) {
+        let idx = match text.find(':') {
+            Some(idx) => idx,
+            None => continue,
+        };
+
+        // Custom properties are case-sensitive.
+        let name = text[..idx].trim();
+        let name = if name.starts_with("--") { name.to_string() } else { name.to_ascii_lowercase() };
+        let mut value = text[(idx + 1)..].trim();
+        let mut important = false;
+        if let Some(idx) = value.rfind('!') {
+            if value[(idx + 1)..].trim().eq_ignore_ascii_case("important") {
+                value = value[..idx].trim();
+                important = true;
+            }
+        }
+
+        if name.is_empty() || value.is_empty() {
+            continue;
+        }
+
+        list.push(Declaration {
+            name,
+            value: value.to_string(),
+            important,
+        });
+    }
+
+    list
+}
+
+/// Splits declarations by `;`, ignoring the one inside quotes and parentheses,
+/// like in `url(data:image/png;base64,...)`.
+fn split_declarations(text: &str) -> Vec<&str> {
+    let mut list = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                ';' if depth == 0 => {
+                    list.push(&text[start..idx]);
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    list.push(&text[start..]);
+
+    list
+}
+
+/// Returns a position right after the block that starts at `start`.
+fn find_block_end(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (idx, c) in text[start..].char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(start + idx + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    None
+}
+
+fn remove_comments(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        s.push_str(&rest[..start]);
+        s.push(' ');
+        rest = match rest[(start + 2)..].find("*/") {
+            Some(end) => &rest[(start + 2 + end + 2)..],
+            None => "",
+        };
+    }
+    s.push_str(rest);
+
+    s
+}
+
+
+/// A selector specificity.
+///
+/// Contains the number of IDs, classes (including attributes and pseudo-classes)
+/// and types selectors.
+pub type Specificity = [u16; 3];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Combinator {
+    None,
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    AdjacentSibling,
+    /// `a ~ b`
+    GeneralSibling,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum AttributeOperator {
+    /// `[attr]`
+    Exists,
+    /// `[attr=value]`
+    Matches(String),
+    /// `[attr~=value]`
+    Contains(String),
+    /// `[attr|=value]`
+    StartsWith(String),
+    /// `[attr^=value]`
+    Prefix(String),
+    /// `[attr$=value]`
+    Suffix(String),
+    /// `[attr*=value]`
+    Substring(String),
+}
+
+impl AttributeOperator {
+    fn matches(&self, value: &str) -> bool {
+        match *self {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Matches(ref v) => value == v,
+            AttributeOperator::Contains(ref v) => value.split_whitespace().any(|s| s == v),
+            AttributeOperator::StartsWith(ref v) => {
+                value == v || (value.starts_with(v.as_str()) && value[v.len()..].starts_with('-'))
+            }
+            AttributeOperator::Prefix(ref v) => !v.is_empty() && value.starts_with(v.as_str()),
+            AttributeOperator::Suffix(ref v) => !v.is_empty() && value.ends_with(v.as_str()),
+            AttributeOperator::Substring(ref v) => !v.is_empty() && value.contains(v.as_str()),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum SubSelector {
+    Id(String),
+    Class(String),
+    Attribute(String, AttributeOperator),
+    FirstChild,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Component {
+    /// A combinator with the previous component.
+    combinator: Combinator,
+    /// A type selector. `None` for the universal one.
+    tag: Option<String>,
+    sub_selectors: Vec<SubSelector>,
+}
+
+impl Component {
+    fn matches<E: Element>(&self, element: &E) -> bool {
+        if let Some(ref tag) = self.tag {
+            if element.local_name() != tag {
+                return false;
+            }
+        }
+
+        self.sub_selectors.iter().all(|sub| match *sub {
+            SubSelector::Id(ref id) => element.attribute("id") == Some(id.as_str()),
+            SubSelector::Class(ref class) => {
+                match element.attribute("class") {
+                    Some(v) => v.split_whitespace().any(|s| s == class),
+                    None => false,
+                }
+            }
+            SubSelector::Attribute(ref name, ref op) => {
+                match element.attribute(name) {
+                    Some(v) => op.matches(v),
+                    None => false,
+                }
+            }
+            SubSelector::FirstChild => element.prev_sibling_element().is_none(),
+        })
+    }
+}
+
+
+/// A complex selector, like `g.icon > path:first-child`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Selector {
+    components: Vec<Component>,
+}
+
+impl Selector {
+    /// Parses a selector.
+    ///
+    /// Returns `None` for invalid and unsupported selectors.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut s = Stream { text, pos: 0 };
+        let mut components = Vec::new();
+        let mut combinator = Combinator::None;
+
+        loop {
+            let mut tag = None;
+            let mut is_universal = false;
+            if s.curr() == Some('*') {
+                s.pos += 1;
+                is_universal = true;
+            } else if let Some(name) = s.consume_ident() {
+                tag = Some(name.to_string());
+            }
+
+            let mut sub_selectors = Vec::new();
+            loop {
+                match s.curr() {
+                    Some('#') => {
+                        s.pos += 1;
+                        sub_selectors.push(SubSelector::Id(s.consume_ident()?.to_string()));
+                    }
+                    Some('.') => {
+                        s.pos += 1;
+                        sub_selectors.push(SubSelector::Class(s.consume_ident()?.to_string()));
+                    }
+                    Some('[') => {
+                        s.pos += 1;
+                        sub_selectors.push(s.consume_attribute()?);
+                    }
+                    Some(':') => {
+                        s.pos += 1;
+                        match s.consume_ident()? {
+                            "first-child" => sub_selectors.push(SubSelector::FirstChild),
+                            _ => return None,
+                        }
+                    }
+                    _ => break,
+                }
+            }
+
+            if tag.is_none() && !is_universal && sub_selectors.is_empty() {
+                return None;
+            }
+
+            components.push(Component { combinator, tag, sub_selectors });
+
+            let had_spaces = s.skip_spaces();
+            combinator = match s.curr() {
+                None => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::AdjacentSibling,
+                Some('~') => Combinator::GeneralSibling,
+                Some(_) if had_spaces => Combinator::Descendant,
+                Some(_) => return None,
+            };
+
+            if combinator != Combinator::Descendant {
+                s.pos += 1;
+                s.skip_spaces();
+            }
+        }
+
+        Some(Selector { components })
+    }
+
+    /// Returns the selector specificity.
+    pub fn specificity(&self) -> Specificity {
+        let mut spec = [0u16; 3];
+        for component in &self.components {
+            if component.tag.is_some() {
+                spec[2] += 1;
+            }
+
+            for sub in &component.sub_selectors {
+                match *sub {
+                    SubSelector::Id(_) => spec[0] += 1,
+                    _ => spec[1] += 1,
+                }
+            }
+        }
+
+        spec
+    }
+
+    /// Checks that the selector matches the element.
+    pub fn matches<E: Element>(&self, element: &E) -> bool {
+        !self.components.is_empty() && match_component(&self.components, element)
+    }
+}
+
+/// Matches the last component and then its combinator recursively.
+fn match_component<E: Element>(components: &[Component], element: &E) -> bool {
+    let (component, prev) = match components.split_last() {
+        Some(v) => v,
+        None => return true,
+    };
+
+    if !component.matches(element) {
+        return false;
+    }
+
+    match component.combinator {
+        Combinator::None => true,
+        Combinator::Descendant => {
+            let mut parent = element.parent_element();
+            while let Some(e) = parent {
+                if match_component(prev, &e) {
+                    return true;
+                }
+
+                parent = e.parent_element();
+            }
+
+            false
+        }
+        Combinator::Child => {
+            match element.parent_element() {
+                Some(e) => match_component(prev, &e),
+                None => false,
+            }
+        }
+        Combinator::AdjacentSibling => {
+            match element.prev_sibling_element() {
+                Some(e) => match_component(prev, &e),
+                None => false,
+            }
+        }
+        Combinator::GeneralSibling => {
+            let mut sibling = element.prev_sibling_element();
+            while let Some(e) = sibling {
+                if match_component(prev, &e) {
+                    return true;
+                }
+
+                sibling = e.prev_sibling_element();
+            }
+
+            false
+        }
+    }
+}
+
+
+struct Stream<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Stream<'a> {
+    fn curr(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_spaces(&mut self) -> bool {
+        let start = self.pos;
+        while let Some(c) = self.curr() {
+            if !c.is_whitespace() {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+
+        self.pos != start
+    }
+
+    fn consume_ident(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        while let Some(c) = self.curr() {
+            if !(c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()) {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+
+        if self.pos == start {
+            None
+        } else {
+            Some(&self.text[start..self.pos])
+        }
+    }
+
+    fn consume_string(&mut self) -> Option<&'a str> {
+        match self.curr() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                let start = self.pos + 1;
+                let end = start + self.text[start..].find(quote)?;
+                self.pos = end + 1;
+                Some(&self.text[start..end])
+            }
+            _ => self.consume_ident(),
+        }
+    }
+
+    /// Consumes an attribute selector after `[`.
+    fn consume_attribute(&mut self) -> Option<SubSelector> {
+        self.skip_spaces();
+        let start = self.pos;
+        while let Some(c) = self.curr() {
+            if !(c.is_alphanumeric() || c == '-' || c == '_' || c == ':') {
+                break;
+            }
+
+            self.pos += c.len_utf8();
+        }
+        let name = self.text[start..self.pos].to_string();
+        if name.is_empty() {
+            return None;
+        }
+        self.skip_spaces();
+
+        let tail = &self.text[self.pos..];
+        let op_len = if tail.starts_with('=') {
+            1
+        } else if tail.get(1..).map_or(false, |t| t.starts_with('=')) {
+            2
+        } else {
+            0
+        };
+        let op = &tail[..op_len];
+        self.pos += op_len;
+        self.skip_spaces();
+
+        let op = if op.is_empty() {
+            AttributeOperator::Exists
+        } else {
+            let value = self.consume_string()?.to_string();
+            self.skip_spaces();
+            match op {
+                "=" => AttributeOperator::Matches(value),
+                "~=" => AttributeOperator::Contains(value),
+                "|=" => AttributeOperator::StartsWith(value),
+                "^=" => AttributeOperator::Prefix(value),
+                "$=" => AttributeOperator::Suffix(value),
+                "*=" => AttributeOperator::Substring(value),
+                _ => return None,
+            }
+        };
+
+        if self.curr() != Some(']') {
+            return None;
+        }
+        self.pos += 1;
+
+        Some(SubSelector::Attribute(name, op))
+    }
+}
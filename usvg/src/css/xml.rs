@@ -0,0 +1,395 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A minimal XML scanner.
+//!
+//! Extracts elements, their attributes and positions,
+//! which is enough to apply CSS before the actual parsing.
+//! Any non-trivial error will be handled by the real XML parser later.
+
+use std::ops::Range;
+
+use crate::{
+    LimitKind,
+    Limits,
+};
+
+
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+
+
+pub struct Attribute<'a> {
+    /// A qualified name.
+    pub name: &'a str,
+    /// An unescaped value.
+    pub value: String,
+    /// A raw value range, without quotes.
+    pub value_range: Range<usize>,
+}
+
+pub struct Element<'a> {
+    /// A local name.
+    pub name: &'a str,
+    /// The element is in the SVG namespace.
+    ///
+    /// Elements without a namespace are treated as SVG ones.
+    pub is_svg: bool,
+    pub attributes: Vec<Attribute<'a>>,
+    pub parent: Option<usize>,
+    pub prev_sibling: Option<usize>,
+    /// An end position of the tag name. Used to insert new attributes.
+    pub name_end: usize,
+    /// An element range, including children and the end tag.
+    pub range: Range<usize>,
+    /// A children range.
+    pub content: Range<usize>,
+}
+
+impl<'a> Element<'a> {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute<'a>> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+}
+
+
+/// A scanning error.
+pub enum Error {
+    /// The text is not a valid XML.
+    Invalid,
+    /// The elements count or nesting depth limit was exceeded.
+    LimitExceeded(LimitKind),
+}
+
+
+/// Scans the XML text.
+///
+/// The elements count and nesting depth limits are checked during the scanning,
+/// so a malicious document will be rejected before any heavy processing.
+pub fn parse<'a>(text: &'a str, limits: &Limits) -> Result<Vec<Element<'a>>, Error> {
+    let mut exceeded = None;
+    match scan(text, limits, &mut exceeded) {
+        Some(elements) => Ok(elements),
+        None => Err(exceeded.map(Error::LimitExceeded).unwrap_or(Error::Invalid)),
+    }
+}
+
+fn scan<'a>(
+    text: &'a str,
+    limits: &Limits,
+    exceeded: &mut Option<LimitKind>,
+) -> Option<Vec<Element<'a>>> {
+    let max_elements = limits.max_elements.unwrap_or(usize::max_value());
+    let max_depth = limits.max_depth.unwrap_or(usize::max_value());
+
+    let mut elements: Vec<Element> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    // The last child element on each nesting level, including the document one.
+    let mut last_child: Vec<Option<usize>> = vec![None];
+
+    let mut pos = 0;
+    while pos < text.len() {
+        let s = &text[pos..];
+        if !s.starts_with('<') {
+            pos = find(text, pos, "<").unwrap_or(text.len());
+        } else if s.starts_with("<!--") {
+            pos = find(text, pos, "-->")? + 3;
+        } else if s.starts_with("<![CDATA[") {
+            pos = find(text, pos, "]]>")? + 3;
+        } else if s.starts_with("<!") {
+            pos = skip_declaration(text, pos)?;
+        } else if s.starts_with("<?") {
+            pos = find(text, pos, "?>")? + 2;
+        } else if s.starts_with("</") {
+            let end = find(text, pos, ">")?;
+            let idx = stack.pop()?;
+            last_child.pop();
+
+            elements[idx].content.end = pos;
+            elements[idx].range.end = end + 1;
+            pos = end + 1;
+        } else {
+            let start = pos;
+            pos += 1;
+            let name_start = pos;
+            pos = skip_name(text, pos);
+            let qname = &text[name_start..pos];
+            if qname.is_empty() {
+                return None;
+            }
+
+            let name_end = pos;
+            let mut attributes = Vec::new();
+            let is_empty;
+            loop {
+                pos = skip_spaces(text, pos);
+                let s = &text[pos..];
+                if s.starts_with("/>") {
+                    is_empty = true;
+                    pos += 2;
+                    break;
+                } else if s.starts_with('>') {
+                    is_empty = false;
+                    pos += 1;
+                    break;
+                }
+
+                let attr_start = pos;
+                pos = skip_name(text, pos);
+                let name = &text[attr_start..pos];
+                if name.is_empty() {
+                    return None;
+                }
+
+                pos = skip_spaces(text, pos);
+                if !text[pos..].starts_with('=') {
+                    return None;
+                }
+                pos = skip_spaces(text, pos + 1);
+
+                let quote = match text[pos..].chars().next() {
+                    Some(c) if c == '"' || c == '\'' => c,
+                    _ => return None,
+                };
+
+                let value_start = pos + 1;
+                let value_end = value_start + text[value_start..].find(quote)?;
+                attributes.push(Attribute {
+                    name,
+                    value: unescape(&text[value_start..value_end]),
+                    value_range: value_start..value_end,
+                });
+                pos = value_end + 1;
+            }
+
+            let idx = elements.len();
+            if idx + 1 > max_elements {
+                *exceeded = Some(LimitKind::Elements);
+                return None;
+            }
+
+            if stack.len() + 1 > max_depth {
+                *exceeded = Some(LimitKind::Depth);
+                return None;
+            }
+
+            let is_svg = is_svg_element(&elements, stack.last().cloned(), &attributes, qname);
+            elements.push(Element {
+                name: local_name(qname),
+                is_svg,
+                attributes,
+                parent: stack.last().cloned(),
+                prev_sibling: *last_child.last()?,
+                name_end,
+                range: start..pos,
+                content: pos..pos,
+            });
+
+            *last_child.last_mut()? = Some(idx);
+
+            if !is_empty {
+                stack.push(idx);
+                last_child.push(None);
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return None;
+    }
+
+    Some(elements)
+}
+
+/// Returns the text content of the range, including `CDATA`.
+pub fn text_content(text: &str, range: Range<usize>) -> String {
+    let text = &text[range];
+    let mut s = String::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        let tail = &text[pos..];
+        if tail.starts_with("<![CDATA[") {
+            let end = find(text, pos, "]]>").unwrap_or(text.len());
+            s.push_str(&text[(pos + 9)..end]);
+            pos = end + 3;
+        } else if tail.starts_with("<!--") {
+            pos = find(text, pos, "-->").map(|p| p + 3).unwrap_or(text.len());
+        } else if tail.starts_with('<') {
+            // Ignore nested elements.
+            pos = find(text, pos, ">").map(|p| p + 1).unwrap_or(text.len());
+        } else {
+            let end = find(text, pos, "<").unwrap_or(text.len());
+            s.push_str(&unescape(&text[pos..end]));
+            pos = end;
+        }
+    }
+
+    s
+}
+
+/// Escapes a string to be used as an attribute value.
+///
+/// Unknown entity references, like the ones declared in `DOCTYPE`,
+/// are preserved by `unescape`, so they are preserved here too.
+pub fn escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        match c {
+            '&' if is_entity_ref(&text[i..]) => s.push('&'),
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '"' => s.push_str("&quot;"),
+            '\'' => s.push_str("&apos;"),
+            _ => s.push(c),
+        }
+    }
+
+    s
+}
+
+/// Resolves predefined and character entity references.
+///
+/// Unknown references are preserved.
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut s = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        s.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let c = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            name if name.starts_with("#x") => {
+                u32::from_str_radix(&name[2..], 16).ok().and_then(::std::char::from_u32)
+            }
+            name if name.starts_with('#') => {
+                name[1..].parse().ok().and_then(::std::char::from_u32)
+            }
+            _ => None,
+        };
+
+        match c {
+            Some(c) => {
+                s.push(c);
+                rest = &rest[(end + 1)..];
+            }
+            None => {
+                s.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    s.push_str(rest);
+
+    s
+}
+
+/// Checks that the text starts with a named entity reference, like `&name;`.
+fn is_entity_ref(text: &str) -> bool {
+    let end = match text.find(';') {
+        Some(end) => end,
+        None => return false,
+    };
+
+    let name = &text[1..end];
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '.' || c == '-')
+}
+
+fn find(text: &str, pos: usize, pattern: &str) -> Option<usize> {
+    text[pos..].find(pattern).map(|idx| pos + idx)
+}
+
+/// Skips `<!DOCTYPE>` and other declarations, including the internal subset.
+fn skip_declaration(text: &str, pos: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (idx, c) in text[pos..].char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth == 0 => return Some(pos + idx + 1),
+                _ => {}
+            }
+        }
+    }
+
+    None
+}
+
+fn skip_name(text: &str, pos: usize) -> usize {
+    let len = text[pos..].find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+                         .unwrap_or(text.len() - pos);
+    pos + len
+}
+
+fn skip_spaces(text: &str, pos: usize) -> usize {
+    let len = text[pos..].find(|c: char| !c.is_whitespace()).unwrap_or(text.len() - pos);
+    pos + len
+}
+
+/// Checks that the element is in the SVG namespace.
+///
+/// The namespace is resolved using `xmlns` attributes of the element and its ancestors.
+fn is_svg_element(
+    elements: &[Element],
+    parent: Option<usize>,
+    attributes: &[Attribute],
+    qname: &str,
+) -> bool {
+    let prefix = match qname.find(':') {
+        Some(idx) => &qname[..idx],
+        None => "",
+    };
+
+    fn find_ns<'a>(attributes: &'a [Attribute], prefix: &str) -> Option<&'a str> {
+        attributes.iter().find(|a| {
+            if prefix.is_empty() {
+                a.name == "xmlns"
+            } else {
+                a.name.starts_with("xmlns:") && &a.name[6..] == prefix
+            }
+        }).map(|a| a.value.as_str())
+    }
+
+    let mut ns = find_ns(attributes, prefix);
+    let mut parent = parent;
+    while let (None, Some(idx)) = (ns, parent) {
+        ns = find_ns(&elements[idx].attributes, prefix);
+        parent = elements[idx].parent;
+    }
+
+    match ns {
+        Some(uri) => uri == SVG_NS,
+        None => prefix.is_empty(),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    match name.find(':') {
+        Some(idx) => &name[(idx + 1)..],
+        None => name,
+    }
+}
@@ -0,0 +1,381 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversion diagnostics.
+//!
+//! Every problem that was found during an SVG conversion is reported
+//! via the `log` crate and, optionally, collected as a `Diagnostic`.
+//!
+//! Rendering backends can report their own problems via `report_diagnostic`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+
+/// A diagnostic severity.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    /// The problem was recovered from, but some data may be lost.
+    Warning,
+    /// The document cannot be converted properly. An empty tree will be produced.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+
+/// A diagnostic code.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiagnosticCode {
+    /// The document has an invalid structure.
+    InvalidStructure,
+    /// An unsupported element was skipped.
+    UnsupportedElement,
+    /// An attribute has an invalid value or cannot be resolved.
+    InvalidAttribute,
+    /// A shape has an invalid geometry and was skipped.
+    InvalidShape,
+    /// An invalid paint server or color.
+    InvalidPaint,
+    /// An element references an element of an unsupported type.
+    InvalidReference,
+    /// A CSS rule is invalid or unsupported and was skipped.
+    InvalidCss,
+    /// A link to a non-existing element was removed.
+    UnresolvedLink,
+    /// A recursive reference was detected and removed.
+    RecursiveReference,
+    /// A filter primitive cannot be applied and was replaced with an empty image.
+    InvalidFilterPrimitive,
+    /// An image has an invalid or unsupported content.
+    InvalidImage,
+    /// A linked file does not exist.
+    ImageNotFound,
+    /// A resource loading was denied by `ResourceLoader`.
+    ResourceDenied,
+    /// None of the requested font families were found.
+    FontNotFound,
+    /// A font file cannot be loaded.
+    FontLoadFailed,
+    /// A fallback font was used.
+    FontFallback,
+    /// A character or a glyph is not present in the available fonts.
+    MissingGlyph,
+    /// Text layouting failed.
+    TextLayoutFailed,
+    /// A blending mode is not supported by the rendering backend
+    /// and was replaced with `normal`.
+    UnsupportedBlendMode,
+}
+
+impl DiagnosticCode {
+    /// Returns a code name.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            DiagnosticCode::InvalidStructure => "invalid-structure",
+            DiagnosticCode::UnsupportedElement => "unsupported-element",
+            DiagnosticCode::InvalidAttribute => "invalid-attribute",
+            DiagnosticCode::InvalidShape => "invalid-shape",
+            DiagnosticCode::InvalidPaint => "invalid-paint",
+            DiagnosticCode::InvalidReference => "invalid-reference",
+            DiagnosticCode::InvalidCss => "invalid-css",
+            DiagnosticCode::UnresolvedLink => "unresolved-link",
+            DiagnosticCode::RecursiveReference => "recursive-reference",
+            DiagnosticCode::InvalidFilterPrimitive => "invalid-filter-primitive",
+            DiagnosticCode::InvalidImage => "invalid-image",
+            DiagnosticCode::ImageNotFound => "image-not-found",
+            DiagnosticCode::ResourceDenied => "resource-denied",
+            DiagnosticCode::FontNotFound => "font-not-found",
+            DiagnosticCode::FontLoadFailed => "font-load-failed",
+            DiagnosticCode::FontFallback => "font-fallback",
+            DiagnosticCode::MissingGlyph => "missing-glyph",
+            DiagnosticCode::TextLayoutFailed => "text-layout-failed",
+            DiagnosticCode::UnsupportedBlendMode => "unsupported-blend-mode",
+        }
+    }
+
+    /// Checks that the diagnostic indicates that some of the SVG data
+    /// cannot be rendered as intended.
+    ///
+    /// Invalid attributes, shapes and paints are not lossy,
+    /// because the SVG spec defines a fallback behavior for them.
+    /// The same goes for font fallback.
+    ///
+    /// Used by the strict mode.
+    pub fn is_lossy(&self) -> bool {
+        match *self {
+            DiagnosticCode::InvalidAttribute |
+            DiagnosticCode::InvalidShape |
+            DiagnosticCode::InvalidPaint |
+            DiagnosticCode::FontFallback => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+
+/// A position in the source SVG data.
+///
+/// Both values start from 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[allow(missing_docs)]
+pub struct SourcePos {
+    pub row: u32,
+    pub col: u32,
+}
+
+
+/// A conversion diagnostic.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    /// A diagnostic code.
+    pub code: DiagnosticCode,
+
+    /// A diagnostic severity.
+    pub severity: Severity,
+
+    /// An ID of the element that caused the diagnostic.
+    ///
+    /// `None` when the element doesn't have an ID or
+    /// when the diagnostic is not related to a specific element.
+    pub element_id: Option<String>,
+
+    /// A position of the element in the source data.
+    ///
+    /// `None` when the element doesn't have an ID, was created during the conversion
+    /// or when the tree was created from an `svgdom::Document`.
+    pub position: Option<SourcePos>,
+
+    /// A human-readable message.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+
+thread_local! {
+    static COLLECTORS: RefCell<Vec<Vec<Diagnostic>>> = RefCell::new(Vec::new());
+
+    // Elements positions by ID in the currently converted document.
+    static POSITIONS: RefCell<Option<HashMap<String, SourcePos>>> = RefCell::new(None);
+}
+
+/// Collects all diagnostics produced by `f`.
+///
+/// Diagnostics are collected per thread. Nested calls are allowed
+/// and an outer call will receive diagnostics from an inner one too.
+///
+/// # Examples
+///
+/// ```no_run
+/// let opt = usvg::Options::default();
+/// let (tree, diagnostics) = usvg::collect_diagnostics(|| {
+///     usvg::Tree::from_file("image.svg", &opt)
+/// });
+///
+/// for d in &diagnostics {
+///     println!("{}", d);
+/// }
+/// ```
+pub fn collect_diagnostics<F, R>(f: F) -> (R, Vec<Diagnostic>)
+    where F: FnOnce() -> R
+{
+    COLLECTORS.with(|c| c.borrow_mut().push(Vec::new()));
+    let res = f();
+    let list = COLLECTORS.with(|c| {
+        let mut collectors = c.borrow_mut();
+        let list = collectors.pop().unwrap_or_default();
+        if let Some(parent) = collectors.last_mut() {
+            parent.extend_from_slice(&list);
+        }
+
+        list
+    });
+
+    (res, list)
+}
+
+/// Runs `f` and discards all diagnostics produced by it.
+///
+/// Used when the same data is processed more than once.
+pub(crate) fn without_diagnostics<F, R>(f: F) -> R
+    where F: FnOnce() -> R
+{
+    COLLECTORS.with(|c| c.borrow_mut().push(Vec::new()));
+    let res = f();
+    COLLECTORS.with(|c| c.borrow_mut().pop());
+    res
+}
+
+/// Runs `f` with a separate elements positions scope.
+pub(crate) fn with_positions<F, R>(f: F) -> R
+    where F: FnOnce() -> R
+{
+    let prev = POSITIONS.with(|p| p.replace(None));
+    let res = f();
+    POSITIONS.with(|p| *p.borrow_mut() = prev);
+    res
+}
+
+/// Sets elements positions for the current scope.
+///
+/// `f` is called only when diagnostics are collected and positions were not set yet,
+/// so an external document will not override the main one.
+pub(crate) fn set_positions<F>(f: F)
+    where F: FnOnce() -> HashMap<String, SourcePos>
+{
+    let is_collecting = COLLECTORS.with(|c| !c.borrow().is_empty());
+    if !is_collecting {
+        return;
+    }
+
+    POSITIONS.with(|p| {
+        let mut p = p.borrow_mut();
+        if p.is_none() {
+            *p = Some(f());
+        }
+    });
+}
+
+fn find_position(element_id: &str) -> Option<SourcePos> {
+    POSITIONS.with(|p| p.borrow().as_ref().and_then(|map| map.get(element_id).cloned()))
+}
+
+/// Reports a diagnostic.
+///
+/// Can be used by renderers to report problems that cannot be detected
+/// during the conversion, like an unsupported blending mode.
+///
+/// Does nothing when called outside of `collect_diagnostics`.
+pub fn report_diagnostic(
+    code: DiagnosticCode,
+    severity: Severity,
+    element_id: &str,
+    message: String,
+) {
+    COLLECTORS.with(|c| {
+        if let Some(list) = c.borrow_mut().last_mut() {
+            list.push(Diagnostic {
+                code,
+                severity,
+                element_id: if element_id.is_empty() { None } else { Some(element_id.to_string()) },
+                position: if element_id.is_empty() { None } else { find_position(element_id) },
+                message,
+            });
+        }
+    });
+}
+
+/// Converts diagnostics into a JSON array.
+///
+/// Each diagnostic is represented as an object with `code`, `severity`,
+/// `element_id`, `position` and `message` keys.
+pub fn diagnostics_to_json(list: &[Diagnostic]) -> String {
+    let mut s = String::from("[");
+    for (i, d) in list.iter().enumerate() {
+        if i != 0 {
+            s.push(',');
+        }
+
+        s.push_str("\n  {\"code\": \"");
+        s.push_str(d.code.as_str());
+        s.push_str("\", \"severity\": \"");
+        s.push_str(&d.severity.to_string());
+        s.push_str("\", \"element_id\": ");
+        match d.element_id {
+            Some(ref id) => write_json_str(id, &mut s),
+            None => s.push_str("null"),
+        }
+        s.push_str(", \"position\": ");
+        match d.position {
+            Some(pos) => s.push_str(&format!("{{\"row\": {}, \"col\": {}}}", pos.row, pos.col)),
+            None => s.push_str("null"),
+        }
+        s.push_str(", \"message\": ");
+        write_json_str(&d.message, &mut s);
+        s.push('}');
+    }
+
+    if !list.is_empty() {
+        s.push('\n');
+    }
+    s.push_str("]\n");
+
+    s
+}
+
+fn write_json_str(text: &str, s: &mut String) {
+    s.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_nested() {
+        let (_, outer) = collect_diagnostics(|| {
+            report_diagnostic(DiagnosticCode::InvalidShape, Severity::Warning, "rect1", "outer".to_string());
+            let (_, inner) = collect_diagnostics(|| {
+                report_diagnostic(DiagnosticCode::InvalidPaint, Severity::Warning, "", "inner".to_string());
+            });
+            assert_eq!(inner.len(), 1);
+            assert_eq!(inner[0].element_id, None);
+        });
+
+        assert_eq!(outer.len(), 2);
+        assert_eq!(outer[0].element_id, Some("rect1".to_string()));
+        assert_eq!(outer[1].code, DiagnosticCode::InvalidPaint);
+    }
+
+    #[test]
+    fn to_json() {
+        let list = vec![Diagnostic {
+            code: DiagnosticCode::InvalidShape,
+            severity: Severity::Warning,
+            element_id: Some("r\"1".to_string()),
+            position: None,
+            message: "Skipped.\n".to_string(),
+        }];
+
+        assert_eq!(diagnostics_to_json(&list),
+"[
+  {\"code\": \"invalid-shape\", \"severity\": \"warning\", \"element_id\": \"r\\\"1\", \
+\"position\": null, \"message\": \"Skipped.\\n\"}
+]
+");
+        assert_eq!(diagnostics_to_json(&[]), "[]\n");
+    }
+}
@@ -7,6 +7,8 @@ use std::fmt;
 
 use svgdom;
 
+use crate::Diagnostic;
+
 /// List of all errors.
 #[derive(Debug)]
 pub enum Error {
@@ -26,12 +28,21 @@ pub enum Error {
     ///
     /// Occurs when width and/or height are <= 0.
     ///
-    /// Also occurs if width, height and viewBox are not set.
-    /// This is against the SVG spec, but an automatic size detection is not supported yet.
+    /// Also occurs if width, height and viewBox are not set
+    /// and the document doesn't have any renderable elements,
+    /// so the size cannot be detected from the content bounding box.
     InvalidSize,
 
     /// Failed to parse an SVG data.
     ParsingFailed(svgdom::ParserError),
+
+    /// SVG cannot be converted without losses.
+    ///
+    /// Occurs only in the strict mode. Contains all lossy diagnostics.
+    Unsupported(Vec<Diagnostic>),
+
+    /// One of the `Limits` was exceeded.
+    LimitExceeded(LimitKind),
 }
 
 impl fmt::Display for Error {
@@ -55,6 +66,50 @@ impl fmt::Display for Error {
             Error::ParsingFailed(ref e) => {
                 write!(f, "SVG data parsing failed cause {}", e)
             }
+            Error::Unsupported(ref list) => {
+                write!(f, "SVG cannot be converted without losses:")?;
+                for d in list {
+                    match d.element_id {
+                        Some(ref id) => write!(f, "\n  #{}: {}", id, d.message)?,
+                        None => write!(f, "\n  {}", d.message)?,
+                    }
+                }
+
+                Ok(())
+            }
+            Error::LimitExceeded(kind) => {
+                write!(f, "the {} limit was exceeded", kind)
+            }
+        }
+    }
+}
+
+/// A kind of an exceeded limit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LimitKind {
+    /// `Limits::max_elements`
+    Elements,
+    /// `Limits::max_depth`
+    Depth,
+    /// `Limits::max_use_expansion`
+    UseExpansion,
+    /// `Limits::max_image_size`
+    ImageSize,
+    /// `Limits::max_pixels`
+    Pixels,
+    /// `Limits::max_filter_region`
+    FilterRegion,
+}
+
+impl fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LimitKind::Elements => write!(f, "elements count"),
+            LimitKind::Depth => write!(f, "nesting depth"),
+            LimitKind::UseExpansion => write!(f, "'use' expansion"),
+            LimitKind::ImageSize => write!(f, "image size"),
+            LimitKind::Pixels => write!(f, "pixels count"),
+            LimitKind::FilterRegion => write!(f, "filter region"),
         }
     }
 }
@@ -39,7 +39,7 @@ and an XML library with some small amount of code.
 - No units (mm, em, etc.)
 - No comments
 - No DTD
-- No CSS (partial support)
+- No CSS
 - No `script` (simply ignoring it)
 
 Full spec can be found [here](https://github.com/RazrFalcon/usvg/blob/master/docs/usvg_spec.adoc).
@@ -47,7 +47,7 @@ Full spec can be found [here](https://github.com/RazrFalcon/usvg/blob/master/doc
 ## Limitations
 
 - Currently, it's not lossless. Some SVG features isn't supported yet and will be ignored.
-- CSS support is minimal.
+- CSS support is limited to CSS 2.1 selectors and `!important`.
 - Scripting and animation isn't supported and not planned.
 - `a` elements will be removed.
 - Unsupported elements:
@@ -134,9 +134,40 @@ macro_rules! try_opt_warn_or {
     };
 }
 
+/// Emits a warning and reports it as a `Diagnostic`.
+///
+/// Usage: `diag!(Severity, DiagnosticCode, element_id, fmt, args...)`.
+macro_rules! diag {
+    ($severity:ident, $code:ident, $id:expr, $($arg:tt)+) => {{
+        let message = format!($($arg)+);
+        log::warn!("{}", message);
+        $crate::diagnostics::report_diagnostic(
+            $crate::diagnostics::DiagnosticCode::$code,
+            $crate::diagnostics::Severity::$severity,
+            &$id,
+            message,
+        );
+    }};
+}
+
+/// Unwraps `Option` and invokes `return $ret` on `None` with a warning `Diagnostic`.
+macro_rules! try_opt_diag_or {
+    ($task:expr, $ret:expr, $code:ident, $id:expr, $($arg:tt)+) => {
+        match $task {
+            Some(v) => v,
+            None => {
+                diag!(Warning, $code, $id, $($arg)+);
+                return $ret;
+            }
+        }
+    };
+}
+
 
 pub mod utils;
 mod convert;
+mod css;
+mod diagnostics;
 mod error;
 mod geom;
 mod options;
@@ -153,6 +184,7 @@ mod short {
 }
 
 
+pub use crate::diagnostics::*;
 pub use crate::error::*;
 pub use crate::geom::*;
 pub use crate::options::*;
@@ -2,7 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fmt;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::{
     ImageRendering,
@@ -11,6 +13,80 @@ use crate::{
 };
 
 
+/// A resource kind.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResourceKind {
+    /// An image referenced by the `image` or `feImage` element.
+    Image,
+    /// A font file.
+    Font,
+    /// An external SVG document referenced by the `use` element.
+    Document,
+}
+
+
+/// A resource loading result.
+#[derive(Clone, Debug)]
+pub enum Resource {
+    /// Resource data.
+    Data(Vec<u8>),
+    /// Load resource using the default method.
+    ///
+    /// Files will be read from the disk relative to `Options::path`
+    /// and `data:` URLs will be decoded.
+    Default,
+    /// Resource loading is not allowed.
+    Denied,
+}
+
+
+/// A resource loader.
+///
+/// Receives every resource reference found during the conversion.
+pub trait ResourceLoader: fmt::Debug {
+    /// Loads a resource.
+    ///
+    /// `href` is the `xlink:href` attribute value as is: a file path, a `data:` URL
+    /// or an arbitrary URI. For fonts, it's a path to a system font file.
+    /// For external SVG documents, it's a path without the `#id` fragment.
+    /// References from nested external documents are resolved relative to
+    /// the referencing document, like `icons/shapes.svg`.
+    fn load(&self, href: &str, kind: ResourceKind) -> Resource;
+}
+
+
+/// Resource limits.
+///
+/// Should be used to process untrusted SVG files.
+///
+/// All limits are disabled by default.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Limits {
+    /// Maximum number of nodes in the document.
+    pub max_elements: Option<usize>,
+
+    /// Maximum nodes nesting depth.
+    pub max_depth: Option<usize>,
+
+    /// Maximum number of nodes that can be created by `use` elements resolving.
+    pub max_use_expansion: Option<usize>,
+
+    /// Maximum size of an image data in bytes.
+    pub max_image_size: Option<u64>,
+
+    /// Maximum number of pixels in the output image.
+    ///
+    /// Also applied to the decoded size of raster images.
+    pub max_pixels: Option<u64>,
+
+    /// Maximum number of pixels in a filter region.
+    ///
+    /// Used only by `resvg`.
+    /// Rendering to an image will fail when any filter exceeds it.
+    pub max_filter_region: Option<u64>,
+}
+
+
 /// Processing options.
 #[derive(Clone, Debug)]
 pub struct Options {
@@ -56,6 +132,38 @@ pub struct Options {
     /// If set to `true`, all non-empty groups with `id` attribute will not
     /// be removed.
     pub keep_named_groups: bool,
+
+    /// Enables the strict mode.
+    ///
+    /// If set to `true`, a conversion that would lose some of the SVG data
+    /// will fail with `Error::Unsupported` instead of producing an incomplete tree.
+    ///
+    /// See `DiagnosticCode::is_lossy` for details.
+    pub strict: bool,
+
+    /// A resource loader.
+    ///
+    /// If not set, all resources will be loaded using the default method.
+    ///
+    /// The loader must be `Send + Sync`, so `Options` can be shared between threads.
+    pub resource_loader: Option<Arc<dyn ResourceLoader + Send + Sync>>,
+
+    /// Resource limits.
+    pub limits: Limits,
+
+    /// A user style sheet.
+    ///
+    /// Applied after the style sheets from the SVG file, so it can be used
+    /// to restyle elements, like `.primary { fill: red }`.
+    /// The `style` attribute still has a higher priority, unless `!important` is used.
+    pub style_sheet: Option<String>,
+
+    /// CSS custom properties.
+    ///
+    /// Set on the root element and override the ones declared there,
+    /// so they can be used to theme an image via `var()`.
+    /// Names can be set with or without the `--` prefix.
+    pub css_variables: Vec<(String, String)>,
 }
 
 impl Default for Options {
@@ -71,6 +179,11 @@ impl Default for Options {
             text_rendering: TextRendering::default(),
             image_rendering: ImageRendering::default(),
             keep_named_groups: false,
+            strict: false,
+            resource_loader: None,
+            limits: Limits::default(),
+            style_sheet: None,
+            css_variables: Vec::new(),
         }
     }
 }
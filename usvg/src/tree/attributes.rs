@@ -249,6 +249,11 @@ pub struct Stroke {
     pub width: StrokeWidth,
     pub linecap: LineCap,
     pub linejoin: LineJoin,
+    /// Indicates that the stroke should be rendered in the device space,
+    /// so its width is not affected by any transform.
+    ///
+    /// `vector-effect="non-scaling-stroke"` in SVG.
+    pub non_scaling: bool,
 }
 
 impl Default for Stroke {
@@ -264,6 +269,7 @@ impl Default for Stroke {
             width: StrokeWidth::default(),
             linecap: LineCap::default(),
             linejoin: LineJoin::default(),
+            non_scaling: false,
         }
     }
 }
@@ -331,6 +337,16 @@ enum_to_string!(FilterInput,
 );
 
 
+/// An `enable-background` property.
+///
+/// Only the `new` value is stored, because `accumulate` is the default one.
+/// Contains an optional clipping rect from the `new <x> <y> <width> <height>` value.
+///
+/// `enable-background` attribute in the SVG.
+#[derive(Clone, Copy, Debug)]
+pub struct EnableBackground(pub Option<Rect>);
+
+
 /// A color interpolation mode.
 #[allow(missing_docs)]
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -428,6 +444,357 @@ enum_to_string!(FeCompositeOperator,
 );
 
 
+/// A color matrix filter primitive kind.
+#[derive(Clone, PartialEq, Debug)]
+#[allow(missing_docs)]
+pub enum FeColorMatrixKind {
+    /// A 4x5 matrix in a row-major order.
+    ///
+    /// Always contains 20 values.
+    Matrix(Vec<f64>),
+    Saturate(PositiveNumber),
+    HueRotate(f64),
+    LuminanceToAlpha,
+}
+
+impl Default for FeColorMatrixKind {
+    fn default() -> Self {
+        FeColorMatrixKind::Matrix(vec![
+            1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ])
+    }
+}
+
+enum_to_string!(FeColorMatrixKind,
+    FeColorMatrixKind::Matrix(..)       => "matrix",
+    FeColorMatrixKind::Saturate(..)     => "saturate",
+    FeColorMatrixKind::HueRotate(..)    => "hueRotate",
+    FeColorMatrixKind::LuminanceToAlpha => "luminanceToAlpha"
+);
+
+
+/// A transfer function used by `FeComponentTransfer`.
+///
+/// <https://www.w3.org/TR/SVG11/filters.html#transferFuncElements>
+#[derive(Clone, PartialEq, Debug)]
+pub enum TransferFunction {
+    /// Keeps a component as is.
+    Identity,
+
+    /// Applies a linear interpolation to a component.
+    ///
+    /// The number list can be empty.
+    Table(Vec<f64>),
+
+    /// Applies a step function to a component.
+    ///
+    /// The number list can be empty.
+    Discrete(Vec<f64>),
+
+    /// Applies a linear shift to a component.
+    #[allow(missing_docs)]
+    Linear {
+        slope: f64,
+        intercept: f64,
+    },
+
+    /// Applies an exponential shift to a component.
+    #[allow(missing_docs)]
+    Gamma {
+        amplitude: f64,
+        exponent: f64,
+        offset: f64,
+    },
+}
+
+enum_default!(TransferFunction, Identity);
+
+enum_to_string!(TransferFunction,
+    TransferFunction::Identity      => "identity",
+    TransferFunction::Table(..)     => "table",
+    TransferFunction::Discrete(..)  => "discrete",
+    TransferFunction::Linear { .. } => "linear",
+    TransferFunction::Gamma { .. }  => "gamma"
+);
+
+impl TransferFunction {
+    /// Checks that the function doesn't affect a component.
+    pub fn is_dummy(&self) -> bool {
+        match *self {
+            TransferFunction::Identity => true,
+            TransferFunction::Table(ref v) => v.is_empty(),
+            TransferFunction::Discrete(ref v) => v.is_empty(),
+            TransferFunction::Linear { slope, intercept } => {
+                slope.fuzzy_eq(&1.0) && intercept.is_fuzzy_zero()
+            }
+            TransferFunction::Gamma { amplitude, exponent, offset } => {
+                amplitude.fuzzy_eq(&1.0) && exponent.fuzzy_eq(&1.0) && offset.is_fuzzy_zero()
+            }
+        }
+    }
+}
+
+
+/// A morphology operation.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FeMorphologyOperator {
+    Erode,
+    Dilate,
+}
+
+enum_to_string!(FeMorphologyOperator,
+    FeMorphologyOperator::Erode     => "erode",
+    FeMorphologyOperator::Dilate    => "dilate"
+);
+
+
+/// A convolve matrix representation.
+///
+/// Used primarily by `FeConvolveMatrix`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConvolveMatrix {
+    x: u32,
+    y: u32,
+    columns: u32,
+    rows: u32,
+    data: Vec<f64>,
+}
+
+impl ConvolveMatrix {
+    /// Creates a new `ConvolveMatrix`.
+    ///
+    /// Returns `None` when:
+    ///
+    /// - `columns` * `rows` != `data.len()`
+    /// - `target_x` >= `columns`
+    /// - `target_y` >= `rows`
+    pub fn new(target_x: u32, target_y: u32, columns: u32, rows: u32, data: Vec<f64>) -> Option<Self> {
+        if columns as usize * rows as usize != data.len()
+           || target_x >= columns
+           || target_y >= rows
+        {
+            return None;
+        }
+
+        Some(ConvolveMatrix {
+            x: target_x,
+            y: target_y,
+            columns,
+            rows,
+            data,
+        })
+    }
+
+    /// Returns a matrix's X target.
+    ///
+    /// `targetX` in the SVG.
+    #[inline]
+    pub fn target_x(&self) -> u32 {
+        self.x
+    }
+
+    /// Returns a matrix's Y target.
+    ///
+    /// `targetY` in the SVG.
+    #[inline]
+    pub fn target_y(&self) -> u32 {
+        self.y
+    }
+
+    /// Returns a number of columns in the matrix.
+    ///
+    /// Part of the `order` attribute in the SVG.
+    #[inline]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    /// Returns a number of rows in the matrix.
+    ///
+    /// Part of the `order` attribute in the SVG.
+    #[inline]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Returns a matrix value at the specified position.
+    ///
+    /// # Panics
+    ///
+    /// - When position is out of bounds.
+    #[inline]
+    pub fn get(&self, x: u32, y: u32) -> f64 {
+        self.data[(y * self.columns + x) as usize]
+    }
+
+    /// Returns a reference to an internal data.
+    ///
+    /// `kernelMatrix` in the SVG.
+    #[inline]
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+}
+
+
+/// An edges processing mode.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EdgeMode {
+    None,
+    Duplicate,
+    Wrap,
+}
+
+enum_default!(EdgeMode, Duplicate);
+
+enum_to_string!(EdgeMode,
+    EdgeMode::None      => "none",
+    EdgeMode::Duplicate => "duplicate",
+    EdgeMode::Wrap      => "wrap"
+);
+
+
+/// A color channel.
+///
+/// Used by `FeDisplacementMap`.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorChannel {
+    R,
+    G,
+    B,
+    A,
+}
+
+enum_default!(ColorChannel, A);
+
+enum_to_string!(ColorChannel,
+    ColorChannel::R => "R",
+    ColorChannel::G => "G",
+    ColorChannel::B => "B",
+    ColorChannel::A => "A"
+);
+
+
+/// A light source kind.
+///
+/// Used by `FeDiffuseLighting` and `FeSpecularLighting`.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FeLightSource {
+    FeDistantLight(FeDistantLight),
+    FePointLight(FePointLight),
+    FeSpotLight(FeSpotLight),
+}
+
+
+/// A distant light source.
+///
+/// `feDistantLight` element in the SVG.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FeDistantLight {
+    /// Direction angle for the light source on the XY plane (clockwise),
+    /// in degrees from the x axis.
+    ///
+    /// `azimuth` in the SVG.
+    pub azimuth: f64,
+
+    /// Direction angle for the light source from the XY plane towards the z axis,
+    /// in degrees.
+    ///
+    /// `elevation` in the SVG.
+    pub elevation: f64,
+}
+
+
+/// A point light source.
+///
+/// `fePointLight` element in the SVG.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FePointLight {
+    /// X location for the light source.
+    ///
+    /// `x` in the SVG.
+    pub x: f64,
+
+    /// Y location for the light source.
+    ///
+    /// `y` in the SVG.
+    pub y: f64,
+
+    /// Z location for the light source.
+    ///
+    /// `z` in the SVG.
+    pub z: f64,
+}
+
+
+/// A spot light source.
+///
+/// `feSpotLight` element in the SVG.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FeSpotLight {
+    /// X location for the light source.
+    ///
+    /// `x` in the SVG.
+    pub x: f64,
+
+    /// Y location for the light source.
+    ///
+    /// `y` in the SVG.
+    pub y: f64,
+
+    /// Z location for the light source.
+    ///
+    /// `z` in the SVG.
+    pub z: f64,
+
+    /// X point at which the light source is pointing.
+    ///
+    /// `pointsAtX` in the SVG.
+    pub points_at_x: f64,
+
+    /// Y point at which the light source is pointing.
+    ///
+    /// `pointsAtY` in the SVG.
+    pub points_at_y: f64,
+
+    /// Z point at which the light source is pointing.
+    ///
+    /// `pointsAtZ` in the SVG.
+    pub points_at_z: f64,
+
+    /// Exponent value controlling the focus for the light source.
+    ///
+    /// `specularExponent` in the SVG.
+    pub specular_exponent: PositiveNumber,
+
+    /// A limiting cone which restricts the region where the light is projected.
+    ///
+    /// `limitingConeAngle` in the SVG.
+    pub limiting_cone_angle: Option<f64>,
+}
+
+
+/// A turbulence function kind.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FeTurbulenceKind {
+    FractalNoise,
+    Turbulence,
+}
+
+enum_to_string!(FeTurbulenceKind,
+    FeTurbulenceKind::FractalNoise  => "fractalNoise",
+    FeTurbulenceKind::Turbulence    => "turbulence"
+);
+
+
 /// Kind of the `feImage` data.
 #[derive(Clone, Debug)]
 pub enum FeImageKind {
@@ -444,9 +811,10 @@ pub enum FeImageKind {
     /// A reference to an SVG object.
     ///
     /// `feImage` can reference any SVG object, just like `use` element.
-    /// But we can't resolve `use` in this case.
+    /// The referenced element is converted into a group inside `defs`
+    /// and this is the ID of this group.
     ///
-    /// Not supported yet.
+    /// The group should be rendered in the user space of the filtered element.
     Use(String),
 }
 
@@ -477,6 +845,100 @@ enum_to_string!(ShapeRendering,
 );
 
 
+/// A paint order.
+///
+/// `paint-order` attribute in the SVG.
+///
+/// Markers order is resolved during the conversion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PaintOrder {
+    /// Fill first, stroke second.
+    FillAndStroke,
+    /// Stroke first, fill second.
+    StrokeAndFill,
+}
+
+enum_default!(PaintOrder, FillAndStroke);
+
+
+/// A group blending mode.
+///
+/// `mix-blend-mode` attribute in the SVG.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    /// Checks that the blending mode operates on each color channel separately.
+    pub fn is_separable(&self) -> bool {
+        match *self {
+              BlendMode::Hue
+            | BlendMode::Saturation
+            | BlendMode::Color
+            | BlendMode::Luminosity => false,
+            _ => true,
+        }
+    }
+}
+
+enum_default!(BlendMode, Normal);
+
+enum_from_str!(BlendMode,
+    "normal"        => BlendMode::Normal,
+    "multiply"      => BlendMode::Multiply,
+    "screen"        => BlendMode::Screen,
+    "overlay"       => BlendMode::Overlay,
+    "darken"        => BlendMode::Darken,
+    "lighten"       => BlendMode::Lighten,
+    "color-dodge"   => BlendMode::ColorDodge,
+    "color-burn"    => BlendMode::ColorBurn,
+    "hard-light"    => BlendMode::HardLight,
+    "soft-light"    => BlendMode::SoftLight,
+    "difference"    => BlendMode::Difference,
+    "exclusion"     => BlendMode::Exclusion,
+    "hue"           => BlendMode::Hue,
+    "saturation"    => BlendMode::Saturation,
+    "color"         => BlendMode::Color,
+    "luminosity"    => BlendMode::Luminosity
+);
+
+enum_to_string!(BlendMode,
+    BlendMode::Normal       => "normal",
+    BlendMode::Multiply     => "multiply",
+    BlendMode::Screen       => "screen",
+    BlendMode::Overlay      => "overlay",
+    BlendMode::Darken       => "darken",
+    BlendMode::Lighten      => "lighten",
+    BlendMode::ColorDodge   => "color-dodge",
+    BlendMode::ColorBurn    => "color-burn",
+    BlendMode::HardLight    => "hard-light",
+    BlendMode::SoftLight    => "soft-light",
+    BlendMode::Difference   => "difference",
+    BlendMode::Exclusion    => "exclusion",
+    BlendMode::Hue          => "hue",
+    BlendMode::Saturation   => "saturation",
+    BlendMode::Color        => "color",
+    BlendMode::Luminosity   => "luminosity"
+);
+
+
 /// A text rendering method.
 ///
 /// `text-rendering` attribute in the SVG.
@@ -8,7 +8,6 @@ use svgdom::{
     self,
     NumberList,
 };
-use log::warn;
 
 // self
 use super::*;
@@ -179,6 +178,90 @@ fn conv_defs(
 
                             fe_elem
                         }
+                        FilterKind::FeColorMatrix(ref matrix) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeColorMatrix);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::Type, matrix.kind.to_string()));
+
+                            match matrix.kind {
+                                FeColorMatrixKind::Matrix(ref values) => {
+                                    fe_elem.set_attribute((AId::Values, NumberList(values.clone())));
+                                }
+                                FeColorMatrixKind::Saturate(n) => {
+                                    fe_elem.set_attribute((AId::Values, NumberList(vec![n.value()])));
+                                }
+                                FeColorMatrixKind::HueRotate(n) => {
+                                    fe_elem.set_attribute((AId::Values, NumberList(vec![n])));
+                                }
+                                FeColorMatrixKind::LuminanceToAlpha => {}
+                            }
+
+                            fe_elem.set_attribute((AId::In, matrix.input.to_string()));
+
+                            fe_elem
+                        }
+                        FilterKind::FeComponentTransfer(ref transfer) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeComponentTransfer);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::In, transfer.input.to_string()));
+
+                            conv_transfer_function(EId::FeFuncR, &transfer.func_r, new_doc, &mut fe_elem);
+                            conv_transfer_function(EId::FeFuncG, &transfer.func_g, new_doc, &mut fe_elem);
+                            conv_transfer_function(EId::FeFuncB, &transfer.func_b, new_doc, &mut fe_elem);
+                            conv_transfer_function(EId::FeFuncA, &transfer.func_a, new_doc, &mut fe_elem);
+
+                            fe_elem
+                        }
+                        FilterKind::FeConvolveMatrix(ref convolve) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeConvolveMatrix);
+                            filter_elem.append(fe_elem.clone());
+
+                            let order = NumberList(vec![
+                                convolve.matrix.columns() as f64,
+                                convolve.matrix.rows() as f64,
+                            ]);
+                            fe_elem.set_attribute((AId::Order, order));
+                            fe_elem.set_attribute((AId::KernelMatrix, NumberList(convolve.matrix.data().to_vec())));
+                            fe_elem.set_attribute((AId::Divisor, convolve.divisor));
+                            fe_elem.set_attribute((AId::Bias, convolve.bias));
+                            fe_elem.set_attribute((AId::TargetX, convolve.matrix.target_x() as f64));
+                            fe_elem.set_attribute((AId::TargetY, convolve.matrix.target_y() as f64));
+                            fe_elem.set_attribute((AId::EdgeMode, convolve.edge_mode.to_string()));
+                            fe_elem.set_attribute((AId::PreserveAlpha, convolve.preserve_alpha.to_string()));
+
+                            fe_elem.set_attribute((AId::In, convolve.input.to_string()));
+
+                            fe_elem
+                        }
+                        FilterKind::FeDiffuseLighting(ref light) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeDiffuseLighting);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::SurfaceScale, light.surface_scale));
+                            fe_elem.set_attribute((AId::DiffuseConstant, light.diffuse_constant.value()));
+                            conv_kernel_unit_length(light.kernel_unit_length, &mut fe_elem);
+                            fe_elem.set_attribute((AId::LightingColor, light.lighting_color));
+                            conv_light_source(&light.light_source, new_doc, &mut fe_elem);
+
+                            fe_elem.set_attribute((AId::In, light.input.to_string()));
+
+                            fe_elem
+                        }
+                        FilterKind::FeDisplacementMap(ref map) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeDisplacementMap);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::Scale, map.scale));
+                            fe_elem.set_attribute((AId::XChannelSelector, map.x_channel_selector.to_string()));
+                            fe_elem.set_attribute((AId::YChannelSelector, map.y_channel_selector.to_string()));
+
+                            fe_elem.set_attribute((AId::In, map.input1.to_string()));
+                            fe_elem.set_attribute((AId::In2, map.input2.to_string()));
+
+                            fe_elem
+                        }
                         FilterKind::FeFlood(ref flood) => {
                             let mut fe_elem = new_doc.create_element(EId::FeFlood);
                             filter_elem.append(fe_elem.clone());
@@ -222,6 +305,37 @@ fn conv_defs(
 
                             fe_elem
                         }
+                        FilterKind::FeMorphology(ref morphology) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeMorphology);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::Operator, morphology.operator.to_string()));
+
+                            let radius = NumberList(vec![
+                                morphology.radius_x.value(),
+                                morphology.radius_y.value()
+                            ]);
+                            fe_elem.set_attribute((AId::Radius, radius));
+
+                            fe_elem.set_attribute((AId::In, morphology.input.to_string()));
+
+                            fe_elem
+                        }
+                        FilterKind::FeSpecularLighting(ref light) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeSpecularLighting);
+                            filter_elem.append(fe_elem.clone());
+
+                            fe_elem.set_attribute((AId::SurfaceScale, light.surface_scale));
+                            fe_elem.set_attribute((AId::SpecularConstant, light.specular_constant.value()));
+                            fe_elem.set_attribute((AId::SpecularExponent, light.specular_exponent));
+                            conv_kernel_unit_length(light.kernel_unit_length, &mut fe_elem);
+                            fe_elem.set_attribute((AId::LightingColor, light.lighting_color));
+                            conv_light_source(&light.light_source, new_doc, &mut fe_elem);
+
+                            fe_elem.set_attribute((AId::In, light.input.to_string()));
+
+                            fe_elem
+                        }
                         FilterKind::FeTile(ref tile) => {
                             let mut fe_elem = new_doc.create_element(EId::FeTile);
                             filter_elem.append(fe_elem.clone());
@@ -230,6 +344,24 @@ fn conv_defs(
 
                             fe_elem
                         }
+                        FilterKind::FeTurbulence(ref turbulence) => {
+                            let mut fe_elem = new_doc.create_element(EId::FeTurbulence);
+                            filter_elem.append(fe_elem.clone());
+
+                            let base_frequency = NumberList(vec![
+                                turbulence.base_frequency_x.value(),
+                                turbulence.base_frequency_y.value()
+                            ]);
+                            fe_elem.set_attribute((AId::BaseFrequency, base_frequency));
+                            fe_elem.set_attribute((AId::NumOctaves, turbulence.num_octaves as f64));
+                            fe_elem.set_attribute((AId::Seed, turbulence.seed as f64));
+
+                            let stitch_tiles = if turbulence.stitch_tiles { "stitch" } else { "noStitch" };
+                            fe_elem.set_attribute((AId::StitchTiles, stitch_tiles));
+                            fe_elem.set_attribute((AId::Type, turbulence.kind.to_string()));
+
+                            fe_elem
+                        }
                         FilterKind::FeImage(ref img) => {
                             let mut fe_elem = new_doc.create_element(EId::FeImage);
                             filter_elem.append(fe_elem.clone());
@@ -240,7 +372,9 @@ fn conv_defs(
                                     let href = conv_image_data(data, format);
                                     fe_elem.set_attribute((AId::Href, href));
                                 }
-                                FeImageKind::Use(..) => {}
+                                FeImageKind::Use(ref id) => {
+                                    link_later.push((id.clone(), AId::Href, fe_elem.clone()));
+                                }
                             }
 
                             fe_elem.set_attribute((AId::PreserveAspectRatio, img.aspect));
@@ -265,6 +399,9 @@ fn conv_defs(
         }
     }
 
+    // Groups referenced by `feImage`.
+    conv_elements(tree, &tree.defs(), defs, new_doc, &mut defs.clone());
+
     for (id, aid, mut elem) in link_later {
         conv_link(tree, defs, aid, &id, &mut elem);
     }
@@ -293,6 +430,10 @@ fn conv_elements(
                 path_elem.set_enum_attribute(AId::Visibility, p.visibility);
                 path_elem.set_enum_attribute(AId::ShapeRendering, p.rendering_mode);
 
+                if p.paint_order == PaintOrder::StrokeAndFill {
+                    path_elem.set_attribute(("paint-order", "stroke"));
+                }
+
                 use svgdom::Path as SvgDomPath;
                 use svgdom::PathSegment as SvgDomPathSegment;
 
@@ -352,14 +493,40 @@ fn conv_elements(
 
                 conv_opt_link(tree, defs, AId::ClipPath, &g.clip_path, &mut g_elem);
                 conv_opt_link(tree, defs, AId::Mask, &g.mask, &mut g_elem);
-                conv_opt_link(tree, defs, AId::Filter, &g.filter, &mut g_elem);
+
+                if g.filter.len() == 1 {
+                    conv_link(tree, defs, AId::Filter, &g.filter[0], &mut g_elem);
+                } else if !g.filter.is_empty() {
+                    // `svgdom` supports only a single link, so a list is stored as a string.
+                    let list: Vec<_> = g.filter.iter().map(|id| format!("url(#{})", id)).collect();
+                    g_elem.set_attribute((AId::Filter, list.join(" ")));
+                }
 
                 if !g.opacity.is_default() {
                     g_elem.set_attribute((AId::Opacity, g.opacity.value()));
                 }
 
+                if let Some(EnableBackground(rect)) = g.enable_background {
+                    let value = if let Some(r) = rect {
+                        format!("new {} {} {} {}", r.x(), r.y(), r.width(), r.height())
+                    } else {
+                        "new".to_string()
+                    };
+
+                    g_elem.set_attribute((AId::EnableBackground, value));
+                }
+
+                if g.blend_mode != BlendMode::Normal {
+                    g_elem.set_attribute(("mix-blend-mode", g.blend_mode.to_string()));
+                }
+
+                if g.isolate {
+                    g_elem.set_attribute(("isolation", "isolate"));
+                }
+
                 if !g_elem.has_id() && g_elem.attributes().len() == 0 {
-                    warn!("Group must have at least one attribute otherwise it's pointless.");
+                    diag!(Warning, InvalidStructure, g.id,
+                          "Group must have at least one attribute otherwise it's pointless.");
                 }
 
                 if !parent.is_tag_name(EId::ClipPath) {
@@ -479,6 +646,10 @@ fn conv_stroke(
         if let Some(ref array) = stroke.dasharray {
             node.set_attribute((AId::StrokeDasharray, NumberList(array.clone())));
         }
+
+        if stroke.non_scaling {
+            node.set_attribute(("vector-effect", "non-scaling-stroke"));
+        }
     }
 }
 
@@ -508,6 +679,88 @@ fn conv_base_grad(
     }
 }
 
+fn conv_transfer_function(
+    eid: EId,
+    func: &TransferFunction,
+    doc: &mut svgdom::Document,
+    node: &mut svgdom::Node,
+) {
+    // Identity is the default value, so we can skip it.
+    if let TransferFunction::Identity = func {
+        return;
+    }
+
+    let mut func_elem = doc.create_element(eid);
+    node.append(func_elem.clone());
+
+    func_elem.set_attribute((AId::Type, func.to_string()));
+
+    match *func {
+        TransferFunction::Identity => {}
+        TransferFunction::Table(ref values) | TransferFunction::Discrete(ref values) => {
+            func_elem.set_attribute((AId::TableValues, NumberList(values.clone())));
+        }
+        TransferFunction::Linear { slope, intercept } => {
+            func_elem.set_attribute((AId::Slope, slope));
+            func_elem.set_attribute((AId::Intercept, intercept));
+        }
+        TransferFunction::Gamma { amplitude, exponent, offset } => {
+            func_elem.set_attribute((AId::Amplitude, amplitude));
+            func_elem.set_attribute((AId::Exponent, exponent));
+            func_elem.set_attribute((AId::Offset, offset));
+        }
+    }
+}
+
+fn conv_kernel_unit_length(
+    len: Option<(f64, f64)>,
+    node: &mut svgdom::Node,
+) {
+    if let Some((x, y)) = len {
+        node.set_attribute((AId::KernelUnitLength, NumberList(vec![x, y])));
+    }
+}
+
+fn conv_light_source(
+    source: &FeLightSource,
+    doc: &mut svgdom::Document,
+    node: &mut svgdom::Node,
+) {
+    match *source {
+        FeLightSource::FeDistantLight(ref light) => {
+            let mut light_elem = doc.create_element(EId::FeDistantLight);
+            node.append(light_elem.clone());
+
+            light_elem.set_attribute((AId::Azimuth, light.azimuth));
+            light_elem.set_attribute((AId::Elevation, light.elevation));
+        }
+        FeLightSource::FePointLight(ref light) => {
+            let mut light_elem = doc.create_element(EId::FePointLight);
+            node.append(light_elem.clone());
+
+            light_elem.set_attribute((AId::X, light.x));
+            light_elem.set_attribute((AId::Y, light.y));
+            light_elem.set_attribute((AId::Z, light.z));
+        }
+        FeLightSource::FeSpotLight(ref light) => {
+            let mut light_elem = doc.create_element(EId::FeSpotLight);
+            node.append(light_elem.clone());
+
+            light_elem.set_attribute((AId::X, light.x));
+            light_elem.set_attribute((AId::Y, light.y));
+            light_elem.set_attribute((AId::Z, light.z));
+            light_elem.set_attribute((AId::PointsAtX, light.points_at_x));
+            light_elem.set_attribute((AId::PointsAtY, light.points_at_y));
+            light_elem.set_attribute((AId::PointsAtZ, light.points_at_z));
+            light_elem.set_attribute((AId::SpecularExponent, light.specular_exponent.value()));
+
+            if let Some(angle) = light.limiting_cone_angle {
+                light_elem.set_attribute((AId::LimitingConeAngle, angle));
+            }
+        }
+    }
+}
+
 fn conv_transform(
     aid: AId,
     ts: &svgdom::Transform,
@@ -540,7 +793,8 @@ fn conv_link(
     if let Some(n) = tree.defs_by_id(id) {
         let defs_id = n.id();
         let link = defs.children().find(|n| *n.id() == *defs_id);
-        let link = try_opt_warn!(link, "Unresolved FuncLink '{}'.", defs_id);
+        let link = try_opt_diag_or!(link, (), UnresolvedLink, node.id(),
+                                    "Unresolved FuncLink '{}'.", defs_id);
         node.set_attribute((aid, link));
     }
 }
@@ -50,35 +50,52 @@ impl Tree {
     ///
     /// Can contain an SVG string or a gzip compressed data.
     pub fn from_data(data: &[u8], opt: &Options) -> Result<Self, Error> {
-        if data.starts_with(&[0x1f, 0x8b]) {
-            let text = deflate(data, data.len())?;
-            Self::from_str(&text, opt)
-        } else {
-            let text = ::std::str::from_utf8(data).map_err(|_| Error::NotAnUtf8Str)?;
-            Self::from_str(text, opt)
-        }
+        Self::parse(|| parse_svg_data(data, opt), opt)
     }
 
     /// Parses `Tree` from the SVG string.
+    ///
+    /// All conversion problems can be collected via `collect_diagnostics`.
     pub fn from_str(text: &str, opt: &Options) -> Result<Self, Error> {
-        let dom_opt = svgdom::ParseOptions {
-            skip_invalid_attributes: true,
-            skip_invalid_css: true,
-            skip_unresolved_classes: true,
-        };
-
-        let doc = svgdom::Document::from_str_with_opt(text, &dom_opt)
-            .map_err(Error::ParsingFailed)?;
-
-        Self::from_dom(doc, &opt)
+        Self::parse(|| parse_svg_str(text, opt), opt)
     }
 
     /// Parses `Tree` from the `svgdom::Document`.
     ///
     /// An empty `Tree` will be returned on any error.
-    pub fn from_dom(mut doc: svgdom::Document, opt: &Options) -> Result<Self, Error> {
-        super::convert::prepare_doc(&mut doc);
-        super::convert::convert_doc(&doc, opt)
+    ///
+    /// In the strict mode, returns `Error::Unsupported` on any lossy diagnostic.
+    pub fn from_dom(doc: svgdom::Document, opt: &Options) -> Result<Self, Error> {
+        Self::parse(|| Ok(doc), opt)
+    }
+
+    // The parsing is done inside the diagnostics collector too,
+    // because the CSS resolving and `svgdom` can drop some of the data.
+    fn parse<F>(parse_doc: F, opt: &Options) -> Result<Self, Error>
+        where F: FnOnce() -> Result<svgdom::Document, Error>
+    {
+        let convert = || -> Result<Self, Error> {
+            let mut doc = parse_doc()?;
+            super::convert::prepare_doc(&mut doc, opt)?;
+            super::convert::convert_doc(&doc, opt)
+        };
+
+        // Elements positions are stored per document.
+        let convert = || crate::diagnostics::with_positions(convert);
+
+        if !opt.strict {
+            return convert();
+        }
+
+        let (tree, diagnostics) = crate::collect_diagnostics(convert);
+        let tree = tree?;
+
+        let lossy: Vec<_> = diagnostics.into_iter().filter(|d| d.code.is_lossy()).collect();
+        if !lossy.is_empty() {
+            return Err(Error::Unsupported(lossy));
+        }
+
+        Ok(tree)
     }
 
     /// Parses `Tree` from the file.
@@ -197,6 +214,14 @@ pub trait NodeExt {
 
     /// Returns a node's tree.
     fn tree(&self) -> Tree;
+
+    /// Returns a node that starts the background image used by the specified filter.
+    ///
+    /// This is the nearest ancestor with `enable-background=new`.
+    ///
+    /// Returns `None` when the filter doesn't use `BackgroundImage` or `BackgroundAlpha`
+    /// inputs or when there are no such ancestor.
+    fn filter_background_start_node(&self, filter: &Filter) -> Option<Node>;
 }
 
 impl NodeExt for Node {
@@ -217,9 +242,57 @@ impl NodeExt for Node {
     fn tree(&self) -> Tree {
         Tree { root: self.root() }
     }
+
+    fn filter_background_start_node(&self, filter: &Filter) -> Option<Node> {
+        fn has_enable_background(node: &Node) -> bool {
+            if let NodeKind::Group(ref g) = *node.borrow() {
+                g.enable_background.is_some()
+            } else {
+                false
+            }
+        }
+
+        if    !filter.has_input(&FilterInput::BackgroundImage)
+           && !filter.has_input(&FilterInput::BackgroundAlpha)
+        {
+            return None;
+        }
+
+        // Skip the current element.
+        self.ancestors().skip(1).find(|node| has_enable_background(node))
+    }
 }
 
 
+/// Parses `svgdom::Document` from the SVG data.
+///
+/// Can contain an SVG string or a gzip compressed data.
+pub(crate) fn parse_svg_data(data: &[u8], opt: &Options) -> Result<svgdom::Document, Error> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        let text = deflate(data, data.len())?;
+        parse_svg_str(&text, opt)
+    } else {
+        let text = ::std::str::from_utf8(data).map_err(|_| Error::NotAnUtf8Str)?;
+        parse_svg_str(text, opt)
+    }
+}
+
+/// Parses `svgdom::Document` from the SVG string.
+///
+/// Style sheets are resolved before the parsing.
+pub(crate) fn parse_svg_str(text: &str, opt: &Options) -> Result<svgdom::Document, Error> {
+    let text = crate::css::resolve(text, opt)?;
+
+    let dom_opt = svgdom::ParseOptions {
+        skip_invalid_attributes: true,
+        skip_invalid_css: true,
+        skip_unresolved_classes: true,
+    };
+
+    svgdom::Document::from_str_with_opt(&text, &dom_opt)
+        .map_err(Error::ParsingFailed)
+}
+
 /// Loads SVG, SVGZ file content.
 pub fn load_svg_file(path: &path::Path) -> Result<String, Error> {
     use std::fs;
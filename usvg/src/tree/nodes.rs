@@ -117,6 +117,11 @@ pub struct Path {
     /// `shape-rendering` in SVG.
     pub rendering_mode: ShapeRendering,
 
+    /// Fill and stroke paint order.
+    ///
+    /// `paint-order` in SVG.
+    pub paint_order: PaintOrder,
+
     /// Segments list.
     ///
     /// All segments are in absolute coordinates.
@@ -132,6 +137,7 @@ impl Default for Path {
             fill: None,
             stroke: None,
             rendering_mode: ShapeRendering::default(),
+            paint_order: PaintOrder::default(),
             segments: Vec::new(),
         }
     }
@@ -205,8 +211,37 @@ pub struct Group {
     /// Element mask.
     pub mask: Option<String>,
 
-    /// Element filter.
-    pub filter: Option<String>,
+    /// A list of element filters.
+    ///
+    /// Filters should be applied in order, each one to the result of the previous one.
+    pub filter: Vec<String>,
+
+    /// A paint used by the `FillPaint` filter input.
+    ///
+    /// Will be set only when the `filter` is set and uses `FillPaint`.
+    pub filter_fill: Option<Paint>,
+
+    /// A paint used by the `StrokePaint` filter input.
+    ///
+    /// Will be set only when the `filter` is set and uses `StrokePaint`.
+    pub filter_stroke: Option<Paint>,
+
+    /// Indicates that this group is a start of a new background image.
+    ///
+    /// Used by the `BackgroundImage` and `BackgroundAlpha` filter inputs.
+    pub enable_background: Option<EnableBackground>,
+
+    /// A blending mode used to combine the group with a backdrop.
+    ///
+    /// `mix-blend-mode` in SVG.
+    pub blend_mode: BlendMode,
+
+    /// Indicates that the group creates a new isolated group.
+    ///
+    /// Children of an isolated group are not blended with the content behind it.
+    ///
+    /// `isolation` in SVG.
+    pub isolate: bool,
 }
 
 impl Default for Group {
@@ -217,7 +252,12 @@ impl Default for Group {
             opacity: Opacity::default(),
             clip_path: None,
             mask: None,
-            filter: None,
+            filter: Vec::new(),
+            filter_fill: None,
+            filter_stroke: None,
+            enable_background: None,
+            blend_mode: BlendMode::default(),
+            isolate: false,
         }
     }
 }
@@ -468,6 +508,13 @@ pub struct Filter {
     pub children: Vec<FilterPrimitive>,
 }
 
+impl Filter {
+    /// Checks that any of the filter primitives uses the specified input.
+    pub fn has_input(&self, input: &FilterInput) -> bool {
+        self.children.iter().any(|c| c.kind.has_input(input))
+    }
+}
+
 
 /// A filter primitive element.
 #[derive(Clone, Debug)]
@@ -504,13 +551,45 @@ pub struct FilterPrimitive {
 #[derive(Clone, Debug)]
 pub enum FilterKind {
     FeBlend(FeBlend),
+    FeColorMatrix(FeColorMatrix),
+    FeComponentTransfer(FeComponentTransfer),
     FeComposite(FeComposite),
+    FeConvolveMatrix(FeConvolveMatrix),
+    FeDiffuseLighting(FeDiffuseLighting),
+    FeDisplacementMap(FeDisplacementMap),
     FeFlood(FeFlood),
     FeGaussianBlur(FeGaussianBlur),
     FeImage(FeImage),
     FeMerge(FeMerge),
+    FeMorphology(FeMorphology),
     FeOffset(FeOffset),
+    FeSpecularLighting(FeSpecularLighting),
     FeTile(FeTile),
+    FeTurbulence(FeTurbulence),
+}
+
+impl FilterKind {
+    /// Checks that the current filter primitive uses the specified input.
+    pub fn has_input(&self, input: &FilterInput) -> bool {
+        match *self {
+            FilterKind::FeBlend(ref fe) => fe.input1 == *input || fe.input2 == *input,
+            FilterKind::FeColorMatrix(ref fe) => fe.input == *input,
+            FilterKind::FeComponentTransfer(ref fe) => fe.input == *input,
+            FilterKind::FeComposite(ref fe) => fe.input1 == *input || fe.input2 == *input,
+            FilterKind::FeConvolveMatrix(ref fe) => fe.input == *input,
+            FilterKind::FeDiffuseLighting(ref fe) => fe.input == *input,
+            FilterKind::FeDisplacementMap(ref fe) => fe.input1 == *input || fe.input2 == *input,
+            FilterKind::FeFlood(_) => false,
+            FilterKind::FeGaussianBlur(ref fe) => fe.input == *input,
+            FilterKind::FeImage(_) => false,
+            FilterKind::FeMerge(ref fe) => fe.inputs.iter().any(|i| i == input),
+            FilterKind::FeMorphology(ref fe) => fe.input == *input,
+            FilterKind::FeOffset(ref fe) => fe.input == *input,
+            FilterKind::FeSpecularLighting(ref fe) => fe.input == *input,
+            FilterKind::FeTile(ref fe) => fe.input == *input,
+            FilterKind::FeTurbulence(_) => false,
+        }
+    }
 }
 
 
@@ -536,6 +615,47 @@ pub struct FeBlend {
 }
 
 
+/// A color matrix filter primitive.
+///
+/// `feColorMatrix` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeColorMatrix {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// A matrix kind.
+    ///
+    /// `type` in the SVG.
+    pub kind: FeColorMatrixKind,
+}
+
+
+/// A component-wise remapping filter primitive.
+///
+/// `feComponentTransfer` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeComponentTransfer {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// `feFuncR` in the SVG.
+    pub func_r: TransferFunction,
+
+    /// `feFuncG` in the SVG.
+    pub func_g: TransferFunction,
+
+    /// `feFuncB` in the SVG.
+    pub func_b: TransferFunction,
+
+    /// `feFuncA` in the SVG.
+    pub func_a: TransferFunction,
+}
+
+
 /// A composite filter primitive.
 ///
 /// `feComposite` element in the SVG.
@@ -558,6 +678,114 @@ pub struct FeComposite {
 }
 
 
+/// A matrix convolution filter primitive.
+///
+/// `feConvolveMatrix` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeConvolveMatrix {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// A matrix.
+    ///
+    /// `order`, `kernelMatrix`, `targetX` and `targetY` in the SVG.
+    pub matrix: ConvolveMatrix,
+
+    /// A matrix divisor.
+    ///
+    /// Never zero.
+    ///
+    /// `divisor` in the SVG.
+    pub divisor: f64,
+
+    /// A kernel matrix bias.
+    ///
+    /// `bias` in the SVG.
+    pub bias: f64,
+
+    /// An edges processing mode.
+    ///
+    /// `edgeMode` in the SVG.
+    pub edge_mode: EdgeMode,
+
+    /// An alpha preserving flag.
+    ///
+    /// `preserveAlpha` in the SVG.
+    pub preserve_alpha: bool,
+}
+
+
+/// A diffuse lighting filter primitive.
+///
+/// `feDiffuseLighting` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeDiffuseLighting {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// A surface height scale.
+    ///
+    /// `surfaceScale` in the SVG.
+    pub surface_scale: f64,
+
+    /// A diffuse lighting constant.
+    ///
+    /// `diffuseConstant` in the SVG.
+    pub diffuse_constant: PositiveNumber,
+
+    /// An intended distance between the normal map samples.
+    ///
+    /// Both values are positive. `None` when not set.
+    ///
+    /// `kernelUnitLength` in the SVG.
+    pub kernel_unit_length: Option<(f64, f64)>,
+
+    /// A lighting color.
+    ///
+    /// `lighting-color` in the SVG.
+    pub lighting_color: Color,
+
+    /// A light source.
+    pub light_source: FeLightSource,
+}
+
+
+/// A displacement map filter primitive.
+///
+/// `feDisplacementMap` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeDisplacementMap {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input1: FilterInput,
+
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in2` in the SVG.
+    pub input2: FilterInput,
+
+    /// Displacement scale factor.
+    ///
+    /// `scale` in the SVG.
+    pub scale: f64,
+
+    /// Indicates a source color channel along the X-axis.
+    ///
+    /// `xChannelSelector` in the SVG.
+    pub x_channel_selector: ColorChannel,
+
+    /// Indicates a source color channel along the Y-axis.
+    ///
+    /// `yChannelSelector` in the SVG.
+    pub y_channel_selector: ColorChannel,
+}
+
+
 /// A flood filter primitive.
 ///
 /// `feFlood` element in the SVG.
@@ -627,6 +855,39 @@ pub struct FeMerge {
 }
 
 
+/// A morphology filter primitive.
+///
+/// `feMorphology` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeMorphology {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// A filter operator.
+    ///
+    /// `operator` in the SVG.
+    pub operator: FeMorphologyOperator,
+
+    /// A filter radius along the X-axis.
+    ///
+    /// A value of zero disables the effect of the given filter primitive,
+    /// even when the other radius is not zero.
+    ///
+    /// `radius` in the SVG.
+    pub radius_x: PositiveNumber,
+
+    /// A filter radius along the Y-axis.
+    ///
+    /// A value of zero disables the effect of the given filter primitive,
+    /// even when the other radius is not zero.
+    ///
+    /// `radius` in the SVG.
+    pub radius_y: PositiveNumber,
+}
+
+
 /// An offset filter primitive.
 ///
 /// `feOffset` element in the SVG.
@@ -645,6 +906,50 @@ pub struct FeOffset {
 }
 
 
+/// A specular lighting filter primitive.
+///
+/// `feSpecularLighting` element in the SVG.
+#[derive(Clone, Debug)]
+pub struct FeSpecularLighting {
+    /// Identifies input for the given filter primitive.
+    ///
+    /// `in` in the SVG.
+    pub input: FilterInput,
+
+    /// A surface height scale.
+    ///
+    /// `surfaceScale` in the SVG.
+    pub surface_scale: f64,
+
+    /// A specular lighting constant.
+    ///
+    /// `specularConstant` in the SVG.
+    pub specular_constant: PositiveNumber,
+
+    /// A specular lighting exponent.
+    ///
+    /// Always in a 1..128 range.
+    ///
+    /// `specularExponent` in the SVG.
+    pub specular_exponent: f64,
+
+    /// An intended distance between the normal map samples.
+    ///
+    /// Both values are positive. `None` when not set.
+    ///
+    /// `kernelUnitLength` in the SVG.
+    pub kernel_unit_length: Option<(f64, f64)>,
+
+    /// A lighting color.
+    ///
+    /// `lighting-color` in the SVG.
+    pub lighting_color: Color,
+
+    /// A light source.
+    pub light_source: FeLightSource,
+}
+
+
 /// A tile filter primitive.
 ///
 /// `feTile` element in the SVG.
@@ -657,6 +962,43 @@ pub struct FeTile {
 }
 
 
+/// A turbulence generation filter primitive.
+///
+/// `feTurbulence` element in the SVG.
+#[derive(Clone, Copy, Debug)]
+pub struct FeTurbulence {
+    /// Identifies the base frequency for the noise function along the X-axis.
+    ///
+    /// `baseFrequency` in the SVG.
+    pub base_frequency_x: PositiveNumber,
+
+    /// Identifies the base frequency for the noise function along the Y-axis.
+    ///
+    /// `baseFrequency` in the SVG.
+    pub base_frequency_y: PositiveNumber,
+
+    /// Identifies the number of octaves for the noise function.
+    ///
+    /// `numOctaves` in the SVG.
+    pub num_octaves: u32,
+
+    /// The starting number for the pseudo random number generator.
+    ///
+    /// `seed` in the SVG.
+    pub seed: i32,
+
+    /// Smooth transitions at the border of tiles.
+    ///
+    /// `stitchTiles` in the SVG.
+    pub stitch_tiles: bool,
+
+    /// Indicates whether the filter primitive should perform a noise or turbulence function.
+    ///
+    /// `type` in the SVG.
+    pub kind: FeTurbulenceKind,
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
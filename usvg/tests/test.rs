@@ -396,6 +396,33 @@ test!(group_with_default_opacity, false,
 </svg>
 ");
 
+test!(group_with_enable_background, false,
+"<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <g enable-background='new'>
+        <rect width='10' height='10'/>
+    </g>
+    <g enable-background='accumulate'>
+        <rect width='10' height='10'/>
+    </g>
+</svg>",
+"<svg
+    xmlns='http://www.w3.org/2000/svg'
+    width='1'
+    height='1'
+    viewBox='0 0 1 1'
+    xmlns:usvg='https://github.com/RazrFalcon/usvg'
+    usvg:version='0.6.1'>
+    <defs/>
+    <g
+        enable-background='new'>
+        <path
+            d='M 0 0 L 10 0 L 10 10 L 0 10 Z'/>
+    </g>
+    <path
+        d='M 0 0 L 10 0 L 10 10 L 0 10 Z'/>
+</svg>
+");
+
 test!(group_with_an_invalid_child, false,
 "<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
     <g opacity='0.5'>
@@ -574,3 +601,1026 @@ test_size_err!(size_detection_err_2,
 //        d='M 0 0 L 10 0 L 10 10 L 0 10 Z'/>
 //</svg>
 //");
+
+#[test]
+fn diagnostics_position() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <g>
+        <rect id='rect1' width='1' height='1' fill='url(#missing1)'/>
+    </g>
+</svg>";
+
+    let (tree, list) = usvg::collect_diagnostics(|| {
+        usvg::Tree::from_str(input, &usvg::Options::default())
+    });
+    assert!(tree.is_ok());
+
+    let d = list.iter().find(|d| d.code == usvg::DiagnosticCode::UnresolvedLink).unwrap();
+    assert_eq!(d.element_id, Some("rect1".to_string()));
+    assert_eq!(d.position, Some(usvg::SourcePos { row: 3, col: 9 }));
+}
+
+#[test]
+fn strict_mode() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 1 1'>
+    <use id='use1' xlink:href='image.svg#rect1'/>
+</svg>";
+
+    assert!(usvg::Tree::from_str(input, &usvg::Options::default()).is_ok());
+
+    let re_opt = usvg::Options {
+        strict: true,
+        .. usvg::Options::default()
+    };
+    match usvg::Tree::from_str(input, &re_opt) {
+        Err(usvg::Error::Unsupported(list)) => {
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].code, usvg::DiagnosticCode::InvalidReference);
+            assert_eq!(list[0].element_id, Some("use1".to_string()));
+        }
+        _ => panic!("strict mode must fail"),
+    }
+}
+
+#[test]
+fn strict_mode_unresolved_links() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 1 1'>
+    <rect id='rect1' width='1' height='1' fill='url(#missing1)'/>
+    <rect id='rect2' width='1' height='1' fill='url(#missing2) red'/>
+    <use id='use1' xlink:href='#missing3'/>
+</svg>";
+
+    assert!(usvg::Tree::from_str(input, &usvg::Options::default()).is_ok());
+
+    let re_opt = usvg::Options {
+        strict: true,
+        .. usvg::Options::default()
+    };
+    match usvg::Tree::from_str(input, &re_opt) {
+        Err(usvg::Error::Unsupported(list)) => {
+            let ids: Vec<_> = list.iter()
+                .filter(|d| d.code == usvg::DiagnosticCode::UnresolvedLink)
+                .map(|d| d.element_id.clone().unwrap_or_default())
+                .collect();
+            // A paint with a fallback is not lossy.
+            assert_eq!(ids, vec!["rect1".to_string(), "use1".to_string()]);
+        }
+        _ => panic!("strict mode must fail"),
+    }
+}
+
+#[test]
+fn resource_loader() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[derive(Debug)]
+    struct Loader {
+        count: AtomicUsize,
+    }
+
+    impl usvg::ResourceLoader for Loader {
+        fn load(&self, href: &str, kind: usvg::ResourceKind) -> usvg::Resource {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            match (href, kind) {
+                ("image.png", usvg::ResourceKind::Image) => usvg::Resource::Data(PNG_MAGIC.to_vec()),
+                _ => usvg::Resource::Denied,
+            }
+        }
+    }
+
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 1 1'>
+    <image id='image1' width='1' height='1' xlink:href='image.png'/>
+    <image id='image2' width='1' height='1' xlink:href='/etc/passwd'/>
+</svg>";
+
+    let loader = Arc::new(Loader { count: AtomicUsize::new(0) });
+    let re_opt = usvg::Options {
+        resource_loader: Some(loader.clone()),
+        .. usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(input, &re_opt).unwrap();
+
+    assert_eq!(loader.count.load(Ordering::SeqCst), 2);
+    assert!(tree.node_by_id("image2").is_none());
+
+    let node = tree.node_by_id("image1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Image(ref img) => {
+            assert_eq!(img.format, usvg::ImageFormat::PNG);
+            match img.data {
+                usvg::ImageData::Raw(ref data) => assert_eq!(data.as_slice(), PNG_MAGIC),
+                _ => panic!("image data must be loaded"),
+            }
+        }
+        _ => panic!("not an image"),
+    }
+}
+
+#[test]
+fn options_send() {
+    fn check<T: Send + Sync>() {}
+    check::<usvg::Options>();
+}
+
+#[test]
+fn limits() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 1 1'>
+    <g id='g1'>
+        <rect width='1' height='1'/>
+        <rect width='1' height='1'/>
+    </g>
+    <use xlink:href='#g1'/>
+    <use xlink:href='#g1'/>
+</svg>";
+
+    fn parse(input: &str, limits: usvg::Limits) -> Result<usvg::Tree, usvg::Error> {
+        let re_opt = usvg::Options {
+            limits,
+            .. usvg::Options::default()
+        };
+        usvg::Tree::from_str(input, &re_opt)
+    }
+
+    assert!(parse(input, usvg::Limits::default()).is_ok());
+
+    match parse(input, usvg::Limits { max_elements: Some(3), .. usvg::Limits::default() }) {
+        Err(usvg::Error::LimitExceeded(usvg::LimitKind::Elements)) => {}
+        _ => panic!("elements limit must be exceeded"),
+    }
+
+    match parse(input, usvg::Limits { max_depth: Some(2), .. usvg::Limits::default() }) {
+        Err(usvg::Error::LimitExceeded(usvg::LimitKind::Depth)) => {}
+        _ => panic!("depth limit must be exceeded"),
+    }
+
+    match parse(input, usvg::Limits { max_use_expansion: Some(5), .. usvg::Limits::default() }) {
+        Err(usvg::Error::LimitExceeded(usvg::LimitKind::UseExpansion)) => {}
+        _ => panic!("use expansion limit must be exceeded"),
+    }
+
+    assert!(parse(input, usvg::Limits { max_use_expansion: Some(6), .. usvg::Limits::default() }).is_ok());
+}
+
+#[test]
+fn limits_ignore_non_elements() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <!-- comment -->
+    <desc>Some text</desc>
+    <!-- comment -->
+    <rect width='1' height='1'/>
+</svg>";
+
+    let re_opt = usvg::Options {
+        limits: usvg::Limits { max_elements: Some(3), .. usvg::Limits::default() },
+        .. usvg::Options::default()
+    };
+    assert!(usvg::Tree::from_str(input, &re_opt).is_ok());
+}
+
+#[test]
+fn image_pixels_limit() {
+    #[derive(Debug)]
+    struct Loader;
+
+    impl usvg::ResourceLoader for Loader {
+        fn load(&self, href: &str, _: usvg::ResourceKind) -> usvg::Resource {
+            match href {
+                // Only a header of a 10000x10000 image.
+                "image.png" => usvg::Resource::Data(
+                    b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x27\x10\x00\x00\x27\x10".to_vec()
+                ),
+                // SOI, APP0 and SOF0 of a 20x10 image.
+                "image.jpg" => usvg::Resource::Data(
+                    b"\xff\xd8\xff\xe0\x00\x04\x00\x00\xff\xc0\x00\x11\x08\x00\x0a\x00\x14".to_vec()
+                ),
+                _ => usvg::Resource::Denied,
+            }
+        }
+    }
+
+    fn parse(href: &str, max_pixels: u64) -> Result<usvg::Tree, usvg::Error> {
+        let input = format!("\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 1 1'>
+    <image width='1' height='1' xlink:href='{}'/>
+</svg>", href);
+
+        let re_opt = usvg::Options {
+            resource_loader: Some(std::sync::Arc::new(Loader)),
+            limits: usvg::Limits { max_pixels: Some(max_pixels), .. usvg::Limits::default() },
+            .. usvg::Options::default()
+        };
+        usvg::Tree::from_str(&input, &re_opt)
+    }
+
+    match parse("image.png", 1000000) {
+        Err(usvg::Error::LimitExceeded(usvg::LimitKind::Pixels)) => {}
+        _ => panic!("pixels limit must be exceeded"),
+    }
+
+    match parse("image.jpg", 199) {
+        Err(usvg::Error::LimitExceeded(usvg::LimitKind::Pixels)) => {}
+        _ => panic!("pixels limit must be exceeded"),
+    }
+
+    assert!(parse("image.jpg", 200).is_ok());
+}
+
+#[test]
+fn auto_size() {
+    use crate::svgdom::FuzzyEq;
+
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' width='50%'>
+    <rect x='10' y='20' width='20' height='30' stroke='black' stroke-width='2'/>
+    <rect x='10' y='10' width='10' height='10' transform='translate(10 10)'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+    let svg = tree.svg_node();
+    assert!(svg.view_box.rect.fuzzy_eq(&usvg::Rect::new(9.0, 19.0, 22.0, 32.0).unwrap()));
+    assert!(svg.size.fuzzy_eq(&usvg::Size::new(11.0, 32.0).unwrap()));
+
+    let input = "<svg xmlns='http://www.w3.org/2000/svg'/>";
+    match usvg::Tree::from_str(input, &usvg::Options::default()) {
+        Err(usvg::Error::InvalidSize) => {}
+        _ => panic!("an empty document must not have a size"),
+    }
+}
+
+#[test]
+fn auto_size_relative_content() {
+    fn view_box(input: &str) -> usvg::Rect {
+        let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+        let svg = tree.svg_node();
+        svg.view_box.rect
+    }
+
+    fn check(r: usvg::Rect, x: f64, y: f64, w: f64, h: f64) {
+        let eq = |a: f64, b: f64| (a - b).abs() < 0.001;
+        assert!(eq(r.x(), x) && eq(r.y(), y) && eq(r.width(), w) && eq(r.height(), h), "{:?}", r);
+    }
+
+    // Percentages are ignored during the size detection
+    // and resolved against the detected size afterwards.
+    let r = view_box("\
+<svg xmlns='http://www.w3.org/2000/svg' width='100%'>
+    <rect x='10' y='10' width='20' height='20'/>
+    <rect x='50%' y='50%' width='1' height='1'/>
+</svg>");
+    check(r, 0.0, 0.0, 30.0, 30.0);
+
+    // A filter region is a part of the bounding box.
+    let r = view_box("\
+<svg xmlns='http://www.w3.org/2000/svg' width='100%'>
+    <filter id='filter1' filterUnits='userSpaceOnUse' x='-10' y='-10' width='40' height='40'>
+        <feFlood flood-color='green'/>
+    </filter>
+    <rect width='20' height='20' filter='url(#filter1)'/>
+</svg>");
+    check(r, -10.0, -10.0, 40.0, 40.0);
+
+    // And a clip path limits it.
+    let r = view_box("\
+<svg xmlns='http://www.w3.org/2000/svg' width='100%'>
+    <clipPath id='clip1'>
+        <rect width='10' height='10'/>
+    </clipPath>
+    <rect width='20' height='20' clip-path='url(#clip1)'/>
+</svg>");
+    check(r, 0.0, 0.0, 10.0, 10.0);
+}
+
+#[test]
+fn external_use() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    struct Loader {
+        count: AtomicUsize,
+    }
+
+    impl usvg::ResourceLoader for Loader {
+        fn load(&self, href: &str, kind: usvg::ResourceKind) -> usvg::Resource {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            match (href, kind) {
+                ("icons.svg", usvg::ResourceKind::Document) => usvg::Resource::Data(b"\
+<svg xmlns='http://www.w3.org/2000/svg'>
+    <linearGradient id='lg1'>
+        <stop offset='0' stop-color='red'/>
+        <stop offset='1' stop-color='blue'/>
+    </linearGradient>
+    <path id='star' d='M 0 0 L 10 0 L 10 10 Z' fill='url(#lg1)'/>
+</svg>".to_vec()),
+                _ => usvg::Resource::Denied,
+            }
+        }
+    }
+
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 20 20'>
+    <linearGradient id='lg1'>
+        <stop offset='0' stop-color='green'/>
+        <stop offset='1' stop-color='black'/>
+    </linearGradient>
+    <rect width='5' height='5' fill='url(#lg1)'/>
+    <use xlink:href='icons.svg#star'/>
+    <use xlink:href='icons.svg#star' x='10'/>
+</svg>";
+
+    let loader = Arc::new(Loader { count: AtomicUsize::new(0) });
+    let re_opt = usvg::Options {
+        resource_loader: Some(loader.clone()),
+        .. usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(input, &re_opt).unwrap();
+
+    assert_eq!(loader.count.load(Ordering::SeqCst), 1);
+    assert!(tree.defs_by_id("lg1").is_some());
+    assert!(tree.defs_by_id("icons-lg1").is_some());
+
+    let mut count = 0;
+    for node in tree.root().descendants() {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            if let Some(usvg::Fill { paint: usvg::Paint::Link(ref id), .. }) = path.fill {
+                if id == "icons-lg1" {
+                    count += 1;
+                }
+            }
+        }
+    }
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn external_use_nested() {
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Loader {
+        hrefs: Mutex<Vec<String>>,
+    }
+
+    impl usvg::ResourceLoader for Loader {
+        fn load(&self, href: &str, kind: usvg::ResourceKind) -> usvg::Resource {
+            assert_eq!(kind, usvg::ResourceKind::Document);
+            self.hrefs.lock().unwrap().push(href.to_string());
+            match href {
+                "icons/sprite.svg" => usvg::Resource::Data(b"\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'>
+    <g id='star'>
+        <use xlink:href='shapes/base.svg#rect1'/>
+    </g>
+    <g id='loop'>
+        <use xlink:href='shapes/base.svg#loop'/>
+    </g>
+</svg>".to_vec()),
+                "icons/shapes/base.svg" => usvg::Resource::Data(b"\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'>
+    <rect id='rect1' width='10' height='10'/>
+    <g id='loop'>
+        <use xlink:href='../sprite.svg#loop'/>
+    </g>
+</svg>".to_vec()),
+                _ => usvg::Resource::Denied,
+            }
+        }
+    }
+
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' viewBox='0 0 20 20'>
+    <use xlink:href='icons/sprite.svg#star'/>
+    <use xlink:href='icons/sprite.svg#loop'/>
+</svg>";
+
+    let loader = std::sync::Arc::new(Loader { hrefs: Mutex::new(Vec::new()) });
+    let re_opt = usvg::Options {
+        resource_loader: Some(loader.clone()),
+        .. usvg::Options::default()
+    };
+    let (tree, diagnostics) = usvg::collect_diagnostics(|| {
+        usvg::Tree::from_str(input, &re_opt)
+    });
+    let tree = tree.unwrap();
+
+    assert_eq!(*loader.hrefs.lock().unwrap(), vec!["icons/sprite.svg", "icons/shapes/base.svg"]);
+    assert!(diagnostics.iter().any(|d| d.code == usvg::DiagnosticCode::RecursiveReference));
+
+    let count = tree.root().descendants()
+        .filter(|n| if let usvg::NodeKind::Path(_) = *n.borrow() { true } else { false })
+        .count();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn user_style_sheet() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <style>
+        g > .primary { fill: green }
+    </style>
+    <g>
+        <rect id='rect1' class='primary' width='1' height='1'/>
+    </g>
+</svg>";
+
+    let re_opt = usvg::Options {
+        style_sheet: Some("g > .primary { fill: #ff0000 }".to_string()),
+        .. usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(input, &re_opt).unwrap();
+
+    let node = tree.node_by_id("rect1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            match path.fill {
+                Some(usvg::Fill { paint: usvg::Paint::Color(c), .. }) => {
+                    assert_eq!(c, usvg::Color::new(255, 0, 0));
+                }
+                _ => panic!("invalid fill"),
+            }
+        }
+        _ => panic!("not a path"),
+    }
+}
+
+#[test]
+fn css_variables() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <rect id='rect1' width='1' height='1' style='fill: var(--accent, #333333)'/>
+</svg>";
+
+    fn fill_color(input: &str, css_variables: Vec<(String, String)>) -> usvg::Color {
+        let re_opt = usvg::Options {
+            css_variables,
+            .. usvg::Options::default()
+        };
+        let tree = usvg::Tree::from_str(input, &re_opt).unwrap();
+
+        let node = tree.node_by_id("rect1").unwrap();
+        let node = node.borrow();
+        match *node {
+            usvg::NodeKind::Path(usvg::Path { fill: Some(usvg::Fill { paint: usvg::Paint::Color(c), .. }), .. }) => c,
+            _ => panic!("invalid fill"),
+        }
+    }
+
+    assert_eq!(fill_color(input, Vec::new()), usvg::Color::new(0x33, 0x33, 0x33));
+    assert_eq!(fill_color(input, vec![("accent".to_string(), "#ff0000".to_string())]),
+               usvg::Color::new(255, 0, 0));
+}
+
+#[test]
+fn paint_order() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <marker id='marker1' markerWidth='10' markerHeight='10'>
+        <rect width='10' height='10'/>
+    </marker>
+    <path id='path1' d='M 10 10 L 90 90' fill='green' stroke='black'
+          paint-order='stroke'/>
+    <path id='path2' d='M 10 10 L 90 90 L 10 90' fill='green' stroke='black'
+          marker-end='url(#marker1)' paint-order='fill markers'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let node = tree.node_by_id("path1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert_eq!(path.paint_order, usvg::PaintOrder::StrokeAndFill);
+        }
+        _ => panic!("not a path"),
+    }
+
+    // A path with markers between the fill and the stroke is split in two.
+    let node = tree.node_by_id("path2").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert!(path.fill.is_some());
+            assert!(path.stroke.is_none());
+        }
+        _ => panic!("not a path"),
+    }
+
+    let marker = node.next_sibling().unwrap();
+    match *marker.borrow() {
+        usvg::NodeKind::Group(_) => {}
+        _ => panic!("not a group"),
+    }
+
+    match *marker.next_sibling().unwrap().borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert!(path.fill.is_none());
+            assert!(path.stroke.is_some());
+        }
+        _ => panic!("not a path"),
+    }
+}
+
+fn paint_order_of(tree: &usvg::Tree, id: &str) -> usvg::PaintOrder {
+    match *tree.node_by_id(id).unwrap().borrow() {
+        usvg::NodeKind::Path(ref path) => path.paint_order,
+        _ => panic!("not a path"),
+    }
+}
+
+#[test]
+fn paint_order_markers() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <marker id='marker1' markerWidth='10' markerHeight='10'>
+        <rect width='10' height='10'/>
+    </marker>
+    <path id='path1' d='M 10 10 L 90 90 L 10 90' fill='green' stroke='black'
+          marker-end='url(#marker1)' paint-order='markers stroke'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    // Markers are rendered first, so the path is not split and keeps its ID.
+    assert_eq!(paint_order_of(&tree, "path1"), usvg::PaintOrder::StrokeAndFill);
+
+    let node = tree.node_by_id("path1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert!(path.fill.is_some());
+            assert!(path.stroke.is_some());
+        }
+        _ => panic!("not a path"),
+    }
+
+    match *node.prev_sibling().unwrap().borrow() {
+        usvg::NodeKind::Group(_) => {}
+        _ => panic!("not a group"),
+    }
+
+    assert!(node.next_sibling().is_none());
+}
+
+#[test]
+fn paint_order_split_id() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <marker id='marker1' markerWidth='10' markerHeight='10'>
+        <rect width='10' height='10'/>
+    </marker>
+    <path id='path1' d='M 10 10 L 90 90 L 10 90' fill='green' stroke='black'
+          marker-end='url(#marker1)' paint-order='stroke markers'/>
+    <rect id='path1-after1' width='10' height='10'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    // The part rendered after markers gets a unique ID derived from the original one.
+    match *tree.node_by_id("path1").unwrap().borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert!(path.fill.is_none());
+            assert!(path.stroke.is_some());
+        }
+        _ => panic!("not a path"),
+    }
+
+    match *tree.node_by_id("path1-after2").unwrap().borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            assert!(path.fill.is_some());
+            assert!(path.stroke.is_none());
+        }
+        _ => panic!("not a path"),
+    }
+}
+
+#[test]
+fn paint_order_invalid() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <path id='path1' d='M 10 10 L 90 90' stroke='black' paint-order='stroke stroke'/>
+    <path id='path2' d='M 10 10 L 90 90' stroke='black' paint-order='stroke text'/>
+    <path id='path3' d='M 10 10 L 90 90' stroke='black' paint-order='normal'/>
+</svg>";
+
+    let (tree, list) = usvg::collect_diagnostics(|| {
+        usvg::Tree::from_str(input, &usvg::Options::default())
+    });
+    let tree = tree.unwrap();
+
+    assert_eq!(paint_order_of(&tree, "path1"), usvg::PaintOrder::FillAndStroke);
+    assert_eq!(paint_order_of(&tree, "path2"), usvg::PaintOrder::FillAndStroke);
+    assert_eq!(paint_order_of(&tree, "path3"), usvg::PaintOrder::FillAndStroke);
+
+    let ids: Vec<_> = list.iter()
+        .filter(|d| d.code == usvg::DiagnosticCode::InvalidAttribute)
+        .map(|d| d.element_id.clone())
+        .collect();
+    assert_eq!(ids, vec![Some("path1".to_string()), Some("path2".to_string())]);
+}
+
+#[test]
+fn paint_order_inheritance() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <g paint-order='stroke'>
+        <path id='path1' d='M 10 10 L 90 90' stroke='black'/>
+        <path id='path2' d='M 10 10 L 90 90' stroke='black' paint-order='fill'/>
+    </g>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+    assert_eq!(paint_order_of(&tree, "path1"), usvg::PaintOrder::StrokeAndFill);
+    assert_eq!(paint_order_of(&tree, "path2"), usvg::PaintOrder::FillAndStroke);
+}
+
+#[test]
+fn blend_mode() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 1 1'>
+    <g id='g1' isolation='isolate'>
+        <rect width='1' height='1' fill='green'/>
+        <rect id='rect1' width='1' height='1' fill='red' mix-blend-mode='luminosity'/>
+    </g>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    // An isolated group must be preserved.
+    let node = tree.node_by_id("g1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Group(ref g) => {
+            assert!(g.isolate);
+            assert_eq!(g.blend_mode, usvg::BlendMode::Normal);
+        }
+        _ => panic!("not a group"),
+    }
+
+    // A blending mode on a shape is moved to a separate group.
+    let node = tree.node_by_id("rect1").unwrap();
+    match *node.parent().unwrap().borrow() {
+        usvg::NodeKind::Group(ref g) => {
+            assert!(!g.isolate);
+            assert_eq!(g.blend_mode, usvg::BlendMode::Luminosity);
+        }
+        _ => panic!("not a group"),
+    }
+}
+
+#[test]
+fn non_scaling_stroke() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'>
+    <g transform='scale(2)'>
+        <path id='path1' d='M 1 1 L 4 4' stroke='black' vector-effect='non-scaling-stroke'/>
+        <path id='path2' d='M 1 1 L 4 4' stroke='black'/>
+    </g>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let is_non_scaling = |id: &str| {
+        let node = tree.node_by_id(id).unwrap();
+        let node = node.borrow();
+        match *node {
+            usvg::NodeKind::Path(usvg::Path { stroke: Some(ref stroke), .. }) => stroke.non_scaling,
+            _ => panic!("invalid stroke"),
+        }
+    };
+
+    assert!(is_non_scaling("path1"));
+    assert!(!is_non_scaling("path2"));
+}
+
+#[test]
+fn marker_context_paint() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'>
+    <marker id='marker1' orient='auto-start-reverse' markerUnits='userSpaceOnUse'
+            markerWidth='4' markerHeight='4' overflow='visible'>
+        <path d='M 0 0 L 2 1 L 0 2 Z' fill='context-stroke' stroke='context-fill'/>
+    </marker>
+    <path id='path1' d='M 1 1 L 8 1' fill='none' stroke='#ff0000'
+          marker-start='url(#marker1)' marker-end='url(#marker1)'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let mut groups = Vec::new();
+    for node in tree.root().descendants() {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            if path.id.is_empty() {
+                // Marker content is filled with the path stroke and has no stroke,
+                // since the path has no fill.
+                match path.fill {
+                    Some(usvg::Fill { paint: usvg::Paint::Color(c), .. }) => {
+                        assert_eq!(c, usvg::Color::new(255, 0, 0));
+                    }
+                    _ => panic!("invalid fill"),
+                }
+                assert!(path.stroke.is_none());
+
+                if let usvg::NodeKind::Group(ref g) = *node.parent().unwrap().borrow() {
+                    groups.push(g.transform);
+                }
+            }
+        }
+    }
+
+    // The start marker is reversed.
+    assert_eq!(groups.len(), 2);
+    assert!((groups[0].a + 1.0).abs() < 1e-6);
+    assert!((groups[1].a - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn css_colors() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'>
+    <linearGradient id='lg1'>
+        <stop offset='0' stop-color='hsla(120, 100%, 50%, 0.5)'/>
+        <stop offset='1' style='stop-color:#0000ff80'/>
+    </linearGradient>
+    <rect id='rect1' width='5' height='5' fill='rgba(255, 0, 0, 0.5)' fill-opacity='0.5'
+          stroke='transparent'/>
+    <rect id='rect2' width='5' height='5' fill='url(#lg1)'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let node = tree.node_by_id("rect1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            let fill = path.fill.as_ref().unwrap();
+            match fill.paint {
+                usvg::Paint::Color(c) => assert_eq!(c, usvg::Color::new(255, 0, 0)),
+                _ => panic!("invalid fill"),
+            }
+            assert_eq!(fill.opacity.value(), 0.25);
+
+            let stroke = path.stroke.as_ref().unwrap();
+            assert_eq!(stroke.opacity.value(), 0.0);
+        }
+        _ => panic!("not a path"),
+    }
+
+    let node = tree.defs_by_id("lg1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::LinearGradient(ref lg) => {
+            let stops = &lg.base.stops;
+            assert_eq!(stops[0].color, usvg::Color::new(0, 255, 0));
+            assert_eq!(stops[0].opacity.value(), 0.5);
+            assert_eq!(stops[1].color, usvg::Color::new(0, 0, 255));
+            assert!((stops[1].opacity.value() - 0.5).abs() < 0.01);
+        }
+        _ => panic!("not a gradient"),
+    }
+}
+
+#[test]
+fn css_colors_resolving() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 10 10'>
+    <g color='rgba(0, 0, 255, 0.5)'>
+        <rect id='rect1' width='5' height='5' fill='currentColor'/>
+    </g>
+    <g fill='rgba(0, 0, 255, 0.5)'>
+        <rect id='rect2' width='5' height='5' fill='inherit'/>
+    </g>
+    <rect id='rect3' width='5' height='5' color='transparent' fill='currentColor'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let fill_opacity = |id: &str| {
+        let node = tree.node_by_id(id).unwrap();
+        let node = node.borrow();
+        match *node {
+            usvg::NodeKind::Path(ref path) => path.fill.as_ref().unwrap().opacity.value(),
+            _ => panic!("not a path"),
+        }
+    };
+
+    assert_eq!(fill_opacity("rect1"), 0.5);
+    assert_eq!(fill_opacity("rect2"), 0.5);
+    assert_eq!(fill_opacity("rect3"), 0.0);
+}
+
+#[test]
+fn transform_origin() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <rect id='rect1' width='10' height='10' transform='scale(2)' transform-origin='center'/>
+    <rect id='rect2' width='10' height='10' transform='scale(2)' transform-origin='10 20'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let ts = |id: &str| {
+        let node = tree.node_by_id(id).unwrap();
+        let node = node.borrow();
+        match *node {
+            usvg::NodeKind::Path(ref path) => path.transform,
+            _ => panic!("not a path"),
+        }
+    };
+
+    assert_eq!(ts("rect1"), usvg::Transform::new(2.0, 0.0, 0.0, 2.0, -50.0, -50.0));
+    assert_eq!(ts("rect2"), usvg::Transform::new(2.0, 0.0, 0.0, 2.0, -10.0, -20.0));
+}
+
+#[test]
+fn clip_path_basic_shapes() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <rect id='rect1' x='10' y='10' width='40' height='20'
+          style='clip-path: inset(5px round 2px) fill-box'/>
+    <rect id='rect2' width='40' height='20' clip-path='circle(50% at 0 0) view-box'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    // Returns the first point of the shape used by the element clip path.
+    let clip_start = |id: &str| {
+        let node = tree.node_by_id(id).unwrap();
+        let clip_id = match *node.parent().unwrap().borrow() {
+            usvg::NodeKind::Group(ref g) => g.clip_path.clone().unwrap(),
+            _ => panic!("not a group"),
+        };
+
+        let clip_node = tree.defs_by_id(&clip_id).unwrap();
+        let path_node = clip_node.first_child().unwrap();
+        let path_node = path_node.borrow();
+        match *path_node {
+            usvg::NodeKind::Path(ref path) => {
+                match path.segments[0] {
+                    usvg::PathSegment::MoveTo { x, y } => (x, y),
+                    _ => panic!("invalid path"),
+                }
+            }
+            _ => panic!("not a path"),
+        }
+    };
+
+    assert_eq!(clip_start("rect1"), (17.0, 15.0));
+    assert_eq!(clip_start("rect2"), (50.0, 0.0));
+}
+
+#[test]
+fn clip_path_inset_radii() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <rect id='rect1' x='10' y='10' width='40' height='20'
+          clip-path='inset(5px round 4px 2px / 3px) fill-box'/>
+    <rect id='rect2' x='10' y='10' width='40' height='20'
+          clip-path='inset(0 round 30px 10px) fill-box'/>
+    <rect id='rect3' x='10' y='10' width='40' height='20'
+          clip-path='inset(0 round 1px 2px 3px 4px 5px) fill-box'/>
+</svg>";
+
+    let (tree, list) = usvg::collect_diagnostics(|| {
+        usvg::Tree::from_str(input, &usvg::Options::default())
+    });
+    let tree = tree.unwrap();
+
+    // Returns the first point of the shape used by the element clip path.
+    let clip_start = |id: &str| {
+        let node = tree.node_by_id(id).unwrap();
+        let clip_id = match *node.parent().unwrap().borrow() {
+            usvg::NodeKind::Group(ref g) => g.clip_path.clone().unwrap(),
+            _ => panic!("not a group"),
+        };
+
+        let clip_node = tree.defs_by_id(&clip_id).unwrap();
+        let path_node = clip_node.first_child().unwrap();
+        let path_node = path_node.borrow();
+        match *path_node {
+            usvg::NodeKind::Path(ref path) => {
+                match path.segments[0] {
+                    usvg::PathSegment::MoveTo { x, y } => (x, y),
+                    _ => panic!("invalid path"),
+                }
+            }
+            _ => panic!("not a path"),
+        }
+    };
+
+    // Per-corner radii.
+    assert_eq!(clip_start("rect1"), (19.0, 15.0));
+    // Overlapping corners are scaled down.
+    assert_eq!(clip_start("rect2"), (25.0, 10.0));
+
+    // More than four radii are invalid.
+    assert!(list.iter().any(|d| d.code == usvg::DiagnosticCode::InvalidAttribute
+                               && d.element_id == Some("rect3".to_string())));
+}
+
+#[test]
+fn filter_functions() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <filter id='filter1'>
+        <feFlood flood-color='green'/>
+    </filter>
+    <rect id='rect1' width='10' height='10'
+          style='filter: blur(2px) url(#filter1) grayscale(50%) drop-shadow(1px 1px red)'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let node = tree.node_by_id("rect1").unwrap();
+    let filters = match *node.parent().unwrap().borrow() {
+        usvg::NodeKind::Group(ref g) => g.filter.clone(),
+        _ => panic!("not a group"),
+    };
+
+    assert_eq!(filters.len(), 3);
+    assert_eq!(filters[1], "filter1");
+
+    // Returns the filter region and the number of primitives.
+    let filter = |id: &str| {
+        let node = tree.defs_by_id(id).unwrap();
+        let node = node.borrow();
+        match *node {
+            usvg::NodeKind::Filter(ref filter) => {
+                let r = filter.rect;
+                ((r.x(), r.y(), r.width(), r.height()), filter.children.len())
+            }
+            _ => panic!("not a filter"),
+        }
+    };
+
+    // The region includes the blur and the shadow.
+    let region = (-6.0, -6.0, 23.0, 23.0);
+    assert_eq!(filter(&filters[0]), (region, 1));
+    assert_eq!(filter(&filters[2]), (region, 5));
+}
+
+#[test]
+fn filter_functions_current_color() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 100 100'>
+    <g color='rgba(255, 0, 0, 0.5)'>
+        <rect id='rect1' width='10' height='10' style='filter: drop-shadow(1px 1px)'/>
+    </g>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    let node = tree.node_by_id("rect1").unwrap();
+    let id = match *node.parent().unwrap().borrow() {
+        usvg::NodeKind::Group(ref g) => g.filter[0].clone(),
+        _ => panic!("not a group"),
+    };
+
+    let node = tree.defs_by_id(&id).unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Filter(ref filter) => {
+            match filter.children[0].kind {
+                usvg::FilterKind::FeFlood(ref fe) => {
+                    assert_eq!(fe.color, usvg::Color::new(255, 0, 0));
+                    assert_eq!(fe.opacity.value(), 0.5);
+                }
+                _ => panic!("not a flood"),
+            }
+        }
+        _ => panic!("not a filter"),
+    };
+}
+
+#[test]
+fn filter_list_recursive_fe_image() {
+    let input = "\
+<svg xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink'
+     viewBox='0 0 100 100'>
+    <filter id='filter1'>
+        <feImage xlink:href='#rect1'/>
+    </filter>
+    <rect id='rect1' width='10' height='10' style='filter: url( \"#filter1\" ) blur(1px)'/>
+</svg>";
+
+    let tree = usvg::Tree::from_str(input, &usvg::Options::default()).unwrap();
+
+    // The recursive link must be removed.
+    let node = tree.defs_by_id("filter1").unwrap();
+    match *node.borrow() {
+        usvg::NodeKind::Filter(ref filter) => {
+            match filter.children[0].kind {
+                usvg::FilterKind::FeImage(ref fe) => {
+                    match fe.data {
+                        usvg::FeImageKind::None => {}
+                        _ => panic!("the link was not removed"),
+                    }
+                }
+                _ => panic!("not an feImage"),
+            }
+        }
+        _ => panic!("not a filter"),
+    };
+}